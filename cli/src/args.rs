@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+use crate::format::OutputFormat;
 use clap::{Args, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 
 #[derive(Parser)]
 #[command(name = "hpc", version, about, long_about = None)]
@@ -15,6 +18,8 @@ pub struct Cli {
         help = "Path to a TOML config file. When omitted, hpc uses the default config file location if available."
     )]
     pub config: Option<PathBuf>,
+    #[command(flatten)]
+    pub output: OutputArgs,
     #[command(subcommand)]
     pub cmd: Cmd,
 }
@@ -43,6 +48,29 @@ impl ToString for WLM {
     }
 }
 
+/// Output format of listing and report commands, accepted before or after the subcommand.
+#[derive(Args, Debug, Clone, Default)]
+pub struct OutputArgs {
+    /// Output format: table, json, jsonl, yaml, csv, tsv or template=TEMPLATE.
+    /// Templates use tera syntax and are rendered once per row, e.g.
+    /// -o template='{{job_id}} {{status}}'.
+    #[arg(short = 'o', long = "output", value_name = "FORMAT", global = true)]
+    pub output: Option<OutputFormat>,
+    /// Same as --output json.
+    #[arg(long, conflicts_with = "output", global = true)]
+    pub json: bool,
+}
+
+impl OutputArgs {
+    pub fn format(&self) -> OutputFormat {
+        match &self.output {
+            Some(format) => format.clone(),
+            None if self.json => OutputFormat::Json,
+            None => OutputFormat::Table,
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct JobArgs {
     #[command(subcommand)]
@@ -61,8 +89,40 @@ pub enum JobCmd {
     Logs(JobLogsArgs),
     /// List files in a job work directory.
     Ls(JobLsArgs),
+    /// Print a file from a job run directory, or a byte range of it.
+    Cat(JobCatArgs),
+    /// Print the first lines of a file from a job run directory.
+    Head(JobHeadArgs),
+    /// Print the last lines of a file from a job run directory.
+    Tail(JobTailArgs),
     /// Retrieve a file or directory from a job run folder.
     Retrieve(JobRetrieveArgs),
+    /// Check uploaded inputs and retrieved outputs against their remote copies.
+    Verify(JobVerifyArgs),
+    /// Report CPU, memory and time-limit efficiency of finished jobs.
+    Efficiency(JobEfficiencyArgs),
+    /// Track jobs that were submitted outside hpc (e.g. with sbatch on the login node).
+    Adopt(JobAdoptArgs),
+    /// Delete the remote run directory of a finished job.
+    Clean(JobCleanArgs),
+    /// Show or edit the labels and note of a job.
+    Label(JobLabelArgs),
+    /// Delete job records from the local database.
+    Rm(JobRmArgs),
+    /// Delete records of finished jobs from the local database, optionally archiving them.
+    Prune(JobPruneArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct JobAdoptArgs {
+    /// Cluster the job runs on.
+    pub cluster: String,
+    /// Scheduler job id to adopt.
+    #[arg(required_unless_present = "all_mine")]
+    pub scheduler_id: Option<i64>,
+    /// Adopt every queued or running job of your cluster user that is not tracked yet.
+    #[arg(long, conflicts_with = "scheduler_id")]
+    pub all_mine: bool,
 }
 
 #[derive(Args, Debug)]
@@ -71,8 +131,6 @@ pub struct JobGetArgs {
     pub job_id: i64,
     #[arg(long)]
     pub cluster: Option<String>,
-    #[arg(long)]
-    pub json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -84,12 +142,280 @@ pub struct JobLogsArgs {
     pub err: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct JobFileArgs {
+    /// Job id from the daemon.
+    pub job_id: i64,
+    /// File to print (absolute or relative to the job root).
+    pub path: String,
+    /// Allow paths outside the job's run directory.
+    #[arg(long)]
+    pub allow_outside: bool,
+    /// Print the file even if it looks binary.
+    #[arg(long)]
+    pub binary: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct JobCatArgs {
+    #[command(flatten)]
+    pub file: JobFileArgs,
+    /// First byte to print; negative values count back from the end of the file.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    pub offset: i64,
+    /// Number of bytes to print (default: up to the end of the file).
+    #[arg(long, value_name = "BYTES")]
+    pub length: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct JobHeadArgs {
+    #[command(flatten)]
+    pub file: JobFileArgs,
+    /// Number of lines to print.
+    #[arg(short = 'n', long, value_name = "N", default_value_t = 10)]
+    pub lines: u32,
+    /// Print the first N bytes instead of lines.
+    #[arg(short = 'c', long, value_name = "N", conflicts_with = "lines")]
+    pub bytes: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct JobTailArgs {
+    #[command(flatten)]
+    pub file: JobFileArgs,
+    /// Number of lines to print.
+    #[arg(short = 'n', long, value_name = "N", default_value_t = 10)]
+    pub lines: u32,
+    /// Print the last N bytes instead of lines.
+    #[arg(short = 'c', long, value_name = "N", conflicts_with = "lines")]
+    pub bytes: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct JobCleanArgs {
+    /// Job id from the daemon. Every job that ran in the same directory must have finished.
+    pub job_id: i64,
+    /// Only show the directory that would be deleted.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct JobLabelArgs {
+    /// Job id from the daemon.
+    pub job_id: i64,
+    /// Labels to add or overwrite. Without any changes the current labels are shown.
+    #[arg(value_name = "KEY=VALUE", value_parser = parse_label)]
+    pub labels: Vec<(String, String)>,
+    /// Drop the label with this key. Repeatable.
+    #[arg(long, value_name = "KEY", action = clap::ArgAction::Append)]
+    pub remove: Vec<String>,
+    /// Replace the note.
+    #[arg(long)]
+    pub note: Option<String>,
+    /// Drop the note.
+    #[arg(long, conflicts_with = "note")]
+    pub clear_note: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct JobRmArgs {
+    /// Job ids from the daemon.
+    #[arg(required = true)]
+    pub job_ids: Vec<i64>,
+    /// Also delete records of jobs that have not finished.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct JobPruneArgs {
+    /// Only jobs that finished before this date (YYYY-MM-DD, UTC) or RFC 3339 time.
+    #[arg(long, value_parser = parse_before)]
+    pub before: String,
+    /// Only jobs that ended in this state (e.g. COMPLETED). Repeatable.
+    #[arg(long = "state", value_name = "STATE", action = clap::ArgAction::Append)]
+    pub states: Vec<String>,
+    /// Only jobs on this cluster.
+    #[arg(long)]
+    pub cluster: Option<String>,
+    /// Append the pruned records, with their labels and notes, to this JSON-lines file
+    /// before deleting them.
+    #[arg(long, value_name = "PATH")]
+    pub archive: Option<PathBuf>,
+    /// Only list the records that would be pruned.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Parse a `--before` value into an RFC 3339 time; a bare date means midnight UTC.
+pub fn parse_before(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let is_date = |s: &str| {
+        s.len() == 10
+            && s.char_indices().all(|(i, c)| {
+                if i == 4 || i == 7 {
+                    c == '-'
+                } else {
+                    c.is_ascii_digit()
+                }
+            })
+    };
+    if is_date(value) {
+        return Ok(format!("{value}T00:00:00Z"));
+    }
+    if value.get(..10).is_some_and(is_date) && value[10..].starts_with(['T', 't']) {
+        return Ok(value.to_string());
+    }
+    Err(format!(
+        "invalid date '{value}'; expected YYYY-MM-DD or an RFC 3339 time"
+    ))
+}
+
+#[derive(Args, Debug)]
+pub struct JobVerifyArgs {
+    /// Job id from the daemon.
+    pub job_id: i64,
+}
+
+#[derive(Args, Debug)]
+pub struct JobEfficiencyArgs {
+    /// Job id from the daemon. Omit and pass --cluster to summarise past jobs.
+    #[arg(required_unless_present = "cluster")]
+    pub job_id: Option<i64>,
+    /// Summarise past jobs on this cluster.
+    #[arg(long, conflicts_with = "job_id")]
+    pub cluster: Option<String>,
+    /// Only include jobs submitted within this window (e.g. 12h, 7d, 2w).
+    #[arg(long, conflicts_with = "job_id", value_parser = parse_since_secs)]
+    pub since: Option<i64>,
+}
+
+/// Parse a window like "30m", "12h", "7d" or "2w" into seconds; a bare number is seconds.
+pub fn parse_since_secs(value: &str) -> Result<i64, String> {
+    let value = value.trim();
+    let (num, unit) = match value.char_indices().last() {
+        Some((idx, c)) if c.is_ascii_alphabetic() => (&value[..idx], c.to_ascii_lowercase()),
+        _ => (value, 's'),
+    };
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return Err(format!("unknown unit '{unit}'; use s, m, h, d or w")),
+    };
+    let num: i64 = num
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}'; expected e.g. 7d"))?;
+    if num < 0 {
+        return Err("duration must not be negative".to_string());
+    }
+    num.checked_mul(multiplier)
+        .ok_or_else(|| "duration is too large".to_string())
+}
+
+/// A point in time given as an age ("2d") or as a date or RFC 3339 time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeBound {
+    Ago(i64),
+    At(String),
+}
+
+impl TimeBound {
+    /// The bound as an RFC 3339 time, with ages counted back from `now`.
+    pub fn resolve(&self, now: OffsetDateTime) -> String {
+        match self {
+            Self::At(at) => at.clone(),
+            Self::Ago(secs) => now
+                .checked_sub(time::Duration::seconds(*secs))
+                .and_then(|at| at.format(&Rfc3339).ok())
+                .unwrap_or_else(|| "0001-01-01T00:00:00Z".to_string()),
+        }
+    }
+}
+
+/// Parse an age like "2d" (see [`parse_since_secs`]), a date or an RFC 3339 time.
+pub fn parse_time_bound(value: &str) -> Result<TimeBound, String> {
+    parse_since_secs(value)
+        .map(TimeBound::Ago)
+        .or_else(|_| parse_before(value).map(TimeBound::At))
+        .map_err(|_| {
+            format!(
+                "invalid time '{}'; expected an age like 2d, a date (YYYY-MM-DD) or an RFC 3339 time",
+                value.trim()
+            )
+        })
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStateFilter {
+    #[value(alias = "queued")]
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JobSortKey {
+    #[default]
+    Created,
+    /// Unfinished jobs first.
+    Finished,
+}
+
 #[derive(Args, Debug)]
 pub struct ListJobsArgs {
     #[arg(long)]
     pub cluster: Option<String>,
+    /// Only jobs carrying this label. Repeatable; all must match.
+    #[arg(
+        long = "label",
+        value_name = "KEY=VALUE",
+        value_parser = parse_label,
+        action = clap::ArgAction::Append
+    )]
+    pub labels: Vec<(String, String)>,
+    /// Only jobs in this state. Repeatable.
+    #[arg(long = "state", value_enum, action = clap::ArgAction::Append)]
+    pub states: Vec<JobStateFilter>,
+    /// Only jobs created at or after this time: an age like 2d, a date or an RFC 3339 time.
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub since: Option<TimeBound>,
+    /// Only jobs created before this time.
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub until: Option<TimeBound>,
+    /// Only jobs that finished at or after this time.
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub finished_since: Option<TimeBound>,
+    /// Only jobs that finished before this time.
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub finished_until: Option<TimeBound>,
+    /// Only jobs submitted from this local directory or one below it.
     #[arg(long)]
-    pub json: bool,
+    pub path: Option<PathBuf>,
+    /// Only the job with this scheduler id.
+    #[arg(long)]
+    pub scheduler_id: Option<i64>,
+    #[arg(long, value_enum, default_value_t = JobSortKey::Created)]
+    pub sort: JobSortKey,
+    /// Reverse the order, e.g. newest first.
+    #[arg(long)]
+    pub reverse: bool,
+    /// Show at most this many jobs and print a cursor for the next page.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub limit: Option<u32>,
+    /// Continue after the page that printed this cursor.
+    #[arg(long)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -97,12 +423,33 @@ pub struct JobRetrieveArgs {
     /// Job id from the daemon.
     pub job_id: i64,
     pub path: String,
-    #[arg(long, help = "Directory where the requested file or directory will be placed.")]
-    pub output: Option<PathBuf>,
-    #[arg(long, help = "Overwrite existing local files.")]
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory where the requested file or directory will be placed."
+    )]
+    pub dest: Option<PathBuf>,
+    /// Overwrite local files that differ from the remote copy.
+    /// Files with the same size and mtime are always skipped, and interrupted
+    /// downloads are resumed.
+    #[arg(long)]
     pub overwrite: bool,
     #[arg(long, help = "Retrieve outputs even if the job has not completed.")]
     pub force: bool,
+    /// Include paths matching PATTERN.
+    /// Same rules as `job submit --include`, matched against paths relative to
+    /// the retrieved directory.
+    #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
+    pub include: Vec<String>,
+    /// Exclude paths matching PATTERN.
+    /// Same rules as `job submit --exclude`, matched against paths relative to
+    /// the retrieved directory.
+    #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
+    pub exclude: Vec<String>,
+    /// Compare SHA-256 checksums of same-sized files whose mtimes differ, and skip them
+    /// (only updating the local mtime) when the contents match.
+    #[arg(long)]
+    pub checksum: bool,
     #[arg(long)]
     pub headless: bool,
 }
@@ -115,12 +462,30 @@ pub struct JobLsArgs {
     pub path: Option<String>,
     #[arg(long)]
     pub cluster: Option<String>,
+    /// Show permissions, size, modification time and symlink targets.
+    #[arg(short = 'l', long)]
+    pub long: bool,
+    /// Show the whole subtree, indented by depth.
+    #[arg(long, conflicts_with = "long")]
+    pub tree: bool,
+    /// Levels to descend into (default: 1, or unlimited with --tree).
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: Option<u32>,
+    /// Only show entries whose name matches the glob (or whose relative path does,
+    /// when the glob contains '/'), plus the directories leading to them.
+    #[arg(long, alias = "pattern", value_name = "GLOB")]
+    pub glob: Option<String>,
 }
 
-#[derive(Args, Debug)]
-pub struct ListClustersArgs {
-    #[arg(long)]
-    pub json: bool,
+impl JobLsArgs {
+    /// Depth sent to the daemon, where 0 means unlimited.
+    pub fn max_depth(&self) -> u32 {
+        match self.depth {
+            Some(depth) => depth,
+            None if self.tree => 0,
+            None => 1,
+        }
+    }
 }
 
 #[derive(Args, Debug)]
@@ -132,7 +497,7 @@ pub struct ClusterArgs {
 #[derive(Subcommand, Debug)]
 pub enum ClusterCmd {
     /// List clusters.
-    List(ListClustersArgs),
+    List,
     /// Show cluster details.
     Get(ClusterGetArgs),
     /// List files on a cluster.
@@ -143,13 +508,98 @@ pub enum ClusterCmd {
     Set(SetClusterArgs),
     /// Delete a cluster and its job records.
     Delete(DeleteClusterArgs),
+    /// Show the cluster-wide Slurm queue.
+    Queue(ClusterQueueArgs),
+    /// Show node and GPU availability per partition.
+    Nodes(ClusterNodesArgs),
+    /// Show your Slurm accounts, allowed QoS and fairshare.
+    Accounts(ClusterAccountsArgs),
+    /// Manage the shared data cache used by `job submit --cache`.
+    Cache(ClusterCacheArgs),
+    /// Show disk usage of the run directories of tracked jobs.
+    Usage(ClusterUsageArgs),
+    /// Delete run directories of finished jobs.
+    Gc(ClusterGcArgs),
 }
 
 #[derive(Args, Debug)]
-pub struct ClusterGetArgs {
+pub struct ClusterUsageArgs {
     pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterGcArgs {
+    pub name: String,
+    /// Only directories whose most recent job finished at least this long ago (e.g. 30d).
+    #[arg(long, value_parser = parse_since_secs)]
+    pub older_than: i64,
+    /// Only directories whose most recent job ended in this state (e.g. COMPLETED).
     #[arg(long)]
-    pub json: bool,
+    pub state: Option<String>,
+    /// Only list the directories that would be deleted.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterCacheArgs {
+    #[command(subcommand)]
+    pub cmd: ClusterCacheCmd,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClusterCacheCmd {
+    /// Delete cached files no run directory uses any more.
+    Gc(ClusterCacheGcArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterCacheGcArgs {
+    pub name: String,
+    /// Only report what would be deleted.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterAccountsArgs {
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterNodesArgs {
+    pub name: String,
+    /// Only nodes in this partition.
+    #[arg(long)]
+    pub partition: Option<String>,
+    /// Also list every node.
+    #[arg(long)]
+    pub nodes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterQueueArgs {
+    pub name: String,
+    /// Only jobs of this user.
+    #[arg(long)]
+    pub user: Option<String>,
+    /// Only jobs of your cluster user.
+    #[arg(long, conflicts_with = "user")]
+    pub mine: bool,
+    /// Only jobs in these partitions (comma separated).
+    #[arg(long)]
+    pub partition: Option<String>,
+    /// Only jobs in these states (comma separated), e.g. PENDING.
+    #[arg(long)]
+    pub state: Option<String>,
+    /// Only pending jobs, with their expected start times.
+    #[arg(long)]
+    pub start: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ClusterGetArgs {
+    pub name: String,
 }
 
 #[derive(Args, Debug)]
@@ -220,6 +670,116 @@ pub struct SubmitArgs {
     /// only (and prunes their contents). Globs support `*`, `?`, `**`, `[]`, `{}`.
     #[arg(long, value_name = "PATTERN", action = clap::ArgAction::Append)]
     pub exclude: Vec<String>,
+    /// Upload paths ignored by .gitignore files and .hpcignore.
+    /// Without this flag, paths not matched by --include/--exclude are checked
+    /// against .hpcignore in the submit root (same syntax as the flags, one rule
+    /// per line: `+ PATTERN` includes, `- PATTERN` or a bare pattern excludes),
+    /// then against .gitignore files; `.git` is never uploaded.
+    #[arg(long)]
+    pub no_ignore_files: bool,
+    /// Show what would be uploaded and the sbatch command that would run, without
+    /// creating remote directories, transferring files or submitting.
+    /// Files count as changed when the remote copy differs in size or mtime.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Compare SHA-256 checksums of same-sized files whose mtimes differ, and skip them
+    /// (only updating the remote mtime and mode) when the contents match.
+    /// With --dry-run, such files are reported unchanged.
+    #[arg(long)]
+    pub checksum: bool,
+    /// Compress the tar stream used to upload many small new files at once with zstd.
+    /// Only takes effect if `zstd` is installed on the cluster.
+    #[arg(long)]
+    pub compress: bool,
+    /// What to do with symbolic links in the submit tree: `skip` leaves them out with a
+    /// warning, `preserve` recreates them on the cluster with the same targets, `follow`
+    /// uploads the files and directories they point to.
+    /// Links pointing outside the submit root are flagged in --dry-run output.
+    #[arg(long, value_enum, default_value_t = SymlinkMode::Skip)]
+    pub symlinks: SymlinkMode,
+    /// Delete files from the remote directory that no longer exist locally.
+    /// Only files an earlier submit to the directory uploaded are deleted, so files the
+    /// jobs created there are kept. Never deletes paths excluded by --exclude or ignore
+    /// files, `slurm-*.out`,
+    /// logs of jobs that ran in the directory, paths the sbatch script's --output and
+    /// --error templates can produce, or paths matching a pattern in .hpcprotect in the
+    /// submit root (same syntax as --include, one per line). Directories are kept.
+    /// With --dry-run, lists the files that would be deleted.
+    #[arg(long)]
+    pub delete: bool,
+    /// Download REMOTE (a file or directory, absolute or relative to the run folder)
+    /// into LOCAL once the job has finished, as `job retrieve --overwrite` would.
+    /// LOCAL defaults to the submitted local path. Repeatable; `job get` shows whether
+    /// the outputs are already local.
+    #[arg(
+        long,
+        value_name = "REMOTE[:LOCAL]",
+        value_parser = parse_fetch_on_complete,
+        action = clap::ArgAction::Append
+    )]
+    pub fetch_on_complete: Vec<FetchOnComplete>,
+    /// Take large input files (1 MiB and up) that are not on the cluster yet from the
+    /// cluster's shared data cache instead of uploading them, and add the ones uploaded.
+    /// Cached files are hard-linked read-only into the run directory (copied when it is on
+    /// another filesystem); `cluster cache gc` removes files no run directory links to.
+    #[arg(long)]
+    pub cache: bool,
+    /// Attach a label to the job, e.g. `--label exp=ablation`. Repeatable; `job list
+    /// --label` filters by them and `job label` edits them later.
+    #[arg(
+        long = "label",
+        value_name = "KEY=VALUE",
+        value_parser = parse_label,
+        action = clap::ArgAction::Append
+    )]
+    pub labels: Vec<(String, String)>,
+    /// Free-form note kept with the job record.
+    #[arg(long)]
+    pub note: Option<String>,
+}
+
+/// An output to download once a job has finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetchOnComplete {
+    pub remote_path: String,
+    pub local_dir: Option<PathBuf>,
+}
+
+/// Parse `REMOTE[:LOCAL]`, splitting at the first ':'.
+pub fn parse_fetch_on_complete(value: &str) -> Result<FetchOnComplete, String> {
+    let (remote_path, local_dir) = match value.split_once(':') {
+        Some((_, "")) => {
+            return Err(format!("missing local directory after ':' in '{value}'"));
+        }
+        Some((remote, local)) => (remote, Some(PathBuf::from(local))),
+        None => (value, None),
+    };
+    if remote_path.trim().is_empty() {
+        return Err(format!("missing remote path in '{value}'"));
+    }
+    Ok(FetchOnComplete {
+        remote_path: remote_path.to_string(),
+        local_dir,
+    })
+}
+
+/// Parse `KEY=VALUE`, splitting at the first '='. Keys must not be empty or contain
+/// whitespace; values may be empty.
+pub fn parse_label(value: &str) -> Result<(String, String), String> {
+    let Some((key, label)) = value.split_once('=') else {
+        return Err(format!("expected KEY=VALUE, got '{value}'"));
+    };
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err(format!("invalid label key in '{value}'"));
+    }
+    Ok((key.to_string(), label.to_string()))
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymlinkMode {
+    Skip,
+    Preserve,
+    Follow,
 }
 
 #[derive(Args, Debug)]
@@ -263,13 +823,320 @@ mod tests {
 
     #[test]
     fn job_retrieve_force_sets_true() {
-        let args = Cli::parse_from(["hpc", "job", "retrieve", "12", "output.txt", "--force"]);
+        let args = Cli::parse_from([
+            "hpc",
+            "job",
+            "retrieve",
+            "12",
+            "output.txt",
+            "--force",
+            "--dest",
+            "out",
+        ]);
         match args.cmd {
             Cmd::Job(job) => match job.cmd {
-                JobCmd::Retrieve(retrieve) => assert!(retrieve.force),
+                JobCmd::Retrieve(retrieve) => {
+                    assert!(retrieve.force);
+                    assert_eq!(retrieve.dest, Some(PathBuf::from("out")));
+                }
                 _ => panic!("expected retrieve command"),
             },
             _ => panic!("expected job command"),
         }
     }
+
+    #[test]
+    fn job_verify_takes_a_job_id() {
+        let args = Cli::parse_from(["hpc", "job", "verify", "12"]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Verify(verify) => assert_eq!(verify.job_id, 12),
+                _ => panic!("expected verify command"),
+            },
+            _ => panic!("expected job command"),
+        }
+        assert!(Cli::try_parse_from(["hpc", "job", "verify"]).is_err());
+    }
+
+    #[test]
+    fn cluster_cache_gc_takes_a_name_and_dry_run() {
+        let args = Cli::parse_from(["hpc", "cluster", "cache", "gc", "alpha", "--dry-run"]);
+        match args.cmd {
+            Cmd::Cluster(cluster) => match cluster.cmd {
+                ClusterCmd::Cache(cache) => match cache.cmd {
+                    ClusterCacheCmd::Gc(gc) => {
+                        assert_eq!(gc.name, "alpha");
+                        assert!(gc.dry_run);
+                    }
+                },
+                _ => panic!("expected cache command"),
+            },
+            _ => panic!("expected cluster command"),
+        }
+    }
+
+    #[test]
+    fn cluster_gc_requires_an_age() {
+        let args = Cli::parse_from([
+            "hpc",
+            "cluster",
+            "gc",
+            "alpha",
+            "--older-than",
+            "30d",
+            "--state",
+            "COMPLETED",
+        ]);
+        match args.cmd {
+            Cmd::Cluster(cluster) => match cluster.cmd {
+                ClusterCmd::Gc(gc) => {
+                    assert_eq!(gc.name, "alpha");
+                    assert_eq!(gc.older_than, 30 * 86400);
+                    assert_eq!(gc.state.as_deref(), Some("COMPLETED"));
+                    assert!(!gc.dry_run);
+                }
+                _ => panic!("expected gc command"),
+            },
+            _ => panic!("expected cluster command"),
+        }
+        assert!(Cli::try_parse_from(["hpc", "cluster", "gc", "alpha"]).is_err());
+    }
+
+    #[test]
+    fn job_prune_requires_a_date_and_collects_states() {
+        let args = Cli::parse_from([
+            "hpc",
+            "job",
+            "prune",
+            "--before",
+            "2026-01-31",
+            "--state",
+            "COMPLETED",
+            "--state",
+            "CANCELLED",
+        ]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Prune(prune) => {
+                    assert_eq!(prune.before, "2026-01-31T00:00:00Z");
+                    assert_eq!(prune.states, ["COMPLETED", "CANCELLED"]);
+                }
+                _ => panic!("expected prune command"),
+            },
+            _ => panic!("expected job command"),
+        }
+        assert!(Cli::try_parse_from(["hpc", "job", "prune"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "prune", "--before", "yesterday"]).is_err());
+        assert_eq!(
+            parse_before("2026-01-31T12:00:00+02:00").unwrap(),
+            "2026-01-31T12:00:00+02:00"
+        );
+        assert!(Cli::try_parse_from(["hpc", "job", "rm"]).is_err());
+    }
+
+    #[test]
+    fn job_label_takes_pairs_and_removals() {
+        let args = Cli::parse_from([
+            "hpc",
+            "job",
+            "label",
+            "7",
+            "exp=ablation",
+            "seed=",
+            "--remove",
+            "lr",
+        ]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Label(label) => {
+                    assert_eq!(label.job_id, 7);
+                    assert_eq!(
+                        label.labels,
+                        [
+                            ("exp".to_string(), "ablation".to_string()),
+                            ("seed".to_string(), String::new())
+                        ]
+                    );
+                    assert_eq!(label.remove, ["lr"]);
+                }
+                _ => panic!("expected label command"),
+            },
+            _ => panic!("expected job command"),
+        }
+        assert!(Cli::try_parse_from(["hpc", "job", "label", "7", "exp"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "list", "--label", "=x"]).is_err());
+        assert!(
+            Cli::try_parse_from(["hpc", "job", "label", "7", "--note", "a", "--clear-note"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn job_efficiency_accepts_job_id_or_cluster() {
+        let args = Cli::parse_from(["hpc", "job", "efficiency", "12"]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Efficiency(eff) => {
+                    assert_eq!(eff.job_id, Some(12));
+                    assert!(eff.cluster.is_none());
+                }
+                _ => panic!("expected efficiency command"),
+            },
+            _ => panic!("expected job command"),
+        }
+
+        let args = Cli::parse_from(["hpc", "job", "efficiency", "--cluster", "c1", "--since", "7d"]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Efficiency(eff) => {
+                    assert_eq!(eff.job_id, None);
+                    assert_eq!(eff.cluster.as_deref(), Some("c1"));
+                    assert_eq!(eff.since, Some(7 * 86400));
+                }
+                _ => panic!("expected efficiency command"),
+            },
+            _ => panic!("expected job command"),
+        }
+
+        assert!(Cli::try_parse_from(["hpc", "job", "efficiency"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "efficiency", "12", "--since", "7d"]).is_err());
+    }
+
+    #[test]
+    fn parse_since_secs_handles_units() {
+        assert_eq!(parse_since_secs("90"), Ok(90));
+        assert_eq!(parse_since_secs("30m"), Ok(1800));
+        assert_eq!(parse_since_secs("12h"), Ok(43200));
+        assert_eq!(parse_since_secs("2W"), Ok(14 * 86400));
+        assert!(parse_since_secs("7y").is_err());
+        assert!(parse_since_secs("d").is_err());
+    }
+
+    #[test]
+    fn parse_fetch_on_complete_splits_at_first_colon() {
+        assert_eq!(
+            parse_fetch_on_complete("results").unwrap(),
+            FetchOnComplete {
+                remote_path: "results".into(),
+                local_dir: None,
+            }
+        );
+        assert_eq!(
+            parse_fetch_on_complete("out/run.log:./logs").unwrap(),
+            FetchOnComplete {
+                remote_path: "out/run.log".into(),
+                local_dir: Some(PathBuf::from("./logs")),
+            }
+        );
+        assert!(parse_fetch_on_complete("results:").is_err());
+        assert!(parse_fetch_on_complete(":./logs").is_err());
+    }
+
+    #[test]
+    fn job_adopt_requires_scheduler_id_or_all_mine() {
+        let args = Cli::parse_from(["hpc", "job", "adopt", "c1", "4242"]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Adopt(adopt) => {
+                    assert_eq!(adopt.cluster, "c1");
+                    assert_eq!(adopt.scheduler_id, Some(4242));
+                    assert!(!adopt.all_mine);
+                }
+                _ => panic!("expected adopt command"),
+            },
+            _ => panic!("expected job command"),
+        }
+
+        let args = Cli::parse_from(["hpc", "job", "adopt", "c1", "--all-mine"]);
+        match args.cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Adopt(adopt) => assert!(adopt.all_mine),
+                _ => panic!("expected adopt command"),
+            },
+            _ => panic!("expected job command"),
+        }
+
+        assert!(Cli::try_parse_from(["hpc", "job", "adopt", "c1"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "adopt", "c1", "1", "--all-mine"]).is_err());
+    }
+
+    #[test]
+    fn cluster_queue_mine_conflicts_with_user() {
+        let args = Cli::parse_from([
+            "hpc", "cluster", "queue", "c1", "--mine", "--state", "PENDING", "--start",
+        ]);
+        match args.cmd {
+            Cmd::Cluster(cluster) => match cluster.cmd {
+                ClusterCmd::Queue(queue) => {
+                    assert_eq!(queue.name, "c1");
+                    assert!(queue.mine);
+                    assert!(queue.start);
+                    assert_eq!(queue.state.as_deref(), Some("PENDING"));
+                    assert_eq!(queue.user, None);
+                }
+                _ => panic!("expected queue command"),
+            },
+            _ => panic!("expected cluster command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["hpc", "cluster", "queue", "c1", "--mine", "--user", "bob"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn output_format_accepts_names_templates_and_json_shorthand() {
+        let args = Cli::parse_from(["hpc", "job", "ls", "7", "-o", "csv"]);
+        assert_eq!(args.output.format(), OutputFormat::Csv);
+
+        let args = Cli::parse_from([
+            "hpc",
+            "job",
+            "list",
+            "--output",
+            "template={{job_id}} {{status}}",
+        ]);
+        assert_eq!(
+            args.output.format(),
+            OutputFormat::Template("{{job_id}} {{status}}".to_string())
+        );
+
+        let args = Cli::parse_from(["hpc", "cluster", "get", "c1", "--json"]);
+        assert_eq!(args.output.format(), OutputFormat::Json);
+
+        assert!(Cli::try_parse_from(["hpc", "job", "logs", "7", "-o", "xml"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "logs", "7", "-o", "template"]).is_err());
+        assert!(
+            Cli::try_parse_from(["hpc", "cluster", "ls", "c1", "--json", "-o", "yaml"]).is_err()
+        );
+    }
+
+    #[test]
+    fn output_format_may_come_before_the_subcommand() {
+        let args = Cli::parse_from(["hpc", "-o", "json", "job", "list"]);
+        assert_eq!(args.output.format(), OutputFormat::Json);
+        assert!(matches!(args.cmd, Cmd::Job(_)));
+
+        let args = Cli::parse_from(["hpc", "--json", "cluster", "ls", "c1"]);
+        assert_eq!(args.output.format(), OutputFormat::Json);
+    }
+
+    #[test]
+    fn job_ls_depth_defaults_follow_tree() {
+        let depth_of = |argv: &[&str]| match Cli::parse_from(argv).cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Ls(ls) => ls.max_depth(),
+                _ => panic!("expected ls command"),
+            },
+            _ => panic!("expected job command"),
+        };
+        assert_eq!(depth_of(&["hpc", "job", "ls", "7"]), 1);
+        assert_eq!(depth_of(&["hpc", "job", "ls", "7", "--tree"]), 0);
+        assert_eq!(depth_of(&["hpc", "job", "ls", "7", "--depth", "2"]), 2);
+        assert_eq!(depth_of(&["hpc", "job", "ls", "7", "-l", "--glob", "*"]), 1);
+
+        assert!(Cli::try_parse_from(["hpc", "job", "ls", "7", "--depth", "0"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "ls", "7", "--long", "--tree"]).is_err());
+    }
 }
@@ -4,18 +4,32 @@
 use crate::errors::{format_server_error, format_status_error};
 use crate::mfa::{clear_transient_mfa, collect_mfa_answers, collect_mfa_answers_transient};
 use crate::stream::{
-    MinDurationSpinner, Spinner, SubmitStreamOutcome, ensure_exit_code, handle_stream_events,
+    MinDurationSpinner, Spinner, SubmitStreamOutcome, TransferProgressBar, ensure_exit_code,
+    handle_stream_events,
     handle_stream_events_with_progress, handle_submit_stream_events, parse_remote_path_failure,
     print_with_green_check_stderr, print_with_red_cross_stderr,
 };
 use anyhow::bail;
 use proto::agent_client::AgentClient;
 use proto::{
-    AddClusterInit, AddClusterRequest, DeleteClusterRequest, DeleteClusterResponse,
-    JobLogsRequest, JobLogsRequestInit, ListClustersRequest, ListClustersResponse, ListJobsRequest,
-    ListJobsResponse, LsRequest, LsRequestInit, ResolveHomeDirRequest, ResolveHomeDirRequestInit,
-    RetrieveJobRequest, RetrieveJobRequestInit, SubmitPathFilterRule, SubmitRequest,
+    AddClusterInit, AddClusterRequest, AdoptJobsRequest, AdoptJobsRequestInit, ArchiveJobsRequest,
+    ArchiveJobsResponse, CacheGcRequest,
+    CacheGcRequestInit, CacheGcResult, CleanRunDirsRequest, CleanRunDirsRequestInit,
+    ClusterAccount,
+    ClusterAccountsRequest, ClusterAccountsRequestInit, ClusterNodesRequest,
+    ClusterNodesRequestInit, ClusterNodesResult, ClusterQueueJob, ClusterQueueRequest,
+    ClusterQueueRequestInit, ClusterUsageRequest, ClusterUsageRequestInit, RunDirUsage,
+    DeleteClusterRequest, DeleteClusterResponse, DeleteJobsRequest, DeleteJobsResponse,
+    JobEfficiencyRequest, JobEfficiencyResponse,
+    JobLogsRequest, JobLogsRequestInit, LabelJobRequest, LabelJobResponse,
+    ListClustersRequest, ListClustersResponse, ListJobsRequest,
+    ListDirRequest, ListDirRequestInit, ListDirResult, ListJobsResponse, LsRequest, LsRequestInit, LsResult, ReadJobFileRequest, ReadJobFileRequestInit, ResolveHomeDirRequest, ResolveHomeDirRequestInit,
+    RetrieveJobRequest, RetrieveJobRequestInit, SubmitRequest, SubmitRequestInit, VerifyJobRequest,
+    VerifyJobRequestInit,
     add_cluster_init, add_cluster_request, list_clusters_unit_response, resolve_home_dir_request,
+    cache_gc_stream_event, cluster_accounts_stream_event, cluster_nodes_stream_event,
+    cluster_queue_stream_event, cluster_usage_stream_event, list_dir_stream_event, ls_stream_event,
+    read_job_file_stream_event,
     resolve_home_dir_request_init, stream_event,
 };
 use std::io::{IsTerminal, Write};
@@ -73,9 +87,8 @@ pub async fn fetch_list_clusters(
 
 pub async fn fetch_list_jobs(
     client: &mut AgentClient<Channel>,
-    cluster: Option<String>,
+    list_jobs_request: ListJobsRequest,
 ) -> anyhow::Result<ListJobsResponse> {
-    let list_jobs_request = ListJobsRequest { name: cluster };
     let response = match timeout(Duration::from_secs(5), client.list_jobs(list_jobs_request)).await
     {
         Ok(Ok(res)) => res.into_inner(),
@@ -89,6 +102,30 @@ pub async fn fetch_list_jobs(
     Ok(response)
 }
 
+pub async fn fetch_job_efficiency(
+    client: &mut AgentClient<Channel>,
+    job_id: Option<i64>,
+    cluster: Option<String>,
+    since_secs: Option<i64>,
+) -> anyhow::Result<JobEfficiencyResponse> {
+    let request = JobEfficiencyRequest {
+        job_id,
+        name: cluster,
+        since_secs,
+    };
+    // May run sacct on the cluster to backfill usage, so allow more than the usual 5s.
+    let response = match timeout(Duration::from_secs(30), client.job_efficiency(request)).await {
+        Ok(Ok(res)) => res.into_inner(),
+        Ok(Err(status)) => {
+            bail!(format_status_error(&status));
+        }
+        Err(e) => {
+            bail!("operation timed out: {}", e)
+        }
+    };
+    Ok(response)
+}
+
 pub async fn send_delete_cluster(
     client: &mut AgentClient<Channel>,
     name: &str,
@@ -113,12 +150,47 @@ pub async fn send_delete_cluster(
     Ok(response)
 }
 
-async fn send_ls_request(
+pub async fn send_delete_jobs(
+    client: &mut AgentClient<Channel>,
+    job_ids: Vec<i64>,
+    force: bool,
+) -> anyhow::Result<DeleteJobsResponse> {
+    let request = DeleteJobsRequest { job_ids, force };
+    match timeout(Duration::from_secs(5), client.delete_jobs(request)).await {
+        Ok(Ok(res)) => Ok(res.into_inner()),
+        Ok(Err(status)) => bail!(format_status_error(&status)),
+        Err(e) => bail!("operation timed out: {}", e),
+    }
+}
+
+pub async fn send_label_job(
+    client: &mut AgentClient<Channel>,
+    request: LabelJobRequest,
+) -> anyhow::Result<LabelJobResponse> {
+    match timeout(Duration::from_secs(5), client.label_job(request)).await {
+        Ok(Ok(res)) => Ok(res.into_inner()),
+        Ok(Err(status)) => bail!(format_status_error(&status)),
+        Err(e) => bail!("operation timed out: {}", e),
+    }
+}
+
+pub async fn send_archive_jobs(
+    client: &mut AgentClient<Channel>,
+    request: ArchiveJobsRequest,
+) -> anyhow::Result<ArchiveJobsResponse> {
+    match timeout(Duration::from_secs(30), client.archive_jobs(request)).await {
+        Ok(Ok(res)) => Ok(res.into_inner()),
+        Ok(Err(status)) => bail!(format_status_error(&status)),
+        Err(e) => bail!("operation timed out: {}", e),
+    }
+}
+
+async fn fetch_ls(
     client: &mut AgentClient<Channel>,
     name: String,
     job_id: Option<i64>,
     path: Option<String>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<LsResult> {
     let (tx_ans, rx_ans) = mpsc::channel::<LsRequest>(16);
     let outbound = ReceiverStream::new(rx_ans);
     tx_ans
@@ -135,45 +207,129 @@ async fn send_ls_request(
         .ls(Request::new(outbound))
         .await
         .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
-    let inbound = response.into_inner();
+    let mut inbound = response.into_inner();
     let tx_mfa = tx_ans.clone();
-    let exit_code = handle_stream_events(inbound, move |answers| {
-        let tx_mfa = tx_mfa.clone();
-        async move {
-            tx_mfa
-                .send(LsRequest {
-                    msg: Some(proto::ls_request::Msg::Mfa(answers)),
-                })
-                .await
-                .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))
+    let mut result = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::LsStreamEvent { event: Some(ev) }) => match ev {
+                ls_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                ls_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                ls_stream_event::Event::Result(listing) => {
+                    result = Some(listing);
+                }
+                ls_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("ls exited with code {code}");
+                    }
+                    break;
+                }
+                ls_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(LsRequest {
+                            msg: Some(proto::ls_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                ls_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::LsStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
         }
-    })
-    .await?;
-    ensure_exit_code(exit_code, "received exit code")
+    }
+    result.ok_or_else(|| anyhow::anyhow!("server closed the stream without a listing"))
 }
 
 pub async fn send_ls(
     client: &mut AgentClient<Channel>,
     name: &str,
     path: &Option<String>,
-) -> anyhow::Result<()> {
-    send_ls_request(client, name.to_owned(), None, path.to_owned()).await
+) -> anyhow::Result<LsResult> {
+    fetch_ls(client, name.to_owned(), None, path.to_owned()).await
 }
 
+/// Lists a job's run directory over SFTP; `max_depth` 0 walks the whole subtree.
 pub async fn send_job_ls(
     client: &mut AgentClient<Channel>,
     job_id: i64,
     path: &Option<String>,
     cluster: &Option<String>,
-) -> anyhow::Result<()> {
-    let name = cluster.clone().unwrap_or_default();
-    send_ls_request(client, name, Some(job_id), path.to_owned()).await
+    max_depth: u32,
+    pattern: &Option<String>,
+) -> anyhow::Result<ListDirResult> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ListDirRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ListDirRequest {
+            msg: Some(proto::list_dir_request::Msg::Init(ListDirRequestInit {
+                name: cluster.clone().unwrap_or_default(),
+                path: path.to_owned(),
+                job_id: Some(job_id),
+                max_depth,
+                pattern: pattern.to_owned(),
+            })),
+        })
+        .await?;
+
+    let response = client
+        .list_dir(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut result = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ListDirStreamEvent { event: Some(ev) }) => match ev {
+                list_dir_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                list_dir_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                list_dir_stream_event::Event::Result(listing) => {
+                    result = Some(listing);
+                }
+                list_dir_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("listing exited with code {code}");
+                    }
+                    break;
+                }
+                list_dir_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ListDirRequest {
+                            msg: Some(proto::list_dir_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                list_dir_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::ListDirStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    result.ok_or_else(|| anyhow::anyhow!("server closed the stream without a listing"))
 }
 
+/// Streams the job's log into `out`; server-side messages still go to stderr.
 pub async fn send_job_logs(
     client: &mut AgentClient<Channel>,
     job_id: i64,
     stderr: bool,
+    out: &mut dyn Write,
 ) -> anyhow::Result<i32> {
     let (tx_ans, rx_ans) = mpsc::channel::<JobLogsRequest>(16);
     let outbound = ReceiverStream::new(rx_ans);
@@ -197,7 +353,7 @@ pub async fn send_job_logs(
         match item {
             Ok(proto::StreamEvent { event: Some(ev) }) => match ev {
                 stream_event::Event::Stdout(bytes) => {
-                    std::io::stdout().write_all(&bytes)?;
+                    out.write_all(&bytes)?;
                 }
                 stream_event::Event::Stderr(bytes) => {
                     std::io::stderr().write_all(&bytes)?;
@@ -222,6 +378,7 @@ pub async fn send_job_logs(
                     exit_code = Some(job_logs_error_exit_code(&err));
                     break;
                 }
+                stream_event::Event::TransferProgress(_) => {}
             },
             Ok(proto::StreamEvent { event: None }) => {}
             Err(status) => {
@@ -235,6 +392,232 @@ pub async fn send_job_logs(
     Ok(exit_code.unwrap_or(0))
 }
 
+/// Streams a slice of a job file into `out`; server-side messages go to stderr.
+pub async fn send_read_job_file(
+    client: &mut AgentClient<Channel>,
+    init: ReadJobFileRequestInit,
+    out: &mut dyn Write,
+) -> anyhow::Result<i32> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ReadJobFileRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ReadJobFileRequest {
+            msg: Some(proto::read_job_file_request::Msg::Init(init)),
+        })
+        .await?;
+
+    let response = client
+        .read_job_file(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut exit_code: Option<i32> = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ReadJobFileStreamEvent { event: Some(ev) }) => match ev {
+                read_job_file_stream_event::Event::Stdout(bytes) => {
+                    out.write_all(&bytes)?;
+                }
+                read_job_file_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                read_job_file_stream_event::Event::Info(_) => {}
+                read_job_file_stream_event::Event::ExitCode(code) => {
+                    exit_code = Some(code);
+                    break;
+                }
+                read_job_file_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ReadJobFileRequest {
+                            msg: Some(proto::read_job_file_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                read_job_file_stream_event::Event::Error(err) => {
+                    if err != "not_found" && err != "invalid_argument" {
+                        eprintln!("{}", format_server_error(&err));
+                    }
+                    exit_code = Some(job_logs_error_exit_code(&err));
+                    break;
+                }
+            },
+            Ok(proto::ReadJobFileStreamEvent { event: None }) => {}
+            Err(status) => {
+                eprintln!("{}", format_status_error(&status));
+                exit_code = Some(1);
+                break;
+            }
+        }
+    }
+    out.flush()?;
+
+    Ok(exit_code.unwrap_or(0))
+}
+
+pub async fn send_job_verify(
+    client: &mut AgentClient<Channel>,
+    job_id: i64,
+) -> anyhow::Result<i32> {
+    let (tx_ans, rx_ans) = mpsc::channel::<VerifyJobRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(VerifyJobRequest {
+            msg: Some(proto::verify_job_request::Msg::Init(VerifyJobRequestInit {
+                job_id,
+            })),
+        })
+        .await?;
+
+    let response = client
+        .verify_job(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut exit_code: Option<i32> = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::StreamEvent { event: Some(ev) }) => match ev {
+                stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                stream_event::Event::ExitCode(code) => {
+                    exit_code = Some(code);
+                    break;
+                }
+                stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(VerifyJobRequest {
+                            msg: Some(proto::verify_job_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                stream_event::Event::Error(err) => {
+                    eprintln!("{}", format_server_error(&err));
+                    exit_code = Some(job_logs_error_exit_code(&err));
+                    break;
+                }
+                stream_event::Event::TransferProgress(_) => {}
+            },
+            Ok(proto::StreamEvent { event: None }) => {}
+            Err(status) => {
+                eprintln!("{}", format_status_error(&status));
+                exit_code = Some(1);
+                break;
+            }
+        }
+    }
+
+    Ok(exit_code.unwrap_or(0))
+}
+
+pub async fn send_clean_run_dirs(
+    client: &mut AgentClient<Channel>,
+    init: CleanRunDirsRequestInit,
+) -> anyhow::Result<i32> {
+    let (tx_ans, rx_ans) = mpsc::channel::<CleanRunDirsRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(CleanRunDirsRequest {
+            msg: Some(proto::clean_run_dirs_request::Msg::Init(init)),
+        })
+        .await?;
+
+    let response = client
+        .clean_run_dirs(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut exit_code: Option<i32> = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::StreamEvent { event: Some(ev) }) => match ev {
+                stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                stream_event::Event::ExitCode(code) => {
+                    exit_code = Some(code);
+                    break;
+                }
+                stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(CleanRunDirsRequest {
+                            msg: Some(proto::clean_run_dirs_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                stream_event::Event::Error(err) => {
+                    eprintln!("{}", format_server_error(&err));
+                    exit_code = Some(job_logs_error_exit_code(&err));
+                    break;
+                }
+                stream_event::Event::TransferProgress(_) => {}
+            },
+            Ok(proto::StreamEvent { event: None }) => {}
+            Err(status) => {
+                eprintln!("{}", format_status_error(&status));
+                exit_code = Some(1);
+                break;
+            }
+        }
+    }
+
+    Ok(exit_code.unwrap_or(0))
+}
+
+pub async fn send_job_adopt(
+    client: &mut AgentClient<Channel>,
+    name: &str,
+    scheduler_id: Option<i64>,
+    all_mine: bool,
+) -> anyhow::Result<i32> {
+    let (tx_ans, rx_ans) = mpsc::channel::<AdoptJobsRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(AdoptJobsRequest {
+            msg: Some(proto::adopt_jobs_request::Msg::Init(AdoptJobsRequestInit {
+                name: name.to_string(),
+                scheduler_id,
+                all_mine,
+            })),
+        })
+        .await?;
+
+    let response = client
+        .adopt_jobs(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let exit_code = handle_stream_events(inbound, move |answers| {
+        let tx_mfa = tx_mfa.clone();
+        async move {
+            tx_mfa
+                .send(AdoptJobsRequest {
+                    msg: Some(proto::adopt_jobs_request::Msg::Mfa(answers)),
+                })
+                .await
+                .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))
+        }
+    })
+    .await?;
+    Ok(exit_code.unwrap_or(0))
+}
+
 fn job_logs_error_exit_code(err: &str) -> i32 {
     match err {
         "invalid_argument" => 2,
@@ -304,15 +687,15 @@ fn resolve_retrieve_local_target(path: &str, local_base: &Path) -> PathBuf {
     }
 }
 
+/// Retrieves `init.path` into `output` (the working directory by default); the local path
+/// of `init` is filled in here.
 pub async fn send_job_retrieve(
     client: &mut AgentClient<Channel>,
-    job_id: i64,
-    path: &str,
+    mut init: RetrieveJobRequestInit,
     output: &Option<PathBuf>,
-    overwrite: bool,
-    force: bool,
     headless: bool,
 ) -> anyhow::Result<i32> {
+    let path = init.path.as_str();
     let display_name = Path::new(path)
         .file_name()
         .and_then(|name| name.to_str())
@@ -327,22 +710,14 @@ pub async fn send_job_retrieve(
     if !local_base.is_absolute() {
         local_base = std::env::current_dir()?.join(local_base);
     }
-    let local_path = local_base.to_string_lossy().into_owned();
     let local_target = resolve_retrieve_local_target(path, &local_base);
+    init.local_path = Some(local_base.to_string_lossy().into_owned());
 
     let (tx_ans, rx_ans) = mpsc::channel::<RetrieveJobRequest>(16);
     let outbound = ReceiverStream::new(rx_ans);
     tx_ans
         .send(RetrieveJobRequest {
-            msg: Some(proto::retrieve_job_request::Msg::Init(
-                RetrieveJobRequestInit {
-                    job_id,
-                    path: path.to_owned(),
-                    local_path: Some(local_path),
-                    overwrite,
-                    force,
-                },
-            )),
+            msg: Some(proto::retrieve_job_request::Msg::Init(init)),
         })
         .await?;
 
@@ -370,7 +745,16 @@ pub async fn send_job_retrieve(
     let mut inbound = response.into_inner();
     let tx_mfa = tx_ans.clone();
     let mut exit_code: Option<i32> = None;
+    let mut progress_bar = TransferProgressBar::new(headless);
     while let Some(item) = inbound.next().await {
+        if !matches!(
+            item,
+            Ok(proto::StreamEvent {
+                event: Some(stream_event::Event::TransferProgress(_))
+            })
+        ) {
+            progress_bar.finish();
+        }
         match item {
             Ok(proto::StreamEvent { event: Some(ev) }) => match ev {
                 stream_event::Event::Stdout(bytes) => {
@@ -420,6 +804,12 @@ pub async fn send_job_retrieve(
                     exit_code = Some(job_retrieve_error_exit_code(&err));
                     break;
                 }
+                stream_event::Event::TransferProgress(progress) => {
+                    if let Some(spinner) = spinner.take() {
+                        spinner.stop(None).await;
+                    }
+                    progress_bar.update(&progress)?;
+                }
             },
             Ok(proto::StreamEvent { event: None }) => {}
             Err(status) => {
@@ -436,6 +826,7 @@ pub async fn send_job_retrieve(
     if let Some(spinner) = spinner.take() {
         spinner.stop(None).await;
     }
+    progress_bar.finish();
 
     let code = exit_code.unwrap_or(0);
     if code == 0 {
@@ -444,30 +835,318 @@ pub async fn send_job_retrieve(
     Ok(code)
 }
 
-pub async fn send_submit(
+pub async fn fetch_cluster_queue(
+    client: &mut AgentClient<Channel>,
+    init: ClusterQueueRequestInit,
+) -> anyhow::Result<Vec<ClusterQueueJob>> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ClusterQueueRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ClusterQueueRequest {
+            msg: Some(proto::cluster_queue_request::Msg::Init(init)),
+        })
+        .await?;
+
+    let response = client
+        .cluster_queue(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut jobs = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ClusterQueueStreamEvent { event: Some(ev) }) => match ev {
+                cluster_queue_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                cluster_queue_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                cluster_queue_stream_event::Event::Result(result) => {
+                    jobs = Some(result.jobs);
+                }
+                cluster_queue_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("squeue exited with code {code}");
+                    }
+                    break;
+                }
+                cluster_queue_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ClusterQueueRequest {
+                            msg: Some(proto::cluster_queue_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                cluster_queue_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::ClusterQueueStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    jobs.ok_or_else(|| anyhow::anyhow!("server closed the stream without a queue listing"))
+}
+
+pub async fn fetch_cluster_nodes(
     client: &mut AgentClient<Channel>,
     name: &str,
-    local_path: &str,
-    remote_path: &Option<String>,
-    new_directory: bool,
-    force: bool,
-    sbatchscript: &str,
-    filters: &[SubmitPathFilterRule],
+    partition: Option<String>,
+) -> anyhow::Result<ClusterNodesResult> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ClusterNodesRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ClusterNodesRequest {
+            msg: Some(proto::cluster_nodes_request::Msg::Init(ClusterNodesRequestInit {
+                name: name.to_string(),
+                partition,
+            })),
+        })
+        .await?;
+
+    let response = client
+        .cluster_nodes(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut result = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ClusterNodesStreamEvent { event: Some(ev) }) => match ev {
+                cluster_nodes_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                cluster_nodes_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                cluster_nodes_stream_event::Event::Result(nodes) => {
+                    result = Some(nodes);
+                }
+                cluster_nodes_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("scontrol exited with code {code}");
+                    }
+                    break;
+                }
+                cluster_nodes_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ClusterNodesRequest {
+                            msg: Some(proto::cluster_nodes_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                cluster_nodes_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::ClusterNodesStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    result.ok_or_else(|| anyhow::anyhow!("server closed the stream without a node listing"))
+}
+
+pub async fn fetch_cluster_accounts(
+    client: &mut AgentClient<Channel>,
+    name: &str,
+) -> anyhow::Result<Vec<ClusterAccount>> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ClusterAccountsRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ClusterAccountsRequest {
+            msg: Some(proto::cluster_accounts_request::Msg::Init(
+                ClusterAccountsRequestInit {
+                    name: name.to_string(),
+                },
+            )),
+        })
+        .await?;
+
+    let response = client
+        .cluster_accounts(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut accounts = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ClusterAccountsStreamEvent { event: Some(ev) }) => match ev {
+                cluster_accounts_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                cluster_accounts_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                cluster_accounts_stream_event::Event::Result(result) => {
+                    accounts = Some(result.accounts);
+                }
+                cluster_accounts_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("sacctmgr exited with code {code}");
+                    }
+                    break;
+                }
+                cluster_accounts_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ClusterAccountsRequest {
+                            msg: Some(proto::cluster_accounts_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                cluster_accounts_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::ClusterAccountsStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    accounts.ok_or_else(|| anyhow::anyhow!("server closed the stream without an account listing"))
+}
+
+pub async fn fetch_cluster_usage(
+    client: &mut AgentClient<Channel>,
+    name: &str,
+) -> anyhow::Result<Vec<RunDirUsage>> {
+    let (tx_ans, rx_ans) = mpsc::channel::<ClusterUsageRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(ClusterUsageRequest {
+            msg: Some(proto::cluster_usage_request::Msg::Init(
+                ClusterUsageRequestInit {
+                    name: name.to_string(),
+                },
+            )),
+        })
+        .await?;
+
+    let response = client
+        .cluster_usage(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut dirs = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::ClusterUsageStreamEvent { event: Some(ev) }) => match ev {
+                cluster_usage_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                cluster_usage_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                cluster_usage_stream_event::Event::Result(result) => {
+                    dirs = Some(result.dirs);
+                }
+                cluster_usage_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("du exited with code {code}");
+                    }
+                    break;
+                }
+                cluster_usage_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(ClusterUsageRequest {
+                            msg: Some(proto::cluster_usage_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                cluster_usage_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::ClusterUsageStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    dirs.ok_or_else(|| anyhow::anyhow!("server closed the stream without a usage listing"))
+}
+
+pub async fn fetch_cache_gc(
+    client: &mut AgentClient<Channel>,
+    name: &str,
+    dry_run: bool,
+) -> anyhow::Result<CacheGcResult> {
+    let (tx_ans, rx_ans) = mpsc::channel::<CacheGcRequest>(16);
+    let outbound = ReceiverStream::new(rx_ans);
+    tx_ans
+        .send(CacheGcRequest {
+            msg: Some(proto::cache_gc_request::Msg::Init(CacheGcRequestInit {
+                name: name.to_string(),
+                dry_run,
+            })),
+        })
+        .await?;
+
+    let response = client
+        .cache_gc(Request::new(outbound))
+        .await
+        .map_err(|status| anyhow::Error::msg(format_status_error(&status)))?;
+    let mut inbound = response.into_inner();
+    let tx_mfa = tx_ans.clone();
+    let mut result = None;
+    while let Some(item) = inbound.next().await {
+        match item {
+            Ok(proto::CacheGcStreamEvent { event: Some(ev) }) => match ev {
+                cache_gc_stream_event::Event::Stdout(bytes) => {
+                    std::io::stdout().write_all(&bytes)?;
+                }
+                cache_gc_stream_event::Event::Stderr(bytes) => {
+                    std::io::stderr().write_all(&bytes)?;
+                }
+                cache_gc_stream_event::Event::Result(v) => {
+                    result = Some(v);
+                }
+                cache_gc_stream_event::Event::ExitCode(code) => {
+                    if code != 0 {
+                        bail!("cache cleanup exited with code {code}");
+                    }
+                    break;
+                }
+                cache_gc_stream_event::Event::Mfa(mfa) => {
+                    let answers = collect_mfa_answers(&mfa).await?;
+                    tx_mfa
+                        .send(CacheGcRequest {
+                            msg: Some(proto::cache_gc_request::Msg::Mfa(answers)),
+                        })
+                        .await
+                        .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))?;
+                }
+                cache_gc_stream_event::Event::Error(err) => {
+                    bail!(format_server_error(&err));
+                }
+            },
+            Ok(proto::CacheGcStreamEvent { event: None }) => {}
+            Err(status) => bail!(format_status_error(&status)),
+        }
+    }
+    result.ok_or_else(|| anyhow::anyhow!("server closed the stream without a cleanup result"))
+}
+
+pub async fn send_submit(
+    client: &mut AgentClient<Channel>,
+    init: SubmitRequestInit,
+    headless: bool,
 ) -> anyhow::Result<()> {
     // outgoing stream client -> server with MFA answers
     let (tx_ans, rx_ans) = mpsc::channel::<SubmitRequest>(16);
     let outbound = ReceiverStream::new(rx_ans);
     tx_ans
         .send(SubmitRequest {
-            msg: Some(proto::submit_request::Msg::Init(proto::SubmitRequestInit {
-                local_path: local_path.to_owned(),
-                remote_path: remote_path.to_owned(),
-                name: name.to_owned(),
-                sbatchscript: sbatchscript.to_owned(),
-                filters: filters.to_vec(),
-                new_directory,
-                force,
-            })),
+            msg: Some(proto::submit_request::Msg::Init(init)),
         })
         .await?;
     // Start Submit RPC
@@ -485,17 +1164,21 @@ pub async fn send_submit(
     };
     let inbound = response.into_inner();
     let tx_mfa = tx_ans.clone();
-    let outcome = handle_submit_stream_events(inbound, move |answers| {
-        let tx_mfa = tx_mfa.clone();
-        async move {
-            tx_mfa
-                .send(SubmitRequest {
-                    msg: Some(proto::submit_request::Msg::Mfa(answers)),
-                })
-                .await
-                .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))
-        }
-    })
+    let outcome = handle_submit_stream_events(
+        inbound,
+        move |answers| {
+            let tx_mfa = tx_mfa.clone();
+            async move {
+                tx_mfa
+                    .send(SubmitRequest {
+                        msg: Some(proto::submit_request::Msg::Mfa(answers)),
+                    })
+                    .await
+                    .map_err(|_| anyhow::anyhow!("server closed while sending MFA answers"))
+            }
+        },
+        headless,
+    )
     .await?;
     match outcome {
         SubmitStreamOutcome::Completed(exit_code) => {
@@ -569,16 +1252,16 @@ pub async fn validate_cluster_live(
     let mut stderr = Vec::new();
     while let Some(item) = inbound.next().await {
         match item {
-            Ok(proto::StreamEvent { event: Some(ev) }) => match ev {
-                stream_event::Event::Stdout(_) => {}
-                stream_event::Event::Stderr(bytes) => {
+            Ok(proto::LsStreamEvent { event: Some(ev) }) => match ev {
+                ls_stream_event::Event::Stdout(_) | ls_stream_event::Event::Result(_) => {}
+                ls_stream_event::Event::Stderr(bytes) => {
                     stderr.extend_from_slice(&bytes);
                 }
-                stream_event::Event::ExitCode(code) => {
+                ls_stream_event::Event::ExitCode(code) => {
                     exit_code = Some(code);
                     break;
                 }
-                stream_event::Event::Mfa(mfa) => {
+                ls_stream_event::Event::Mfa(mfa) => {
                     if let Some(spinner) = spinner.take() {
                         spinner.stop(None).await;
                     }
@@ -593,14 +1276,14 @@ pub async fn validate_cluster_live(
                     clear_transient_mfa(lines.saturating_add(1))?;
                     spinner = Some(Spinner::start(&check_message));
                 }
-                stream_event::Event::Error(err) => {
+                ls_stream_event::Event::Error(err) => {
                     if let Some(spinner) = spinner.take() {
                         spinner.stop(None).await;
                     }
                     return Err(anyhow::anyhow!(format_server_error(&err)));
                 }
             },
-            Ok(proto::StreamEvent { event: None }) => {}
+            Ok(proto::LsStreamEvent { event: None }) => {}
             Err(status) => {
                 if let Some(spinner) = spinner.take() {
                     spinner.stop(None).await;
@@ -809,7 +1492,7 @@ pub async fn send_resolve_home_dir(
                     }
                     bail!(format_server_error(&err));
                 }
-                None => {}
+                Some(stream_event::Event::TransferProgress(_)) | None => {}
             },
             Err(status) => {
                 if let Some(spinner) = connect_spinner.take() {
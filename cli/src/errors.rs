@@ -14,6 +14,7 @@ const CANCELED: &str = "canceled";
 const REMOTE_ERROR: &str = "remote_error";
 const LOCAL_ERROR: &str = "local_error";
 const PERMISSION_DENIED: &str = "permission_denied";
+const ACCOUNTING_UNAVAILABLE: &str = "accounting_unavailable";
 
 pub fn format_server_error(raw: &str) -> String {
     if let Some(message) = describe_error_code(raw) {
@@ -64,6 +65,9 @@ fn describe_error_code(code: &str) -> Option<&'static str> {
         REMOTE_ERROR => Some("Remote operation failed."),
         LOCAL_ERROR => Some("Local operation failed."),
         PERMISSION_DENIED => Some("Permission denied."),
+        ACCOUNTING_UNAVAILABLE => Some(
+            "Slurm accounting (sacct) is not available on this cluster.",
+        ),
         _ => None,
     }
 }
@@ -1,16 +1,141 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+use std::path::{Path, PathBuf};
+
 use clap::ArgMatches;
-use proto::{SubmitPathFilterAction, SubmitPathFilterRule};
+use proto::{
+    JobLabel, ListJobsRequest, ReadJobFileRequestInit, SubmitPathFilterAction,
+    SubmitPathFilterRule, SubmitSymlinkPolicy, list_jobs_request,
+};
+use time::OffsetDateTime;
+
+use crate::args::{
+    JobCatArgs, JobFileArgs, JobHeadArgs, JobSortKey, JobStateFilter, JobTailArgs, ListJobsArgs,
+    SymlinkMode,
+};
+
+pub fn submit_symlink_policy(mode: SymlinkMode) -> SubmitSymlinkPolicy {
+    match mode {
+        SymlinkMode::Skip => SubmitSymlinkPolicy::SkipSymlinks,
+        SymlinkMode::Preserve => SubmitSymlinkPolicy::PreserveSymlinks,
+        SymlinkMode::Follow => SubmitSymlinkPolicy::FollowSymlinks,
+    }
+}
+
+pub fn job_labels(labels: Vec<(String, String)>) -> Vec<JobLabel> {
+    labels
+        .into_iter()
+        .map(|(key, value)| JobLabel { key, value })
+        .collect()
+}
+
+/// The `ListJobs` request of `hpc job list`, with ages counted back from `now` and
+/// `--path` resolved against `cwd`.
+pub fn list_jobs_request(args: ListJobsArgs, now: OffsetDateTime, cwd: &Path) -> ListJobsRequest {
+    let states = args
+        .states
+        .iter()
+        .map(|state| match state {
+            JobStateFilter::Pending => list_jobs_request::State::Pending,
+            JobStateFilter::Running => list_jobs_request::State::Running,
+            JobStateFilter::Completed => list_jobs_request::State::Completed,
+            JobStateFilter::Failed => list_jobs_request::State::Failed,
+        } as i32)
+        .collect();
+    let sort = match args.sort {
+        JobSortKey::Created => list_jobs_request::Sort::Created,
+        JobSortKey::Finished => list_jobs_request::Sort::Finished,
+    };
+    let local_path_prefix = args.path.map(|path| {
+        let path = cwd.join(PathBuf::from(
+            shellexpand::tilde(&path.to_string_lossy()).as_ref(),
+        ));
+        // Submitted paths are canonical; keep the path as given if it no longer exists.
+        path.canonicalize().unwrap_or(path).display().to_string()
+    });
+    ListJobsRequest {
+        name: args.cluster,
+        labels: job_labels(args.labels),
+        states,
+        created_after: args.since.map(|at| at.resolve(now)),
+        created_before: args.until.map(|at| at.resolve(now)),
+        finished_after: args.finished_since.map(|at| at.resolve(now)),
+        finished_before: args.finished_until.map(|at| at.resolve(now)),
+        local_path_prefix,
+        scheduler_id: args.scheduler_id,
+        sort: sort as i32,
+        descending: args.reverse,
+        limit: args.limit,
+        cursor: args.cursor,
+    }
+}
+
+fn read_job_file_request(file: JobFileArgs) -> ReadJobFileRequestInit {
+    ReadJobFileRequestInit {
+        job_id: file.job_id,
+        path: file.path,
+        allow_outside: file.allow_outside,
+        allow_binary: file.binary,
+        ..Default::default()
+    }
+}
+
+pub fn job_cat_request(args: JobCatArgs) -> ReadJobFileRequestInit {
+    ReadJobFileRequestInit {
+        offset: args.offset,
+        length: args.length,
+        ..read_job_file_request(args.file)
+    }
+}
+
+/// `-c N` is the byte range `[0, N)`; otherwise the first `-n` lines.
+pub fn job_head_request(args: JobHeadArgs) -> ReadJobFileRequestInit {
+    match args.bytes {
+        Some(bytes) => ReadJobFileRequestInit {
+            length: Some(bytes),
+            ..read_job_file_request(args.file)
+        },
+        None => ReadJobFileRequestInit {
+            head_lines: Some(args.lines),
+            ..read_job_file_request(args.file)
+        },
+    }
+}
+
+/// `-c N` is the last N bytes; otherwise the last `-n` lines.
+pub fn job_tail_request(args: JobTailArgs) -> ReadJobFileRequestInit {
+    match args.bytes {
+        Some(bytes) => ReadJobFileRequestInit {
+            offset: -i64::try_from(bytes).unwrap_or(i64::MAX),
+            ..read_job_file_request(args.file)
+        },
+        None => ReadJobFileRequestInit {
+            tail_lines: Some(args.lines),
+            ..read_job_file_request(args.file)
+        },
+    }
+}
 
 pub fn submit_filters_from_matches(matches: &ArgMatches) -> Vec<SubmitPathFilterRule> {
+    job_filters_from_matches(matches, "submit")
+}
+
+pub fn retrieve_filters_from_matches(matches: &ArgMatches) -> Vec<SubmitPathFilterRule> {
+    job_filters_from_matches(matches, "retrieve")
+}
+
+/// Collects `--include`/`--exclude` of `hpc job <subcommand>` in command-line order.
+fn job_filters_from_matches(matches: &ArgMatches, subcommand: &str) -> Vec<SubmitPathFilterRule> {
     let Some(("job", job_matches)) = matches.subcommand() else {
         return Vec::new();
     };
-    let Some(("submit", sub_matches)) = job_matches.subcommand() else {
+    let Some((name, sub_matches)) = job_matches.subcommand() else {
         return Vec::new();
     };
+    if name != subcommand {
+        return Vec::new();
+    }
 
     let mut ordered: Vec<(usize, SubmitPathFilterAction, String)> = Vec::new();
     let mut push_rules = |arg: &str, action: SubmitPathFilterAction| {
@@ -75,4 +200,126 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn list_jobs_request_resolves_ages_and_paths() {
+        use crate::args::{Cmd, JobCmd};
+        use clap::Parser;
+        use time::format_description::well_known::Rfc3339;
+
+        let cli = Cli::parse_from([
+            "hpc",
+            "job",
+            "list",
+            "--state",
+            "failed",
+            "--state",
+            "queued",
+            "--since",
+            "2d",
+            "--finished-until",
+            "2026-03-01",
+            "--path",
+            "missing/project",
+            "--limit",
+            "20",
+            "--reverse",
+        ]);
+        let Cmd::Job(job) = cli.cmd else {
+            panic!("expected job command");
+        };
+        let JobCmd::List(args) = job.cmd else {
+            panic!("expected list command");
+        };
+        let now = OffsetDateTime::parse("2026-03-10T12:00:00Z", &Rfc3339).unwrap();
+        let request = list_jobs_request(args, now, Path::new("/nonexistent-cwd"));
+        assert_eq!(
+            request.states,
+            [
+                list_jobs_request::State::Failed as i32,
+                list_jobs_request::State::Pending as i32
+            ]
+        );
+        assert_eq!(
+            request.created_after.as_deref(),
+            Some("2026-03-08T12:00:00Z")
+        );
+        assert_eq!(
+            request.finished_before.as_deref(),
+            Some("2026-03-01T00:00:00Z")
+        );
+        assert_eq!(
+            request.local_path_prefix.as_deref(),
+            Some("/nonexistent-cwd/missing/project")
+        );
+        assert_eq!(request.limit, Some(20));
+        assert!(request.descending);
+        assert_eq!(request.sort, list_jobs_request::Sort::Created as i32);
+        assert!(Cli::try_parse_from(["hpc", "job", "list", "--limit", "0"]).is_err());
+        assert!(Cli::try_parse_from(["hpc", "job", "list", "--since", "soon"]).is_err());
+    }
+
+    #[test]
+    fn retrieve_filters_are_read_from_the_retrieve_command() {
+        let matches = Cli::command().get_matches_from([
+            "hpc",
+            "job",
+            "retrieve",
+            "12",
+            "results",
+            "--exclude",
+            "*.tmp",
+            "--include",
+            "keep.tmp",
+        ]);
+        assert!(submit_filters_from_matches(&matches).is_empty());
+        let patterns: Vec<String> = retrieve_filters_from_matches(&matches)
+            .into_iter()
+            .map(|f| f.pattern)
+            .collect();
+        assert_eq!(patterns, vec!["*.tmp", "keep.tmp"]);
+    }
+
+    #[test]
+    fn job_file_commands_select_ranges_and_lines() {
+        use crate::args::{Cmd, JobCmd};
+        use clap::Parser;
+
+        let request = |argv: &[&str]| match Cli::parse_from(argv).cmd {
+            Cmd::Job(job) => match job.cmd {
+                JobCmd::Cat(args) => job_cat_request(args),
+                JobCmd::Head(args) => job_head_request(args),
+                JobCmd::Tail(args) => job_tail_request(args),
+                _ => panic!("expected a job file command"),
+            },
+            _ => panic!("expected job command"),
+        };
+
+        let cat = request(&["hpc", "job", "cat", "7", "results/metrics.json"]);
+        assert_eq!(cat.job_id, 7);
+        assert_eq!(cat.path, "results/metrics.json");
+        assert_eq!((cat.offset, cat.length), (0, None));
+        assert!(!cat.allow_outside && !cat.allow_binary);
+
+        let cat = request(&[
+            "hpc", "job", "cat", "7", "out.bin", "--offset", "-64", "--length", "16", "--binary",
+        ]);
+        assert_eq!((cat.offset, cat.length), (-64, Some(16)));
+        assert!(cat.allow_binary);
+
+        let head = request(&["hpc", "job", "head", "7", "slurm.out", "--allow-outside"]);
+        assert_eq!(head.head_lines, Some(10));
+        assert!(head.allow_outside);
+        let head = request(&["hpc", "job", "head", "7", "slurm.out", "-c", "100"]);
+        assert_eq!((head.head_lines, head.length), (None, Some(100)));
+
+        let tail = request(&["hpc", "job", "tail", "7", "slurm.out", "-n", "3"]);
+        assert_eq!((tail.tail_lines, tail.offset), (Some(3), 0));
+        let tail = request(&["hpc", "job", "tail", "7", "slurm.out", "--bytes", "100"]);
+        assert_eq!((tail.tail_lines, tail.offset), (None, -100));
+
+        assert!(
+            Cli::try_parse_from(["hpc", "job", "tail", "7", "x", "-n", "3", "-c", "9"]).is_err()
+        );
+    }
 }
@@ -1,8 +1,15 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
-use proto::{ListClustersUnitResponse, ListJobsUnitResponse};
+use proto::{
+    ClusterAccount, ClusterNode, RunDirUsage, ClusterNodesResult, ClusterQueueJob, GpuTypeCapacity, PartitionCapacity, JobEfficiencyUnitResponse, ListClustersUnitResponse, ListJobsUnitResponse,
+    DirEntry, JobFetchStatus, JobLabel, ListDirResult, LsEntry, SubmitDryRunResult, TransferProgress, job_fetch_status,
+    ls_entry, submit_dry_run_item,
+    submit_dry_run_symlink,
+};
 use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 
 pub fn cluster_host_string(item: &ListClustersUnitResponse) -> String {
     match item.host {
@@ -57,13 +64,195 @@ pub fn job_to_json(item: &ListJobsUnitResponse) -> serde_json::Value {
         "created_at": item.created_at.as_str(),
         "finished_at": item.finished_at.as_deref(),
         "scheduler_id": item.scheduler_id,
+        "origin": item.origin.as_str(),
+        "cleaned_at": item.cleaned_at.as_deref(),
+        "job_name": item.job_name.as_deref(),
+        "note": item.note.as_deref(),
+        "labels": item
+            .labels
+            .iter()
+            .map(|label| (label.key.clone(), json!(label.value)))
+            .collect::<serde_json::Map<_, _>>(),
+        "fetch_on_complete": item
+            .fetch_on_complete
+            .iter()
+            .map(|rule| json!({
+                "remote_path": rule.remote_path.as_str(),
+                "local_dir": rule.local_dir.as_str(),
+                "state": fetch_state_label(rule),
+                "error": rule.error.as_deref(),
+                "fetched_at": rule.fetched_at.as_deref(),
+            }))
+            .collect::<Vec<_>>(),
     })
 }
 
+fn fetch_state_label(rule: &JobFetchStatus) -> &'static str {
+    match job_fetch_status::State::try_from(rule.state) {
+        Ok(job_fetch_status::State::Pending) => "pending",
+        Ok(job_fetch_status::State::Running) => "running",
+        Ok(job_fetch_status::State::Done) => "done",
+        Ok(job_fetch_status::State::Failed) => "failed",
+        _ => "unknown",
+    }
+}
+
 pub fn format_json(value: serde_json::Value) -> anyhow::Result<String> {
     Ok(serde_json::to_string_pretty(&value)?)
 }
 
+/// How listing and report commands print their result (`-o/--output`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    /// One compact JSON document per row.
+    Jsonl,
+    Yaml,
+    Csv,
+    Tsv,
+    /// A tera template rendered once per row, e.g. `{{job_id}} {{status}}`.
+    Template(String),
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(template) = value.strip_prefix("template=") {
+            if template.trim().is_empty() {
+                return Err("template is empty; use template='{{field}} ...'".to_string());
+            }
+            return Ok(Self::Template(template.to_string()));
+        }
+        match value {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "yaml" => Ok(Self::Yaml),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "template" => Err("template needs a body: template='{{field}} ...'".to_string()),
+            other => Err(format!(
+                "unknown output format '{other}'; expected table, json, jsonl, yaml, csv, tsv or template=TEMPLATE"
+            )),
+        }
+    }
+}
+
+/// A command's result in the shapes the structured output formats need.
+#[derive(Clone, Debug)]
+pub struct Records {
+    /// Rendered whole by `json` and `yaml`.
+    pub document: serde_json::Value,
+    /// Rendered one per line by `jsonl`, `csv`, `tsv` and templates.
+    pub rows: Vec<serde_json::Value>,
+}
+
+impl Records {
+    pub fn list(rows: Vec<serde_json::Value>) -> Self {
+        Self {
+            document: serde_json::Value::Array(rows.clone()),
+            rows,
+        }
+    }
+
+    pub fn one(item: serde_json::Value) -> Self {
+        Self {
+            rows: vec![item.clone()],
+            document: item,
+        }
+    }
+}
+
+/// Renders `records` in `format`; `table` is only called for [`OutputFormat::Table`].
+pub fn format_output(
+    format: &OutputFormat,
+    records: &Records,
+    table: impl FnOnce() -> String,
+) -> anyhow::Result<String> {
+    match format {
+        OutputFormat::Table => Ok(table()),
+        OutputFormat::Json => Ok(format!("{}\n", format_json(records.document.clone())?)),
+        OutputFormat::Jsonl => {
+            let mut output = String::new();
+            for row in &records.rows {
+                output.push_str(&serde_json::to_string(row)?);
+                output.push('\n');
+            }
+            Ok(output)
+        }
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(&records.document)?),
+        OutputFormat::Csv => format_delimited(&records.rows, b','),
+        OutputFormat::Tsv => format_delimited(&records.rows, b'\t'),
+        OutputFormat::Template(template) => format_template(template, &records.rows),
+    }
+}
+
+/// One column per top-level field, in order of first appearance. Nested values are
+/// written as compact JSON and missing or null fields as empty cells.
+fn format_delimited(rows: &[serde_json::Value], delimiter: u8) -> anyhow::Result<String> {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        match row {
+            serde_json::Value::Object(map) => {
+                for key in map.keys() {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+            _ if !columns.contains(&"value") => columns.push("value"),
+            _ => {}
+        }
+    }
+    let cell = |value: Option<&serde_json::Value>| match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(&columns)?;
+    for row in rows {
+        let record: Vec<String> = columns
+            .iter()
+            .map(|column| match row {
+                serde_json::Value::Object(map) => cell(map.get(*column)),
+                other => cell(Some(other)),
+            })
+            .collect();
+        writer.write_record(&record)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Renders `template` once per row, with the row's fields as template variables.
+fn format_template(template: &str, rows: &[serde_json::Value]) -> anyhow::Result<String> {
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template("output", template)
+        .map_err(|e| anyhow::Error::new(e).context("invalid output template"))?;
+    let mut output = String::new();
+    for row in rows {
+        let context = match row {
+            serde_json::Value::Object(_) => tera::Context::from_value(row.clone())?,
+            other => {
+                let mut context = tera::Context::new();
+                context.insert("value", other);
+                context
+            }
+        };
+        let rendered = tera
+            .render("output", &context)
+            .map_err(|e| anyhow::Error::new(e).context("could not render output template"))?;
+        output.push_str(&rendered);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
 fn str_width(value: &str) -> usize {
     value.chars().count()
 }
@@ -180,84 +369,43 @@ pub fn format_jobs_table(jobs: &[ListJobsUnitResponse]) -> String {
     let headers = [
         "job id",
         "cluster id",
+        "job name",
         "status",
         "created",
         "finished",
         "scheduler id",
+        "labels",
     ];
-    let mut rows: Vec<(String, String, String, String, String, String)> = Vec::new();
-
-    for item in jobs.iter() {
-        let job_id = item.job_id.to_string();
-        let scheduler_id = item
-            .scheduler_id
-            .map(|id| id.to_string())
-            .unwrap_or_else(|| "-".to_string());
-        let completed_str = job_status(item);
-        let finished_at = item.finished_at.clone().unwrap_or_else(|| "-".to_string());
-        rows.push((
-            job_id,
-            item.name.clone(),
-            completed_str.to_string(),
-            item.created_at.clone(),
-            finished_at,
-            scheduler_id,
-        ));
-    }
-
-    let mut widths: [usize; 6] = [
-        str_width(headers[0]),
-        str_width(headers[1]),
-        str_width(headers[2]),
-        str_width(headers[3]),
-        str_width(headers[4]),
-        str_width(headers[5]),
-    ];
-    for row in rows.iter() {
-        widths[0] = widths[0].max(str_width(&row.0));
-        widths[1] = widths[1].max(str_width(&row.1));
-        widths[2] = widths[2].max(str_width(&row.2));
-        widths[3] = widths[3].max(str_width(&row.3));
-        widths[4] = widths[4].max(str_width(&row.4));
-        widths[5] = widths[5].max(str_width(&row.5));
-    }
-
-    let mut output = String::new();
-    output.push_str(&format!(
-        "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {:<w4$}  {:<w5$}\n",
-        headers[0],
-        headers[1],
-        headers[2],
-        headers[3],
-        headers[4],
-        headers[5],
-        w0 = widths[0],
-        w1 = widths[1],
-        w2 = widths[2],
-        w3 = widths[3],
-        w4 = widths[4],
-        w5 = widths[5],
-    ));
+    let rows: Vec<[String; 8]> = jobs
+        .iter()
+        .map(|item| {
+            [
+                item.job_id.to_string(),
+                item.name.clone(),
+                item.job_name.clone().unwrap_or_else(|| "-".to_string()),
+                job_status(item).to_string(),
+                item.created_at.clone(),
+                item.finished_at.clone().unwrap_or_else(|| "-".to_string()),
+                item.scheduler_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                format_labels(&item.labels),
+            ]
+        })
+        .collect();
+    format_rows(&headers, &rows)
+}
 
-    for row in rows {
-        output.push_str(&format!(
-            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {:<w4$}  {:<w5$}\n",
-            row.0,
-            row.1,
-            row.2,
-            row.3,
-            row.4,
-            row.5,
-            w0 = widths[0],
-            w1 = widths[1],
-            w2 = widths[2],
-            w3 = widths[3],
-            w4 = widths[4],
-            w5 = widths[5],
-        ));
+/// Labels as `key=value` pairs separated by commas, or "-".
+pub fn format_labels(labels: &[JobLabel]) -> String {
+    if labels.is_empty() {
+        return "-".to_string();
     }
-
-    output
+    labels
+        .iter()
+        .map(|label| format!("{}={}", label.key, label.value))
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 pub fn format_jobs_json(jobs: &[ListJobsUnitResponse]) -> anyhow::Result<String> {
@@ -271,8 +419,8 @@ pub fn format_job_details(item: &ListJobsUnitResponse) -> String {
         .map(|id| id.to_string())
         .unwrap_or_else(|| "-".to_string());
     let completed_str = job_status(item);
-    format!(
-        "job_id: {}\nlocal_path: {}\nremote_path: {}\nname: {}\nstatus: {}\nterminal_state: {}\ncreated: {}\nfinished: {}\nscheduler_id: {}\n",
+    let mut output = format!(
+        "job_id: {}\nlocal_path: {}\nremote_path: {}\nname: {}\nstatus: {}\nterminal_state: {}\ncreated: {}\nfinished: {}\nscheduler_id: {}\norigin: {}\n",
         item.job_id,
         item.local_path.as_str(),
         item.remote_path.as_str(),
@@ -281,14 +429,1015 @@ pub fn format_job_details(item: &ListJobsUnitResponse) -> String {
         item.terminal_state.as_deref().unwrap_or("-"),
         item.created_at,
         item.finished_at.as_deref().unwrap_or("-"),
-        scheduler_id
-    )
+        scheduler_id,
+        item.origin
+    );
+    if let Some(job_name) = item.job_name.as_deref() {
+        output.push_str(&format!("job_name: {job_name}\n"));
+    }
+    if !item.labels.is_empty() {
+        output.push_str(&format!("labels: {}\n", format_labels(&item.labels)));
+    }
+    if let Some(note) = item.note.as_deref() {
+        output.push_str(&format!("note: {note}\n"));
+    }
+    if let Some(cleaned_at) = item.cleaned_at.as_deref() {
+        output.push_str(&format!("run directory deleted: {cleaned_at}\n"));
+    }
+    for rule in &item.fetch_on_complete {
+        let state = match (fetch_state_label(rule), rule.error.as_deref()) {
+            ("done", _) => format!("done {}", rule.fetched_at.as_deref().unwrap_or("-")),
+            (label, Some(error)) => format!("{label}: {error}"),
+            (label, None) => label.to_string(),
+        };
+        output.push_str(&format!(
+            "fetch: {} -> {} ({state})\n",
+            rule.remote_path, rule.local_dir
+        ));
+    }
+    output
 }
 
 pub fn format_job_details_json(item: &ListJobsUnitResponse) -> anyhow::Result<String> {
     format_json(job_to_json(item))
 }
 
+fn format_secs(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+    let hms = format!("{:02}:{:02}:{:02}", rem / 3600, (rem % 3600) / 60, rem % 60);
+    if days > 0 { format!("{days}-{hms}") } else { hms }
+}
+
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes.max(0) as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn run_dir_usage_to_json(item: &RunDirUsage) -> serde_json::Value {
+    json!({
+        "remote_path": item.remote_path,
+        "bytes": item.bytes,
+        "job_ids": item.job_ids,
+        "state": item.state,
+        "finished_at": item.finished_at,
+        "running": item.running,
+    })
+}
+
+pub fn format_cluster_usage_json(dirs: &[RunDirUsage]) -> anyhow::Result<String> {
+    let data: Vec<serde_json::Value> = dirs.iter().map(run_dir_usage_to_json).collect();
+    format_json(serde_json::Value::Array(data))
+}
+
+/// One row per run directory, largest first, and the total.
+pub fn format_cluster_usage_table(dirs: &[RunDirUsage]) -> String {
+    let headers = ["size", "remote path", "jobs", "state", "finished"];
+    let mut sorted: Vec<&RunDirUsage> = dirs.iter().collect();
+    sorted.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then(a.remote_path.cmp(&b.remote_path))
+    });
+    let rows: Vec<[String; 5]> = sorted
+        .iter()
+        .map(|dir| {
+            let ids: Vec<String> = dir.job_ids.iter().map(|id| id.to_string()).collect();
+            [
+                dir.bytes
+                    .map(|b| format_bytes(b as i64))
+                    .unwrap_or_else(|| "missing".to_string()),
+                dir.remote_path.clone(),
+                ids.join(","),
+                if dir.running {
+                    "RUNNING".to_string()
+                } else {
+                    dir.state.clone().unwrap_or_else(|| "-".to_string())
+                },
+                dir.finished_at.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    let total: u64 = dirs.iter().filter_map(|dir| dir.bytes).sum();
+    let mut output = format_rows(&headers, &rows);
+    output.push_str(&format!(
+        "total: {} in {} directories\n",
+        format_bytes(total as i64),
+        dirs.iter().filter(|dir| dir.bytes.is_some()).count()
+    ));
+    output
+}
+
+pub fn format_cache_gc_summary(blobs: u64, bytes: u64, dry_run: bool) -> String {
+    let verb = if dry_run { "would remove" } else { "removed" };
+    let noun = if blobs == 1 { "file" } else { "files" };
+    format!(
+        "{verb} {blobs} unused cached {noun} ({})",
+        format_bytes(bytes as i64)
+    )
+}
+
+fn format_percent(ratio: Option<f64>) -> String {
+    ratio
+        .map(|r| format!("{:.1}%", r * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, c), v| (s + v, c + 1));
+    if count == 0 { None } else { Some(sum / count as f64) }
+}
+
+pub fn job_efficiency_to_json(item: &JobEfficiencyUnitResponse) -> serde_json::Value {
+    let steps: Vec<serde_json::Value> = item
+        .steps
+        .iter()
+        .map(|step| {
+            json!({
+                "step_id": step.step_id.as_str(),
+                "state": step.state.as_str(),
+                "elapsed_secs": step.elapsed_secs,
+                "total_cpu_secs": step.total_cpu_secs,
+                "alloc_cpus": step.alloc_cpus,
+                "max_rss_bytes": step.max_rss_bytes,
+                "cpu_efficiency": step.cpu_efficiency,
+                "mem_efficiency": step.mem_efficiency,
+            })
+        })
+        .collect();
+    json!({
+        "job_id": item.job_id,
+        "name": item.name.as_str(),
+        "scheduler_id": item.scheduler_id,
+        "terminal_state": item.terminal_state.as_deref(),
+        "created_at": item.created_at.as_str(),
+        "has_usage": item.has_usage,
+        "elapsed_secs": item.elapsed_secs,
+        "timelimit_secs": item.timelimit_secs,
+        "alloc_cpus": item.alloc_cpus,
+        "total_cpu_secs": item.total_cpu_secs,
+        "req_mem_bytes": item.req_mem_bytes,
+        "max_rss_bytes": item.max_rss_bytes,
+        "cpu_efficiency": item.cpu_efficiency,
+        "mem_efficiency": item.mem_efficiency,
+        "time_utilisation": item.time_utilisation,
+        "steps": steps,
+    })
+}
+
+pub fn format_job_efficiency(item: &JobEfficiencyUnitResponse) -> String {
+    let scheduler_id = item
+        .scheduler_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let mut output = format!(
+        "job_id: {}\nname: {}\nscheduler_id: {}\nstate: {}\n",
+        item.job_id,
+        item.name,
+        scheduler_id,
+        item.terminal_state.as_deref().unwrap_or("-"),
+    );
+    if !item.has_usage {
+        output.push_str("no accounting data available for this job yet\n");
+        return output;
+    }
+    let core_walltime = item.elapsed_secs * item.alloc_cpus;
+    output.push_str(&format!(
+        "cores: {}\nelapsed: {}\ntime_limit: {}\ntime_used: {}\ncpu_utilized: {}\ncpu_efficiency: {} of {} core-walltime\nmemory_utilized: {}\nmemory_efficiency: {} of {}\n",
+        item.alloc_cpus,
+        format_secs(item.elapsed_secs),
+        item.timelimit_secs
+            .map(format_secs)
+            .unwrap_or_else(|| "-".to_string()),
+        format_percent(item.time_utilisation),
+        format_secs(item.total_cpu_secs.round() as i64),
+        format_percent(item.cpu_efficiency),
+        format_secs(core_walltime),
+        item.max_rss_bytes
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string()),
+        format_percent(item.mem_efficiency),
+        item.req_mem_bytes
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string()),
+    ));
+    if item.steps.is_empty() {
+        return output;
+    }
+
+    let headers = ["step", "state", "elapsed", "cpu eff", "mem eff", "max rss"];
+    let rows: Vec<[String; 6]> = item
+        .steps
+        .iter()
+        .map(|step| {
+            [
+                step.step_id.clone(),
+                step.state.clone(),
+                format_secs(step.elapsed_secs),
+                format_percent(step.cpu_efficiency),
+                format_percent(step.mem_efficiency),
+                step.max_rss_bytes
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    output.push('\n');
+    output.push_str(&format_rows(&headers, &rows));
+    output
+}
+
+pub fn format_job_efficiency_json(item: &JobEfficiencyUnitResponse) -> anyhow::Result<String> {
+    format_json(job_efficiency_to_json(item))
+}
+
+fn efficiency_summary_json(jobs: &[JobEfficiencyUnitResponse]) -> serde_json::Value {
+    let with_usage: Vec<&JobEfficiencyUnitResponse> =
+        jobs.iter().filter(|job| job.has_usage).collect();
+    json!({
+        "jobs": jobs.len(),
+        "jobs_with_usage": with_usage.len(),
+        "mean_cpu_efficiency": mean(with_usage.iter().filter_map(|job| job.cpu_efficiency)),
+        "mean_mem_efficiency": mean(with_usage.iter().filter_map(|job| job.mem_efficiency)),
+        "mean_time_utilisation": mean(with_usage.iter().filter_map(|job| job.time_utilisation)),
+        "peak_max_rss_bytes": with_usage.iter().filter_map(|job| job.max_rss_bytes).max(),
+        "peak_elapsed_secs": with_usage.iter().map(|job| job.elapsed_secs).max(),
+    })
+}
+
+pub fn format_job_efficiency_table(jobs: &[JobEfficiencyUnitResponse]) -> String {
+    let headers = [
+        "job id",
+        "scheduler id",
+        "state",
+        "cores",
+        "elapsed",
+        "cpu eff",
+        "mem eff",
+        "time used",
+        "max rss",
+    ];
+    let rows: Vec<[String; 9]> = jobs
+        .iter()
+        .map(|job| {
+            [
+                job.job_id.to_string(),
+                job.scheduler_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                job.terminal_state.clone().unwrap_or_else(|| "-".to_string()),
+                if job.has_usage {
+                    job.alloc_cpus.to_string()
+                } else {
+                    "-".to_string()
+                },
+                if job.has_usage {
+                    format_secs(job.elapsed_secs)
+                } else {
+                    "-".to_string()
+                },
+                format_percent(job.cpu_efficiency),
+                format_percent(job.mem_efficiency),
+                format_percent(job.time_utilisation),
+                job.max_rss_bytes
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    let mut output = format_rows(&headers, &rows);
+
+    let summary = efficiency_summary_json(jobs);
+    let ratio = |key: &str| format_percent(summary[key].as_f64());
+    output.push_str(&format!(
+        "\njobs: {} ({} with usage)\nmean_cpu_efficiency: {}\nmean_memory_efficiency: {}\nmean_time_used: {}\npeak_memory: {}\nlongest_elapsed: {}\n",
+        jobs.len(),
+        summary["jobs_with_usage"],
+        ratio("mean_cpu_efficiency"),
+        ratio("mean_mem_efficiency"),
+        ratio("mean_time_utilisation"),
+        summary["peak_max_rss_bytes"]
+            .as_i64()
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string()),
+        summary["peak_elapsed_secs"]
+            .as_i64()
+            .map(format_secs)
+            .unwrap_or_else(|| "-".to_string()),
+    ));
+    output
+}
+
+pub fn format_job_efficiency_summary_json(
+    jobs: &[JobEfficiencyUnitResponse],
+) -> anyhow::Result<String> {
+    format_json(job_efficiency_summary_records(jobs).document)
+}
+
+/// Rows are the jobs; the summary is only part of the whole document.
+pub fn job_efficiency_summary_records(jobs: &[JobEfficiencyUnitResponse]) -> Records {
+    let rows: Vec<serde_json::Value> = jobs.iter().map(job_efficiency_to_json).collect();
+    Records {
+        document: json!({
+            "jobs": rows.clone(),
+            "summary": efficiency_summary_json(jobs),
+        }),
+        rows,
+    }
+}
+
+/// Marks queue rows for jobs the daemon tracks.
+pub const TRACKED_JOB_MARKER: char = '*';
+
+pub fn cluster_queue_job_to_json(item: &ClusterQueueJob) -> serde_json::Value {
+    json!({
+        "job_id": item.job_id,
+        "scheduler_id": item.scheduler_id,
+        "user": item.user,
+        "partition": item.partition,
+        "name": item.name,
+        "state": item.state,
+        "reason": item.reason,
+        "priority": item.priority,
+        "start_time": item.start_time,
+        "time_used": item.time_used,
+        "time_limit": item.time_limit,
+        "nodes": item.nodes,
+        "cpus": item.cpus,
+        "nodelist": item.nodelist,
+        "tracked_job_id": item.tracked_job_id,
+    })
+}
+
+pub fn format_cluster_queue_json(jobs: &[ClusterQueueJob]) -> anyhow::Result<String> {
+    let data: Vec<serde_json::Value> = jobs.iter().map(cluster_queue_job_to_json).collect();
+    format_json(serde_json::Value::Array(data))
+}
+
+pub fn format_cluster_queue_table(jobs: &[ClusterQueueJob]) -> String {
+    let headers = [
+        " ",
+        "scheduler id",
+        "job id",
+        "user",
+        "partition",
+        "name",
+        "state",
+        "reason",
+        "priority",
+        "start",
+        "time",
+        "limit",
+        "nodes",
+        "cpus",
+    ];
+    let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let rows: Vec<[String; 14]> = jobs
+        .iter()
+        .map(|job| {
+            [
+                if job.tracked_job_id.is_some() {
+                    TRACKED_JOB_MARKER.to_string()
+                } else {
+                    String::new()
+                },
+                job.job_id.clone(),
+                job.tracked_job_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                job.user.clone(),
+                job.partition.clone(),
+                job.name.clone(),
+                job.state.clone(),
+                or_dash(&job.reason),
+                job.priority
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                or_dash(&job.start_time),
+                job.time_used.clone(),
+                job.time_limit.clone(),
+                job.nodelist
+                    .clone()
+                    .unwrap_or_else(|| job.nodes.to_string()),
+                job.cpus.to_string(),
+            ]
+        })
+        .collect();
+    let mut output = format_rows(&headers, &rows);
+    let tracked = jobs.iter().filter(|job| job.tracked_job_id.is_some()).count();
+    output.push_str(&format!(
+        "\njobs: {} ({tracked} tracked, marked {TRACKED_JOB_MARKER})\n",
+        jobs.len()
+    ));
+    output
+}
+
+fn gpu_type_label(gpu_type: &str) -> &str {
+    if gpu_type.is_empty() { "(untyped)" } else { gpu_type }
+}
+
+fn format_mb(mb: i64) -> String {
+    format_bytes(mb.saturating_mul(1024 * 1024))
+}
+
+fn partition_capacity_to_json(item: &PartitionCapacity) -> serde_json::Value {
+    json!({
+        "partition": item.partition,
+        "nodes_total": item.nodes_total,
+        "nodes_idle": item.nodes_idle,
+        "nodes_mixed": item.nodes_mixed,
+        "nodes_allocated": item.nodes_allocated,
+        "nodes_unavailable": item.nodes_unavailable,
+        "cpus_total": item.cpus_total,
+        "cpus_alloc": item.cpus_alloc,
+        "cpus_idle": item.cpus_idle,
+        "memory_total_mb": item.memory_total_mb,
+        "memory_alloc_mb": item.memory_alloc_mb,
+        "gpus_total": item.gpus_total,
+        "gpus_alloc": item.gpus_alloc,
+        "gpus_idle": item.gpus_idle,
+    })
+}
+
+fn gpu_type_capacity_to_json(item: &GpuTypeCapacity) -> serde_json::Value {
+    json!({
+        "gpu_type": item.gpu_type,
+        "nodes_total": item.nodes_total,
+        "gpus_total": item.gpus_total,
+        "gpus_alloc": item.gpus_alloc,
+        "gpus_idle": item.gpus_idle,
+        "partitions": item.partitions,
+    })
+}
+
+fn cluster_node_to_json(item: &ClusterNode) -> serde_json::Value {
+    let gpus: Vec<serde_json::Value> = item
+        .gpus
+        .iter()
+        .map(|g| json!({ "gpu_type": g.gpu_type, "total": g.total, "alloc": g.alloc }))
+        .collect();
+    json!({
+        "name": item.name,
+        "state": item.state,
+        "available": item.available,
+        "partitions": item.partitions,
+        "cpus_total": item.cpus_total,
+        "cpus_alloc": item.cpus_alloc,
+        "memory_total_mb": item.memory_total_mb,
+        "memory_alloc_mb": item.memory_alloc_mb,
+        "memory_free_mb": item.memory_free_mb,
+        "gpus": gpus,
+    })
+}
+
+pub fn format_cluster_nodes_json(result: &ClusterNodesResult) -> anyhow::Result<String> {
+    format_json(cluster_nodes_records(result, false).document)
+}
+
+/// Rows are partitions, or nodes with `with_nodes`, like the table.
+pub fn cluster_nodes_records(result: &ClusterNodesResult, with_nodes: bool) -> Records {
+    let rows = if with_nodes {
+        result.nodes.iter().map(cluster_node_to_json).collect()
+    } else {
+        result
+            .partitions
+            .iter()
+            .map(partition_capacity_to_json)
+            .collect()
+    };
+    Records {
+        document: json!({
+            "partitions": result.partitions.iter().map(partition_capacity_to_json).collect::<Vec<_>>(),
+            "gpu_types": result.gpu_types.iter().map(gpu_type_capacity_to_json).collect::<Vec<_>>(),
+            "nodes": result.nodes.iter().map(cluster_node_to_json).collect::<Vec<_>>(),
+        }),
+        rows,
+    }
+}
+
+/// Render capacity per partition and per GPU type, plus one row per node with `with_nodes`.
+pub fn format_cluster_nodes(result: &ClusterNodesResult, with_nodes: bool) -> String {
+    let headers = [
+        "partition",
+        "nodes",
+        "idle",
+        "mixed",
+        "alloc",
+        "unavail",
+        "cpus idle/total",
+        "mem alloc/total",
+        "gpus idle/total",
+    ];
+    let rows: Vec<[String; 9]> = result
+        .partitions
+        .iter()
+        .map(|p| {
+            [
+                p.partition.clone(),
+                p.nodes_total.to_string(),
+                p.nodes_idle.to_string(),
+                p.nodes_mixed.to_string(),
+                p.nodes_allocated.to_string(),
+                p.nodes_unavailable.to_string(),
+                format!("{}/{}", p.cpus_idle, p.cpus_total),
+                format!(
+                    "{}/{}",
+                    format_mb(p.memory_alloc_mb),
+                    format_mb(p.memory_total_mb)
+                ),
+                format!("{}/{}", p.gpus_idle, p.gpus_total),
+            ]
+        })
+        .collect();
+    let mut output = format_rows(&headers, &rows);
+
+    if !result.gpu_types.is_empty() {
+        let headers = ["gpu type", "nodes", "idle", "alloc", "total", "partitions"];
+        let rows: Vec<[String; 6]> = result
+            .gpu_types
+            .iter()
+            .map(|g| {
+                [
+                    gpu_type_label(&g.gpu_type).to_string(),
+                    g.nodes_total.to_string(),
+                    g.gpus_idle.to_string(),
+                    g.gpus_alloc.to_string(),
+                    g.gpus_total.to_string(),
+                    g.partitions.join(","),
+                ]
+            })
+            .collect();
+        output.push('\n');
+        output.push_str(&format_rows(&headers, &rows));
+    }
+
+    if with_nodes {
+        let headers = ["node", "state", "cpus alloc/total", "mem alloc/total", "gpus", "partitions"];
+        let rows: Vec<[String; 6]> = result
+            .nodes
+            .iter()
+            .map(|n| {
+                let gpus = n
+                    .gpus
+                    .iter()
+                    .map(|g| format!("{}:{}/{}", gpu_type_label(&g.gpu_type), g.alloc, g.total))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                [
+                    n.name.clone(),
+                    n.state.clone(),
+                    format!("{}/{}", n.cpus_alloc, n.cpus_total),
+                    format!(
+                        "{}/{}",
+                        format_mb(n.memory_alloc_mb),
+                        format_mb(n.memory_total_mb)
+                    ),
+                    if gpus.is_empty() { "-".to_string() } else { gpus },
+                    n.partitions.join(","),
+                ]
+            })
+            .collect();
+        output.push('\n');
+        output.push_str(&format_rows(&headers, &rows));
+    }
+    output
+}
+
+pub fn cluster_account_to_json(item: &ClusterAccount) -> serde_json::Value {
+    json!({
+        "account": item.account,
+        "partition": item.partition,
+        "qos": item.qos,
+        "default_qos": item.default_qos,
+        "raw_shares": item.raw_shares,
+        "norm_shares": item.norm_shares,
+        "raw_usage": item.raw_usage,
+        "effective_usage": item.effective_usage,
+        "fairshare": item.fairshare,
+    })
+}
+
+pub fn format_cluster_accounts_json(accounts: &[ClusterAccount]) -> anyhow::Result<String> {
+    let data: Vec<serde_json::Value> = accounts.iter().map(cluster_account_to_json).collect();
+    format_json(serde_json::Value::Array(data))
+}
+
+pub fn format_cluster_accounts_table(accounts: &[ClusterAccount]) -> String {
+    let headers = [
+        "account",
+        "partition",
+        "qos",
+        "default qos",
+        "fairshare",
+        "raw usage",
+        "effective usage",
+    ];
+    let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let decimal = |value: Option<f64>| {
+        value
+            .map(|v| format!("{v:.3}"))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let rows: Vec<[String; 7]> = accounts
+        .iter()
+        .map(|a| {
+            [
+                a.account.clone(),
+                or_dash(&a.partition),
+                if a.qos.is_empty() {
+                    "-".to_string()
+                } else {
+                    a.qos.join(",")
+                },
+                or_dash(&a.default_qos),
+                decimal(a.fairshare),
+                a.raw_usage
+                    .map(|u| u.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                decimal(a.effective_usage),
+            ]
+        })
+        .collect();
+    format_rows(&headers, &rows)
+}
+
+/// Lists the files a submit would upload and every symbolic link with what happens to it,
+/// then per-status totals and the sbatch command. Unchanged files only show up in the totals.
+pub fn format_submit_dry_run(result: &SubmitDryRunResult) -> String {
+    use submit_dry_run_item::Status;
+    let label = |status: Status| match status {
+        Status::New => "new",
+        Status::Changed => "changed",
+        Status::Unchanged => "unchanged",
+        Status::Delete => "delete",
+        Status::Unspecified => "-",
+    };
+    let order = [Status::New, Status::Changed, Status::Unchanged];
+    let mut totals = [(0usize, 0u64); 3];
+    let mut deletes = (0usize, 0u64);
+    let mut rows: Vec<[String; 3]> = Vec::new();
+    for item in &result.items {
+        let status = Status::try_from(item.status).unwrap_or(Status::Unspecified);
+        if let Some(idx) = order.iter().position(|s| *s == status) {
+            totals[idx].0 += 1;
+            totals[idx].1 += item.size;
+        }
+        if status == Status::Delete {
+            deletes.0 += 1;
+            deletes.1 += item.size;
+        }
+        if status != Status::Unchanged {
+            rows.push([
+                label(status).to_string(),
+                format_bytes(item.size as i64),
+                item.path.clone(),
+            ]);
+        }
+    }
+    let mut output = String::new();
+    if !rows.is_empty() {
+        output.push_str(&format_rows(&["status", "size", "path"], &rows));
+        output.push('\n');
+    }
+    if !result.symlinks.is_empty() {
+        use submit_dry_run_symlink::Action;
+        let links: Vec<[String; 3]> = result
+            .symlinks
+            .iter()
+            .map(|link| {
+                let action = match Action::try_from(link.action).unwrap_or(Action::Unspecified) {
+                    Action::Recreate => "recreate",
+                    Action::Follow => "follow",
+                    Action::Skip => "skip",
+                    Action::Broken => "broken",
+                    Action::Unspecified => "-",
+                };
+                let mut target = link.target.clone();
+                if link.outside_root {
+                    target.push_str(" (outside submit root)");
+                }
+                [action.to_string(), link.path.clone(), target]
+            })
+            .collect();
+        output.push_str(&format_rows(&["symlink", "path", "target"], &links));
+        output.push('\n');
+    }
+    let summary = order
+        .iter()
+        .zip(totals.iter())
+        .map(|(status, (count, bytes))| {
+            let noun = if *count == 1 { "file" } else { "files" };
+            format!(
+                "{}: {count} {noun} ({})",
+                label(*status),
+                format_bytes(*bytes as i64)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    output.push_str(&summary);
+    if result.checksum {
+        output.push_str(" [compared by checksum]");
+    }
+    output.push('\n');
+    let (upload_count, upload_bytes) = (totals[0].0 + totals[1].0, totals[0].1 + totals[1].1);
+    output.push_str(&format!(
+        "to upload: {upload_count} {} ({})\n",
+        if upload_count == 1 { "file" } else { "files" },
+        format_bytes(upload_bytes as i64)
+    ));
+    if deletes.0 > 0 {
+        output.push_str(&format!(
+            "to delete: {} {} ({})\n",
+            deletes.0,
+            if deletes.0 == 1 { "file" } else { "files" },
+            format_bytes(deletes.1 as i64)
+        ));
+    }
+    output.push_str(&format!("sbatch command: {}\n", result.sbatch_command));
+    output
+}
+
+pub fn transfer_progress_to_json(item: &TransferProgress) -> serde_json::Value {
+    json!({
+        "event": "transfer_progress",
+        "files_total": item.files_total,
+        "files_done": item.files_done,
+        "bytes_total": item.bytes_total,
+        "bytes_sent": item.bytes_sent,
+        "bytes_skipped": item.bytes_skipped,
+        "current_file": item.current_file,
+    })
+}
+
+/// One-line progress bar: completed bytes count both sent and skipped ones.
+pub fn format_transfer_progress(item: &TransferProgress, bar_width: usize) -> String {
+    let done = item.bytes_sent + item.bytes_skipped;
+    let ratio = if item.bytes_total == 0 {
+        if item.files_done >= item.files_total { 1.0 } else { 0.0 }
+    } else {
+        (done as f64 / item.bytes_total as f64).min(1.0)
+    };
+    let filled = (ratio * bar_width as f64).round() as usize;
+    let mut line = format!(
+        "[{}{}] {:>3.0}%  {}/{}  {}/{} files",
+        "#".repeat(filled),
+        "-".repeat(bar_width - filled),
+        ratio * 100.0,
+        format_bytes(done as i64),
+        format_bytes(item.bytes_total as i64),
+        item.files_done,
+        item.files_total,
+    );
+    if item.bytes_skipped > 0 {
+        line.push_str(&format!(
+            "  ({} unchanged)",
+            format_bytes(item.bytes_skipped as i64)
+        ));
+    }
+    if !item.current_file.is_empty() {
+        line.push_str("  ");
+        line.push_str(&item.current_file);
+    }
+    line
+}
+
+fn ls_kind_label(kind: i32) -> &'static str {
+    match ls_entry::Kind::try_from(kind) {
+        Ok(ls_entry::Kind::File) => "file",
+        Ok(ls_entry::Kind::Directory) => "dir",
+        Ok(ls_entry::Kind::Symlink) => "symlink",
+        Ok(ls_entry::Kind::Other) => "other",
+        _ => "unknown",
+    }
+}
+
+fn format_unix_time(secs: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(secs)
+        .ok()
+        .and_then(|at| at.format(&Rfc3339).ok())
+        .unwrap_or_else(|| secs.to_string())
+}
+
+pub fn ls_entry_to_json(entry: &LsEntry) -> serde_json::Value {
+    json!({
+        "name": entry.name.as_str(),
+        "type": ls_kind_label(entry.kind),
+        "size": entry.size,
+        "mtime": format_unix_time(entry.mtime),
+    })
+}
+
+/// One row per entry; directories get a trailing `/`.
+pub fn format_ls_table(entries: &[LsEntry]) -> String {
+    let headers = ["name", "type", "size", "modified"];
+    let rows: Vec<[String; 4]> = entries
+        .iter()
+        .map(|entry| {
+            let mut name = entry.name.clone();
+            if entry.kind == ls_entry::Kind::Directory as i32 {
+                name.push('/');
+            }
+            [
+                name,
+                ls_kind_label(entry.kind).to_string(),
+                format_bytes(i64::try_from(entry.size).unwrap_or(i64::MAX)),
+                format_unix_time(entry.mtime),
+            ]
+        })
+        .collect();
+    format_rows(&headers, &rows)
+}
+
+/// How `hpc job ls` renders a [`ListDirResult`] as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirListingStyle {
+    /// One row per entry with its type, size and modification time.
+    Short,
+    /// `ls -l`-like rows with permissions and symlink targets.
+    Long,
+    /// Names indented by depth.
+    Tree,
+}
+
+/// `drwxr-xr-x`-style permissions.
+fn format_mode(kind: i32, mode: u32) -> String {
+    let mut out = String::with_capacity(10);
+    out.push(match ls_entry::Kind::try_from(kind) {
+        Ok(ls_entry::Kind::Directory) => 'd',
+        Ok(ls_entry::Kind::Symlink) => 'l',
+        Ok(ls_entry::Kind::File) => '-',
+        _ => '?',
+    });
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    out
+}
+
+fn dir_entry_name(entry: &DirEntry) -> String {
+    let mut name = entry.path.clone();
+    if entry.kind == ls_entry::Kind::Directory as i32 {
+        name.push('/');
+    }
+    name
+}
+
+pub fn dir_entry_to_json(entry: &DirEntry) -> serde_json::Value {
+    json!({
+        "path": entry.path.as_str(),
+        "type": ls_kind_label(entry.kind),
+        "size": entry.size,
+        "mode": format!("{:04o}", entry.mode),
+        "mtime": entry.mtime.map(format_unix_time),
+        "symlink_target": entry.symlink_target.as_deref(),
+        "depth": entry.depth,
+    })
+}
+
+/// The whole listing for `json`/`yaml`, one entry per row for the other formats.
+pub fn list_dir_records(result: &ListDirResult) -> Records {
+    let entries: Vec<serde_json::Value> = result.entries.iter().map(dir_entry_to_json).collect();
+    Records {
+        document: json!({
+            "path": result.path.as_str(),
+            "entries": entries.clone(),
+            "total_files": result.total_files,
+            "total_dirs": result.total_dirs,
+            "total_bytes": result.total_bytes,
+            "truncated": result.truncated,
+        }),
+        rows: entries,
+    }
+}
+
+pub fn format_dir_listing(result: &ListDirResult, style: DirListingStyle) -> String {
+    let mtime = |entry: &DirEntry| entry.mtime.map(format_unix_time).unwrap_or("-".to_string());
+    let size = |entry: &DirEntry| format_bytes(i64::try_from(entry.size).unwrap_or(i64::MAX));
+    let mut output = match style {
+        DirListingStyle::Short => {
+            let headers = ["name", "type", "size", "modified"];
+            let rows: Vec<[String; 4]> = result
+                .entries
+                .iter()
+                .map(|entry| {
+                    [
+                        dir_entry_name(entry),
+                        ls_kind_label(entry.kind).to_string(),
+                        size(entry),
+                        mtime(entry),
+                    ]
+                })
+                .collect();
+            format_rows(&headers, &rows)
+        }
+        DirListingStyle::Long => {
+            let headers = ["mode", "size", "modified", "name"];
+            let rows: Vec<[String; 4]> = result
+                .entries
+                .iter()
+                .map(|entry| {
+                    let mut name = dir_entry_name(entry);
+                    if let Some(target) = &entry.symlink_target {
+                        name.push_str(" -> ");
+                        name.push_str(target);
+                    }
+                    [
+                        format_mode(entry.kind, entry.mode),
+                        size(entry),
+                        mtime(entry),
+                        name,
+                    ]
+                })
+                .collect();
+            format_rows(&headers, &rows)
+        }
+        DirListingStyle::Tree => {
+            let mut output = format!("{}\n", result.path);
+            for entry in &result.entries {
+                let indent = "  ".repeat(entry.depth.max(1) as usize);
+                let mut name = entry
+                    .path
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                if entry.kind == ls_entry::Kind::Directory as i32 {
+                    name.push('/');
+                } else if let Some(target) = &entry.symlink_target {
+                    name = format!("{name} -> {target}");
+                }
+                output.push_str(&format!("{indent}{name}\n"));
+            }
+            output
+        }
+    };
+    output.push_str(&format!(
+        "{} files, {} directories, {}\n",
+        result.total_files,
+        result.total_dirs,
+        format_bytes(i64::try_from(result.total_bytes).unwrap_or(i64::MAX))
+    ));
+    if result.truncated {
+        output.push_str("listing truncated by the daemon; narrow it with --depth or --glob\n");
+    }
+    output
+}
+
+/// Job log output as one row per line, numbered from 1.
+pub fn job_log_records(job_id: i64, stream: &str, output: &[u8]) -> Records {
+    let text = String::from_utf8_lossy(output);
+    Records::list(
+        text.lines()
+            .enumerate()
+            .map(|(index, line)| {
+                json!({
+                    "job_id": job_id,
+                    "stream": stream,
+                    "line": index + 1,
+                    "text": line,
+                })
+            })
+            .collect(),
+    )
+}
+
+fn format_rows<const N: usize>(headers: &[&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = headers.map(str_width);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(str_width(cell));
+        }
+    }
+    let render = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+    let mut output = render(headers.to_vec());
+    for row in rows {
+        output.push_str(&render(row.iter().map(String::as_str).collect()));
+    }
+    output
+}
+
 pub fn job_status(item: &ListJobsUnitResponse) -> &'static str {
     if !item.is_completed {
         if item
@@ -344,6 +1493,15 @@ mod tests {
             scheduler_state: scheduler_state.map(|s| s.to_string()),
             local_path: "/tmp/project".to_string(),
             remote_path: "/remote/project".to_string(),
+            origin: "submitted".to_string(),
+            fetch_on_complete: Vec::new(),
+            cleaned_at: None,
+            job_name: Some("train".to_string()),
+            note: None,
+            labels: vec![JobLabel {
+                key: "exp".to_string(),
+                value: "ablation".to_string(),
+            }],
         }
     }
 
@@ -385,6 +1543,44 @@ mod tests {
         assert_eq!(job_status(&completed_unknown), "completed");
     }
 
+    #[test]
+    fn format_job_details_lists_fetch_rules() {
+        let mut job = sample_job(true, Some("COMPLETED"), None);
+        job.fetch_on_complete = vec![
+            JobFetchStatus {
+                remote_path: "results".to_string(),
+                local_dir: "/tmp/project".to_string(),
+                state: job_fetch_status::State::Done as i32,
+                error: None,
+                fetched_at: Some("2024-01-01T01:01:00Z".to_string()),
+            },
+            JobFetchStatus {
+                remote_path: "logs".to_string(),
+                local_dir: "/tmp/logs".to_string(),
+                state: job_fetch_status::State::Failed as i32,
+                error: Some("No such file".to_string()),
+                fetched_at: None,
+            },
+        ];
+        let output = format_job_details(&job);
+        assert!(output.contains("fetch: results -> /tmp/project (done 2024-01-01T01:01:00Z)\n"));
+        assert!(output.contains("fetch: logs -> /tmp/logs (failed: No such file)\n"));
+        let json = job_to_json(&job);
+        assert_eq!(json["fetch_on_complete"][0]["state"], "done");
+        assert_eq!(json["fetch_on_complete"][1]["error"], "No such file");
+    }
+
+    #[test]
+    fn job_details_show_name_labels_and_note() {
+        let mut job = sample_job(true, Some("COMPLETED"), None);
+        job.note = Some("baseline".to_string());
+        let output = format_job_details(&job);
+        assert!(output.contains("job_name: train\nlabels: exp=ablation\nnote: baseline\n"));
+        let json = job_to_json(&job);
+        assert_eq!(json["labels"]["exp"], "ablation");
+        assert_eq!(json["note"], "baseline");
+    }
+
     #[test]
     fn format_clusters_table_includes_headers_and_rows() {
         let cluster = sample_cluster(Some(proto::list_clusters_unit_response::Host::Hostname(
@@ -406,6 +1602,8 @@ mod tests {
         assert!(output.contains("job id"));
         assert!(output.contains("cluster-a"));
         assert!(output.contains("completed"));
+        assert!(output.contains("train"));
+        assert!(output.contains("exp=ablation"));
     }
 
     #[test]
@@ -418,4 +1616,462 @@ mod tests {
         assert_eq!(json["name"], "cluster-a");
         assert_eq!(json["address"], "node");
     }
+
+    fn sample_efficiency(has_usage: bool) -> JobEfficiencyUnitResponse {
+        JobEfficiencyUnitResponse {
+            name: "cluster-a".to_string(),
+            job_id: 42,
+            scheduler_id: Some(99),
+            terminal_state: Some("COMPLETED".to_string()),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            has_usage,
+            elapsed_secs: 600,
+            timelimit_secs: Some(2400),
+            alloc_cpus: 4,
+            total_cpu_secs: 1200.0,
+            req_mem_bytes: Some(4 << 30),
+            max_rss_bytes: Some(1 << 30),
+            cpu_efficiency: has_usage.then_some(0.5),
+            mem_efficiency: has_usage.then_some(0.25),
+            time_utilisation: has_usage.then_some(0.25),
+            steps: vec![proto::JobStepEfficiency {
+                step_id: "99.batch".to_string(),
+                state: "COMPLETED".to_string(),
+                elapsed_secs: 600,
+                total_cpu_secs: 1200.0,
+                alloc_cpus: 4,
+                max_rss_bytes: Some(1 << 30),
+                cpu_efficiency: Some(0.5),
+                mem_efficiency: Some(0.25),
+            }],
+        }
+    }
+
+    #[test]
+    fn format_job_efficiency_reports_seff_style_lines() {
+        let output = format_job_efficiency(&sample_efficiency(true));
+        assert!(output.contains("cpu_efficiency: 50.0% of 00:40:00 core-walltime"));
+        assert!(output.contains("memory_efficiency: 25.0% of 4.0 GiB"));
+        assert!(output.contains("time_used: 25.0%"));
+        assert!(output.contains("99.batch"));
+
+        let missing = format_job_efficiency(&sample_efficiency(false));
+        assert!(missing.contains("no accounting data available"));
+    }
+
+    #[test]
+    fn format_job_efficiency_table_summarises_jobs() {
+        let jobs = [sample_efficiency(true), sample_efficiency(false)];
+        let output = format_job_efficiency_table(&jobs);
+        assert!(output.contains("cpu eff"));
+        assert!(output.contains("jobs: 2 (1 with usage)"));
+        assert!(output.contains("mean_cpu_efficiency: 50.0%"));
+        assert!(output.contains("peak_memory: 1.0 GiB"));
+    }
+
+    #[test]
+    fn format_secs_and_bytes_are_human_readable() {
+        assert_eq!(format_secs(59), "00:00:59");
+        assert_eq!(format_secs(90061), "1-01:01:01");
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+    }
+
+    fn sample_queue_job(job_id: &str, tracked_job_id: Option<i64>) -> ClusterQueueJob {
+        ClusterQueueJob {
+            job_id: job_id.to_string(),
+            scheduler_id: job_id.parse().unwrap_or_default(),
+            user: "alice".to_string(),
+            partition: "gpu".to_string(),
+            name: "train".to_string(),
+            state: "PENDING".to_string(),
+            reason: Some("Priority".to_string()),
+            priority: Some(4242),
+            start_time: Some("2026-10-19T08:00:00".to_string()),
+            time_used: "0:00".to_string(),
+            time_limit: "1-00:00:00".to_string(),
+            nodes: 2,
+            cpus: 16,
+            nodelist: None,
+            tracked_job_id,
+        }
+    }
+
+    #[test]
+    fn format_cluster_queue_table_marks_tracked_jobs() {
+        let jobs = [sample_queue_job("101", Some(7)), sample_queue_job("102", None)];
+        let output = format_cluster_queue_table(&jobs);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].contains("scheduler id"));
+        assert!(lines[1].starts_with("*  101"));
+        assert!(lines[1].contains("Priority"));
+        assert!(lines[2].starts_with("   102"));
+        assert!(output.contains("jobs: 2 (1 tracked, marked *)"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_cluster_queue_json(&jobs).unwrap()).unwrap();
+        assert_eq!(json[0]["tracked_job_id"], 7);
+        assert_eq!(json[1]["tracked_job_id"], serde_json::Value::Null);
+    }
+
+    fn sample_nodes() -> ClusterNodesResult {
+        ClusterNodesResult {
+            nodes: vec![ClusterNode {
+                name: "g1".to_string(),
+                state: "MIXED".to_string(),
+                available: true,
+                partitions: vec!["gpu".to_string()],
+                cpus_total: 64,
+                cpus_alloc: 16,
+                memory_total_mb: 2048,
+                memory_alloc_mb: 1024,
+                memory_free_mb: None,
+                gpus: vec![proto::ClusterNodeGpus {
+                    gpu_type: "h200".to_string(),
+                    total: 4,
+                    alloc: 1,
+                }],
+            }],
+            partitions: vec![PartitionCapacity {
+                partition: "gpu".to_string(),
+                nodes_total: 1,
+                nodes_mixed: 1,
+                cpus_total: 64,
+                cpus_alloc: 16,
+                cpus_idle: 48,
+                memory_total_mb: 2048,
+                memory_alloc_mb: 1024,
+                gpus_total: 4,
+                gpus_alloc: 1,
+                gpus_idle: 3,
+                ..Default::default()
+            }],
+            gpu_types: vec![GpuTypeCapacity {
+                gpu_type: "h200".to_string(),
+                nodes_total: 1,
+                gpus_total: 4,
+                gpus_alloc: 1,
+                gpus_idle: 3,
+                partitions: vec!["gpu".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn format_cluster_nodes_shows_partition_and_gpu_capacity() {
+        let result = sample_nodes();
+        let output = format_cluster_nodes(&result, false);
+        assert!(output.contains("gpus idle/total"));
+        assert!(output.contains("48/64"));
+        assert!(output.contains("1.0 GiB/2.0 GiB"));
+        assert!(output.contains("h200"));
+        assert!(!output.contains("g1"));
+
+        let output = format_cluster_nodes(&result, true);
+        assert!(output.contains("h200:1/4"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_cluster_nodes_json(&result).unwrap()).unwrap();
+        assert_eq!(json["partitions"][0]["gpus_idle"], 3);
+        assert_eq!(json["gpu_types"][0]["partitions"][0], "gpu");
+        assert_eq!(json["nodes"][0]["gpus"][0]["alloc"], 1);
+    }
+
+    #[test]
+    fn format_cluster_usage_sorts_by_size_and_totals() {
+        let dirs = [
+            RunDirUsage {
+                remote_path: "/base/small".to_string(),
+                bytes: Some(2048),
+                job_ids: vec![1, 4],
+                state: Some("COMPLETED".to_string()),
+                finished_at: Some("2026-01-01T00:00:00Z".to_string()),
+                running: false,
+            },
+            RunDirUsage {
+                remote_path: "/base/big".to_string(),
+                bytes: Some(3 * 1024 * 1024),
+                job_ids: vec![2],
+                running: true,
+                ..Default::default()
+            },
+            RunDirUsage {
+                remote_path: "/base/gone".to_string(),
+                job_ids: vec![3],
+                ..Default::default()
+            },
+        ];
+        let output = format_cluster_usage_table(&dirs);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[1].starts_with("3.0 MiB") && lines[1].contains("RUNNING"));
+        assert!(lines[2].contains("/base/small") && lines[2].contains("1,4"));
+        assert!(lines[3].starts_with("missing"));
+        assert_eq!(lines[4], "total: 3.0 MiB in 2 directories");
+    }
+
+    #[test]
+    fn format_cache_gc_summary_reports_freed_space() {
+        assert_eq!(
+            format_cache_gc_summary(2, 3 * 1024 * 1024, false),
+            "removed 2 unused cached files (3.0 MiB)"
+        );
+        assert_eq!(
+            format_cache_gc_summary(1, 10, true),
+            "would remove 1 unused cached file (10 B)"
+        );
+    }
+
+    #[test]
+    fn format_cluster_accounts_lists_qos_and_fairshare() {
+        let accounts = [
+            ClusterAccount {
+                account: "proj_a".to_string(),
+                qos: vec!["normal".to_string(), "long".to_string()],
+                default_qos: Some("normal".to_string()),
+                fairshare: Some(0.75),
+                raw_usage: Some(1234),
+                ..Default::default()
+            },
+            ClusterAccount {
+                account: "proj_b".to_string(),
+                partition: Some("gpu".to_string()),
+                ..Default::default()
+            },
+        ];
+        let output = format_cluster_accounts_table(&accounts);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("account"));
+        assert!(lines[1].contains("normal,long"));
+        assert!(lines[1].contains("0.750"));
+        assert!(lines[1].contains("1234"));
+        assert!(lines[2].contains("gpu"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_cluster_accounts_json(&accounts).unwrap()).unwrap();
+        assert_eq!(json[0]["qos"][1], "long");
+        assert_eq!(json[1]["fairshare"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn format_submit_dry_run_lists_uploads_and_totals() {
+        use proto::SubmitDryRunItem;
+        use submit_dry_run_item::Status;
+        let item = |path: &str, status: Status, size: u64| SubmitDryRunItem {
+            path: path.to_string(),
+            status: status as i32,
+            size,
+        };
+        let result = SubmitDryRunResult {
+            remote_path: "/scratch/run".to_string(),
+            items: vec![
+                item("job.sbatch", Status::New, 100),
+                item("src/main.py", Status::Changed, 2048),
+                item("data/big.bin", Status::Unchanged, 4096),
+                item("old/input.csv", Status::Delete, 512),
+            ],
+            sbatch_command: "cd '/scratch/run' && sbatch 'job.sbatch'".to_string(),
+            checksum: false,
+            symlinks: vec![proto::SubmitDryRunSymlink {
+                path: "data".to_string(),
+                target: "/shared/data".to_string(),
+                action: submit_dry_run_symlink::Action::Skip as i32,
+                outside_root: true,
+            }],
+        };
+        let output = format_submit_dry_run(&result);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("status"));
+        assert!(lines[1].starts_with("new"));
+        assert!(lines[1].ends_with("job.sbatch"));
+        assert!(lines[2].contains("2.0 KiB"));
+        assert!(!output.contains("data/big.bin"));
+        let link = lines.iter().find(|l| l.starts_with("skip")).unwrap();
+        assert!(link.ends_with("/shared/data (outside submit root)"));
+        assert!(output.contains(
+            "new: 1 file (100 B), changed: 1 file (2.0 KiB), unchanged: 1 file (4.0 KiB)"
+        ));
+        assert!(output.contains("to upload: 2 files (2.1 KiB)"));
+        assert!(lines[3].starts_with("delete"));
+        assert!(output.contains("to delete: 1 file (512 B)"));
+        assert!(output.ends_with("sbatch command: cd '/scratch/run' && sbatch 'job.sbatch'\n"));
+    }
+
+    #[test]
+    fn format_transfer_progress_renders_bar_and_counts() {
+        let item = TransferProgress {
+            files_total: 4,
+            files_done: 1,
+            bytes_total: 4096,
+            bytes_sent: 1024,
+            bytes_skipped: 1024,
+            current_file: "src/main.py".to_string(),
+        };
+        assert_eq!(
+            format_transfer_progress(&item, 10),
+            "[#####-----]  50%  2.0 KiB/4.0 KiB  1/4 files  (1.0 KiB unchanged)  src/main.py"
+        );
+
+        let empty = TransferProgress::default();
+        assert!(format_transfer_progress(&empty, 4).starts_with("[####] 100%"));
+
+        let json = transfer_progress_to_json(&item);
+        assert_eq!(json["event"], "transfer_progress");
+        assert_eq!(json["bytes_skipped"], 1024);
+    }
+
+    #[test]
+    fn format_output_renders_rows_in_each_format() {
+        let records = Records::list(vec![
+            json!({"job_id": 1, "status": "completed", "labels": {"exp": "a"}}),
+            json!({"job_id": 2, "status": "failed, retried", "note": null}),
+        ]);
+        let table = || "table\n".to_string();
+
+        assert_eq!(
+            format_output(&OutputFormat::Table, &records, table).unwrap(),
+            "table\n"
+        );
+        let jsonl = format_output(&OutputFormat::Jsonl, &records, table).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(jsonl.starts_with(r#"{"job_id":1,"#));
+        let yaml = format_output(&OutputFormat::Yaml, &records, table).unwrap();
+        assert!(yaml.starts_with("- job_id: 1\n"));
+        assert_eq!(
+            format_output(&OutputFormat::Csv, &records, table).unwrap(),
+            "job_id,labels,status,note\n1,\"{\"\"exp\"\":\"\"a\"\"}\",completed,\n2,,\"failed, retried\",\n"
+        );
+        assert_eq!(
+            format_output(&OutputFormat::Tsv, &records, table).unwrap(),
+            "job_id\tlabels\tstatus\tnote\n1\t\"{\"\"exp\"\":\"\"a\"\"}\"\tcompleted\t\n2\t\tfailed, retried\t\n"
+        );
+        let template = OutputFormat::Template("{{ job_id }} {{ status | upper }}".to_string());
+        assert_eq!(
+            format_output(&template, &records, table).unwrap(),
+            "1 COMPLETED\n2 FAILED, RETRIED\n"
+        );
+    }
+
+    #[test]
+    fn output_format_parses_names_and_templates() {
+        assert_eq!("yaml".parse::<OutputFormat>(), Ok(OutputFormat::Yaml));
+        assert_eq!(
+            "template={{name}}".parse::<OutputFormat>(),
+            Ok(OutputFormat::Template("{{name}}".to_string()))
+        );
+        assert!("template=".parse::<OutputFormat>().is_err());
+        assert!("xml".parse::<OutputFormat>().is_err());
+
+        let records = Records::one(json!({"name": "a"}));
+        let broken = OutputFormat::Template("{{ name".to_string());
+        assert!(format_output(&broken, &records, String::new).is_err());
+    }
+
+    #[test]
+    fn ls_entries_render_as_table_and_json() {
+        let entries = vec![
+            LsEntry {
+                name: "results".to_string(),
+                kind: ls_entry::Kind::Directory as i32,
+                size: 4096,
+                mtime: 1767225600,
+            },
+            LsEntry {
+                name: "slurm.out".to_string(),
+                kind: ls_entry::Kind::File as i32,
+                size: 120,
+                mtime: 1767225660,
+            },
+        ];
+        let table = format_ls_table(&entries);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("name"));
+        assert!(lines[1].starts_with("results/"));
+        assert!(lines[1].contains("4.0 KiB"));
+        assert!(lines[2].contains("120 B"));
+        assert!(lines[2].ends_with("2026-01-01T00:01:00Z"));
+
+        let json = ls_entry_to_json(&entries[1]);
+        assert_eq!(json["type"], "file");
+        assert_eq!(json["size"], 120);
+        assert_eq!(json["mtime"], "2026-01-01T00:01:00Z");
+    }
+
+    fn dir_listing_sample() -> ListDirResult {
+        let entry = |path: &str, kind: ls_entry::Kind, size: u64, mode: u32| DirEntry {
+            path: path.to_string(),
+            kind: kind as i32,
+            size,
+            mode,
+            mtime: Some(1767225600),
+            symlink_target: None,
+            depth: path.matches('/').count() as u32 + 1,
+        };
+        let mut latest = entry("latest", ls_entry::Kind::Symlink, 7, 0o777);
+        latest.symlink_target = Some("results/run-2".to_string());
+        latest.mtime = None;
+        ListDirResult {
+            path: "/scratch/runs/7".to_string(),
+            entries: vec![
+                latest,
+                entry("results", ls_entry::Kind::Directory, 4096, 0o755),
+                entry("results/metrics.json", ls_entry::Kind::File, 200, 0o644),
+                entry("slurm.out", ls_entry::Kind::File, 120, 0o600),
+            ],
+            total_files: 3,
+            total_dirs: 1,
+            total_bytes: 327,
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn dir_listing_renders_long_and_tree_styles() {
+        let listing = dir_listing_sample();
+
+        let long = format_dir_listing(&listing, DirListingStyle::Long);
+        let lines: Vec<&str> = long.lines().collect();
+        assert!(lines[0].starts_with("mode"));
+        assert!(lines[1].starts_with("lrwxrwxrwx"));
+        assert!(lines[1].ends_with("latest -> results/run-2"));
+        assert!(lines[2].starts_with("drwxr-xr-x"));
+        assert!(lines[2].ends_with("results/"));
+        assert!(lines[4].starts_with("-rw-------"));
+        assert_eq!(lines[5], "3 files, 1 directories, 327 B");
+
+        let tree = format_dir_listing(&listing, DirListingStyle::Tree);
+        assert_eq!(
+            tree,
+            "/scratch/runs/7\n  latest -> results/run-2\n  results/\n    metrics.json\n  \
+             slurm.out\n3 files, 1 directories, 327 B\n"
+        );
+
+        let mut truncated = listing.clone();
+        truncated.truncated = true;
+        let short = format_dir_listing(&truncated, DirListingStyle::Short);
+        let rows: Vec<&str> = short.lines().collect();
+        assert!(rows[3].starts_with("results/metrics.json"));
+        assert!(short.ends_with("narrow it with --depth or --glob\n"));
+    }
+
+    #[test]
+    fn list_dir_records_keep_totals_in_the_document() {
+        let records = list_dir_records(&dir_listing_sample());
+        assert_eq!(records.rows.len(), 4);
+        assert_eq!(records.rows[0]["type"], "symlink");
+        assert_eq!(records.rows[0]["mtime"], serde_json::Value::Null);
+        assert_eq!(records.rows[0]["symlink_target"], "results/run-2");
+        assert_eq!(records.rows[3]["mode"], "0600");
+        assert_eq!(records.rows[2]["depth"], 2);
+        assert_eq!(records.document["total_bytes"], 327);
+        assert_eq!(records.document["entries"][1]["path"], "results");
+    }
+
+    #[test]
+    fn job_log_records_number_lines() {
+        let records = job_log_records(7, "stderr", b"first\nsecond\n");
+        assert_eq!(records.rows.len(), 2);
+        assert_eq!(records.rows[1]["line"], 2);
+        assert_eq!(records.rows[1]["text"], "second");
+        assert_eq!(records.rows[1]["stream"], "stderr");
+    }
 }
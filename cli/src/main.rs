@@ -3,27 +3,41 @@
 
 use anyhow::bail;
 use clap::{CommandFactory, FromArgMatches};
-use cli::args::{Cli, ClusterCmd, Cmd, JobCmd};
+use cli::args::{Cli, ClusterCacheCmd, ClusterCmd, Cmd, JobCmd};
 use cli::client::{
-    fetch_list_clusters, fetch_list_jobs, send_add_cluster, send_delete_cluster, send_job_logs,
-    send_job_ls, send_job_retrieve, send_ls, send_ping, send_resolve_home_dir, send_submit,
-    validate_cluster_live,
+    fetch_cache_gc, fetch_cluster_accounts, fetch_cluster_nodes, fetch_cluster_queue,
+    fetch_cluster_usage, fetch_job_efficiency, fetch_list_clusters, fetch_list_jobs,
+    send_add_cluster, send_archive_jobs, send_clean_run_dirs, send_delete_cluster,
+    send_delete_jobs, send_job_adopt, send_job_logs, send_job_ls, send_job_retrieve,
+    send_job_verify, send_label_job, send_ls, send_ping, send_read_job_file, send_resolve_home_dir,
+    send_submit, validate_cluster_live,
 };
 use cli::config;
-use cli::filters::submit_filters_from_matches;
+use cli::filters::{
+    job_cat_request, job_head_request, job_labels, job_tail_request, list_jobs_request,
+    retrieve_filters_from_matches, submit_filters_from_matches, submit_symlink_policy,
+};
 use cli::format::{
-    cluster_host_string, format_cluster_details, format_cluster_details_json, format_clusters_json,
-    format_clusters_table, format_job_details, format_job_details_json, format_jobs_json,
-    format_jobs_table,
+    DirListingStyle, OutputFormat, Records, TRACKED_JOB_MARKER, cluster_account_to_json,
+    cluster_host_string, cluster_nodes_records, cluster_queue_job_to_json, cluster_to_json,
+    format_cache_gc_summary, format_cluster_accounts_table, format_cluster_details,
+    format_cluster_nodes, format_cluster_queue_table, format_cluster_usage_table,
+    format_clusters_table, format_dir_listing, format_job_details, format_job_efficiency,
+    format_job_efficiency_table, format_jobs_table, format_labels, format_ls_table, format_output,
+    job_efficiency_summary_records, job_efficiency_to_json, job_log_records, job_to_json,
+    list_dir_records, ls_entry_to_json, run_dir_usage_to_json,
 };
 use cli::interactive::{
     confirm_action, prompt_default_base_path, resolve_add_cluster_args,
     validate_default_base_path_with_feedback,
 };
 use cli::sbatch::resolve_sbatch_script;
-use cli::stream::print_with_green_check_stdout;
-use proto::ListJobsUnitResponse;
+use cli::stream::{print_with_green_check_stdout, print_with_green_marked_lines_stdout};
 use proto::agent_client::AgentClient;
+use proto::{
+    ArchiveJobsRequest, CleanRunDirsRequestInit, ClusterQueueRequestInit, LabelJobRequest,
+    ListJobsRequest, ListJobsUnitResponse, clean_run_dirs_request_init,
+};
 use std::io::Write;
 use std::path::PathBuf;
 
@@ -55,7 +69,9 @@ async fn main() -> anyhow::Result<()> {
     let matches = cmd.get_matches();
     let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
     let submit_filters = submit_filters_from_matches(&matches);
+    let retrieve_filters = retrieve_filters_from_matches(&matches);
     let daemon_endpoint = config::daemon_endpoint(cli.config.clone())?;
+    let format = cli.output.format();
     match cli.cmd {
         Cmd::Ping => {
             let mut client = AgentClient::connect(daemon_endpoint.clone()).await?;
@@ -93,6 +109,17 @@ async fn main() -> anyhow::Result<()> {
                         args.headless,
                     )?;
                     let resolved_local_path_display = resolved_local_path.display().to_string();
+                    let cwd = std::env::current_dir()?;
+                    let fetch_on_complete = args
+                        .fetch_on_complete
+                        .into_iter()
+                        .map(|rule| proto::JobFetchRule {
+                            remote_path: rule.remote_path,
+                            local_dir: rule
+                                .local_dir
+                                .map(|dir| cwd.join(dir).display().to_string()),
+                        })
+                        .collect();
                     print_with_green_check_stdout(&format!(
                         "Selected sbatch script: {sbatchscript}"
                     ))?;
@@ -102,27 +129,51 @@ async fn main() -> anyhow::Result<()> {
                     let _ = std::io::stdout().flush();
                     send_submit(
                         &mut client,
-                        &args.name,
-                        &resolved_local_path_display,
-                        &args.remote_path,
-                        args.new_directory,
-                        args.force,
-                        &sbatchscript,
-                        &submit_filters,
+                        proto::SubmitRequestInit {
+                            local_path: resolved_local_path_display,
+                            remote_path: args.remote_path,
+                            name: args.name,
+                            sbatchscript,
+                            filters: submit_filters,
+                            new_directory: args.new_directory,
+                            force: args.force,
+                            no_ignore_files: args.no_ignore_files,
+                            dry_run: args.dry_run,
+                            checksum: args.checksum,
+                            compress: args.compress,
+                            symlinks: submit_symlink_policy(args.symlinks) as i32,
+                            delete: args.delete,
+                            fetch_on_complete,
+                            cache: args.cache,
+                            labels: job_labels(args.labels),
+                            note: args.note,
+                        },
+                        args.headless,
                     )
                     .await?
                 }
                 JobCmd::List(args) => {
-                    let response = fetch_list_jobs(&mut client, args.cluster).await?;
-                    if args.json {
-                        let output = format_jobs_json(&response.jobs)?;
-                        println!("{output}");
-                    } else {
-                        print!("{}", format_jobs_table(&response.jobs));
+                    let request = list_jobs_request(
+                        args,
+                        time::OffsetDateTime::now_utc(),
+                        &std::env::current_dir()?,
+                    );
+                    let response = fetch_list_jobs(&mut client, request).await?;
+                    let records = Records::list(response.jobs.iter().map(job_to_json).collect());
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || format_jobs_table(&response.jobs))?
+                    );
+                    if let Some(cursor) = response.next_cursor.as_deref() {
+                        eprintln!("more jobs follow; continue with --cursor {cursor}");
                     }
                 }
                 JobCmd::Get(args) => {
-                    let response = fetch_list_jobs(&mut client, args.cluster.clone()).await?;
+                    let request = ListJobsRequest {
+                        name: args.cluster.clone(),
+                        ..ListJobsRequest::default()
+                    };
+                    let response = fetch_list_jobs(&mut client, request).await?;
                     let matches: Vec<&ListJobsUnitResponse> = response
                         .jobs
                         .iter()
@@ -136,12 +187,11 @@ async fn main() -> anyhow::Result<()> {
                             bail!("job id {} not found", args.job_id);
                         }
                         [job] => {
-                            if args.json {
-                                let output = format_job_details_json(job)?;
-                                println!("{output}");
-                            } else {
-                                print!("{}", format_job_details(job));
-                            }
+                            let records = Records::one(job_to_json(job));
+                            print!(
+                                "{}",
+                                format_output(&format, &records, || { format_job_details(job) })?
+                            );
                         }
                         _ => {
                             if args.cluster.is_some() {
@@ -155,22 +205,188 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
                 JobCmd::Logs(args) => {
-                    let code = send_job_logs(&mut client, args.job_id, args.err).await?;
+                    let code = if format == OutputFormat::Table {
+                        send_job_logs(&mut client, args.job_id, args.err, &mut std::io::stdout())
+                            .await?
+                    } else {
+                        let mut output = Vec::new();
+                        let code =
+                            send_job_logs(&mut client, args.job_id, args.err, &mut output).await?;
+                        if code == 0 {
+                            let stream = if args.err { "stderr" } else { "stdout" };
+                            let records = job_log_records(args.job_id, stream, &output);
+                            print!("{}", format_output(&format, &records, String::new)?);
+                        }
+                        code
+                    };
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                JobCmd::Cat(args) => {
+                    let code = send_read_job_file(
+                        &mut client,
+                        job_cat_request(args),
+                        &mut std::io::stdout(),
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                JobCmd::Head(args) => {
+                    let code = send_read_job_file(
+                        &mut client,
+                        job_head_request(args),
+                        &mut std::io::stdout(),
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                JobCmd::Tail(args) => {
+                    let code = send_read_job_file(
+                        &mut client,
+                        job_tail_request(args),
+                        &mut std::io::stdout(),
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                JobCmd::Clean(args) => {
+                    let code = send_clean_run_dirs(
+                        &mut client,
+                        CleanRunDirsRequestInit {
+                            target: Some(clean_run_dirs_request_init::Target::JobId(args.job_id)),
+                            dry_run: args.dry_run,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                JobCmd::Label(args) => {
+                    let response = send_label_job(
+                        &mut client,
+                        LabelJobRequest {
+                            job_id: args.job_id,
+                            set: job_labels(args.labels),
+                            remove: args.remove,
+                            note: args.note,
+                            clear_note: args.clear_note,
+                        },
+                    )
+                    .await?;
+                    let Some(job) = response.job else {
+                        bail!("job id {} not found", args.job_id);
+                    };
+                    println!("labels: {}", format_labels(&job.labels));
+                    if let Some(note) = job.note.as_deref() {
+                        println!("note: {note}");
+                    }
+                }
+                JobCmd::Rm(args) => {
+                    let response = send_delete_jobs(&mut client, args.job_ids, args.force).await?;
+                    if !response.deleted.is_empty() {
+                        let ids: Vec<String> =
+                            response.deleted.iter().map(|id| id.to_string()).collect();
+                        println!("Deleted job records: {}", ids.join(", "));
+                    }
+                    for id in &response.not_found {
+                        eprintln!("job {id} not found");
+                    }
+                    for id in &response.running {
+                        eprintln!("job {id} has not finished; pass --force to delete its record");
+                    }
+                    if !response.not_found.is_empty() || !response.running.is_empty() {
+                        std::process::exit(1);
+                    }
+                }
+                JobCmd::Prune(args) => {
+                    let archive_path = match args.archive {
+                        Some(path) => {
+                            let path =
+                                PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).as_ref());
+                            Some(std::env::current_dir()?.join(path))
+                        }
+                        None => None,
+                    };
+                    let response = send_archive_jobs(
+                        &mut client,
+                        ArchiveJobsRequest {
+                            name: args.cluster,
+                            before: Some(args.before),
+                            states: args.states,
+                            archive_path: archive_path
+                                .as_ref()
+                                .map(|path| path.to_string_lossy().into_owned()),
+                            dry_run: args.dry_run,
+                        },
+                    )
+                    .await?;
+                    if response.jobs.is_empty() {
+                        println!("No job records match.");
+                    } else if args.dry_run {
+                        println!("Would prune {} job records:", response.jobs.len());
+                        print!("{}", format_jobs_table(&response.jobs));
+                    } else if let Some(path) = archive_path {
+                        println!(
+                            "Pruned {} job records (archived to {}).",
+                            response.jobs.len(),
+                            path.display()
+                        );
+                    } else {
+                        println!("Pruned {} job records.", response.jobs.len());
+                    }
+                }
+                JobCmd::Verify(args) => {
+                    let code = send_job_verify(&mut client, args.job_id).await?;
                     if code != 0 {
                         std::process::exit(code);
                     }
                 }
                 JobCmd::Ls(args) => {
-                    send_job_ls(&mut client, args.job_id, &args.path, &args.cluster).await?
+                    let listing = send_job_ls(
+                        &mut client,
+                        args.job_id,
+                        &args.path,
+                        &args.cluster,
+                        args.max_depth(),
+                        &args.glob,
+                    )
+                    .await?;
+                    let style = if args.tree {
+                        DirListingStyle::Tree
+                    } else if args.long {
+                        DirListingStyle::Long
+                    } else {
+                        DirListingStyle::Short
+                    };
+                    print!(
+                        "{}",
+                        format_output(&format, &list_dir_records(&listing), || {
+                            format_dir_listing(&listing, style)
+                        })?
+                    );
                 }
                 JobCmd::Retrieve(args) => {
                     let code = send_job_retrieve(
                         &mut client,
-                        args.job_id,
-                        &args.path,
-                        &args.output,
-                        args.overwrite,
-                        args.force,
+                        proto::RetrieveJobRequestInit {
+                            job_id: args.job_id,
+                            path: args.path,
+                            local_path: None,
+                            overwrite: args.overwrite,
+                            force: args.force,
+                            filters: retrieve_filters,
+                            checksum: args.checksum,
+                        },
+                        &args.dest,
                         args.headless,
                     )
                     .await?;
@@ -178,19 +394,51 @@ async fn main() -> anyhow::Result<()> {
                         std::process::exit(code);
                     }
                 }
+                JobCmd::Efficiency(args) => {
+                    let response =
+                        fetch_job_efficiency(&mut client, args.job_id, args.cluster, args.since)
+                            .await?;
+                    let output = if args.job_id.is_some() {
+                        let Some(job) = response.jobs.first() else {
+                            bail!("job id {} not found", args.job_id.unwrap_or_default());
+                        };
+                        let records = Records::one(job_efficiency_to_json(job));
+                        format_output(&format, &records, || format_job_efficiency(job))?
+                    } else {
+                        let records = job_efficiency_summary_records(&response.jobs);
+                        format_output(&format, &records, || {
+                            format_job_efficiency_table(&response.jobs)
+                        })?
+                    };
+                    print!("{output}");
+                }
+                JobCmd::Adopt(args) => {
+                    let code = send_job_adopt(
+                        &mut client,
+                        &args.cluster,
+                        args.scheduler_id,
+                        args.all_mine,
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
             }
         }
         Cmd::Cluster(cluster_args) => {
             let mut client = AgentClient::connect(daemon_endpoint.clone()).await?;
             match cluster_args.cmd {
-                ClusterCmd::List(args) => {
+                ClusterCmd::List => {
                     let response = fetch_list_clusters(&mut client, "").await?;
-                    if args.json {
-                        let output = format_clusters_json(&response.clusters)?;
-                        println!("{output}");
-                    } else {
-                        print!("{}", format_clusters_table(&response.clusters));
-                    }
+                    let records =
+                        Records::list(response.clusters.iter().map(cluster_to_json).collect());
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || {
+                            format_clusters_table(&response.clusters)
+                        })?
+                    );
                 }
                 ClusterCmd::Get(args) => {
                     let response = fetch_list_clusters(&mut client, "").await?;
@@ -201,15 +449,20 @@ async fn main() -> anyhow::Result<()> {
                     else {
                         bail!("cluster '{}' not found", args.name);
                     };
-                    if args.json {
-                        let output = format_cluster_details_json(cluster)?;
-                        println!("{output}");
-                    } else {
-                        print!("{}", format_cluster_details(cluster));
-                    }
+                    let records = Records::one(cluster_to_json(cluster));
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || { format_cluster_details(cluster) })?
+                    );
                 }
                 ClusterCmd::Ls(args) => {
-                    send_ls(&mut client, &args.name, &args.path).await?
+                    let listing = send_ls(&mut client, &args.name, &args.path).await?;
+                    let records =
+                        Records::list(listing.entries.iter().map(ls_entry_to_json).collect());
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || { format_ls_table(&listing.entries) })?
+                    );
                 }
                 ClusterCmd::Add(args) => {
                     println!("Adding new cluster...");
@@ -262,9 +515,7 @@ async fn main() -> anyhow::Result<()> {
                         loop {
                             let base_path = prompt_default_base_path(&home_dir)?;
                             match validate_default_base_path_with_feedback(
-                                &base_path,
-                                true,
-                                headless,
+                                &base_path, true, headless,
                             ) {
                                 Ok(()) => {
                                     resolved.default_base_path = Some(base_path);
@@ -385,6 +636,95 @@ async fn main() -> anyhow::Result<()> {
                     }
                     println!("Cluster '{}' deleted.", args.name);
                 }
+                ClusterCmd::Queue(args) => {
+                    let jobs = fetch_cluster_queue(
+                        &mut client,
+                        ClusterQueueRequestInit {
+                            name: args.name,
+                            user: args.user,
+                            mine: args.mine,
+                            partition: args.partition,
+                            state: args.state,
+                            start: args.start,
+                        },
+                    )
+                    .await?;
+                    match &format {
+                        OutputFormat::Table => print_with_green_marked_lines_stdout(
+                            &format_cluster_queue_table(&jobs),
+                            TRACKED_JOB_MARKER,
+                        )?,
+                        format => {
+                            let records =
+                                Records::list(jobs.iter().map(cluster_queue_job_to_json).collect());
+                            print!("{}", format_output(format, &records, String::new)?);
+                        }
+                    }
+                }
+                ClusterCmd::Nodes(args) => {
+                    let result =
+                        fetch_cluster_nodes(&mut client, &args.name, args.partition).await?;
+                    let records = cluster_nodes_records(&result, args.nodes);
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || {
+                            format_cluster_nodes(&result, args.nodes)
+                        })?
+                    );
+                }
+                ClusterCmd::Accounts(args) => {
+                    let accounts = fetch_cluster_accounts(&mut client, &args.name).await?;
+                    let records =
+                        Records::list(accounts.iter().map(cluster_account_to_json).collect());
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || {
+                            if accounts.is_empty() {
+                                format!("no associations found for your user on '{}'\n", args.name)
+                            } else {
+                                format_cluster_accounts_table(&accounts)
+                            }
+                        })?
+                    );
+                }
+                ClusterCmd::Usage(args) => {
+                    let dirs = fetch_cluster_usage(&mut client, &args.name).await?;
+                    let records = Records::list(dirs.iter().map(run_dir_usage_to_json).collect());
+                    print!(
+                        "{}",
+                        format_output(&format, &records, || {
+                            if dirs.is_empty() {
+                                format!("no run directories are tracked on '{}'\n", args.name)
+                            } else {
+                                format_cluster_usage_table(&dirs)
+                            }
+                        })?
+                    );
+                }
+                ClusterCmd::Gc(args) => {
+                    let code = send_clean_run_dirs(
+                        &mut client,
+                        CleanRunDirsRequestInit {
+                            target: Some(clean_run_dirs_request_init::Target::Name(args.name)),
+                            older_than_secs: Some(args.older_than),
+                            state: args.state,
+                            dry_run: args.dry_run,
+                        },
+                    )
+                    .await?;
+                    if code != 0 {
+                        std::process::exit(code);
+                    }
+                }
+                ClusterCmd::Cache(cache) => match cache.cmd {
+                    ClusterCacheCmd::Gc(args) => {
+                        let result = fetch_cache_gc(&mut client, &args.name, args.dry_run).await?;
+                        println!(
+                            "{}",
+                            format_cache_gc_summary(result.blobs, result.bytes, args.dry_run)
+                        );
+                    }
+                },
             }
         }
     }
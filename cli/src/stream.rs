@@ -2,6 +2,7 @@
 // Copyright (C) 2026 Alex Sizykh
 
 use crate::errors::{format_server_error, format_status_error};
+use crate::format::{format_submit_dry_run, format_transfer_progress, transfer_progress_to_json};
 use crate::mfa::collect_mfa_answers;
 use anyhow::bail;
 use crossterm::{
@@ -9,8 +10,8 @@ use crossterm::{
     style::{Color, Print, ResetColor, SetForegroundColor},
 };
 use proto::{
-    MfaAnswer, StreamEvent, SubmitStreamEvent, stream_event, submit_result, submit_status,
-    submit_stream_event,
+    MfaAnswer, StreamEvent, SubmitStreamEvent, TransferProgress, stream_event, submit_result,
+    submit_status, submit_stream_event,
 };
 use ratatui::symbols::braille;
 use std::future::Future;
@@ -113,6 +114,58 @@ impl MinDurationSpinner {
     }
 }
 
+const PROGRESS_BAR_WIDTH: usize = 24;
+
+/// Renders `TransferProgress` events: a redrawn bar on a terminal, newline-delimited
+/// JSON on stdout in headless mode, nothing otherwise.
+pub struct TransferProgressBar {
+    headless: bool,
+    use_tty: bool,
+    drawn: bool,
+}
+
+impl TransferProgressBar {
+    pub fn new(headless: bool) -> Self {
+        Self {
+            headless,
+            use_tty: std::io::stderr().is_terminal(),
+            drawn: false,
+        }
+    }
+
+    pub fn update(&mut self, progress: &TransferProgress) -> anyhow::Result<()> {
+        if self.headless {
+            println!("{}", transfer_progress_to_json(progress));
+            return Ok(());
+        }
+        if !self.use_tty {
+            return Ok(());
+        }
+        let mut line = format_transfer_progress(progress, PROGRESS_BAR_WIDTH);
+        if let Ok((cols, _)) = crossterm::terminal::size()
+            && line.chars().count() >= cols as usize
+        {
+            line = line
+                .chars()
+                .take((cols as usize).saturating_sub(1))
+                .collect();
+        }
+        let mut stderr = std::io::stderr();
+        write!(stderr, "\r{line}\x1b[K")?;
+        stderr.flush()?;
+        self.drawn = true;
+        Ok(())
+    }
+
+    /// Moves past the bar so following output starts on a fresh line.
+    pub fn finish(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum OutputStream {
     Stdout,
@@ -213,6 +266,7 @@ where
                     exit_code = Some(1);
                     break;
                 }
+                stream_event::Event::TransferProgress(_) => {}
             },
             Ok(StreamEvent { event: None }) => log::info!("received empty event"),
             Err(status) => {
@@ -283,6 +337,7 @@ where
                     cancel_spinner = true;
                     break;
                 }
+                stream_event::Event::TransferProgress(_) => {}
             },
             Ok(StreamEvent { event: None }) => log::info!("received empty event"),
             Err(status) => {
@@ -312,6 +367,7 @@ pub enum SubmitStreamOutcome {
 pub async fn handle_submit_stream_events<S, F, Fut>(
     mut inbound: S,
     mut send_mfa: F,
+    headless: bool,
 ) -> anyhow::Result<SubmitStreamOutcome>
 where
     S: Stream<Item = Result<SubmitStreamEvent, Status>> + Unpin,
@@ -321,6 +377,7 @@ where
     let mut exit_code: Option<i32> = None;
     let mut spinner: Option<Spinner> = None;
     let mut printed_remote_path = false;
+    let mut progress_bar = TransferProgressBar::new(headless);
     let ctrl_c = tokio::signal::ctrl_c();
     tokio::pin!(ctrl_c);
 
@@ -330,6 +387,7 @@ where
                 if let Some(spinner) = spinner.take() {
                     spinner.stop(None).await;
                 }
+                progress_bar.finish();
                 return Ok(SubmitStreamOutcome::Canceled);
             }
             item = inbound.next() => item,
@@ -337,6 +395,14 @@ where
         let Some(item) = item else {
             break;
         };
+        if !matches!(
+            item,
+            Ok(SubmitStreamEvent {
+                event: Some(submit_stream_event::Event::TransferProgress(_))
+            })
+        ) {
+            progress_bar.finish();
+        }
         match item {
             Ok(SubmitStreamEvent { event: Some(ev) }) => match ev {
                 submit_stream_event::Event::Stdout(bytes) => {
@@ -401,6 +467,23 @@ where
                         submit_status::Phase::Unspecified => {}
                     }
                 }
+                submit_stream_event::Event::TransferProgress(progress) => {
+                    if let Some(spinner) = spinner.take() {
+                        spinner.stop(None).await;
+                    }
+                    progress_bar.update(&progress)?;
+                }
+                submit_stream_event::Event::DryRun(result) => {
+                    if let Some(spinner) = spinner.take() {
+                        spinner.stop(None).await;
+                    }
+                    print_with_green_check_stdout(
+                        "Dry run: nothing was transferred or submitted.",
+                    )?;
+                    print!("{}", format_submit_dry_run(&result));
+                    exit_code = Some(0);
+                    break;
+                }
                 submit_stream_event::Event::SubmitResult(result) => {
                     if let Some(spinner) = spinner.take() {
                         spinner.stop(None).await;
@@ -524,6 +607,28 @@ fn write_stdout_with_green_ticks(bytes: &[u8]) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print `text` to stdout, colouring lines that start with `marker` green on a terminal.
+pub fn print_with_green_marked_lines_stdout(text: &str, marker: char) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return write_all(&mut stdout, text.as_bytes());
+    }
+    for line in text.split_inclusive('\n') {
+        if line.starts_with(marker) {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Green),
+                Print(line.trim_end_matches('\n')),
+                ResetColor,
+                Print("\n")
+            )?;
+        } else {
+            write_all(&mut stdout, line.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
 pub fn print_with_green_check_stdout(message: &str) -> anyhow::Result<()> {
     let mut line = String::from("✓ ");
     line.push_str(message);
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+use crate::agent::slurm::{
+    SlurmAssociation, SshareEntry, parse_sacctmgr_assoc, parse_sshare, sacctmgr_assoc_command,
+    sshare_command,
+};
+use crate::ssh::SessionManager;
+use crate::state::db::{ClusterAccount, HostRecord, HostStore};
+
+/// Join associations with the user's `sshare` rows of the same account.
+pub fn merge_accounts(
+    assocs: Vec<SlurmAssociation>,
+    shares: &[SshareEntry],
+) -> Vec<ClusterAccount> {
+    assocs
+        .into_iter()
+        .map(|assoc| {
+            let share = shares.iter().find(|s| s.account == assoc.account);
+            ClusterAccount {
+                account: assoc.account,
+                partition: assoc.partition,
+                qos: assoc.qos,
+                default_qos: assoc.default_qos,
+                raw_shares: share.and_then(|s| s.raw_shares),
+                norm_shares: share.and_then(|s| s.norm_shares),
+                raw_usage: share.and_then(|s| s.raw_usage),
+                effective_usage: share.and_then(|s| s.effective_usage),
+                fairshare: share.and_then(|s| s.fairshare),
+            }
+        })
+        .collect()
+}
+
+/// Query associations and fairshare of the cluster user and cache them.
+///
+/// A failing `sshare` only drops the fairshare columns; a failing `sacctmgr` is an error.
+pub async fn fetch_and_store_accounts(
+    sm: &SessionManager,
+    hosts: &HostStore,
+    host: &HostRecord,
+) -> anyhow::Result<Vec<ClusterAccount>> {
+    let (out, err, code) = sm
+        .exec_capture(&sacctmgr_assoc_command(&host.username))
+        .await?;
+    if code != 0 {
+        anyhow::bail!(
+            "sacctmgr returned {code}: {}",
+            String::from_utf8_lossy(&err).trim()
+        );
+    }
+    let assocs = parse_sacctmgr_assoc(&String::from_utf8_lossy(&out));
+
+    let shares = match sm.exec_capture(&sshare_command(&host.username)).await {
+        Ok((out, _, 0)) => parse_sshare(&String::from_utf8_lossy(&out)),
+        Ok((_, err, code)) => {
+            log::debug!(
+                "sshare returned {code} on {}: {}",
+                host.name,
+                String::from_utf8_lossy(&err).trim()
+            );
+            Vec::new()
+        }
+        Err(e) => {
+            log::debug!("sshare failed on {}: {e}", host.name);
+            Vec::new()
+        }
+    };
+
+    let accounts = merge_accounts(assocs, &shares);
+    hosts.replace_cluster_accounts(host.id, &accounts).await?;
+    Ok(accounts)
+}
+
+/// Warnings for an account/QoS request that the cached associations don't allow.
+///
+/// Nothing is reported without cached associations, since the cluster may not use them.
+pub fn account_warnings(
+    accounts: &[ClusterAccount],
+    account: Option<&str>,
+    qos: Option<&str>,
+) -> Vec<String> {
+    if accounts.is_empty() {
+        return Vec::new();
+    }
+    let mut warnings = Vec::new();
+    let candidates: Vec<&ClusterAccount> = match account {
+        Some(name) => {
+            let matching: Vec<&ClusterAccount> =
+                accounts.iter().filter(|a| a.account == name).collect();
+            if matching.is_empty() {
+                let mut known: Vec<&str> = accounts.iter().map(|a| a.account.as_str()).collect();
+                known.sort();
+                known.dedup();
+                warnings.push(format!(
+                    "account '{name}' is not associated with your user (known: {})",
+                    known.join(", ")
+                ));
+                return warnings;
+            }
+            matching
+        }
+        None => accounts.iter().collect(),
+    };
+    if let Some(qos) = qos
+        && !candidates
+            .iter()
+            .any(|a| a.qos.is_empty() || a.qos.iter().any(|q| q == qos))
+    {
+        let mut allowed: Vec<&str> = candidates
+            .iter()
+            .flat_map(|a| a.qos.iter().map(String::as_str))
+            .collect();
+        allowed.sort();
+        allowed.dedup();
+        let target = match account {
+            Some(name) => format!("account '{name}'"),
+            None => "any of your accounts".to_string(),
+        };
+        warnings.push(format!(
+            "qos '{qos}' is not allowed for {target} (allowed: {})",
+            allowed.join(", ")
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assoc(account: &str, qos: &[&str]) -> SlurmAssociation {
+        SlurmAssociation {
+            account: account.into(),
+            partition: None,
+            qos: qos.iter().map(|q| q.to_string()).collect(),
+            default_qos: None,
+        }
+    }
+
+    fn accounts() -> Vec<ClusterAccount> {
+        merge_accounts(
+            vec![
+                assoc("proj_a", &["normal", "long"]),
+                assoc("proj_b", &["gpu"]),
+            ],
+            &[SshareEntry {
+                account: "proj_a".into(),
+                raw_shares: Some(1),
+                norm_shares: Some(0.5),
+                raw_usage: Some(42),
+                effective_usage: Some(0.1),
+                fairshare: Some(0.8),
+            }],
+        )
+    }
+
+    #[test]
+    fn merges_fairshare_by_account() {
+        let merged = accounts();
+        assert_eq!(merged[0].fairshare, Some(0.8));
+        assert_eq!(merged[0].raw_usage, Some(42));
+        assert_eq!(merged[1].fairshare, None);
+    }
+
+    #[test]
+    fn warns_about_unknown_account_and_disallowed_qos() {
+        let accounts = accounts();
+        assert!(account_warnings(&accounts, Some("proj_a"), Some("long")).is_empty());
+        assert!(account_warnings(&accounts, None, Some("gpu")).is_empty());
+        assert!(account_warnings(&[], Some("nope"), Some("nope")).is_empty());
+
+        let warnings = account_warnings(&accounts, Some("nope"), None);
+        assert_eq!(
+            warnings,
+            vec!["account 'nope' is not associated with your user (known: proj_a, proj_b)"]
+        );
+        let warnings = account_warnings(&accounts, Some("proj_b"), Some("long"));
+        assert_eq!(
+            warnings,
+            vec!["qos 'long' is not allowed for account 'proj_b' (allowed: gpu)"]
+        );
+    }
+}
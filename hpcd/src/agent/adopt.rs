@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+use crate::agent::sbatch;
+use crate::agent::slurm::{
+    ScontrolJob, parse_scontrol_job, parse_squeue_job_ids, slurm_state_is_terminal,
+};
+use crate::ssh::{SessionManager, sh_escape};
+use crate::state::db::{HostRecord, HostStore, HostStoreError, JOB_ORIGIN_ADOPTED, NewJob};
+use thiserror::Error as ThisError;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdoptOutcome {
+    /// A new jobs row was created with this internal id.
+    Adopted(i64),
+    /// The scheduler job is already tracked under this internal id.
+    AlreadyTracked(i64),
+}
+
+#[derive(Debug, ThisError)]
+pub enum AdoptError {
+    #[error("scheduler job {0} not found")]
+    NotFound(i64),
+    #[error("remote command failed: {0}")]
+    Remote(String),
+    #[error("database error: {0}")]
+    Database(#[from] HostStoreError),
+}
+
+/// Active scheduler job ids of the cluster user, via `squeue`.
+pub async fn list_user_job_ids(
+    sm: &SessionManager,
+    username: &str,
+) -> Result<Vec<i64>, AdoptError> {
+    let command = format!("squeue -h -u {} -o %A", sh_escape(username));
+    let (out, err, code) = sm
+        .exec_capture(&command)
+        .await
+        .map_err(|e| AdoptError::Remote(e.to_string()))?;
+    if code != 0 {
+        return Err(AdoptError::Remote(format!(
+            "squeue returned {code}: {}",
+            String::from_utf8_lossy(&err).trim()
+        )));
+    }
+    Ok(parse_squeue_job_ids(&String::from_utf8_lossy(&out)))
+}
+
+/// Start tracking a job that was submitted outside the daemon.
+///
+/// Paths come from `scontrol show job -o`; jobs already known for the host are left as is.
+pub async fn adopt_job(
+    sm: &SessionManager,
+    hosts: &HostStore,
+    host: &HostRecord,
+    scheduler_id: i64,
+) -> Result<AdoptOutcome, AdoptError> {
+    if let Some(existing) = hosts.job_id_for_scheduler_id(host.id, scheduler_id).await? {
+        return Ok(AdoptOutcome::AlreadyTracked(existing));
+    }
+
+    let command = format!("scontrol show job {scheduler_id} -o");
+    let (out, err, code) = sm
+        .exec_capture(&command)
+        .await
+        .map_err(|e| AdoptError::Remote(e.to_string()))?;
+    if code != 0 {
+        let err_text = String::from_utf8_lossy(&err);
+        if err_text.to_ascii_lowercase().contains("invalid job id") {
+            return Err(AdoptError::NotFound(scheduler_id));
+        }
+        return Err(AdoptError::Remote(format!(
+            "scontrol returned {code}: {}",
+            err_text.trim()
+        )));
+    }
+    let Some(job) = parse_scontrol_job(&String::from_utf8_lossy(&out)) else {
+        return Err(AdoptError::NotFound(scheduler_id));
+    };
+
+    let new_job = adopted_new_job(host, &job);
+    let id = hosts.insert_job(&new_job, JOB_ORIGIN_ADOPTED).await?;
+    match job.job_state.as_deref() {
+        Some(state) if slurm_state_is_terminal(state) => {
+            hosts.mark_job_completed(id, Some(state)).await?;
+        }
+        Some(state) => {
+            hosts.update_job_scheduler_state(id, Some(state)).await?;
+        }
+        None => {}
+    }
+    Ok(AdoptOutcome::Adopted(id))
+}
+
+fn adopted_new_job(host: &HostRecord, job: &ScontrolJob) -> NewJob {
+    let stdout_path = job.std_out.clone().unwrap_or_else(|| {
+        sbatch::resolve_log_path(
+            sbatch::DEFAULT_STDOUT_TEMPLATE,
+            &job.work_dir,
+            job.job_id,
+            None,
+            job.user.as_deref().or(Some(host.username.as_str())),
+        )
+    });
+    // Slurm reports StdErr == StdOut when both streams go to one file; store that as "no
+    // separate stderr" like native submissions do.
+    let stderr_path = job.std_err.clone().filter(|path| *path != stdout_path);
+    NewJob {
+        scheduler_id: Some(job.job_id),
+        host_id: host.id,
+        local_path: String::new(),
+        remote_path: job.work_dir.clone(),
+        stdout_path,
+        stderr_path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> HostRecord {
+        HostRecord {
+            id: 3,
+            name: "cluster-a".into(),
+            username: "alice".into(),
+            address: crate::state::db::Address::Hostname("login".into()),
+            slurm: crate::state::db::SlurmVersion {
+                major: 23,
+                minor: 11,
+                patch: 5,
+            },
+            distro: crate::state::db::Distro {
+                name: "ubuntu".into(),
+                version: "22.04".into(),
+            },
+            kernel_version: "6.5.0".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            updated_at: "2026-01-01T00:00:00Z".into(),
+            port: 22,
+            identity_path: None,
+            accounting_available: true,
+            default_base_path: None,
+        }
+    }
+
+    fn scontrol_job(std_out: Option<&str>, std_err: Option<&str>) -> ScontrolJob {
+        ScontrolJob {
+            job_id: 77,
+            user: Some("alice".into()),
+            job_state: Some("RUNNING".into()),
+            work_dir: "/home/alice/proj".into(),
+            std_out: std_out.map(str::to_string),
+            std_err: std_err.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn adopted_job_uses_scontrol_paths() {
+        let job = scontrol_job(
+            Some("/home/alice/proj/out.log"),
+            Some("/home/alice/proj/err.log"),
+        );
+        let nj = adopted_new_job(&host(), &job);
+        assert_eq!(nj.scheduler_id, Some(77));
+        assert_eq!(nj.host_id, 3);
+        assert_eq!(nj.local_path, "");
+        assert_eq!(nj.remote_path, "/home/alice/proj");
+        assert_eq!(nj.stdout_path, "/home/alice/proj/out.log");
+        assert_eq!(nj.stderr_path.as_deref(), Some("/home/alice/proj/err.log"));
+    }
+
+    #[test]
+    fn adopted_job_falls_back_to_default_stdout_and_merges_stderr() {
+        let nj = adopted_new_job(&host(), &scontrol_job(None, None));
+        assert_eq!(nj.stdout_path, "/home/alice/proj/slurm-77.out");
+        assert_eq!(nj.stderr_path, None);
+
+        let same = "/home/alice/proj/slurm-77.out";
+        let nj = adopted_new_job(&host(), &scontrol_job(Some(same), Some(same)));
+        assert_eq!(nj.stderr_path, None);
+    }
+}
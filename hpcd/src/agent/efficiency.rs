@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+use crate::agent::slurm::{SacctStepUsage, parse_sacct_usage, sacct_usage_command};
+use crate::ssh::SessionManager;
+use crate::state::db::{HostStore, JobRecord, JobStepUsage};
+use proto::{JobEfficiencyUnitResponse, JobStepEfficiency};
+use std::collections::HashMap;
+
+impl From<SacctStepUsage> for JobStepUsage {
+    fn from(step: SacctStepUsage) -> Self {
+        JobStepUsage {
+            step_id: step.step_id,
+            state: step.state,
+            elapsed_secs: step.elapsed_secs,
+            total_cpu_secs: step.total_cpu_secs,
+            alloc_cpus: step.alloc_cpus,
+            nnodes: step.nnodes,
+            max_rss_bytes: step.max_rss_bytes,
+            req_mem_bytes: step.req_mem_bytes,
+            timelimit_secs: step.timelimit_secs,
+        }
+    }
+}
+
+/// Fetch sacct usage for the given jobs in one call and store it.
+///
+/// `jobs` pairs internal job ids with scheduler ids. Returns how many jobs got usage stored;
+/// jobs sacct knows nothing about are left untouched.
+pub async fn fetch_and_store_job_usage(
+    sm: &SessionManager,
+    hosts: &HostStore,
+    jobs: &[(i64, i64)],
+) -> anyhow::Result<usize> {
+    if jobs.is_empty() {
+        return Ok(0);
+    }
+    let scheduler_ids: Vec<i64> = jobs.iter().map(|(_, sid)| *sid).collect();
+    let command = sacct_usage_command(&scheduler_ids);
+    let (out, err, code) = sm.exec_capture(&command).await?;
+    if code != 0 {
+        anyhow::bail!(
+            "sacct returned {code}: {}",
+            String::from_utf8_lossy(&err).trim()
+        );
+    }
+
+    let mut by_scheduler_id: HashMap<i64, Vec<JobStepUsage>> = HashMap::new();
+    for step in parse_sacct_usage(&String::from_utf8_lossy(&out)) {
+        if let Some(sid) = step.scheduler_id() {
+            by_scheduler_id.entry(sid).or_default().push(step.into());
+        }
+    }
+
+    let mut stored = 0;
+    for (job_id, sid) in jobs {
+        let Some(steps) = by_scheduler_id.get(sid) else {
+            continue;
+        };
+        hosts.replace_job_usage(*job_id, steps).await?;
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+fn ratio(num: f64, den: f64) -> Option<f64> {
+    if den > 0.0 { Some(num / den) } else { None }
+}
+
+fn step_cpu_efficiency(step: &JobStepUsage) -> Option<f64> {
+    ratio(
+        step.total_cpu_secs,
+        step.elapsed_secs as f64 * step.alloc_cpus as f64,
+    )
+}
+
+/// Build a seff-style report for a job from its stored sacct usage.
+///
+/// CPU efficiency uses the allocation line (falling back to the sum of steps when sacct reports
+/// no CPU time there), memory efficiency compares the largest step MaxRSS to the memory
+/// requested per node, and time utilisation compares elapsed time to the time limit. MaxRSS
+/// is the peak of a single task, so the request of a multi-node job is split over its nodes.
+pub fn job_efficiency_to_api_unit_response(
+    job: &JobRecord,
+    steps: &[JobStepUsage],
+) -> JobEfficiencyUnitResponse {
+    let allocation = steps
+        .iter()
+        .find(|s| !s.step_id.contains('.'))
+        .or(steps.first());
+    let req_mem_bytes = allocation.and_then(|a| a.req_mem_bytes);
+    let req_mem_per_node = allocation
+        .and_then(|a| Some(a.req_mem_bytes? / a.nnodes.max(1)))
+        .filter(|b| *b > 0);
+    let max_rss_bytes = steps.iter().filter_map(|s| s.max_rss_bytes).max();
+
+    let mut out = JobEfficiencyUnitResponse {
+        name: job.name.clone(),
+        job_id: job.id,
+        scheduler_id: job.scheduler_id,
+        terminal_state: job.terminal_state.clone(),
+        created_at: job.created_at.clone(),
+        has_usage: allocation.is_some(),
+        req_mem_bytes,
+        max_rss_bytes,
+        ..Default::default()
+    };
+    let Some(allocation) = allocation else {
+        return out;
+    };
+
+    let mut total_cpu_secs = allocation.total_cpu_secs;
+    if total_cpu_secs <= 0.0 {
+        total_cpu_secs = steps
+            .iter()
+            .filter(|s| s.step_id.contains('.'))
+            .map(|s| s.total_cpu_secs)
+            .sum();
+    }
+    out.elapsed_secs = allocation.elapsed_secs;
+    out.timelimit_secs = allocation.timelimit_secs;
+    out.alloc_cpus = allocation.alloc_cpus;
+    out.total_cpu_secs = total_cpu_secs;
+    out.cpu_efficiency = ratio(
+        total_cpu_secs,
+        allocation.elapsed_secs as f64 * allocation.alloc_cpus as f64,
+    );
+    out.mem_efficiency = match (max_rss_bytes, req_mem_per_node) {
+        (Some(used), Some(req)) => ratio(used as f64, req as f64),
+        _ => None,
+    };
+    out.time_utilisation = allocation
+        .timelimit_secs
+        .and_then(|limit| ratio(allocation.elapsed_secs as f64, limit as f64));
+    out.steps = steps
+        .iter()
+        .map(|s| JobStepEfficiency {
+            step_id: s.step_id.clone(),
+            state: s.state.clone(),
+            elapsed_secs: s.elapsed_secs,
+            total_cpu_secs: s.total_cpu_secs,
+            alloc_cpus: s.alloc_cpus,
+            max_rss_bytes: s.max_rss_bytes,
+            cpu_efficiency: step_cpu_efficiency(s),
+            mem_efficiency: match (s.max_rss_bytes, req_mem_per_node) {
+                (Some(used), Some(req)) => ratio(used as f64, req as f64),
+                _ => None,
+            },
+        })
+        .collect();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> JobRecord {
+        JobRecord {
+            id: 1,
+            scheduler_id: Some(123),
+            name: "cluster-a".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            finished_at: Some("2026-01-01T00:10:00Z".into()),
+            is_completed: true,
+            terminal_state: Some("COMPLETED".into()),
+            scheduler_state: Some("COMPLETED".into()),
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-123.out".into(),
+            stderr_path: None,
+            origin: "submitted".into(),
+            cleaned_at: None,
+            job_name: None,
+            note: None,
+        }
+    }
+
+    fn step(id: &str, total_cpu: f64, rss: Option<i64>, req: Option<i64>) -> JobStepUsage {
+        JobStepUsage {
+            step_id: id.into(),
+            state: "COMPLETED".into(),
+            elapsed_secs: 600,
+            total_cpu_secs: total_cpu,
+            alloc_cpus: 4,
+            nnodes: 1,
+            max_rss_bytes: rss,
+            req_mem_bytes: req,
+            timelimit_secs: Some(2400),
+        }
+    }
+
+    #[test]
+    fn computes_seff_style_ratios() {
+        let steps = vec![
+            step("123", 1200.0, None, Some(4096)),
+            step("123.batch", 1200.0, Some(1024), None),
+            step("123.extern", 0.0, Some(10), None),
+        ];
+        let report = job_efficiency_to_api_unit_response(&job(), &steps);
+        assert!(report.has_usage);
+        assert_eq!(report.cpu_efficiency, Some(0.5));
+        assert_eq!(report.mem_efficiency, Some(0.25));
+        assert_eq!(report.time_utilisation, Some(0.25));
+        assert_eq!(report.max_rss_bytes, Some(1024));
+        assert_eq!(report.steps.len(), 3);
+        assert_eq!(report.steps[1].cpu_efficiency, Some(0.5));
+        assert_eq!(report.steps[1].mem_efficiency, Some(0.25));
+    }
+
+    #[test]
+    fn falls_back_to_step_cpu_time() {
+        let steps = vec![
+            step("123", 0.0, None, Some(4096)),
+            step("123.0", 600.0, Some(2048), None),
+            step("123.1", 600.0, Some(1024), None),
+        ];
+        let report = job_efficiency_to_api_unit_response(&job(), &steps);
+        assert_eq!(report.total_cpu_secs, 1200.0);
+        assert_eq!(report.cpu_efficiency, Some(0.5));
+        assert_eq!(report.mem_efficiency, Some(0.5));
+    }
+
+    #[test]
+    fn multi_node_memory_efficiency_is_per_node() {
+        let mut allocation = step("123", 2400.0, None, Some(8192));
+        allocation.nnodes = 2;
+        let mut run = step("123.0", 2400.0, Some(2048), None);
+        run.nnodes = 2;
+        let steps = vec![allocation, step("123.batch", 0.0, Some(512), None), run];
+        let report = job_efficiency_to_api_unit_response(&job(), &steps);
+        assert_eq!(report.req_mem_bytes, Some(8192));
+        assert_eq!(report.mem_efficiency, Some(0.5));
+        assert_eq!(report.steps[1].mem_efficiency, Some(0.125));
+        assert_eq!(report.steps[2].mem_efficiency, Some(0.5));
+    }
+
+    #[test]
+    fn no_usage_yields_empty_report() {
+        let report = job_efficiency_to_api_unit_response(&job(), &[]);
+        assert!(!report.has_usage);
+        assert_eq!(report.cpu_efficiency, None);
+        assert!(report.steps.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Background download of the outputs a job declared with `--fetch-on-complete`.
+//!
+//! Rules are stored with the job at submit time. After every job check, the pending rules
+//! of finished jobs are claimed and retrieved like `hpc job retrieve --overwrite` would,
+//! one task per job, and the outcome is recorded on each rule. Verified downloads are
+//! added to the job's file manifest. Rules of a cluster that
+//! cannot be reached stay pending and are tried again on the next check. Rules of a
+//! cluster that asks for MFA fail, as nobody is there to answer the prompt.
+
+use crate::agent::rpc::resolve_retrieve_local_target;
+use crate::agent::service::AgentSvc;
+use crate::agent::verify::manifest_entries;
+use crate::ssh::{MfaUnanswered, RetrieveOptions};
+use crate::state::db::{
+    FETCH_DONE, FETCH_FAILED, FETCH_PENDING, JobFetchRule, JobRecord, NewJobFile,
+};
+use crate::util;
+use crate::util::remote_path::normalize_path;
+use anyhow::Context;
+use proto::{MfaAnswer, StreamEvent};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tonic::Status;
+
+/// Claims the pending fetch rules of finished jobs and downloads them in the background.
+pub async fn spawn_pending_fetches(svc: &AgentSvc) -> anyhow::Result<()> {
+    let rules = svc.hosts().claim_pending_job_fetch_rules().await?;
+    let mut by_job: BTreeMap<i64, Vec<JobFetchRule>> = BTreeMap::new();
+    for rule in rules {
+        by_job.entry(rule.job_id).or_default().push(rule);
+    }
+    for (job_id, rules) in by_job {
+        let svc = svc.clone();
+        tokio::spawn(async move {
+            fetch_job_outputs(&svc, job_id, rules).await;
+        });
+    }
+    Ok(())
+}
+
+async fn fetch_job_outputs(svc: &AgentSvc, job_id: i64, rules: Vec<JobFetchRule>) {
+    let hosts = svc.hosts();
+    let job = match hosts.get_job_by_job_id(job_id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("could not load job {job_id} to fetch its outputs: {e}");
+            postpone(svc, &rules, &e.to_string()).await;
+            return;
+        }
+    };
+    let sm = match svc.get_sessionmanager(&job.name).await {
+        Ok(sm) => sm,
+        Err(e) => {
+            log::warn!(
+                "failed to get session for {} to fetch job {job_id}: {e}",
+                job.name
+            );
+            postpone(svc, &rules, &e.to_string()).await;
+            return;
+        }
+    };
+    // Nobody listens to a background download: progress and MFA prompts go nowhere.
+    let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(1);
+    drop(evt_rx);
+    if sm.needs_connect().await {
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(1);
+        drop(mfa_tx);
+        if let Err(e) = sm.ensure_connected(&evt_tx, &mut mfa_rx).await {
+            if e.chain().any(|cause| cause.is::<MfaUnanswered>()) {
+                log::warn!(
+                    "{} requires MFA; not fetching outputs of job {job_id} in the background",
+                    job.name
+                );
+                let reason = format!(
+                    "{} requires MFA, which a background fetch cannot answer; use `hpc job retrieve`",
+                    job.name
+                );
+                set_rules_state(svc, &rules, FETCH_FAILED, &reason).await;
+                return;
+            }
+            log::warn!(
+                "failed to connect to {} to fetch job {job_id}: {e}",
+                job.name
+            );
+            postpone(svc, &rules, &e.to_string()).await;
+            return;
+        }
+    }
+
+    for rule in rules {
+        let (state, error) = match fetch_rule(&sm, &job, &rule, &evt_tx).await {
+            Ok(files) => {
+                log::info!(
+                    "fetched {} of job {job_id} into {}",
+                    rule.remote_path,
+                    rule.local_dir
+                );
+                if let Err(e) = hosts.record_job_files(job_id, &files).await {
+                    log::warn!("failed to record file manifest of job {job_id}: {e}");
+                }
+                (FETCH_DONE, None)
+            }
+            Err(e) => {
+                log::warn!(
+                    "failed to fetch {} of job {job_id}: {e:#}",
+                    rule.remote_path
+                );
+                (FETCH_FAILED, Some(format!("{e:#}")))
+            }
+        };
+        if let Err(e) = hosts
+            .update_job_fetch_rule(rule.id, state, error.as_deref())
+            .await
+        {
+            log::warn!("failed to record fetch state of job {job_id}: {e}");
+        }
+    }
+}
+
+async fn fetch_rule(
+    sm: &crate::ssh::SessionManager,
+    job: &JobRecord,
+    rule: &JobFetchRule,
+    evt_tx: &tokio::sync::mpsc::Sender<Result<StreamEvent, Status>>,
+) -> anyhow::Result<Vec<NewJobFile>> {
+    let path_is_absolute = Path::new(&rule.remote_path).is_absolute();
+    let remote_path = if path_is_absolute {
+        normalize_path(&rule.remote_path)
+    } else {
+        util::remote_path::resolve_relative(&job.remote_path, &rule.remote_path)
+    }
+    .to_string_lossy()
+    .into_owned();
+    let local_target = resolve_retrieve_local_target(
+        &rule.remote_path,
+        &remote_path,
+        Path::new(&rule.local_dir),
+        path_is_absolute,
+    )
+    .with_context(|| format!("invalid remote path {}", rule.remote_path))?;
+    let options = RetrieveOptions {
+        overwrite: true,
+        ..Default::default()
+    };
+    let verified = sm
+        .retrieve_path(&remote_path, &local_target, &options, evt_tx)
+        .await?;
+    Ok(manifest_entries(&verified, false))
+}
+
+/// Hands `rules` back to the queue so the next job check tries them again.
+async fn postpone(svc: &AgentSvc, rules: &[JobFetchRule], reason: &str) {
+    set_rules_state(svc, rules, FETCH_PENDING, reason).await;
+}
+
+async fn set_rules_state(svc: &AgentSvc, rules: &[JobFetchRule], state: &str, reason: &str) {
+    for rule in rules {
+        if let Err(e) = svc
+            .hosts()
+            .update_job_fetch_rule(rule.id, state, Some(reason))
+            .await
+        {
+            log::warn!("failed to record fetch state of rule {}: {e}", rule.id);
+        }
+    }
+}
@@ -1,11 +1,30 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
-use crate::ssh::{SyncFilterAction, SyncFilterRule};
-use crate::state::db::{HostRecord, HostStore, JobRecord};
 use crate::agent::error_codes;
-use proto::{ListClustersUnitResponse, ListJobsUnitResponse};
-use proto::{SubmitPathFilterAction, SubmitPathFilterRule, list_clusters_unit_response};
+use crate::agent::sbatch::{
+    DEFAULT_STDOUT_TEMPLATE, log_template_glob, parse_sbatch_log_templates,
+};
+use crate::agent::slurm::QueueEntry;
+use crate::ssh::{
+    DirListing, EntryKind, SymlinkAction, SymlinkEntry, SymlinkPolicy, SyncFilterAction,
+    SyncFilterRule, SyncItemStatus, SyncPreviewItem,
+};
+use crate::state::db::{
+    ClusterAccount, FETCH_DONE, FETCH_FAILED, FETCH_PENDING, FETCH_RUNNING, HostRecord, HostStore,
+    JobFetchRule, JobLabel, JobQuery, JobRecord, JobSort, JobStatus, NewJobFetchRule, db_time,
+};
+use proto::{
+    ClusterQueueJob, JobFetchStatus, ListClustersUnitResponse, ListDirResult, ListJobsRequest,
+    ListJobsUnitResponse, LsEntry, job_fetch_status, list_jobs_request, ls_entry,
+};
+use proto::{
+    SubmitDryRunItem, SubmitDryRunSymlink, SubmitPathFilterAction, SubmitPathFilterRule,
+    SubmitSymlinkPolicy, list_clusters_unit_response, submit_dry_run_item, submit_dry_run_symlink,
+};
+use std::path::Path;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tonic::Status;
 
 pub fn build_sync_filters(
@@ -31,6 +50,225 @@ pub fn build_sync_filters(
     Ok(out)
 }
 
+/// Fetch rules of a submit, with outputs placed in `default_local_dir` unless a rule names
+/// its own (absolute) local directory.
+pub fn build_fetch_rules(
+    rules: Vec<proto::JobFetchRule>,
+    default_local_dir: &str,
+) -> Result<Vec<NewJobFetchRule>, Status> {
+    let mut out = Vec::with_capacity(rules.len());
+    for rule in rules {
+        if rule.remote_path.trim().is_empty() {
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        let local_dir = match rule.local_dir {
+            Some(dir) if !Path::new(&dir).is_absolute() => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+            Some(dir) => dir,
+            None => default_local_dir.to_string(),
+        };
+        out.push(NewJobFetchRule {
+            remote_path: rule.remote_path,
+            local_dir,
+        });
+    }
+    Ok(out)
+}
+
+/// Labels of a request as key/value pairs. Keys must be non-empty and free of `=` and
+/// whitespace.
+pub fn build_job_labels(labels: Vec<proto::JobLabel>) -> Result<Vec<(String, String)>, Status> {
+    labels
+        .into_iter()
+        .map(|label| {
+            if !is_valid_label_key(&label.key) {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+            Ok((label.key, label.value))
+        })
+        .collect()
+}
+
+/// The job query of a `ListJobs` request, without the cluster.
+pub fn build_job_query(request: ListJobsRequest) -> Result<JobQuery, Status> {
+    let invalid = || Status::invalid_argument(error_codes::INVALID_ARGUMENT);
+    let statuses = request
+        .states
+        .iter()
+        .map(|state| match list_jobs_request::State::try_from(*state) {
+            Ok(list_jobs_request::State::Pending) => Ok(JobStatus::Pending),
+            Ok(list_jobs_request::State::Running) => Ok(JobStatus::Running),
+            Ok(list_jobs_request::State::Completed) => Ok(JobStatus::Completed),
+            Ok(list_jobs_request::State::Failed) => Ok(JobStatus::Failed),
+            Ok(list_jobs_request::State::Unspecified) | Err(_) => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let sort = match list_jobs_request::Sort::try_from(request.sort) {
+        Ok(list_jobs_request::Sort::Unspecified | list_jobs_request::Sort::Created) => {
+            JobSort::Created
+        }
+        Ok(list_jobs_request::Sort::Finished) => JobSort::Finished,
+        Err(_) => return Err(invalid()),
+    };
+    // Bounds are compared as text with the stored times, so they take the same format.
+    let utc = |value: Option<String>| -> Result<Option<String>, Status> {
+        value
+            .map(|v| {
+                OffsetDateTime::parse(&v, &Rfc3339)
+                    .map(db_time)
+                    .map_err(|_| invalid())
+            })
+            .transpose()
+    };
+    if request.limit == Some(0)
+        || request
+            .local_path_prefix
+            .as_deref()
+            .is_some_and(|prefix| !Path::new(prefix).is_absolute())
+    {
+        return Err(invalid());
+    }
+    Ok(JobQuery {
+        host_id: None,
+        statuses,
+        created_after: utc(request.created_after)?,
+        created_before: utc(request.created_before)?,
+        finished_after: utc(request.finished_after)?,
+        finished_before: utc(request.finished_before)?,
+        local_path_prefix: request.local_path_prefix,
+        scheduler_id: request.scheduler_id,
+        labels: build_job_labels(request.labels)?,
+        sort,
+        descending: request.descending,
+        limit: request.limit,
+        after: request
+            .cursor
+            .map(|cursor| cursor.parse().map_err(|_| invalid()))
+            .transpose()?,
+    })
+}
+
+pub fn is_valid_label_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains('=') && !key.chars().any(char::is_whitespace)
+}
+
+pub fn job_label_to_api(label: &JobLabel) -> proto::JobLabel {
+    proto::JobLabel {
+        key: label.key.clone(),
+        value: label.value.clone(),
+    }
+}
+
+fn entry_kind_to_api(kind: EntryKind) -> ls_entry::Kind {
+    match kind {
+        EntryKind::File => ls_entry::Kind::File,
+        EntryKind::Dir => ls_entry::Kind::Directory,
+        EntryKind::Symlink => ls_entry::Kind::Symlink,
+        EntryKind::Other => ls_entry::Kind::Other,
+    }
+}
+
+/// `ls` entries of a one-level listing, sorted by name.
+pub fn dir_listing_to_ls_entries(listing: DirListing) -> Vec<LsEntry> {
+    listing
+        .entries
+        .into_iter()
+        .map(|entry| LsEntry {
+            name: entry.path,
+            kind: entry_kind_to_api(entry.kind) as i32,
+            size: entry.size,
+            mtime: entry.mtime.map_or(0, i64::from),
+        })
+        .collect()
+}
+
+pub fn dir_listing_to_api(path: String, listing: DirListing) -> ListDirResult {
+    let (total_files, total_dirs, total_bytes) = listing.totals();
+    let entries = listing
+        .entries
+        .into_iter()
+        .map(|entry| proto::DirEntry {
+            path: entry.path,
+            kind: entry_kind_to_api(entry.kind) as i32,
+            size: entry.size,
+            mode: entry.mode,
+            mtime: entry.mtime.map(i64::from),
+            symlink_target: entry.symlink_target,
+            depth: entry.depth,
+        })
+        .collect();
+    ListDirResult {
+        path,
+        entries,
+        total_files,
+        total_dirs,
+        total_bytes,
+        truncated: listing.truncated,
+    }
+}
+
+pub fn build_symlink_policy(policy: i32) -> Result<SymlinkPolicy, Status> {
+    match SubmitSymlinkPolicy::try_from(policy) {
+        Ok(SubmitSymlinkPolicy::Unspecified | SubmitSymlinkPolicy::SkipSymlinks) => {
+            Ok(SymlinkPolicy::Skip)
+        }
+        Ok(SubmitSymlinkPolicy::PreserveSymlinks) => Ok(SymlinkPolicy::Preserve),
+        Ok(SubmitSymlinkPolicy::FollowSymlinks) => Ok(SymlinkPolicy::Follow),
+        Err(_) => Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT)),
+    }
+}
+
+/// Patterns, relative to `remote_path`, that a mirror sync must not delete: the logs of
+/// jobs already recorded there and whatever the log templates of the sbatch script at
+/// `sbatch_path` can produce.
+pub async fn mirror_protected_patterns(
+    hs: &HostStore,
+    name: &str,
+    remote_path: &str,
+    sbatch_path: &Path,
+) -> anyhow::Result<Vec<String>> {
+    let root = format!("{}/", remote_path.trim_end_matches('/'));
+    let mut patterns = Vec::new();
+    for path in hs.log_paths_for_remote_path(name, remote_path).await? {
+        if let Some(rel) = path.strip_prefix(&root) {
+            // Recorded paths are already expanded; doubling `%` keeps them literal.
+            patterns.push(format!("/{}", log_template_glob(&rel.replace('%', "%%"))));
+        }
+    }
+    let templates = match std::fs::read_to_string(sbatch_path) {
+        Ok(contents) => parse_sbatch_log_templates(&contents),
+        Err(e) => {
+            log::debug!(
+                "couldn't read {} for log templates: {e}",
+                sbatch_path.display()
+            );
+            return Ok(patterns);
+        }
+    };
+    let stdout = templates
+        .stdout
+        .unwrap_or_else(|| DEFAULT_STDOUT_TEMPLATE.to_string());
+    for template in std::iter::once(stdout).chain(templates.stderr) {
+        let rel = if Path::new(&template).is_absolute() {
+            match template.strip_prefix(&root) {
+                Some(rel) => rel.to_string(),
+                None => continue,
+            }
+        } else {
+            template
+        };
+        if rel.split('/').any(|part| part == "..") {
+            continue;
+        }
+        patterns.push(format!(
+            "/{}",
+            log_template_glob(rel.trim_start_matches("./"))
+        ));
+    }
+    Ok(patterns)
+}
+
 pub async fn get_default_base_path(hs: &HostStore, name: &str) -> Result<String, Status> {
     let host_data = match hs.get_by_name(name).await {
         Ok(Some(v)) => v,
@@ -81,5 +319,98 @@ pub fn db_job_record_to_api_unit_response(jr: &JobRecord) -> ListJobsUnitRespons
         scheduler_state: jr.scheduler_state.clone(),
         local_path: jr.local_path.clone(),
         remote_path: jr.remote_path.clone(),
+        origin: jr.origin.clone(),
+        fetch_on_complete: Vec::new(),
+        cleaned_at: jr.cleaned_at.clone(),
+        job_name: jr.job_name.clone(),
+        note: jr.note.clone(),
+        labels: Vec::new(),
+    }
+}
+
+pub fn job_fetch_rule_to_api(rule: &JobFetchRule) -> JobFetchStatus {
+    let state = match rule.state.as_str() {
+        FETCH_PENDING => job_fetch_status::State::Pending,
+        FETCH_RUNNING => job_fetch_status::State::Running,
+        FETCH_DONE => job_fetch_status::State::Done,
+        FETCH_FAILED => job_fetch_status::State::Failed,
+        _ => job_fetch_status::State::Unspecified,
+    };
+    JobFetchStatus {
+        remote_path: rule.remote_path.clone(),
+        local_dir: rule.local_dir.clone(),
+        state: state as i32,
+        error: rule.error.clone(),
+        fetched_at: rule.fetched_at.clone(),
+    }
+}
+
+pub fn queue_entry_to_api_job(entry: QueueEntry, tracked_job_id: Option<i64>) -> ClusterQueueJob {
+    ClusterQueueJob {
+        job_id: entry.job_id,
+        scheduler_id: entry.scheduler_id,
+        user: entry.user,
+        partition: entry.partition,
+        name: entry.name,
+        state: entry.state,
+        reason: entry.reason,
+        priority: entry.priority,
+        start_time: entry.start_time,
+        time_used: entry.time_used,
+        time_limit: entry.time_limit,
+        nodes: entry.nodes,
+        cpus: entry.cpus,
+        nodelist: entry.nodelist,
+        tracked_job_id,
+    }
+}
+
+pub fn db_cluster_account_to_api(account: ClusterAccount) -> proto::ClusterAccount {
+    proto::ClusterAccount {
+        account: account.account,
+        partition: account.partition,
+        qos: account.qos,
+        default_qos: account.default_qos,
+        raw_shares: account.raw_shares,
+        norm_shares: account.norm_shares,
+        raw_usage: account.raw_usage,
+        effective_usage: account.effective_usage,
+        fairshare: account.fairshare,
+    }
+}
+
+fn rel_path_to_api(path: &std::path::Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn symlink_entry_to_api(entry: &SymlinkEntry) -> SubmitDryRunSymlink {
+    let action = match entry.action {
+        SymlinkAction::Recreate => submit_dry_run_symlink::Action::Recreate,
+        SymlinkAction::Follow => submit_dry_run_symlink::Action::Follow,
+        SymlinkAction::Skip => submit_dry_run_symlink::Action::Skip,
+        SymlinkAction::Broken => submit_dry_run_symlink::Action::Broken,
+    };
+    SubmitDryRunSymlink {
+        path: rel_path_to_api(&entry.rel_path),
+        target: entry.target.to_string_lossy().into_owned(),
+        action: action as i32,
+        outside_root: entry.outside_root,
+    }
+}
+
+pub fn sync_preview_item_to_api(item: &SyncPreviewItem) -> SubmitDryRunItem {
+    let status = match item.status {
+        SyncItemStatus::New => submit_dry_run_item::Status::New,
+        SyncItemStatus::Changed => submit_dry_run_item::Status::Changed,
+        SyncItemStatus::Unchanged => submit_dry_run_item::Status::Unchanged,
+        SyncItemStatus::Delete => submit_dry_run_item::Status::Delete,
+    };
+    SubmitDryRunItem {
+        path: rel_path_to_api(&item.rel_path),
+        status: status as i32,
+        size: item.size,
     }
 }
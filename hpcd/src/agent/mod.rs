@@ -1,15 +1,23 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+mod accounts;
 mod add_cluster;
+mod adopt;
+mod efficiency;
 mod error_codes;
+mod fetch;
 mod helpers;
+mod nodes;
+mod prune;
 mod rpc;
+mod rundirs;
 mod sbatch;
 mod service;
 mod sessions;
 mod submit;
 mod types;
+mod verify;
 
 pub mod managers;
 pub mod os;
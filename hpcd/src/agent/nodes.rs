@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+use crate::agent::slurm::Node;
+use proto::{ClusterNode, ClusterNodeGpus, ClusterNodesResult, GpuTypeCapacity, PartitionCapacity};
+use std::collections::BTreeMap;
+
+fn node_to_api(node: &Node) -> ClusterNode {
+    ClusterNode {
+        name: node.name.clone(),
+        state: node.state.clone(),
+        available: node.is_available(),
+        partitions: node.partitions.clone(),
+        cpus_total: node.cpus_total,
+        cpus_alloc: node.cpus_alloc,
+        memory_total_mb: node.memory_total_mb,
+        memory_alloc_mb: node.memory_alloc_mb,
+        memory_free_mb: node.memory_free_mb,
+        gpus: node
+            .gpus
+            .iter()
+            .map(|g| ClusterNodeGpus {
+                gpu_type: g.gpu_type.clone(),
+                total: g.total,
+                alloc: g.alloc,
+            })
+            .collect(),
+    }
+}
+
+fn add_node_to_partition(capacity: &mut PartitionCapacity, node: &Node) {
+    let available = node.is_available();
+    capacity.nodes_total += 1;
+    if available {
+        match node.base_state().as_str() {
+            "IDLE" => capacity.nodes_idle += 1,
+            "MIXED" => capacity.nodes_mixed += 1,
+            "ALLOCATED" | "COMPLETING" => capacity.nodes_allocated += 1,
+            _ => {}
+        }
+    } else {
+        capacity.nodes_unavailable += 1;
+    }
+    capacity.cpus_total += node.cpus_total;
+    capacity.cpus_alloc += node.cpus_alloc;
+    capacity.memory_total_mb += node.memory_total_mb;
+    capacity.memory_alloc_mb += node.memory_alloc_mb;
+    for gpus in &node.gpus {
+        capacity.gpus_total += gpus.total;
+        capacity.gpus_alloc += gpus.alloc;
+        if available {
+            capacity.gpus_idle += (gpus.total - gpus.alloc).max(0);
+        }
+    }
+    if available {
+        capacity.cpus_idle += (node.cpus_total - node.cpus_alloc).max(0);
+    }
+}
+
+/// Aggregate nodes per partition and per GPU type.
+///
+/// With `partition` set, only nodes in that partition are reported. Nodes in several partitions
+/// count towards each of them; GPU types are listed under an empty name when the GRES is untyped.
+pub fn summarize_nodes(nodes: &[Node], partition: Option<&str>) -> ClusterNodesResult {
+    let nodes: Vec<&Node> = nodes
+        .iter()
+        .filter(|n| partition.is_none_or(|p| n.partitions.iter().any(|np| np == p)))
+        .collect();
+
+    let mut partitions: BTreeMap<&str, PartitionCapacity> = BTreeMap::new();
+    let mut gpu_types: BTreeMap<&str, GpuTypeCapacity> = BTreeMap::new();
+    for node in &nodes {
+        for name in &node.partitions {
+            if partition.is_some_and(|p| p != name) {
+                continue;
+            }
+            let capacity = partitions
+                .entry(name.as_str())
+                .or_insert_with(|| PartitionCapacity {
+                    partition: name.clone(),
+                    ..Default::default()
+                });
+            add_node_to_partition(capacity, node);
+        }
+        for gpus in &node.gpus {
+            let capacity =
+                gpu_types
+                    .entry(gpus.gpu_type.as_str())
+                    .or_insert_with(|| GpuTypeCapacity {
+                        gpu_type: gpus.gpu_type.clone(),
+                        ..Default::default()
+                    });
+            capacity.nodes_total += 1;
+            capacity.gpus_total += gpus.total;
+            capacity.gpus_alloc += gpus.alloc;
+            if node.is_available() {
+                capacity.gpus_idle += (gpus.total - gpus.alloc).max(0);
+            }
+            for name in &node.partitions {
+                if !capacity.partitions.contains(name) {
+                    capacity.partitions.push(name.clone());
+                }
+            }
+        }
+    }
+    for capacity in gpu_types.values_mut() {
+        capacity.partitions.sort();
+    }
+
+    ClusterNodesResult {
+        nodes: nodes.iter().map(|n| node_to_api(n)).collect(),
+        partitions: partitions.into_values().collect(),
+        gpu_types: gpu_types.into_values().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::slurm::NodeGpus;
+
+    fn node(
+        name: &str,
+        state: &str,
+        partitions: &[&str],
+        cpus: (i64, i64),
+        gpus: &[(&str, i64, i64)],
+    ) -> Node {
+        Node {
+            name: name.into(),
+            state: state.into(),
+            partitions: partitions.iter().map(|p| p.to_string()).collect(),
+            cpus_total: cpus.0,
+            cpus_alloc: cpus.1,
+            memory_total_mb: 1000,
+            memory_alloc_mb: 250,
+            memory_free_mb: None,
+            gpus: gpus
+                .iter()
+                .map(|(t, total, alloc)| NodeGpus {
+                    gpu_type: t.to_string(),
+                    total: *total,
+                    alloc: *alloc,
+                })
+                .collect(),
+        }
+    }
+
+    fn sample() -> Vec<Node> {
+        vec![
+            node(
+                "g1",
+                "MIXED",
+                &["gpu", "short"],
+                (64, 16),
+                &[("h200", 4, 1)],
+            ),
+            node("g2", "IDLE+DRAIN", &["gpu"], (64, 0), &[("h200", 4, 0)]),
+            node("g3", "IDLE", &["gpu"], (64, 0), &[("a100", 8, 0)]),
+            node("c1", "ALLOCATED", &["cpu", "short"], (48, 48), &[]),
+        ]
+    }
+
+    #[test]
+    fn aggregates_per_partition() {
+        let result = summarize_nodes(&sample(), None);
+        let names: Vec<&str> = result
+            .partitions
+            .iter()
+            .map(|p| p.partition.as_str())
+            .collect();
+        assert_eq!(names, vec!["cpu", "gpu", "short"]);
+
+        let gpu = &result.partitions[1];
+        assert_eq!(gpu.nodes_total, 3);
+        assert_eq!(
+            (gpu.nodes_idle, gpu.nodes_mixed, gpu.nodes_unavailable),
+            (1, 1, 1)
+        );
+        assert_eq!(
+            (gpu.cpus_total, gpu.cpus_alloc, gpu.cpus_idle),
+            (192, 16, 112)
+        );
+        assert_eq!((gpu.gpus_total, gpu.gpus_alloc, gpu.gpus_idle), (16, 1, 11));
+        assert_eq!(gpu.memory_total_mb, 3000);
+
+        let short = &result.partitions[2];
+        assert_eq!((short.nodes_mixed, short.nodes_allocated), (1, 1));
+        assert_eq!(short.cpus_idle, 48);
+    }
+
+    #[test]
+    fn aggregates_per_gpu_type() {
+        let result = summarize_nodes(&sample(), None);
+        assert_eq!(result.gpu_types.len(), 2);
+        let a100 = &result.gpu_types[0];
+        assert_eq!(a100.gpu_type, "a100");
+        assert_eq!((a100.gpus_total, a100.gpus_idle), (8, 8));
+        let h200 = &result.gpu_types[1];
+        assert_eq!(h200.nodes_total, 2);
+        assert_eq!(
+            (h200.gpus_total, h200.gpus_alloc, h200.gpus_idle),
+            (8, 1, 3)
+        );
+        assert_eq!(h200.partitions, vec!["gpu", "short"]);
+    }
+
+    #[test]
+    fn partition_filter_limits_nodes_and_summaries() {
+        let result = summarize_nodes(&sample(), Some("short"));
+        assert_eq!(result.nodes.len(), 2);
+        assert_eq!(result.partitions.len(), 1);
+        assert_eq!(result.partitions[0].partition, "short");
+        assert_eq!(result.gpu_types.len(), 1);
+        assert!(!summarize_nodes(&sample(), None).nodes[1].available);
+    }
+}
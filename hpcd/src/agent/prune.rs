@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Pruning finished job records from the database, on request or by the retention policy
+//! of the config file. Pruned records can be appended to a JSON-lines archive first, one
+//! object per job in the shape `hpc job list --json` prints, labels and note included.
+//!
+//! The archive path is chosen by the client and may be any absolute local path. This is
+//! intended: like the destinations of `retrieve`, it names a file of the user the daemon
+//! runs as, and the daemon only ever appends to it.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::agent::helpers::db_job_record_to_api_unit_response;
+use crate::config::RetentionPolicy;
+use crate::state::db::{HostStore, JobLabel, JobRecord};
+
+/// Which finished jobs to prune.
+#[derive(Debug, Default)]
+pub(crate) struct PruneFilter {
+    /// Only jobs on this cluster.
+    pub name: Option<String>,
+    /// Only jobs that finished (or, without a finish time, were created) before this.
+    pub before: Option<OffsetDateTime>,
+    /// Only jobs whose terminal state is one of these, compared case-insensitively.
+    pub states: Vec<String>,
+}
+
+impl PruneFilter {
+    fn matches(&self, job: &JobRecord) -> bool {
+        if !job.is_completed {
+            return false;
+        }
+        if self.name.as_ref().is_some_and(|name| *name != job.name) {
+            return false;
+        }
+        if !self.states.is_empty()
+            && !job.terminal_state.as_deref().is_some_and(|state| {
+                self.states
+                    .iter()
+                    .any(|want| want.eq_ignore_ascii_case(state))
+            })
+        {
+            return false;
+        }
+        let Some(before) = self.before else {
+            return true;
+        };
+        let at = job.finished_at.as_deref().unwrap_or(&job.created_at);
+        OffsetDateTime::parse(at, &Rfc3339).is_ok_and(|at| at < before)
+    }
+}
+
+/// The filter the retention policy applies at `now`.
+pub(crate) fn retention_filter(policy: &RetentionPolicy, now: OffsetDateTime) -> PruneFilter {
+    let max_age = time::Duration::days(policy.max_age_days.min(i64::MAX as u64 / 86400) as i64);
+    PruneFilter {
+        name: None,
+        before: now.checked_sub(max_age),
+        states: policy.states.clone(),
+    }
+}
+
+/// Appends one JSON object per job to `path`, creating the file and its directory.
+/// `labels` may hold the labels of other jobs too.
+pub(crate) fn append_to_archive(
+    path: &Path,
+    jobs: &[JobRecord],
+    labels: &[JobLabel],
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let mut lines = String::new();
+    for job in jobs {
+        let job_labels = labels.iter().filter(|label| label.job_id == job.id);
+        lines.push_str(&archive_line(job, job_labels).to_string());
+        lines.push('\n');
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open archive {}", path.display()))?;
+    file.write_all(lines.as_bytes())
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("failed to write archive {}", path.display()))
+}
+
+fn archive_line<'a>(
+    job: &JobRecord,
+    labels: impl Iterator<Item = &'a JobLabel>,
+) -> serde_json::Value {
+    let item = db_job_record_to_api_unit_response(job);
+    serde_json::json!({
+        "job_id": item.job_id,
+        "name": item.name,
+        "scheduler_id": item.scheduler_id,
+        "local_path": item.local_path,
+        "remote_path": item.remote_path,
+        "created_at": item.created_at,
+        "finished_at": item.finished_at,
+        "terminal_state": item.terminal_state,
+        "scheduler_state": item.scheduler_state,
+        "origin": item.origin,
+        "cleaned_at": item.cleaned_at,
+        "job_name": item.job_name,
+        "note": item.note,
+        "labels": labels
+            .map(|label| (label.key.clone(), serde_json::json!(label.value)))
+            .collect::<serde_json::Map<_, _>>(),
+        "stdout_path": job.stdout_path,
+        "stderr_path": job.stderr_path,
+    })
+}
+
+/// Deletes the finished jobs matching `filter`, archiving them first when `archive` is
+/// set, and returns them. With `dry_run` nothing is written.
+pub(crate) async fn prune_jobs(
+    hosts: &HostStore,
+    filter: &PruneFilter,
+    archive: Option<&Path>,
+    dry_run: bool,
+) -> anyhow::Result<Vec<JobRecord>> {
+    let mut jobs: Vec<JobRecord> = hosts
+        .list_all_jobs()
+        .await?
+        .into_iter()
+        .filter(|job| filter.matches(job))
+        .collect();
+    jobs.sort_by_key(|job| job.id);
+    if dry_run || jobs.is_empty() {
+        return Ok(jobs);
+    }
+    if let Some(path) = archive {
+        let path = path.to_path_buf();
+        let to_archive = jobs.clone();
+        let labels = hosts.list_job_labels().await?;
+        tokio::task::spawn_blocking(move || append_to_archive(&path, &to_archive, &labels))
+            .await??;
+    }
+    let ids: Vec<i64> = jobs.iter().map(|job| job.id).collect();
+    hosts.delete_jobs(&ids).await?;
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn job(id: i64, state: Option<&str>, finished_at: Option<&str>) -> JobRecord {
+        JobRecord {
+            id,
+            scheduler_id: Some(100 + id),
+            name: "cluster-a".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            finished_at: finished_at.map(str::to_string),
+            is_completed: state.is_some(),
+            terminal_state: state.map(str::to_string),
+            scheduler_state: state.map(str::to_string),
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm.out".into(),
+            stderr_path: None,
+            origin: "submitted".into(),
+            cleaned_at: None,
+            job_name: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn filter_only_matches_old_finished_jobs_in_the_given_states() {
+        let policy = RetentionPolicy {
+            max_age_days: 30,
+            states: vec!["completed".into()],
+            archive_path: None,
+        };
+        let now = OffsetDateTime::parse("2026-03-01T00:00:00Z", &Rfc3339).unwrap();
+        let filter = retention_filter(&policy, now);
+
+        assert!(filter.matches(&job(1, Some("COMPLETED"), Some("2026-01-15T00:00:00Z"))));
+        assert!(!filter.matches(&job(2, Some("COMPLETED"), Some("2026-02-15T00:00:00Z"))));
+        assert!(!filter.matches(&job(3, Some("FAILED"), Some("2026-01-15T00:00:00Z"))));
+        assert!(!filter.matches(&job(4, None, None)));
+        // Without a finish time the creation time counts.
+        assert!(filter.matches(&job(5, Some("COMPLETED"), None)));
+
+        let other_cluster = PruneFilter {
+            name: Some("cluster-b".into()),
+            ..PruneFilter::default()
+        };
+        assert!(!other_cluster.matches(&job(1, Some("COMPLETED"), None)));
+    }
+
+    #[test]
+    fn archive_appends_one_json_object_per_job() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("archive").join("jobs.jsonl");
+        let labels = [
+            JobLabel {
+                job_id: 2,
+                key: "exp".into(),
+                value: "ablation".into(),
+            },
+            JobLabel {
+                job_id: 3,
+                key: "exp".into(),
+                value: "other".into(),
+            },
+        ];
+        append_to_archive(&path, &[job(1, Some("COMPLETED"), None)], &[]).unwrap();
+        let mut noted = job(2, Some("FAILED"), None);
+        noted.note = Some("baseline".into());
+        append_to_archive(&path, &[noted], &labels).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["job_id"], 1);
+        assert_eq!(lines[1]["terminal_state"], "FAILED");
+        assert_eq!(lines[1]["stdout_path"], "/remote/run/slurm.out");
+        assert_eq!(lines[0]["labels"], serde_json::json!({}));
+        assert_eq!(lines[1]["labels"], serde_json::json!({"exp": "ablation"}));
+        assert_eq!(lines[1]["note"], "baseline");
+    }
+}
@@ -1,38 +1,73 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+use crate::agent::accounts::{account_warnings, fetch_and_store_accounts};
 use crate::agent::add_cluster::{
     map_net_error, normalize_default_base_path, parse_add_cluster_host, parse_add_cluster_port,
     resolve_host_addr,
 };
+use crate::agent::adopt::{AdoptError, AdoptOutcome, adopt_job, list_user_job_ids};
+use crate::agent::efficiency::{fetch_and_store_job_usage, job_efficiency_to_api_unit_response};
+use crate::agent::error_codes;
 use crate::agent::helpers::{
-    build_sync_filters, db_host_record_to_api_unit_response, db_job_record_to_api_unit_response,
-    get_default_base_path,
+    build_fetch_rules, build_job_labels, build_job_query, build_symlink_policy, build_sync_filters,
+    db_cluster_account_to_api, db_host_record_to_api_unit_response,
+    db_job_record_to_api_unit_response, dir_listing_to_api, dir_listing_to_ls_entries,
+    get_default_base_path, is_valid_label_key, job_fetch_rule_to_api, job_label_to_api,
+    mirror_protected_patterns, queue_entry_to_api_job, symlink_entry_to_api,
+    sync_preview_item_to_api,
+};
+use crate::agent::nodes::summarize_nodes;
+use crate::agent::prune::{PruneFilter, prune_jobs};
+use crate::agent::rundirs::{
+    RunDir, gc_candidates, group_run_dirs, measure_run_dirs, removal_refusal, remove_run_dir,
 };
-use crate::agent::error_codes;
 use crate::agent::sbatch;
 use crate::agent::service::AgentSvc;
+use crate::agent::slurm::{
+    SCONTROL_SHOW_NODES_CMD, parse_scontrol_nodes, parse_squeue_queue, squeue_queue_command,
+};
 use crate::agent::submit::{resolve_remote_sbatch_path, resolve_submit_remote_path};
-use crate::agent::types::{AgentSvcError, OutStream, SubmitOutStream};
-use crate::ssh::sh_escape;
-use crate::state::db::{Address, HostStoreError};
+use crate::agent::types::{
+    AgentSvcError, CacheGcOutStream, ClusterAccountsOutStream, ClusterNodesOutStream,
+    ClusterQueueOutStream, ClusterUsageOutStream, ListDirOutStream, LsOutStream, OutStream,
+    ReadJobFileOutStream, SubmitOutStream,
+};
+use crate::agent::verify::{FileCheck, format_checks, manifest_entries, verify_job_files};
+use crate::ssh::{FileSlice, ListDirOptions, NamePattern, NotAFile, sh_escape};
+use crate::state::db::{Address, HostStore, HostStoreError, JOB_ORIGIN_SUBMITTED, db_time};
 use crate::util;
 use crate::util::reachability;
-use crate::util::remote_path::normalize_path;
+use crate::util::remote_path::{normalize_path, resolve_remote, resolve_within};
 use proto::agent_server::Agent;
 use proto::{
-    AddClusterRequest, DeleteClusterRequest, DeleteClusterResponse, ListClustersRequest,
-    ListClustersResponse, ListClustersUnitResponse, ListJobsRequest, ListJobsResponse,
-    JobLogsRequest, JobLogsRequestInit, LsRequest, LsRequestInit, MfaAnswer, PingReply,
-    PingRequest, RetrieveJobRequest, RetrieveJobRequestInit, StreamEvent, SubmitRequest,
-    SubmitResult, SubmitStatus, SubmitStreamEvent, stream_event,
-    submit_result, submit_status, submit_stream_event,
+    AddClusterRequest, AdoptJobsRequest, AdoptJobsRequestInit, ArchiveJobsRequest,
+    ArchiveJobsResponse, CacheGcRequest, CacheGcRequestInit, CacheGcResult, CacheGcStreamEvent,
+    CleanRunDirsRequest, CleanRunDirsRequestInit, ClusterAccountsRequest,
+    ClusterAccountsRequestInit, ClusterAccountsResult, ClusterAccountsStreamEvent,
+    ClusterNodesRequest, ClusterNodesRequestInit, ClusterNodesStreamEvent, ClusterQueueRequest,
+    ClusterQueueRequestInit, ClusterQueueResult, ClusterQueueStreamEvent, ClusterUsageRequest,
+    ClusterUsageRequestInit, ClusterUsageResult, ClusterUsageStreamEvent, DeleteClusterRequest,
+    DeleteClusterResponse, DeleteJobsRequest, DeleteJobsResponse, JobEfficiencyRequest,
+    JobEfficiencyResponse, JobFetchStatus, JobLogsRequest, JobLogsRequestInit, LabelJobRequest,
+    LabelJobResponse, ListClustersRequest, ListClustersResponse, ListClustersUnitResponse,
+    ListDirRequest, ListDirRequestInit, ListDirStreamEvent, ListJobsRequest, ListJobsResponse,
+    LsRequest, LsRequestInit, LsResult, LsStreamEvent, MfaAnswer, PingReply, PingRequest,
+    ReadJobFileRequest, ReadJobFileRequestInit, ReadJobFileStreamEvent, RetrieveJobRequest,
+    RetrieveJobRequestInit, StreamEvent, SubmitDryRunResult, SubmitRequest, SubmitResult,
+    SubmitStatus, SubmitStreamEvent, VerifyJobRequest, VerifyJobRequestInit, cache_gc_stream_event,
+    clean_run_dirs_request_init, cluster_accounts_stream_event, cluster_nodes_stream_event,
+    cluster_queue_stream_event, cluster_usage_stream_event, list_dir_stream_event, ls_stream_event,
+    read_job_file_stream_event, stream_event, submit_result, submit_status, submit_stream_event,
 };
 use russh_sftp::client::error::Error as SftpError;
 use russh_sftp::protocol::StatusCode as SftpStatusCode;
+use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tonic::Status;
 
 async fn fetch_remote_home_dir(
@@ -120,7 +155,7 @@ fn is_sftp_missing_path(err: &anyhow::Error) -> bool {
     })
 }
 
-fn resolve_retrieve_local_target(
+pub(super) fn resolve_retrieve_local_target(
     path: &str,
     remote_path: &str,
     local_base: &Path,
@@ -238,14 +273,94 @@ fn format_address(addr: &Address) -> String {
     }
 }
 
+/// Resolves the cluster and remote path a listing targets: `path` relative to the job's
+/// run directory when `job_id` is set, otherwise to the cluster's default base path.
+/// Errors carry the reason for the caller's log line.
+async fn resolve_listing_target(
+    hs: &HostStore,
+    name: String,
+    path: Option<String>,
+    job_id: Option<i64>,
+) -> Result<(String, String), (Status, &'static str)> {
+    if path.as_deref().is_some_and(str::is_empty) {
+        return Err((
+            Status::invalid_argument(error_codes::INVALID_ARGUMENT),
+            "empty_path",
+        ));
+    }
+    let absolute = path
+        .as_deref()
+        .filter(|v| Path::new(v).is_absolute())
+        .map(|v| normalize_path(v).to_string_lossy().into_owned());
+    if let Some(job_id) = job_id {
+        let job = match hs.get_job_by_job_id(job_id).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                return Err((
+                    Status::invalid_argument(error_codes::NOT_FOUND),
+                    "job_not_found",
+                ));
+            }
+            Err(e) => {
+                log::debug!("could not fetch job id {job_id}: {e}");
+                return Err((Status::internal(error_codes::INTERNAL_ERROR), "db_error"));
+            }
+        };
+        if !name.is_empty() && name != job.name {
+            return Err((
+                Status::invalid_argument(error_codes::NOT_FOUND),
+                "name_mismatch",
+            ));
+        }
+        let list_path = match (absolute, path) {
+            (Some(v), _) => v,
+            (None, Some(v)) => util::remote_path::resolve_relative(&job.remote_path, v)
+                .to_string_lossy()
+                .into_owned(),
+            (None, None) => normalize_path(&job.remote_path)
+                .to_string_lossy()
+                .into_owned(),
+        };
+        return Ok((job.name, list_path));
+    }
+    if name.is_empty() {
+        return Err((
+            Status::invalid_argument(error_codes::INVALID_ARGUMENT),
+            "empty_name",
+        ));
+    }
+    if let Some(v) = absolute {
+        return Ok((name, v));
+    }
+    let base_path = match get_default_base_path(hs, &name).await {
+        Ok(v) => v,
+        Err(status) => return Err((status, "no_base_path")),
+    };
+    let list_path = match path {
+        Some(v) => util::remote_path::resolve_relative(&base_path, v),
+        None => normalize_path(&base_path),
+    };
+    Ok((name, list_path.to_string_lossy().into_owned()))
+}
+
 #[tonic::async_trait]
 impl Agent for AgentSvc {
-    type LsStream = OutStream;
+    type LsStream = LsOutStream;
+    type ListDirStream = ListDirOutStream;
     type RetrieveJobStream = OutStream;
+    type VerifyJobStream = OutStream;
     type JobLogsStream = OutStream;
+    type ReadJobFileStream = ReadJobFileOutStream;
     type SubmitStream = SubmitOutStream;
     type AddClusterStream = OutStream;
     type ResolveHomeDirStream = OutStream;
+    type AdoptJobsStream = OutStream;
+    type ClusterQueueStream = ClusterQueueOutStream;
+    type ClusterNodesStream = ClusterNodesOutStream;
+    type ClusterAccountsStream = ClusterAccountsOutStream;
+    type CacheGcStream = CacheGcOutStream;
+    type ClusterUsageStream = ClusterUsageOutStream;
+    type CleanRunDirsStream = OutStream;
 
     async fn ping(
         &self,
@@ -261,9 +376,7 @@ impl Agent for AgentSvc {
                 }))
             }
             m => {
-                log::warn!(
-                    "ping rejected remote_addr={remote_addr} message={m}"
-                );
+                log::warn!("ping rejected remote_addr={remote_addr} message={m}");
                 Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT))
             }
         }
@@ -347,9 +460,7 @@ impl Agent for AgentSvc {
                 );
                 log::debug!("failed to connect to {target}: {e}");
                 let code = error_codes::code_for_ssh_error(&e);
-                let _ = evt_tx
-                    .send(Err(Status::aborted(code)))
-                    .await;
+                let _ = evt_tx.send(Err(Status::aborted(code))).await;
                 return;
             };
 
@@ -402,11 +513,9 @@ impl Agent for AgentSvc {
             .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
 
         let (name, path, job_id) = match init.msg {
-            Some(proto::ls_request::Msg::Init(LsRequestInit {
-                name,
-                path,
-                job_id,
-            })) => (name, path, job_id),
+            Some(proto::ls_request::Msg::Init(LsRequestInit { name, path, job_id })) => {
+                (name, path, job_id)
+            }
             _ => {
                 return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
@@ -420,7 +529,7 @@ impl Agent for AgentSvc {
             "ls start remote_addr={remote_addr} name={name} job_id={job_id_label} requested_path={requested_path_label}"
         );
 
-        let (mfa_tx, mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
         tokio::spawn(async move {
             while let Ok(Some(item)) = inbound.message().await {
                 if let Some(proto::ls_request::Msg::Mfa(ans)) = item.msg
@@ -431,87 +540,256 @@ impl Agent for AgentSvc {
             }
         });
 
-        let (name, list_path) = if let Some(job_id) = job_id {
-            let job = match self.hosts().get_job_by_job_id(job_id).await {
-                Ok(Some(v)) => v,
-                Ok(None) => {
+        let (name, list_path) =
+            match resolve_listing_target(self.hosts().as_ref(), name, path, job_id).await {
+                Ok(v) => v,
+                Err((status, reason)) => {
                     log::warn!(
-                        "ls failed remote_addr={remote_addr} job_id={job_id_label} reason=job_not_found"
+                        "ls failed remote_addr={remote_addr} job_id={job_id_label} reason={reason}"
                     );
-                    return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+                    return Err(status);
                 }
-                Err(e) => {
+            };
+
+        log::info!("ls resolved remote_addr={remote_addr} name={name} list_path={list_path}");
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::UnknownName) => {
+                log::warn!("ls failed remote_addr={remote_addr} name={name} reason=not_found");
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<LsStreamEvent, Status>>(64);
+        tokio::spawn(async move {
+            let send_event = |event: ls_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx.send(Ok(LsStreamEvent { event: Some(event) })).await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "ls failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(ls_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let options = ListDirOptions {
+                max_depth: Some(1),
+                pattern: None,
+            };
+            let listing = match mgr.list_dir(&list_path, &options).await {
+                Ok(v) => v,
+                Err(err) if is_sftp_missing_path(&err) => {
                     log::warn!(
-                        "ls failed remote_addr={remote_addr} job_id={job_id_label} reason=db_error"
+                        "ls failed remote_addr={remote_addr} name={name} reason=remote_path_missing path={list_path}"
                     );
-                    log::debug!("could not fetch job id {job_id}: {e}");
-                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                    send_event(ls_stream_event::Event::Stderr(
+                        format!("{list_path}: No such file or directory\n").into_bytes(),
+                    ))
+                    .await;
+                    send_event(ls_stream_event::Event::Error(
+                        error_codes::NOT_FOUND.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "ls failed remote_addr={remote_addr} name={name} reason=sftp_failed error={err}"
+                    );
+                    send_event(ls_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
                 }
             };
-            if !name.is_empty() && name != job.name {
+            let entries = dir_listing_to_ls_entries(listing);
+            log::info!(
+                "ls completed remote_addr={remote_addr} name={name} entries={}",
+                entries.len()
+            );
+            send_event(ls_stream_event::Event::Result(LsResult {
+                path: list_path,
+                entries,
+            }))
+            .await;
+            send_event(ls_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: LsOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn list_dir(
+        &self,
+        request: tonic::Request<tonic::Streaming<ListDirRequest>>,
+    ) -> Result<tonic::Response<Self::ListDirStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in list_dir: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ListDirRequestInit {
+            name,
+            path,
+            job_id,
+            max_depth,
+            pattern,
+        } = match init.msg {
+            Some(proto::list_dir_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        let job_id_label = job_id
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "<none>".to_string());
+        log::info!(
+            "list_dir start remote_addr={remote_addr} name={name} job_id={job_id_label} path={path:?} max_depth={max_depth} pattern={pattern:?}"
+        );
+        if let Some(pattern) = pattern.as_deref()
+            && let Err(e) = NamePattern::compile(pattern)
+        {
+            log::warn!(
+                "list_dir failed remote_addr={remote_addr} job_id={job_id_label} reason=invalid_pattern error={e}"
+            );
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        let options = ListDirOptions {
+            max_depth: (max_depth > 0).then_some(max_depth),
+            pattern,
+        };
+
+        let (name, list_path) = match resolve_listing_target(
+            self.hosts().as_ref(),
+            name,
+            path,
+            job_id,
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err((status, reason)) => {
                 log::warn!(
-                    "ls failed remote_addr={remote_addr} job_id={job_id_label} name={name} reason=name_mismatch"
+                    "list_dir failed remote_addr={remote_addr} job_id={job_id_label} reason={reason}"
+                );
+                return Err(status);
+            }
+        };
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::UnknownName) => {
+                log::warn!(
+                    "list_dir failed remote_addr={remote_addr} name={name} reason=not_found"
                 );
                 return Err(Status::invalid_argument(error_codes::NOT_FOUND));
             }
-            let (job_name, run_path) = (job.name, job.remote_path);
-            let list_path = match path {
-                Some(v) => {
-                    if v.is_empty() {
-                        log::warn!(
-                            "ls failed remote_addr={remote_addr} job_id={job_id_label} reason=empty_path"
-                        );
-                        return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
-                    }
-                    if PathBuf::from(&v).is_absolute() {
-                        normalize_path(v).to_string_lossy().into_owned()
-                    } else {
-                        util::remote_path::resolve_relative(&run_path, v)
-                            .to_string_lossy()
-                            .into_owned()
-                    }
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::list_dir_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<ListDirStreamEvent, Status>>(64);
+        tokio::spawn(async move {
+            let send_event = |event: list_dir_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ListDirStreamEvent { event: Some(event) }))
+                        .await;
                 }
-                None => normalize_path(&run_path).to_string_lossy().into_owned(),
             };
-            (job_name, list_path)
-        } else {
-            if name.is_empty() {
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
                 log::warn!(
-                    "ls failed remote_addr={remote_addr} reason=empty_name"
+                    "list_dir failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
                 );
-                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+                send_event(list_dir_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
             }
-            let list_path = match path {
-                Some(v) => {
-                    if v.is_empty() {
-                        log::warn!(
-                            "ls failed remote_addr={remote_addr} name={name} reason=empty_path"
-                        );
-                        return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
-                    }
-                    if PathBuf::from(&v).is_absolute() {
-                        normalize_path(v).to_string_lossy().into_owned()
-                    } else {
-                        let default_base_path =
-                            get_default_base_path(self.hosts().as_ref(), &name).await?;
-                        let base_path = PathBuf::from(default_base_path);
-                        util::remote_path::resolve_relative(base_path, v)
-                            .to_string_lossy()
-                            .into_owned()
-                    }
+
+            let listing = match mgr.list_dir(&list_path, &options).await {
+                Ok(v) => v,
+                Err(err) if is_sftp_missing_path(&err) => {
+                    log::warn!(
+                        "list_dir failed remote_addr={remote_addr} name={name} reason=remote_path_missing path={list_path}"
+                    );
+                    send_event(list_dir_stream_event::Event::Stderr(
+                        format!("{list_path}: No such file or directory\n").into_bytes(),
+                    ))
+                    .await;
+                    send_event(list_dir_stream_event::Event::Error(
+                        error_codes::NOT_FOUND.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "list_dir failed remote_addr={remote_addr} name={name} reason=sftp_failed error={err}"
+                    );
+                    send_event(list_dir_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
                 }
-                None => normalize_path(get_default_base_path(self.hosts().as_ref(), &name).await?)
-                    .to_string_lossy()
-                    .into_owned(),
             };
-            (name, list_path)
-        };
+            log::info!(
+                "list_dir completed remote_addr={remote_addr} name={name} path={list_path} entries={} truncated={}",
+                listing.entries.len(),
+                listing.truncated
+            );
+            send_event(list_dir_stream_event::Event::Result(dir_listing_to_api(
+                list_path, listing,
+            )))
+            .await;
+            send_event(list_dir_stream_event::Event::ExitCode(0)).await;
+        });
 
-        log::info!(
-            "ls resolved remote_addr={remote_addr} name={name} list_path={list_path}"
-        );
-        let command = format!("ls -- {}", sh_escape(&list_path));
-        self.run_command(command, &name, mfa_rx).await
+        let out: ListDirOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
     }
 
     async fn retrieve_job(
@@ -530,14 +808,18 @@ impl Agent for AgentSvc {
             })?
             .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
 
-        let (job_id, path, local_path, overwrite, force) = match init.msg {
+        let (job_id, path, local_path, overwrite, force, filters, checksum) = match init.msg {
             Some(proto::retrieve_job_request::Msg::Init(RetrieveJobRequestInit {
                 job_id,
                 path,
                 local_path,
                 overwrite,
                 force,
-            })) => (job_id, path, local_path, overwrite, force),
+                filters,
+                checksum,
+            })) => (
+                job_id, path, local_path, overwrite, force, filters, checksum,
+            ),
             _ => {
                 return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
@@ -562,6 +844,15 @@ impl Agent for AgentSvc {
                 return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
         };
+        let filters = match build_sync_filters(filters) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!(
+                    "retrieve_job failed remote_addr={remote_addr} job_id={job_id} reason=invalid_filters"
+                );
+                return Err(e);
+            }
+        };
 
         let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
         tokio::spawn(async move {
@@ -624,9 +915,8 @@ impl Agent for AgentSvc {
                 log::warn!(
                     "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=job_not_completed"
                 );
-                let message = format!(
-                    "Job {job_id} is not completed; use --force to retrieve anyway.\n"
-                );
+                let message =
+                    format!("Job {job_id} is not completed; use --force to retrieve anyway.\n");
                 let _ = evt_tx
                     .send(Ok(StreamEvent {
                         event: Some(stream_event::Event::Stderr(message.into_bytes())),
@@ -680,7 +970,9 @@ impl Agent for AgentSvc {
                             error_codes::NETWORK_ERROR
                         }
                         other_error => {
-                            log::debug!("unexpected session manager error for {name}: {other_error}");
+                            log::debug!(
+                                "unexpected session manager error for {name}: {other_error}"
+                            );
                             error_codes::INTERNAL_ERROR
                         }
                     };
@@ -759,65 +1051,80 @@ impl Agent for AgentSvc {
                 }
             };
 
-            if let Err(err) = mgr
-                .retrieve_path(&remote_path, &local_target, overwrite)
+            let options = crate::ssh::RetrieveOptions {
+                overwrite,
+                checksum,
+                filters: &filters,
+                parallelism: None,
+            };
+            let verified = match mgr
+                .retrieve_path(&remote_path, &local_target, &options, &evt_tx)
                 .await
             {
-                if is_sftp_missing_path(&err) {
-                    log::warn!(
-                        "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=remote_path_missing error={err}"
-                    );
-                    let _ = evt_tx
-                        .send(Ok(StreamEvent {
-                            event: Some(stream_event::Event::Stderr(
-                                b"No such file or directory\n".to_vec(),
-                            )),
-                        }))
-                        .await;
-                    let _ = evt_tx
-                        .send(Ok(StreamEvent {
-                            event: Some(stream_event::Event::Error(
-                                error_codes::NOT_FOUND.to_string(),
-                            )),
-                        }))
-                        .await;
-                    return;
-                }
-                if is_local_path_conflict(&err) {
+                Ok(verified) => verified,
+                Err(err) => {
+                    if is_sftp_missing_path(&err) {
+                        log::warn!(
+                            "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=remote_path_missing error={err}"
+                        );
+                        let _ = evt_tx
+                            .send(Ok(StreamEvent {
+                                event: Some(stream_event::Event::Stderr(
+                                    b"No such file or directory\n".to_vec(),
+                                )),
+                            }))
+                            .await;
+                        let _ = evt_tx
+                            .send(Ok(StreamEvent {
+                                event: Some(stream_event::Event::Error(
+                                    error_codes::NOT_FOUND.to_string(),
+                                )),
+                            }))
+                            .await;
+                        return;
+                    }
+                    if is_local_path_conflict(&err) {
+                        log::warn!(
+                            "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=local_path_exists error={err}"
+                        );
+                        let message = format!("{err}; use --overwrite to replace it.");
+                        let _ = evt_tx
+                            .send(Ok(StreamEvent {
+                                event: Some(stream_event::Event::Stderr(
+                                    format!("{message}\n").into_bytes(),
+                                )),
+                            }))
+                            .await;
+                        let _ = evt_tx
+                            .send(Ok(StreamEvent {
+                                event: Some(stream_event::Event::Error(
+                                    error_codes::CONFLICT.to_string(),
+                                )),
+                            }))
+                            .await;
+                        return;
+                    }
                     log::warn!(
-                        "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=local_path_exists error={err}"
+                        "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=remote_retrieve_failed error={err}"
                     );
-                    let message = format!("{err}; use --overwrite to replace it.");
-                    let _ = evt_tx
-                        .send(Ok(StreamEvent {
-                            event: Some(stream_event::Event::Stderr(
-                                format!("{message}\n").into_bytes(),
-                            )),
-                        }))
-                        .await;
+                    log::debug!("retrieve path failed: {err}");
                     let _ = evt_tx
                         .send(Ok(StreamEvent {
                             event: Some(stream_event::Event::Error(
-                                error_codes::CONFLICT.to_string(),
+                                error_codes::REMOTE_ERROR.to_string(),
                             )),
                         }))
                         .await;
                     return;
                 }
-                log::warn!(
-                    "retrieve_job failed remote_addr={audit_remote_addr} job_id={job_id} name={name} reason=remote_retrieve_failed error={err}"
-                );
-                log::debug!("retrieve path failed: {err}");
-                let _ = evt_tx
-                    .send(Ok(StreamEvent {
-                        event: Some(stream_event::Event::Error(
-                            error_codes::REMOTE_ERROR.to_string(),
-                        )),
-                    }))
-                    .await;
-                return;
-            }
-
+            };
+            if let Err(e) = hs
+                .record_job_files(job_id, &manifest_entries(&verified, false))
+                .await
+            {
+                log::warn!("failed to record file manifest of job {job_id}: {e}");
+            }
+
             log::info!(
                 "retrieve_job completed remote_addr={audit_remote_addr} job_id={job_id} name={name} remote_path={remote_path} local_target={}",
                 local_target.to_string_lossy()
@@ -833,6 +1140,207 @@ impl Agent for AgentSvc {
         Ok(tonic::Response::new(out))
     }
 
+    async fn verify_job(
+        &self,
+        request: tonic::Request<tonic::Streaming<VerifyJobRequest>>,
+    ) -> Result<tonic::Response<Self::VerifyJobStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in verify_job: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let job_id = match init.msg {
+            Some(proto::verify_job_request::Msg::Init(VerifyJobRequestInit { job_id })) => job_id,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        log::info!("verify_job start remote_addr={remote_addr} job_id={job_id}");
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::verify_job_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(64);
+        let hs = self.hosts();
+        let svc = self.clone();
+        let audit_remote_addr = remote_addr.clone();
+        tokio::spawn(async move {
+            let job = match hs.get_job_by_job_id(job_id).await {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    log::warn!(
+                        "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} reason=job_not_found"
+                    );
+                    let _ = evt_tx
+                        .send(Ok(StreamEvent {
+                            event: Some(stream_event::Event::Error(
+                                error_codes::NOT_FOUND.to_string(),
+                            )),
+                        }))
+                        .await;
+                    return;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} reason=db_error"
+                    );
+                    log::debug!("could not fetch job id {job_id}: {e}");
+                    let _ = evt_tx
+                        .send(Ok(StreamEvent {
+                            event: Some(stream_event::Event::Error(
+                                error_codes::INTERNAL_ERROR.to_string(),
+                            )),
+                        }))
+                        .await;
+                    return;
+                }
+            };
+            let files = match hs.list_job_files(job_id).await {
+                Ok(files) => files,
+                Err(e) => {
+                    log::warn!(
+                        "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} reason=db_error"
+                    );
+                    log::debug!("could not list files of job {job_id}: {e}");
+                    let _ = evt_tx
+                        .send(Ok(StreamEvent {
+                            event: Some(stream_event::Event::Error(
+                                error_codes::INTERNAL_ERROR.to_string(),
+                            )),
+                        }))
+                        .await;
+                    return;
+                }
+            };
+            if files.is_empty() {
+                let message = format!(
+                    "no retrieved files are recorded for job {job_id}; retrieve them with 'hpc job retrieve'\n"
+                );
+                let _ = evt_tx
+                    .send(Ok(StreamEvent {
+                        event: Some(stream_event::Event::Stderr(message.into_bytes())),
+                    }))
+                    .await;
+                let _ = evt_tx
+                    .send(Ok(StreamEvent {
+                        event: Some(stream_event::Event::ExitCode(0)),
+                    }))
+                    .await;
+                return;
+            }
+
+            let mgr = match svc.get_sessionmanager(&job.name).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} name={} reason=session_unavailable",
+                        job.name
+                    );
+                    let message = match e {
+                        AgentSvcError::UnknownName => error_codes::NOT_FOUND,
+                        AgentSvcError::NetworkError(e) => {
+                            log::debug!("network error for {}: {e}", job.name);
+                            error_codes::NETWORK_ERROR
+                        }
+                        other_error => {
+                            log::debug!(
+                                "unexpected session manager error for {}: {other_error}",
+                                job.name
+                            );
+                            error_codes::INTERNAL_ERROR
+                        }
+                    };
+                    let _ = evt_tx
+                        .send(Ok(StreamEvent {
+                            event: Some(stream_event::Event::Error(message.to_string())),
+                        }))
+                        .await;
+                    return;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} name={} reason=connect_failed error={err}",
+                    job.name
+                );
+                let _ = evt_tx
+                    .send(Ok(StreamEvent {
+                        event: Some(stream_event::Event::Error(
+                            error_codes::code_for_ssh_error(&err).to_string(),
+                        )),
+                    }))
+                    .await;
+                return;
+            }
+
+            let checks = match verify_job_files(&mgr, &files).await {
+                Ok(checks) => checks,
+                Err(err) => {
+                    log::warn!(
+                        "verify_job failed remote_addr={audit_remote_addr} job_id={job_id} name={} reason=remote_hash_failed error={err}",
+                        job.name
+                    );
+                    let _ = evt_tx
+                        .send(Ok(StreamEvent {
+                            event: Some(stream_event::Event::Error(
+                                error_codes::REMOTE_ERROR.to_string(),
+                            )),
+                        }))
+                        .await;
+                    return;
+                }
+            };
+            let _ = evt_tx
+                .send(Ok(StreamEvent {
+                    event: Some(stream_event::Event::Stdout(
+                        format_checks(&files, &checks).into_bytes(),
+                    )),
+                }))
+                .await;
+            let failed = checks
+                .iter()
+                .filter(|check| **check != FileCheck::Ok)
+                .count();
+            log::info!(
+                "verify_job completed remote_addr={audit_remote_addr} job_id={job_id} name={} files={} failed={failed}",
+                job.name,
+                files.len()
+            );
+            if failed > 0 {
+                let message = format!("{failed} of {} files failed verification\n", files.len());
+                let _ = evt_tx
+                    .send(Ok(StreamEvent {
+                        event: Some(stream_event::Event::Stderr(message.into_bytes())),
+                    }))
+                    .await;
+            }
+            let _ = evt_tx
+                .send(Ok(StreamEvent {
+                    event: Some(stream_event::Event::ExitCode(i32::from(failed > 0))),
+                }))
+                .await;
+        });
+
+        let out: OutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
     async fn job_logs(
         &self,
         request: tonic::Request<tonic::Streaming<JobLogsRequest>>,
@@ -857,9 +1365,7 @@ impl Agent for AgentSvc {
                 return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
         };
-        log::info!(
-            "job_logs start remote_addr={remote_addr} job_id={job_id} stderr={stderr}"
-        );
+        log::info!("job_logs start remote_addr={remote_addr} job_id={job_id} stderr={stderr}");
 
         let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
         tokio::spawn(async move {
@@ -923,10 +1429,8 @@ impl Agent for AgentSvc {
                 match job.stderr_path.clone() {
                     Some(path) if !path.trim().is_empty() => path,
                     _ => {
-                        let message = format!(
-                            "stderr log file is not configured for job {}",
-                            job_id
-                        );
+                        let message =
+                            format!("stderr log file is not configured for job {}", job_id);
                         let _ = evt_tx
                             .send(Ok(StreamEvent {
                                 event: Some(stream_event::Event::Stderr(
@@ -946,8 +1450,7 @@ impl Agent for AgentSvc {
                 }
             } else {
                 if job.stdout_path.trim().is_empty() {
-                    let message =
-                        format!("stdout log file is not configured for job {}", job_id);
+                    let message = format!("stdout log file is not configured for job {}", job_id);
                     let _ = evt_tx
                         .send(Ok(StreamEvent {
                             event: Some(stream_event::Event::Stderr(
@@ -1075,81 +1578,317 @@ impl Agent for AgentSvc {
         Ok(tonic::Response::new(out))
     }
 
-    async fn list_clusters(
+    async fn read_job_file(
         &self,
-        request: tonic::Request<ListClustersRequest>,
-    ) -> Result<tonic::Response<ListClustersResponse>, Status> {
+        request: tonic::Request<tonic::Streaming<ReadJobFileRequest>>,
+    ) -> Result<tonic::Response<Self::ReadJobFileStream>, Status> {
         let remote_addr = format_remote_addr(request.remote_addr());
-        let _inbound = request.into_inner();
-        let hosts = match self.hosts().list_hosts(None).await {
-            Ok(v) => v,
-            Err(e) => match e {
-                HostStoreError::Sqlx(sql_err) => {
-                    log::error!("unknown error at sqlx level: {sql_err}");
-                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
-                }
-                other_error => {
-                    log::error!("internal error: unexpected error: {other_error}");
-                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
-                }
-            },
-        };
-        let mut clusters: Vec<ListClustersUnitResponse> = hosts
-            .iter()
-            .map(db_host_record_to_api_unit_response)
-            .collect();
+        let mut inbound = request.into_inner();
 
-        for (cluster, host) in clusters.iter_mut().zip(hosts.iter()) {
-            let reachable = match reachability::check_host_reachable(&host.address, host.port).await
-            {
-                Ok(value) => value,
-                Err(err) => {
-                    log::debug!(
-                        "reachability check failed name={} host={} error={err}",
-                        host.name,
-                        format_address(&host.address)
-                    );
-                    false
-                }
-            };
-            cluster.reachable = reachable;
-            if reachable && self.sessions().is_connected(&cluster.name).await {
-                cluster.connected = true;
-            } else {
-                cluster.connected = false;
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in read_job_file: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ReadJobFileRequestInit {
+            job_id,
+            path,
+            allow_outside,
+            offset,
+            length,
+            head_lines,
+            tail_lines,
+            allow_binary,
+        } = match init.msg {
+            Some(proto::read_job_file_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
-        }
-        let connected_count = clusters.iter().filter(|cluster| cluster.connected).count();
+        };
         log::info!(
-            "list_clusters remote_addr={remote_addr} count={} connected={connected_count}",
-            clusters.len()
+            "read_job_file start remote_addr={remote_addr} job_id={job_id} path={path:?} allow_outside={allow_outside} offset={offset} length={length:?} head_lines={head_lines:?} tail_lines={tail_lines:?}"
         );
-
-        Ok(ListClustersResponse { clusters }.into())
-    }
-
-    async fn delete_cluster(
-        &self,
-        request: tonic::Request<DeleteClusterRequest>,
-    ) -> Result<tonic::Response<DeleteClusterResponse>, Status> {
-        let remote_addr = format_remote_addr(request.remote_addr());
-        let name = request.into_inner().name.trim().to_string();
-        if name.is_empty() {
+        if path.trim().is_empty() || (head_lines.is_some() && tail_lines.is_some()) {
             log::warn!(
-                "delete_cluster failed remote_addr={remote_addr} reason=empty_name"
+                "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=invalid_argument"
             );
             return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
         }
-        log::info!("delete_cluster start remote_addr={remote_addr} name={name}");
+        let slice = FileSlice {
+            offset,
+            length,
+            head_lines,
+            tail_lines,
+        };
 
-        let deleted = match self.hosts().delete_by_name(&name).await {
-            Ok(value) => value,
-            Err(e) => match e {
-                HostStoreError::Sqlx(sql_err) => {
-                    log::error!("sqlx error deleting cluster '{}': {sql_err}", name);
-                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::read_job_file_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
                 }
-                other_error => {
+            }
+        });
+
+        let (evt_tx, evt_rx) =
+            tokio::sync::mpsc::channel::<Result<ReadJobFileStreamEvent, Status>>(64);
+        let hs = self.hosts();
+        let svc = self.clone();
+        tokio::spawn(async move {
+            let send_event = |event: read_job_file_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ReadJobFileStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+            let fail = |message: Option<String>, code: &'static str| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    if let Some(message) = message {
+                        let _ = evt_tx
+                            .send(Ok(ReadJobFileStreamEvent {
+                                event: Some(read_job_file_stream_event::Event::Stderr(
+                                    format!("{message}\n").into_bytes(),
+                                )),
+                            }))
+                            .await;
+                    }
+                    let _ = evt_tx
+                        .send(Ok(ReadJobFileStreamEvent {
+                            event: Some(read_job_file_stream_event::Event::Error(code.to_string())),
+                        }))
+                        .await;
+                }
+            };
+
+            let job = match hs.get_job_by_job_id(job_id).await {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=job_not_found"
+                    );
+                    let message = format!(
+                        "job {job_id} does not exist; you can list all job with 'hpc job list'"
+                    );
+                    fail(Some(message), error_codes::NOT_FOUND).await;
+                    return;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=db_error"
+                    );
+                    log::debug!("could not fetch job id {job_id}: {e}");
+                    fail(None, error_codes::INTERNAL_ERROR).await;
+                    return;
+                }
+            };
+
+            let resolved = if allow_outside {
+                Some(resolve_remote(&job.remote_path, &path))
+            } else {
+                resolve_within(&job.remote_path, &path)
+            };
+            let Some(file_path) = resolved else {
+                log::warn!(
+                    "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=outside_run_dir path={path:?}"
+                );
+                let message = format!(
+                    "{path} is outside the run directory of job {job_id}; pass --allow-outside to read it anyway"
+                );
+                fail(Some(message), error_codes::INVALID_ARGUMENT).await;
+                return;
+            };
+            let file_path = file_path.to_string_lossy().into_owned();
+
+            let mgr = match svc.get_sessionmanager(&job.name).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} name={} reason=session_unavailable",
+                        job.name
+                    );
+                    let code = match e {
+                        AgentSvcError::UnknownName => error_codes::NOT_FOUND,
+                        AgentSvcError::NetworkError(e) => {
+                            log::debug!("network error for {}: {e}", job.name);
+                            error_codes::NETWORK_ERROR
+                        }
+                        other_error => {
+                            log::debug!(
+                                "unexpected session manager error for {}: {other_error}",
+                                job.name
+                            );
+                            error_codes::INTERNAL_ERROR
+                        }
+                    };
+                    fail(None, code).await;
+                    return;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "read_job_file failed remote_addr={remote_addr} job_id={job_id} name={} reason=connect_failed error={err}",
+                    job.name
+                );
+                fail(None, error_codes::code_for_ssh_error(&err)).await;
+                return;
+            }
+
+            let mut reader = match mgr.open_file_slice(&file_path, &slice).await {
+                Ok(v) => v,
+                Err(err) if is_sftp_missing_path(&err) => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=remote_path_missing path={file_path}"
+                    );
+                    let message = format!("{file_path}: No such file or directory");
+                    fail(Some(message), error_codes::NOT_FOUND).await;
+                    return;
+                }
+                Err(err) if err.downcast_ref::<NotAFile>().is_some() => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=not_a_file path={file_path}"
+                    );
+                    fail(Some(err.to_string()), error_codes::INVALID_ARGUMENT).await;
+                    return;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=sftp_failed error={err}"
+                    );
+                    fail(None, error_codes::REMOTE_ERROR).await;
+                    return;
+                }
+            };
+            let info = reader.info();
+            let binary = info.binary;
+            let file_info = proto::JobFileInfo {
+                path: info.path.clone(),
+                size: info.size,
+                start: info.start,
+                end: info.end,
+                binary,
+            };
+            send_event(read_job_file_stream_event::Event::Info(file_info)).await;
+            if binary && !allow_binary {
+                log::warn!(
+                    "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=binary_content path={file_path}"
+                );
+                let message = format!(
+                    "{file_path} looks like a binary file; pass --binary to print it anyway, or use 'hpc job retrieve'"
+                );
+                fail(Some(message), error_codes::INVALID_ARGUMENT).await;
+                return;
+            }
+
+            let mut sent = 0u64;
+            loop {
+                match reader.next_chunk().await {
+                    Ok(Some(chunk)) => {
+                        sent += chunk.len() as u64;
+                        send_event(read_job_file_stream_event::Event::Stdout(chunk)).await;
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        log::warn!(
+                            "read_job_file failed remote_addr={remote_addr} job_id={job_id} reason=read_failed error={err}"
+                        );
+                        fail(None, error_codes::REMOTE_ERROR).await;
+                        return;
+                    }
+                }
+            }
+            log::info!(
+                "read_job_file completed remote_addr={remote_addr} job_id={job_id} path={file_path} bytes={sent}"
+            );
+            send_event(read_job_file_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: ReadJobFileOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn list_clusters(
+        &self,
+        request: tonic::Request<ListClustersRequest>,
+    ) -> Result<tonic::Response<ListClustersResponse>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let _inbound = request.into_inner();
+        let hosts = match self.hosts().list_hosts(None).await {
+            Ok(v) => v,
+            Err(e) => match e {
+                HostStoreError::Sqlx(sql_err) => {
+                    log::error!("unknown error at sqlx level: {sql_err}");
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+                other_error => {
+                    log::error!("internal error: unexpected error: {other_error}");
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+            },
+        };
+        let mut clusters: Vec<ListClustersUnitResponse> = hosts
+            .iter()
+            .map(db_host_record_to_api_unit_response)
+            .collect();
+
+        for (cluster, host) in clusters.iter_mut().zip(hosts.iter()) {
+            let reachable = match reachability::check_host_reachable(&host.address, host.port).await
+            {
+                Ok(value) => value,
+                Err(err) => {
+                    log::debug!(
+                        "reachability check failed name={} host={} error={err}",
+                        host.name,
+                        format_address(&host.address)
+                    );
+                    false
+                }
+            };
+            cluster.reachable = reachable;
+            if reachable && self.sessions().is_connected(&cluster.name).await {
+                cluster.connected = true;
+            } else {
+                cluster.connected = false;
+            }
+        }
+        let connected_count = clusters.iter().filter(|cluster| cluster.connected).count();
+        log::info!(
+            "list_clusters remote_addr={remote_addr} count={} connected={connected_count}",
+            clusters.len()
+        );
+
+        Ok(ListClustersResponse { clusters }.into())
+    }
+
+    async fn delete_cluster(
+        &self,
+        request: tonic::Request<DeleteClusterRequest>,
+    ) -> Result<tonic::Response<DeleteClusterResponse>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let name = request.into_inner().name.trim().to_string();
+        if name.is_empty() {
+            log::warn!("delete_cluster failed remote_addr={remote_addr} reason=empty_name");
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        log::info!("delete_cluster start remote_addr={remote_addr} name={name}");
+
+        let deleted = match self.hosts().delete_by_name(&name).await {
+            Ok(value) => value,
+            Err(e) => match e {
+                HostStoreError::Sqlx(sql_err) => {
+                    log::error!("sqlx error deleting cluster '{}': {sql_err}", name);
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+                other_error => {
                     log::error!(
                         "unexpected error deleting cluster '{}': {other_error}",
                         name
@@ -1167,75 +1906,317 @@ impl Agent for AgentSvc {
         }
 
         self.sessions().remove_and_shutdown(&name).await;
-        log::info!(
-            "delete_cluster completed remote_addr={remote_addr} name={name}"
-        );
+        log::info!("delete_cluster completed remote_addr={remote_addr} name={name}");
 
         Ok(tonic::Response::new(DeleteClusterResponse {
             deleted: true,
         }))
     }
 
-    async fn submit(
+    async fn delete_jobs(
         &self,
-        request: tonic::Request<tonic::Streaming<SubmitRequest>>,
-    ) -> Result<tonic::Response<Self::SubmitStream>, Status> {
+        request: tonic::Request<DeleteJobsRequest>,
+    ) -> Result<tonic::Response<DeleteJobsResponse>, Status> {
         let remote_addr = format_remote_addr(request.remote_addr());
-        let mut inbound = request.into_inner();
-        let init = inbound
-            .message()
-            .await
-            .map_err(|e| {
-                log::debug!("read error in submit: {e}");
-                Status::unknown(error_codes::INTERNAL_ERROR)
-            })?
-            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+        let DeleteJobsRequest { job_ids, force } = request.into_inner();
+        if job_ids.is_empty() {
+            log::warn!("delete_jobs failed remote_addr={remote_addr} reason=no_job_ids");
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        log::info!(
+            "delete_jobs start remote_addr={remote_addr} jobs={} force={force}",
+            job_ids.len()
+        );
 
-        let (local_path, remote_path, name, sbatchscript, filters, new_directory, force) =
-            match init.msg {
-                Some(proto::submit_request::Msg::Init(i)) => (
-                    i.local_path,
-                    i.remote_path,
-                    i.name,
-                    i.sbatchscript,
-                    i.filters,
-                    i.new_directory,
-                    i.force,
-                ),
-            _ => return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT)),
-        };
-        let requested_remote_path = remote_path.as_deref().unwrap_or("<default>");
+        let mut response = DeleteJobsResponse::default();
+        let mut to_delete = Vec::new();
+        for job_id in job_ids {
+            match self.hosts().get_job_by_job_id(job_id).await {
+                Ok(Some(job)) if !job.is_completed && !force => response.running.push(job_id),
+                Ok(Some(_)) => to_delete.push(job_id),
+                Ok(None) => response.not_found.push(job_id),
+                Err(e) => {
+                    log::error!("could not fetch job id {job_id}: {e}");
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+            }
+        }
+        if let Err(e) = self.hosts().delete_jobs(&to_delete).await {
+            log::error!("could not delete job records: {e}");
+            return Err(Status::internal(error_codes::INTERNAL_ERROR));
+        }
+        response.deleted = to_delete;
         log::info!(
-            "submit start remote_addr={remote_addr} name={name} local_path={local_path} requested_remote_path={requested_remote_path} sbatch={sbatchscript}"
+            "delete_jobs completed remote_addr={remote_addr} deleted={} not_found={} running={}",
+            response.deleted.len(),
+            response.not_found.len(),
+            response.running.len()
         );
-        let filters = match build_sync_filters(filters) {
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn label_job(
+        &self,
+        request: tonic::Request<LabelJobRequest>,
+    ) -> Result<tonic::Response<LabelJobResponse>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let LabelJobRequest {
+            job_id,
+            set,
+            remove,
+            note,
+            clear_note,
+        } = request.into_inner();
+        let set = match build_job_labels(set) {
             Ok(value) => value,
             Err(e) => {
                 log::warn!(
-                    "submit failed remote_addr={remote_addr} name={name} reason=invalid_filters"
+                    "label_job failed remote_addr={remote_addr} job_id={job_id} reason=invalid_labels"
                 );
                 return Err(e);
             }
         };
+        if remove.iter().any(|key| !is_valid_label_key(key)) || (clear_note && note.is_some()) {
+            log::warn!(
+                "label_job failed remote_addr={remote_addr} job_id={job_id} reason=invalid_argument"
+            );
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        match self.hosts().get_job_by_job_id(job_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                log::warn!(
+                    "label_job failed remote_addr={remote_addr} job_id={job_id} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::error!("could not fetch job id {job_id}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        }
 
-        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<SubmitStreamEvent, Status>>(64);
-        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
-        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
-
-        tokio::spawn(async move {
-            while let Ok(Some(item)) = inbound.message().await {
-                if let Some(proto::submit_request::Msg::Mfa(ans)) = item.msg
-                    && mfa_tx.send(ans).await.is_err()
-                {
-                    break;
-                }
+        let hosts = self.hosts();
+        let updated = async {
+            hosts.update_job_labels(job_id, &set, &remove).await?;
+            if clear_note {
+                hosts.clear_job_note(job_id).await?;
+            } else if note.is_some() {
+                hosts.set_job_details(job_id, None, note.as_deref()).await?;
             }
-            let _ = cancel_tx.send(true);
-        });
+            let job = hosts.get_job_by_job_id(job_id).await?;
+            let labels = hosts.list_labels_for_job(job_id).await?;
+            Ok::<_, HostStoreError>(job.map(|job| {
+                let mut job = db_job_record_to_api_unit_response(&job);
+                job.labels = labels.iter().map(job_label_to_api).collect();
+                job
+            }))
+        }
+        .await;
+        let job = match updated {
+            Ok(job) => job,
+            Err(e) => {
+                log::error!("could not update labels of job {job_id}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!(
+            "label_job completed remote_addr={remote_addr} job_id={job_id} set={} removed={}",
+            set.len(),
+            remove.len()
+        );
+        Ok(tonic::Response::new(LabelJobResponse { job }))
+    }
 
-        let mgr = match self.get_sessionmanager(&name).await {
-            Ok(v) => v,
-            Err(e) => match e {
+    async fn archive_jobs(
+        &self,
+        request: tonic::Request<ArchiveJobsRequest>,
+    ) -> Result<tonic::Response<ArchiveJobsResponse>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let ArchiveJobsRequest {
+            name,
+            before,
+            states,
+            archive_path,
+            dry_run,
+        } = request.into_inner();
+        let before = match before
+            .as_deref()
+            .map(|v| OffsetDateTime::parse(v, &Rfc3339))
+        {
+            Some(Ok(v)) => Some(v),
+            Some(Err(e)) => {
+                log::warn!(
+                    "archive_jobs failed remote_addr={remote_addr} reason=invalid_before error={e}"
+                );
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+            None => None,
+        };
+        if let Some(ref name) = name
+            && !matches!(self.hosts().get_by_name(name).await, Ok(Some(_)))
+        {
+            log::warn!(
+                "archive_jobs failed remote_addr={remote_addr} name={name} reason=not_found"
+            );
+            return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+        }
+        // Any absolute path is accepted on purpose, as for retrieve destinations; see
+        // the prune module.
+        let archive_path = archive_path.map(PathBuf::from);
+        if archive_path
+            .as_deref()
+            .is_some_and(|path| !path.is_absolute())
+        {
+            log::warn!(
+                "archive_jobs failed remote_addr={remote_addr} reason=relative_archive_path"
+            );
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        log::info!(
+            "archive_jobs start remote_addr={remote_addr} name={name:?} before={before:?} states={states:?} dry_run={dry_run}"
+        );
+
+        let filter = PruneFilter {
+            name,
+            before,
+            states,
+        };
+        let pruned =
+            match prune_jobs(&self.hosts(), &filter, archive_path.as_deref(), dry_run).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("archive_jobs failed remote_addr={remote_addr} error={e:#}");
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+            };
+        log::info!(
+            "archive_jobs completed remote_addr={remote_addr} jobs={} dry_run={dry_run}",
+            pruned.len()
+        );
+        Ok(tonic::Response::new(ArchiveJobsResponse {
+            jobs: pruned
+                .iter()
+                .map(db_job_record_to_api_unit_response)
+                .collect(),
+        }))
+    }
+
+    async fn submit(
+        &self,
+        request: tonic::Request<tonic::Streaming<SubmitRequest>>,
+    ) -> Result<tonic::Response<Self::SubmitStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in submit: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let (
+            local_path,
+            remote_path,
+            name,
+            sbatchscript,
+            filters,
+            new_directory,
+            force,
+            no_ignore_files,
+            dry_run,
+            checksum,
+            compress,
+            symlinks,
+            delete,
+            fetch_on_complete,
+            cache,
+            labels,
+            note,
+        ) = match init.msg {
+            Some(proto::submit_request::Msg::Init(i)) => (
+                i.local_path,
+                i.remote_path,
+                i.name,
+                i.sbatchscript,
+                i.filters,
+                i.new_directory,
+                i.force,
+                i.no_ignore_files,
+                i.dry_run,
+                i.checksum,
+                i.compress,
+                i.symlinks,
+                i.delete,
+                i.fetch_on_complete,
+                i.cache,
+                i.labels,
+                i.note,
+            ),
+            _ => return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT)),
+        };
+        let requested_remote_path = remote_path.as_deref().unwrap_or("<default>");
+        log::info!(
+            "submit start remote_addr={remote_addr} name={name} local_path={local_path} requested_remote_path={requested_remote_path} sbatch={sbatchscript} dry_run={dry_run}"
+        );
+        let filters = match build_sync_filters(filters) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!(
+                    "submit failed remote_addr={remote_addr} name={name} reason=invalid_filters"
+                );
+                return Err(e);
+            }
+        };
+        let symlinks = match build_symlink_policy(symlinks) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!(
+                    "submit failed remote_addr={remote_addr} name={name} reason=invalid_symlink_policy"
+                );
+                return Err(e);
+            }
+        };
+        let fetch_rules = match build_fetch_rules(fetch_on_complete, &local_path) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!(
+                    "submit failed remote_addr={remote_addr} name={name} reason=invalid_fetch_rule"
+                );
+                return Err(e);
+            }
+        };
+        let labels = match build_job_labels(labels) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!(
+                    "submit failed remote_addr={remote_addr} name={name} reason=invalid_labels"
+                );
+                return Err(e);
+            }
+        };
+        let note = note.filter(|note| !note.trim().is_empty());
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<SubmitStreamEvent, Status>>(64);
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::submit_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+            let _ = cancel_tx.send(true);
+        });
+
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(e) => match e {
                 AgentSvcError::UnknownName => {
                     log::warn!(
                         "submit failed remote_addr={remote_addr} name={name} reason=unknown_cluster"
@@ -1292,18 +2273,20 @@ impl Agent for AgentSvc {
                         }
                     }
                     other => {
-                        let default_base_path =
-                            match get_default_base_path(hs.as_ref(), &name).await {
-                                Ok(value) => value,
-                                Err(e) => {
-                                    log::warn!(
-                                        "submit failed remote_addr={remote_addr} name={name} reason=default_base_path_unavailable"
-                                    );
-                                    return Err(e);
-                                }
-                            };
+                        let default_base_path = match get_default_base_path(hs.as_ref(), &name)
+                            .await
+                        {
+                            Ok(value) => value,
+                            Err(e) => {
+                                log::warn!(
+                                    "submit failed remote_addr={remote_addr} name={name} reason=default_base_path_unavailable"
+                                );
+                                return Err(e);
+                            }
+                        };
                         let random_suffix = util::random::generate_run_directory_name();
-                        match resolve_submit_remote_path(other, &default_base_path, &random_suffix) {
+                        match resolve_submit_remote_path(other, &default_base_path, &random_suffix)
+                        {
                             Ok(value) => value,
                             Err(e) => {
                                 log::warn!(
@@ -1321,10 +2304,7 @@ impl Agent for AgentSvc {
             "submit resolved remote_addr={remote_addr} name={name} remote_path={remote_path}"
         );
         if !force {
-            match hs
-                .running_job_id_for_remote_path(&name, &remote_path)
-                .await
-            {
+            match hs.running_job_id_for_remote_path(&name, &remote_path).await {
                 Ok(Some(job_id)) => {
                     log::warn!(
                         "submit failed remote_addr={remote_addr} name={name} reason=remote_path_in_use job_id={job_id} remote_path={remote_path}"
@@ -1407,7 +2387,107 @@ impl Agent for AgentSvc {
             &remote_path
         );
 
+        let protect = if delete {
+            let sbatch_path = PathBuf::from(&local_path).join(&sbatchscript);
+            match mirror_protected_patterns(hs.as_ref(), &name, &remote_path, &sbatch_path).await {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!(
+                        "submit failed remote_addr={remote_addr} name={name} reason=job_lookup_failed error={e}"
+                    );
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        let uploaded = if delete {
+            match hs.uploaded_paths_for_remote_path(&name, &remote_path).await {
+                Ok(paths) => paths,
+                Err(e) => {
+                    log::warn!(
+                        "submit failed remote_addr={remote_addr} name={name} reason=job_lookup_failed error={e}"
+                    );
+                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                }
+            }
+        } else {
+            HashSet::new()
+        };
+
+        let cache_dir = if cache {
+            match get_default_base_path(hs.as_ref(), &name).await {
+                Ok(base_path) => Some(crate::ssh::cache_dir_for(&base_path)),
+                Err(e) => {
+                    log::warn!(
+                        "submit failed remote_addr={remote_addr} name={name} reason=cache_unavailable"
+                    );
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+
         let mut cancel_rx = cancel_rx.clone();
+        if dry_run {
+            tokio::spawn(async move {
+                let options = crate::ssh::SyncOptions {
+                    block_size: None,
+                    parallelism: None,
+                    filters: &filters,
+                    ignore_files: !no_ignore_files,
+                    compress: false,
+                    checksum,
+                    symlinks,
+                    delete,
+                    protect: &protect,
+                    uploaded: &uploaded,
+                    cache_dir: cache_dir.as_deref(),
+                };
+                let preview = tokio::select! {
+                    res = mgr.preview_sync(&local_path, &remote_path, options) => res,
+                    _ = evt_tx.closed() => {
+                        return;
+                    }
+                    _ = cancel_rx.changed() => {
+                        return;
+                    }
+                };
+                let event = match preview {
+                    Ok(preview) => {
+                        let remote_sbatch_script_path =
+                            resolve_remote_sbatch_path(&remote_path, &sbatchscript);
+                        let sbatch_command = crate::agent::slurm::path_to_sbatch_command(
+                            &remote_sbatch_script_path,
+                            Some(&remote_path),
+                        );
+                        log::info!(
+                            "submit dry-run completed remote_addr={remote_addr} name={name} remote_path={remote_path} files={}",
+                            preview.items.len()
+                        );
+                        submit_stream_event::Event::DryRun(SubmitDryRunResult {
+                            remote_path: remote_path.clone(),
+                            items: preview.items.iter().map(sync_preview_item_to_api).collect(),
+                            sbatch_command,
+                            checksum,
+                            symlinks: preview.symlinks.iter().map(symlink_entry_to_api).collect(),
+                        })
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "submit failed remote_addr={remote_addr} name={name} reason=dry_run_failed error={err}"
+                        );
+                        submit_stream_event::Event::Error(error_codes::REMOTE_ERROR.to_string())
+                    }
+                };
+                let _ = evt_tx
+                    .send(Ok(SubmitStreamEvent { event: Some(event) }))
+                    .await;
+            });
+            let out: SubmitOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+            return Ok(tonic::Response::new(out));
+        }
         let hs = hs.clone();
         let audit_remote_addr = remote_addr.clone();
         tokio::spawn(async move {
@@ -1428,6 +2508,14 @@ impl Agent for AgentSvc {
                 block_size: Some(1024 * 1024),
                 parallelism: None,
                 filters: &filters,
+                ignore_files: !no_ignore_files,
+                compress,
+                checksum,
+                symlinks,
+                delete,
+                protect: &protect,
+                uploaded: &uploaded,
+                cache_dir: cache_dir.as_deref(),
             };
             let sync_result = tokio::select! {
                 res = mgr.sync_dir(
@@ -1444,21 +2532,24 @@ impl Agent for AgentSvc {
                     return;
                 }
             };
-            if let Err(err) = sync_result {
-                log::warn!(
-                    "submit failed remote_addr={audit_remote_addr} name={name} reason=sync_failed error={err}"
-                );
-                log::debug!("sync failed for submit: {err}");
-                let _ = evt_tx
-                    .send(Ok(SubmitStreamEvent {
-                        event: Some(submit_stream_event::Event::SubmitResult(SubmitResult {
-                            status: submit_result::Status::Failed as i32,
-                            job_id: None,
-                            detail: error_codes::REMOTE_ERROR.to_string(),
-                        })),
-                    }))
-                    .await;
-                return;
+            let uploaded = match sync_result {
+                Ok(uploaded) => uploaded,
+                Err(err) => {
+                    log::warn!(
+                        "submit failed remote_addr={audit_remote_addr} name={name} reason=sync_failed error={err}"
+                    );
+                    log::debug!("sync failed for submit: {err}");
+                    let _ = evt_tx
+                        .send(Ok(SubmitStreamEvent {
+                            event: Some(submit_stream_event::Event::SubmitResult(SubmitResult {
+                                status: submit_result::Status::Failed as i32,
+                                job_id: None,
+                                detail: error_codes::REMOTE_ERROR.to_string(),
+                            })),
+                        }))
+                        .await;
+                    return;
+                }
             };
             if evt_tx
                 .send(Ok(SubmitStreamEvent {
@@ -1476,6 +2567,38 @@ impl Agent for AgentSvc {
             if evt_tx.is_closed() || *cancel_rx.borrow() {
                 return;
             }
+            let sbatch_path = {
+                let sbatch_path = PathBuf::from(&sbatchscript);
+                if sbatch_path.is_absolute() {
+                    sbatch_path
+                } else {
+                    PathBuf::from(&local_path).join(&sbatchscript)
+                }
+            };
+            if let Ok(contents) = std::fs::read_to_string(&sbatch_path) {
+                let requested = sbatch::parse_sbatch_accounting(&contents);
+                if (requested.account.is_some() || requested.qos.is_some())
+                    && let Ok(Some(hr)) = hs.get_by_name(&name).await
+                    && let Ok((accounts, _)) = hs.list_cluster_accounts(hr.id).await
+                {
+                    for warning in account_warnings(
+                        &accounts,
+                        requested.account.as_deref(),
+                        requested.qos.as_deref(),
+                    ) {
+                        log::info!(
+                            "submit account warning remote_addr={audit_remote_addr} name={name} warning={warning}"
+                        );
+                        let _ = evt_tx
+                            .send(Ok(SubmitStreamEvent {
+                                event: Some(submit_stream_event::Event::Stderr(
+                                    format!("warning: {warning}\n").into_bytes(),
+                                )),
+                            }))
+                            .await;
+                    }
+                }
+            }
             let remote_sbatch_script_path = resolve_remote_sbatch_path(&remote_path, &sbatchscript);
 
             let sbatch_command = crate::agent::slurm::path_to_sbatch_command(
@@ -1564,14 +2687,6 @@ impl Agent for AgentSvc {
             }
             let scheduler_id = scheduler_id.unwrap();
 
-            let sbatch_path = {
-                let sbatch_path = PathBuf::from(&sbatchscript);
-                if sbatch_path.is_absolute() {
-                    sbatch_path
-                } else {
-                    PathBuf::from(&local_path).join(&sbatchscript)
-                }
-            };
             let Ok(Some(hr)) = hs.get_by_name(&name).await else {
                 log::warn!(
                     "submit failed remote_addr={audit_remote_addr} name={name} reason=unknown_cluster"
@@ -1613,7 +2728,8 @@ impl Agent for AgentSvc {
                 .filter(|name| !name.trim().is_empty())
                 .unwrap_or("job");
             let job_name = job_name.unwrap_or_else(|| default_job_name.to_string());
-            let stdout_template = stdout.unwrap_or_else(|| sbatch::DEFAULT_STDOUT_TEMPLATE.to_string());
+            let stdout_template =
+                stdout.unwrap_or_else(|| sbatch::DEFAULT_STDOUT_TEMPLATE.to_string());
             let stdout_path = sbatch::resolve_log_path(
                 &stdout_template,
                 &remote_path,
@@ -1621,7 +2737,8 @@ impl Agent for AgentSvc {
                 Some(job_name.as_str()),
                 Some(hr.username.as_str()),
             );
-            let stderr_path = stderr.and_then(|value| {
+            let stderr_path = stderr
+                .and_then(|value| {
                     let trimmed = value.trim();
                     if trimmed.is_empty() {
                         None
@@ -1647,7 +2764,7 @@ impl Agent for AgentSvc {
                 stdout_path,
                 stderr_path,
             };
-            match hs.insert_job(&nj).await {
+            match hs.insert_job(&nj, JOB_ORIGIN_SUBMITTED).await {
                 Ok(job_id) => {
                     log::info!(
                         "submit completed remote_addr={audit_remote_addr} name={name} job_id={job_id} scheduler_id={:?} local_path={} remote_path={}",
@@ -1655,6 +2772,24 @@ impl Agent for AgentSvc {
                         nj.local_path,
                         nj.remote_path
                     );
+                    if let Err(e) = hs.insert_job_fetch_rules(job_id, &fetch_rules).await {
+                        log::warn!("failed to store fetch rules for job {job_id} on {name}: {e}");
+                    }
+                    if let Err(e) = hs
+                        .set_job_details(job_id, Some(job_name.as_str()), note.as_deref())
+                        .await
+                    {
+                        log::warn!("failed to store job name for job {job_id} on {name}: {e}");
+                    }
+                    if let Err(e) = hs.update_job_labels(job_id, &labels, &[]).await {
+                        log::warn!("failed to store labels for job {job_id} on {name}: {e}");
+                    }
+                    if let Err(e) = hs
+                        .record_job_files(job_id, &manifest_entries(&uploaded, true))
+                        .await
+                    {
+                        log::warn!("failed to record file manifest of job {job_id}: {e}");
+                    }
                     let _ = evt_tx
                         .send(Ok(SubmitStreamEvent {
                             event: Some(submit_stream_event::Event::SubmitResult(SubmitResult {
@@ -1775,22 +2910,25 @@ impl Agent for AgentSvc {
         let reachability_addr = addr.clone();
         let reachability_port = port;
         tokio::spawn(async move {
-            let reachable =
-                match reachability::check_host_reachable(&reachability_addr, reachability_port).await
-                {
-                    Ok(value) => value,
-                    Err(err) => {
-                        log::warn!(
-                            "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=reachability_failed error={err}"
-                        );
-                        let status = match &reachability_addr {
-                            Address::Hostname(hostname) => map_net_error(hostname, err),
-                            Address::Ip(_) => Status::aborted(error_codes::NETWORK_ERROR),
-                        };
-                        let _ = evt_tx.send(Err(status)).await;
-                        return;
-                    }
-                };
+            let reachable = match reachability::check_host_reachable(
+                &reachability_addr,
+                reachability_port,
+            )
+            .await
+            {
+                Ok(value) => value,
+                Err(err) => {
+                    log::warn!(
+                        "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=reachability_failed error={err}"
+                    );
+                    let status = match &reachability_addr {
+                        Address::Hostname(hostname) => map_net_error(hostname, err),
+                        Address::Ip(_) => Status::aborted(error_codes::NETWORK_ERROR),
+                    };
+                    let _ = evt_tx.send(Err(status)).await;
+                    return;
+                }
+            };
             if !reachable {
                 log::warn!(
                     "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=host_unreachable"
@@ -1810,9 +2948,7 @@ impl Agent for AgentSvc {
                 );
                 log::debug!("failed to connect to {name}: {e}");
                 let code = error_codes::code_for_ssh_error(&e);
-                let _ = evt_tx
-                    .send(Err(Status::aborted(code)))
-                    .await;
+                let _ = evt_tx.send(Err(Status::aborted(code))).await;
                 return;
             };
 
@@ -1866,9 +3002,7 @@ impl Agent for AgentSvc {
                     log::warn!(
                         "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=wlm_metadata_decode_failed error={e}"
                     );
-                    log::debug!(
-                        "failed to gather cluster metadata for {name}: decode error: {e}"
-                    );
+                    log::debug!("failed to gather cluster metadata for {name}: decode error: {e}");
                     let _ = evt_tx
                         .send(Err(Status::aborted(error_codes::REMOTE_ERROR)))
                         .await;
@@ -1953,11 +3087,7 @@ impl Agent for AgentSvc {
                     return;
                 }
             };
-            let os_label = format!(
-                "OS: {} {}",
-                os_info.id.as_str(),
-                os_info.version.as_str()
-            );
+            let os_label = format!("OS: {} {}", os_info.id.as_str(), os_info.version.as_str());
             send_add_cluster_progress(&evt_tx, &os_label).await;
             let kernel_label = format!("Kernel: {}", os_info.kernel.as_str());
             send_add_cluster_progress(&evt_tx, &kernel_label).await;
@@ -2005,9 +3135,7 @@ impl Agent for AgentSvc {
                     log::warn!(
                         "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=slurm_version_decode_failed error={e}"
                     );
-                    log::debug!(
-                        "failed to gather slurm version for {name}: decode error: {e}"
-                    );
+                    log::debug!("failed to gather slurm version for {name}: decode error: {e}");
                     let _ = evt_tx
                         .send(Err(Status::aborted(error_codes::REMOTE_ERROR)))
                         .await;
@@ -2019,9 +3147,7 @@ impl Agent for AgentSvc {
                 log::warn!(
                     "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=slurm_version_unexpected_output"
                 );
-                log::debug!(
-                    "failed to gather slurm version for {name}: unexpected output: {out}"
-                );
+                log::debug!("failed to gather slurm version for {name}: unexpected output: {out}");
                 let _ = evt_tx
                     .send(Err(Status::aborted(error_codes::REMOTE_ERROR)))
                     .await;
@@ -2099,30 +3225,33 @@ impl Agent for AgentSvc {
             };
             send_add_cluster_progress(&evt_tx, &format!("Accounting: {accounting_state}")).await;
 
-            let resolved_default_base_path =
-                match resolve_default_base_path(default_base_path, &home_dir) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        log::warn!(
-                            "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=default_base_path_invalid error_code={:?}",
-                            e.code()
-                        );
-                        let _ = evt_tx.send(Err(e)).await;
-                        return;
-                    }
-                };
-            let normalized_default_base_path =
-                match normalize_default_base_path(resolved_default_base_path) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        log::warn!(
-                            "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=default_base_path_invalid error_code={:?}",
-                            e.code()
-                        );
-                        let _ = evt_tx.send(Err(e)).await;
-                        return;
-                    }
-                };
+            let resolved_default_base_path = match resolve_default_base_path(
+                default_base_path,
+                &home_dir,
+            ) {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=default_base_path_invalid error_code={:?}",
+                        e.code()
+                    );
+                    let _ = evt_tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            let normalized_default_base_path = match normalize_default_base_path(
+                resolved_default_base_path,
+            ) {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_upsert failed remote_addr={audit_remote_addr} name={audit_name} host={audit_host_label} reason=default_base_path_invalid error_code={:?}",
+                        e.code()
+                    );
+                    let _ = evt_tx.send(Err(e)).await;
+                    return;
+                }
+            };
             if let Some(ref dbp) = normalized_default_base_path {
                 let command = format!("mkdir -p {}", dbp.to_string_lossy());
                 let (_, err, code) = match sm.exec_capture(&command).await {
@@ -2203,41 +3332,1255 @@ impl Agent for AgentSvc {
         let remote_addr = format_remote_addr(request.remote_addr());
         let inbound = request.into_inner();
         let name_filter = inbound.name.clone();
+        let mut query = match build_job_query(inbound) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("list_jobs failed remote_addr={remote_addr} reason=invalid_filters");
+                return Err(e);
+            }
+        };
+        if let Some(ref v) = name_filter {
+            let Ok(Some(hr)) = self.hosts().get_by_name(v).await else {
+                log::warn!("list_jobs failed remote_addr={remote_addr} name={v} reason=not_found");
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            };
+            query.host_id = Some(hr.id);
+        }
+        let page = match self.hosts().query_jobs(&query).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("couldn't list jobs: {}", e);
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let mut fetch_rules: HashMap<i64, Vec<JobFetchStatus>> = HashMap::new();
+        match self.hosts().list_job_fetch_rules().await {
+            Ok(rules) => {
+                for rule in &rules {
+                    fetch_rules
+                        .entry(rule.job_id)
+                        .or_default()
+                        .push(job_fetch_rule_to_api(rule));
+                }
+            }
+            Err(e) => log::debug!("couldn't list job fetch rules: {}", e),
+        }
+        let mut labels: HashMap<i64, Vec<proto::JobLabel>> = HashMap::new();
+        match self.hosts().list_job_labels().await {
+            Ok(rows) => {
+                for label in &rows {
+                    labels
+                        .entry(label.job_id)
+                        .or_default()
+                        .push(job_label_to_api(label));
+                }
+            }
+            Err(e) => log::debug!("couldn't list job labels: {}", e),
+        }
+        let api_jobs: Vec<_> = page
+            .jobs
+            .into_iter()
+            .map(|jr| {
+                let mut job = db_job_record_to_api_unit_response(&jr);
+                job.fetch_on_complete = fetch_rules.remove(&jr.id).unwrap_or_default();
+                job.labels = labels.remove(&jr.id).unwrap_or_default();
+                job
+            })
+            .collect();
+        let name_label = name_filter.as_deref().unwrap_or("<all>");
+        log::info!(
+            "list_jobs remote_addr={remote_addr} name={name_label} count={}",
+            api_jobs.len()
+        );
+        Ok(tonic::Response::new(ListJobsResponse {
+            jobs: api_jobs,
+            next_cursor: page.next.map(|cursor| cursor.to_string()),
+        }))
+    }
 
-        let jobs = match inbound.name {
-            Some(ref v) => {
-                let Ok(Some(hr)) = self.hosts().get_by_name(v).await else {
+    async fn job_efficiency(
+        &self,
+        request: tonic::Request<JobEfficiencyRequest>,
+    ) -> Result<tonic::Response<JobEfficiencyResponse>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let inbound = request.into_inner();
+
+        let (host, jobs) = match (inbound.job_id, inbound.name.as_deref()) {
+            (Some(job_id), _) => {
+                let job = match self.hosts().get_job_by_job_id(job_id).await {
+                    Ok(Some(v)) => v,
+                    Ok(None) => {
+                        log::warn!(
+                            "job_efficiency failed remote_addr={remote_addr} job_id={job_id} reason=not_found"
+                        );
+                        return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+                    }
+                    Err(e) => {
+                        log::debug!("couldn't fetch job {job_id}: {e}");
+                        return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                    }
+                };
+                let Ok(Some(host)) = self.hosts().get_by_name(&job.name).await else {
                     log::warn!(
-                        "list_jobs failed remote_addr={remote_addr} name={v} reason=not_found"
+                        "job_efficiency failed remote_addr={remote_addr} job_id={job_id} reason=host_not_found"
                     );
                     return Err(Status::invalid_argument(error_codes::NOT_FOUND));
                 };
-
-                match self.hosts().list_jobs_for_host(hr.id).await {
+                (host, vec![job])
+            }
+            (None, Some(name)) => {
+                let Ok(Some(host)) = self.hosts().get_by_name(name).await else {
+                    log::warn!(
+                        "job_efficiency failed remote_addr={remote_addr} name={name} reason=not_found"
+                    );
+                    return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+                };
+                let since = match inbound.since_secs {
+                    Some(secs) if secs < 0 => {
+                        return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+                    }
+                    Some(secs) => Some(db_time(
+                        OffsetDateTime::now_utc() - time::Duration::seconds(secs),
+                    )),
+                    None => None,
+                };
+                let jobs = match self
+                    .hosts()
+                    .list_completed_jobs_for_host_since(host.id, since.as_deref())
+                    .await
+                {
                     Ok(v) => v,
                     Err(e) => {
-                        log::debug!("couldn't list jobs for host '{}': {}", hr.name, e);
+                        log::debug!("couldn't list jobs for host '{name}': {e}");
                         return Err(Status::internal(error_codes::INTERNAL_ERROR));
                     }
-                }
+                };
+                (host, jobs)
+            }
+            (None, None) => {
+                log::warn!(
+                    "job_efficiency failed remote_addr={remote_addr} reason=missing_job_or_cluster"
+                );
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
             }
-            None => match self.hosts().list_all_jobs().await {
+        };
+        if !host.accounting_available {
+            log::warn!(
+                "job_efficiency failed remote_addr={remote_addr} name={} reason=accounting_unavailable",
+                host.name
+            );
+            return Err(Status::failed_precondition(
+                error_codes::ACCOUNTING_UNAVAILABLE,
+            ));
+        }
+        log::info!(
+            "job_efficiency start remote_addr={remote_addr} name={} jobs={}",
+            host.name,
+            jobs.len()
+        );
+
+        let mut usage = Vec::with_capacity(jobs.len());
+        let mut missing = Vec::new();
+        for job in &jobs {
+            let steps = match self.hosts().list_job_usage(job.id).await {
                 Ok(v) => v,
                 Err(e) => {
-                    log::debug!("couldn't list jobs for all hosts: {}", e);
+                    log::debug!("couldn't load usage for job {}: {e}", job.id);
                     return Err(Status::internal(error_codes::INTERNAL_ERROR));
                 }
-            },
-        };
+            };
+            // Running jobs are refreshed every time; finished ones only until usage is stored.
+            if let Some(sid) = job.scheduler_id
+                && (steps.is_empty() || !job.is_completed)
+            {
+                missing.push((job.id, sid));
+            }
+            usage.push(steps);
+        }
+
+        if !missing.is_empty() {
+            match self.connected_sessionmanager(&host.name).await {
+                Some(sm) => match fetch_and_store_job_usage(&sm, &self.hosts(), &missing).await {
+                    Ok(stored) => {
+                        log::debug!(
+                            "job_efficiency fetched usage for {stored}/{} jobs on {}",
+                            missing.len(),
+                            host.name
+                        );
+                        for (idx, job) in jobs.iter().enumerate() {
+                            if missing.iter().any(|(id, _)| *id == job.id)
+                                && let Ok(steps) = self.hosts().list_job_usage(job.id).await
+                            {
+                                usage[idx] = steps;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        log::debug!("couldn't fetch usage on {}: {e}", host.name);
+                    }
+                },
+                None => {
+                    log::debug!(
+                        "job_efficiency serving stored usage only; {} is not connected",
+                        host.name
+                    );
+                }
+            }
+        }
+
         let api_jobs: Vec<_> = jobs
-            .into_iter()
-            .map(|jr| db_job_record_to_api_unit_response(&jr))
+            .iter()
+            .zip(usage.iter())
+            .map(|(job, steps)| job_efficiency_to_api_unit_response(job, steps))
             .collect();
-        let name_label = name_filter.as_deref().unwrap_or("<all>");
         log::info!(
-            "list_jobs remote_addr={remote_addr} name={name_label} count={}",
+            "job_efficiency remote_addr={remote_addr} name={} count={}",
+            host.name,
             api_jobs.len()
         );
-        Ok(tonic::Response::new(ListJobsResponse { jobs: api_jobs }))
+        Ok(tonic::Response::new(JobEfficiencyResponse {
+            jobs: api_jobs,
+        }))
+    }
+
+    async fn adopt_jobs(
+        &self,
+        request: tonic::Request<tonic::Streaming<AdoptJobsRequest>>,
+    ) -> Result<tonic::Response<Self::AdoptJobsStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in adopt_jobs: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let AdoptJobsRequestInit {
+            name,
+            scheduler_id,
+            all_mine,
+        } = match init.msg {
+            Some(proto::adopt_jobs_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        if scheduler_id.is_some() == all_mine {
+            log::warn!(
+                "adopt_jobs failed remote_addr={remote_addr} name={name} reason=invalid_selection"
+            );
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        let host = match self.hosts().get_by_name(&name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                log::warn!(
+                    "adopt_jobs failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!(
+            "adopt_jobs start remote_addr={remote_addr} name={name} scheduler_id={scheduler_id:?} all_mine={all_mine}"
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::adopt_jobs_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(64);
+        let hs = self.hosts();
+        tokio::spawn(async move {
+            let send_line = |event: stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx.send(Ok(StreamEvent { event: Some(event) })).await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "adopt_jobs failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_line(stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let scheduler_ids = match scheduler_id {
+                Some(id) => vec![id],
+                None => match list_user_job_ids(&mgr, &host.username).await {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        log::warn!(
+                            "adopt_jobs failed remote_addr={remote_addr} name={name} reason=squeue_failed error={e}"
+                        );
+                        send_line(stream_event::Event::Error(
+                            error_codes::REMOTE_ERROR.to_string(),
+                        ))
+                        .await;
+                        return;
+                    }
+                },
+            };
+            if scheduler_ids.is_empty() {
+                send_line(stream_event::Event::Stdout(
+                    format!("no active jobs found for {} on {name}\n", host.username).into_bytes(),
+                ))
+                .await;
+            }
+
+            let mut adopted = 0;
+            let mut failed = 0;
+            for sid in scheduler_ids {
+                match adopt_job(&mgr, &hs, &host, sid).await {
+                    Ok(AdoptOutcome::Adopted(job_id)) => {
+                        adopted += 1;
+                        send_line(stream_event::Event::Stdout(
+                            format!("adopted scheduler job {sid} as job {job_id}\n").into_bytes(),
+                        ))
+                        .await;
+                    }
+                    Ok(AdoptOutcome::AlreadyTracked(job_id)) => {
+                        send_line(stream_event::Event::Stdout(
+                            format!("scheduler job {sid} is already tracked as job {job_id}\n")
+                                .into_bytes(),
+                        ))
+                        .await;
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        log::debug!("couldn't adopt scheduler job {sid} on {name}: {e}");
+                        let code = match e {
+                            AdoptError::NotFound(_) => error_codes::NOT_FOUND,
+                            AdoptError::Remote(_) => error_codes::REMOTE_ERROR,
+                            AdoptError::Database(_) => error_codes::INTERNAL_ERROR,
+                        };
+                        if scheduler_id.is_some() {
+                            send_line(stream_event::Event::Stderr(
+                                format!("couldn't adopt scheduler job {sid}: {e}\n").into_bytes(),
+                            ))
+                            .await;
+                            log::warn!(
+                                "adopt_jobs failed remote_addr={remote_addr} name={name} scheduler_id={sid} reason={code}"
+                            );
+                            send_line(stream_event::Event::Error(code.to_string())).await;
+                            return;
+                        }
+                        send_line(stream_event::Event::Stderr(
+                            format!("couldn't adopt scheduler job {sid}: {e}\n").into_bytes(),
+                        ))
+                        .await;
+                    }
+                }
+            }
+            log::info!(
+                "adopt_jobs completed remote_addr={remote_addr} name={name} adopted={adopted} failed={failed}"
+            );
+            send_line(stream_event::Event::ExitCode(if failed > 0 {
+                1
+            } else {
+                0
+            }))
+            .await;
+        });
+
+        let out: OutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn cluster_queue(
+        &self,
+        request: tonic::Request<tonic::Streaming<ClusterQueueRequest>>,
+    ) -> Result<tonic::Response<Self::ClusterQueueStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in cluster_queue: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ClusterQueueRequestInit {
+            name,
+            user,
+            mine,
+            partition,
+            state,
+            start,
+        } = match init.msg {
+            Some(proto::cluster_queue_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        if mine && user.is_some() {
+            log::warn!(
+                "cluster_queue failed remote_addr={remote_addr} name={name} reason=conflicting_user_filters"
+            );
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        let host = match self.hosts().get_by_name(&name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                log::warn!(
+                    "cluster_queue failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let user = if mine {
+            Some(host.username.clone())
+        } else {
+            user
+        };
+        let command = squeue_queue_command(
+            user.as_deref(),
+            partition.as_deref(),
+            state.as_deref(),
+            start,
+        );
+        log::info!(
+            "cluster_queue start remote_addr={remote_addr} name={name} user={user:?} partition={partition:?} state={state:?} start={start}"
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::cluster_queue_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) =
+            tokio::sync::mpsc::channel::<Result<ClusterQueueStreamEvent, Status>>(64);
+        let hs = self.hosts();
+        tokio::spawn(async move {
+            let send_event = |event: cluster_queue_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ClusterQueueStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "cluster_queue failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(cluster_queue_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let (out, err, code) = match mgr.exec_capture(&command).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_queue failed remote_addr={remote_addr} name={name} reason=exec_failed error={e}"
+                    );
+                    send_event(cluster_queue_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+            };
+            if code != 0 {
+                log::warn!(
+                    "cluster_queue failed remote_addr={remote_addr} name={name} reason=squeue_failed code={code}"
+                );
+                send_event(cluster_queue_stream_event::Event::Stderr(err)).await;
+                send_event(cluster_queue_stream_event::Event::ExitCode(code)).await;
+                return;
+            }
+
+            let tracked: HashMap<i64, i64> = match hs.list_jobs_for_host(host.id).await {
+                Ok(jobs) => jobs
+                    .into_iter()
+                    .filter_map(|j| j.scheduler_id.map(|sid| (sid, j.id)))
+                    .collect(),
+                Err(e) => {
+                    log::debug!("couldn't list tracked jobs for {name}: {e}");
+                    HashMap::new()
+                }
+            };
+            let jobs: Vec<_> = parse_squeue_queue(&String::from_utf8_lossy(&out))
+                .into_iter()
+                .map(|entry| {
+                    let tracked_job_id = tracked.get(&entry.scheduler_id).copied();
+                    queue_entry_to_api_job(entry, tracked_job_id)
+                })
+                .collect();
+            log::info!(
+                "cluster_queue completed remote_addr={remote_addr} name={name} jobs={}",
+                jobs.len()
+            );
+            send_event(cluster_queue_stream_event::Event::Result(
+                ClusterQueueResult { jobs },
+            ))
+            .await;
+            send_event(cluster_queue_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: ClusterQueueOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn cluster_nodes(
+        &self,
+        request: tonic::Request<tonic::Streaming<ClusterNodesRequest>>,
+    ) -> Result<tonic::Response<Self::ClusterNodesStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in cluster_nodes: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ClusterNodesRequestInit { name, partition } = match init.msg {
+            Some(proto::cluster_nodes_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        match self.hosts().get_by_name(&name).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                log::warn!(
+                    "cluster_nodes failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!(
+            "cluster_nodes start remote_addr={remote_addr} name={name} partition={partition:?}"
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::cluster_nodes_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) =
+            tokio::sync::mpsc::channel::<Result<ClusterNodesStreamEvent, Status>>(64);
+        tokio::spawn(async move {
+            let send_event = |event: cluster_nodes_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ClusterNodesStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "cluster_nodes failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(cluster_nodes_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let (out, err, code) = match mgr.exec_capture(SCONTROL_SHOW_NODES_CMD).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_nodes failed remote_addr={remote_addr} name={name} reason=exec_failed error={e}"
+                    );
+                    send_event(cluster_nodes_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+            };
+            if code != 0 {
+                log::warn!(
+                    "cluster_nodes failed remote_addr={remote_addr} name={name} reason=scontrol_failed code={code}"
+                );
+                send_event(cluster_nodes_stream_event::Event::Stderr(err)).await;
+                send_event(cluster_nodes_stream_event::Event::ExitCode(code)).await;
+                return;
+            }
+
+            let nodes = parse_scontrol_nodes(&String::from_utf8_lossy(&out));
+            let result = summarize_nodes(&nodes, partition.as_deref());
+            log::info!(
+                "cluster_nodes completed remote_addr={remote_addr} name={name} nodes={}",
+                result.nodes.len()
+            );
+            send_event(cluster_nodes_stream_event::Event::Result(result)).await;
+            send_event(cluster_nodes_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: ClusterNodesOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn cluster_accounts(
+        &self,
+        request: tonic::Request<tonic::Streaming<ClusterAccountsRequest>>,
+    ) -> Result<tonic::Response<Self::ClusterAccountsStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in cluster_accounts: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ClusterAccountsRequestInit { name } = match init.msg {
+            Some(proto::cluster_accounts_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        let host = match self.hosts().get_by_name(&name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                log::warn!(
+                    "cluster_accounts failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        if !host.accounting_available {
+            log::warn!(
+                "cluster_accounts failed remote_addr={remote_addr} name={name} reason=accounting_unavailable"
+            );
+            return Err(Status::failed_precondition(
+                error_codes::ACCOUNTING_UNAVAILABLE,
+            ));
+        }
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!("cluster_accounts start remote_addr={remote_addr} name={name}");
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::cluster_accounts_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) =
+            tokio::sync::mpsc::channel::<Result<ClusterAccountsStreamEvent, Status>>(64);
+        let hs = self.hosts();
+        tokio::spawn(async move {
+            let send_event = |event: cluster_accounts_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ClusterAccountsStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "cluster_accounts failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(cluster_accounts_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let accounts = match fetch_and_store_accounts(&mgr, &hs, &host).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_accounts failed remote_addr={remote_addr} name={name} reason=sacctmgr_failed error={e}"
+                    );
+                    send_event(cluster_accounts_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+            };
+            log::info!(
+                "cluster_accounts completed remote_addr={remote_addr} name={name} accounts={}",
+                accounts.len()
+            );
+            send_event(cluster_accounts_stream_event::Event::Result(
+                ClusterAccountsResult {
+                    accounts: accounts
+                        .into_iter()
+                        .map(db_cluster_account_to_api)
+                        .collect(),
+                },
+            ))
+            .await;
+            send_event(cluster_accounts_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: ClusterAccountsOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn cache_gc(
+        &self,
+        request: tonic::Request<tonic::Streaming<CacheGcRequest>>,
+    ) -> Result<tonic::Response<Self::CacheGcStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in cache_gc: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let CacheGcRequestInit { name, dry_run } = match init.msg {
+            Some(proto::cache_gc_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        let cache_dir = match get_default_base_path(self.hosts().as_ref(), &name).await {
+            Ok(base_path) => crate::ssh::cache_dir_for(&base_path),
+            Err(e) => {
+                log::warn!(
+                    "cache_gc failed remote_addr={remote_addr} name={name} reason=default_base_path_unavailable"
+                );
+                return Err(e);
+            }
+        };
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!(
+            "cache_gc start remote_addr={remote_addr} name={name} cache_dir={cache_dir} dry_run={dry_run}"
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::cache_gc_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<CacheGcStreamEvent, Status>>(64);
+        tokio::spawn(async move {
+            let send_event = |event: cache_gc_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(CacheGcStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "cache_gc failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(cache_gc_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let (blobs, bytes) = match mgr.cache_gc(&cache_dir, dry_run).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cache_gc failed remote_addr={remote_addr} name={name} reason=remote_gc_failed error={e}"
+                    );
+                    send_event(cache_gc_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+            };
+            log::info!(
+                "cache_gc completed remote_addr={remote_addr} name={name} blobs={blobs} bytes={bytes} dry_run={dry_run}"
+            );
+            send_event(cache_gc_stream_event::Event::Result(CacheGcResult {
+                blobs,
+                bytes,
+            }))
+            .await;
+            send_event(cache_gc_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: CacheGcOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn cluster_usage(
+        &self,
+        request: tonic::Request<tonic::Streaming<ClusterUsageRequest>>,
+    ) -> Result<tonic::Response<Self::ClusterUsageStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in cluster_usage: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let ClusterUsageRequestInit { name } = match init.msg {
+            Some(proto::cluster_usage_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        let host = match self.hosts().get_by_name(&name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                log::warn!(
+                    "cluster_usage failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let jobs = match self.hosts().list_jobs_for_host(host.id).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("couldn't list jobs for host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let dirs: Vec<RunDir> = group_run_dirs(jobs)
+            .into_iter()
+            .filter(|dir| !dir.is_cleaned())
+            .collect();
+        let mgr = match self.get_sessionmanager(&name).await {
+            Ok(v) => v,
+            Err(AgentSvcError::NetworkError(e)) => {
+                log::debug!("network error for {name}: {e}");
+                return Err(Status::internal(error_codes::NETWORK_ERROR));
+            }
+            Err(e) => {
+                log::debug!("unexpected session manager error for {name}: {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        log::info!(
+            "cluster_usage start remote_addr={remote_addr} name={name} dirs={}",
+            dirs.len()
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::cluster_usage_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) =
+            tokio::sync::mpsc::channel::<Result<ClusterUsageStreamEvent, Status>>(64);
+        tokio::spawn(async move {
+            let send_event = |event: cluster_usage_stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx
+                        .send(Ok(ClusterUsageStreamEvent { event: Some(event) }))
+                        .await;
+                }
+            };
+
+            if let Err(err) = mgr.ensure_connected_with(&evt_tx, &mut mfa_rx).await {
+                log::warn!(
+                    "cluster_usage failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                );
+                send_event(cluster_usage_stream_event::Event::Error(
+                    error_codes::code_for_ssh_error(&err).to_string(),
+                ))
+                .await;
+                return;
+            }
+
+            let sizes = match measure_run_dirs(&mgr, &dirs).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!(
+                        "cluster_usage failed remote_addr={remote_addr} name={name} reason=du_failed error={e}"
+                    );
+                    send_event(cluster_usage_stream_event::Event::Error(
+                        error_codes::REMOTE_ERROR.to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+            };
+            log::info!(
+                "cluster_usage completed remote_addr={remote_addr} name={name} dirs={} present={}",
+                dirs.len(),
+                sizes.len()
+            );
+            send_event(cluster_usage_stream_event::Event::Result(
+                ClusterUsageResult {
+                    dirs: dirs
+                        .iter()
+                        .map(|dir| dir.usage(sizes.get(&dir.remote_path).copied()))
+                        .collect(),
+                },
+            ))
+            .await;
+            send_event(cluster_usage_stream_event::Event::ExitCode(0)).await;
+        });
+
+        let out: ClusterUsageOutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
+    }
+
+    async fn clean_run_dirs(
+        &self,
+        request: tonic::Request<tonic::Streaming<CleanRunDirsRequest>>,
+    ) -> Result<tonic::Response<Self::CleanRunDirsStream>, Status> {
+        let remote_addr = format_remote_addr(request.remote_addr());
+        let mut inbound = request.into_inner();
+
+        let init = inbound
+            .message()
+            .await
+            .map_err(|e| {
+                log::debug!("read error in clean_run_dirs: {e}");
+                Status::unknown(error_codes::INTERNAL_ERROR)
+            })?
+            .ok_or_else(|| Status::invalid_argument(error_codes::INVALID_ARGUMENT))?;
+
+        let CleanRunDirsRequestInit {
+            target,
+            older_than_secs,
+            state,
+            dry_run,
+        } = match init.msg {
+            Some(proto::clean_run_dirs_request::Msg::Init(v)) => v,
+            _ => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        if older_than_secs.is_some_and(|secs| secs < 0) {
+            return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+        }
+        let (name, job_id) = match target {
+            Some(clean_run_dirs_request_init::Target::JobId(job_id)) => {
+                match self.hosts().get_job_by_job_id(job_id).await {
+                    Ok(Some(job)) => (job.name, Some(job_id)),
+                    Ok(None) => {
+                        log::warn!(
+                            "clean_run_dirs failed remote_addr={remote_addr} job_id={job_id} reason=job_not_found"
+                        );
+                        return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+                    }
+                    Err(e) => {
+                        log::debug!("could not fetch job id {job_id}: {e}");
+                        return Err(Status::internal(error_codes::INTERNAL_ERROR));
+                    }
+                }
+            }
+            Some(clean_run_dirs_request_init::Target::Name(name)) => (name, None),
+            None => {
+                return Err(Status::invalid_argument(error_codes::INVALID_ARGUMENT));
+            }
+        };
+        let host = match self.hosts().get_by_name(&name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                log::warn!(
+                    "clean_run_dirs failed remote_addr={remote_addr} name={name} reason=not_found"
+                );
+                return Err(Status::invalid_argument(error_codes::NOT_FOUND));
+            }
+            Err(e) => {
+                log::debug!("couldn't fetch host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let Some(base_path) = host.default_base_path.clone() else {
+            log::warn!(
+                "clean_run_dirs failed remote_addr={remote_addr} name={name} reason=default_base_path_unavailable"
+            );
+            return Err(Status::failed_precondition(error_codes::INVALID_ARGUMENT));
+        };
+        let jobs = match self.hosts().list_jobs_for_host(host.id).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("couldn't list jobs for host '{name}': {e}");
+                return Err(Status::internal(error_codes::INTERNAL_ERROR));
+            }
+        };
+        let all_dirs = group_run_dirs(jobs);
+        let dirs: Vec<RunDir> = match job_id {
+            Some(job_id) => all_dirs
+                .into_iter()
+                .filter(|dir| dir.jobs.iter().any(|job| job.id == job_id))
+                .collect(),
+            None => gc_candidates(
+                &all_dirs,
+                OffsetDateTime::now_utc(),
+                older_than_secs,
+                state.as_deref(),
+            )
+            .into_iter()
+            .cloned()
+            .collect(),
+        };
+        log::info!(
+            "clean_run_dirs start remote_addr={remote_addr} name={name} job_id={job_id:?} dirs={} dry_run={dry_run}",
+            dirs.len()
+        );
+
+        let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(16);
+        tokio::spawn(async move {
+            while let Ok(Some(item)) = inbound.message().await {
+                if let Some(proto::clean_run_dirs_request::Msg::Mfa(ans)) = item.msg
+                    && mfa_tx.send(ans).await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(64);
+        let hs = self.hosts();
+        let svc = self.clone();
+        tokio::spawn(async move {
+            let send_event = |event: stream_event::Event| {
+                let evt_tx = evt_tx.clone();
+                async move {
+                    let _ = evt_tx.send(Ok(StreamEvent { event: Some(event) })).await;
+                }
+            };
+
+            if dirs.is_empty() {
+                send_event(stream_event::Event::Stderr(
+                    b"no run directories match\n".to_vec(),
+                ))
+                .await;
+                send_event(stream_event::Event::ExitCode(0)).await;
+                return;
+            }
+            let mgr = if dry_run {
+                None
+            } else {
+                let mgr = match svc.get_sessionmanager(&name).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        log::warn!(
+                            "clean_run_dirs failed remote_addr={remote_addr} name={name} reason=session_unavailable"
+                        );
+                        let message = match e {
+                            AgentSvcError::NetworkError(e) => {
+                                log::debug!("network error for {name}: {e}");
+                                error_codes::NETWORK_ERROR
+                            }
+                            other_error => {
+                                log::debug!(
+                                    "unexpected session manager error for {name}: {other_error}"
+                                );
+                                error_codes::INTERNAL_ERROR
+                            }
+                        };
+                        send_event(stream_event::Event::Error(message.to_string())).await;
+                        return;
+                    }
+                };
+                if let Err(err) = mgr.ensure_connected(&evt_tx, &mut mfa_rx).await {
+                    log::warn!(
+                        "clean_run_dirs failed remote_addr={remote_addr} name={name} reason=connect_failed error={err}"
+                    );
+                    send_event(stream_event::Event::Error(
+                        error_codes::code_for_ssh_error(&err).to_string(),
+                    ))
+                    .await;
+                    return;
+                }
+                Some(mgr)
+            };
+
+            let mut deleted = 0usize;
+            let mut skipped = 0usize;
+            for dir in &dirs {
+                let path = dir.remote_path.as_str();
+                let refusal = match hs.running_job_id_overlapping_remote_path(&name, path).await {
+                    Ok(Some(running)) => Some(format!("job {running} is still running")),
+                    Ok(None) => removal_refusal(path, &base_path),
+                    Err(e) => {
+                        log::debug!("could not check running jobs in {path}: {e}");
+                        Some("could not check for running jobs".to_string())
+                    }
+                };
+                if let Some(reason) = refusal {
+                    skipped += 1;
+                    send_event(stream_event::Event::Stderr(
+                        format!("skipping {path}: {reason}\n").into_bytes(),
+                    ))
+                    .await;
+                    continue;
+                }
+                let Some(mgr) = mgr.as_ref() else {
+                    send_event(stream_event::Event::Stdout(
+                        format!("would delete {path} ({})\n", dir.describe_jobs()).into_bytes(),
+                    ))
+                    .await;
+                    continue;
+                };
+                if let Err(e) = remove_run_dir(mgr, path).await {
+                    log::warn!(
+                        "clean_run_dirs failed remote_addr={remote_addr} name={name} path={path} reason=rm_failed error={e}"
+                    );
+                    skipped += 1;
+                    send_event(stream_event::Event::Stderr(
+                        format!("failed to delete {path}: {e}\n").into_bytes(),
+                    ))
+                    .await;
+                    continue;
+                }
+                if let Err(e) = hs.mark_remote_path_cleaned(host.id, path).await {
+                    log::warn!("could not record cleanup of {path} on {name}: {e}");
+                }
+                deleted += 1;
+                send_event(stream_event::Event::Stdout(
+                    format!("deleted {path} ({})\n", dir.describe_jobs()).into_bytes(),
+                ))
+                .await;
+            }
+            log::info!(
+                "clean_run_dirs completed remote_addr={remote_addr} name={name} deleted={deleted} skipped={skipped} dry_run={dry_run}"
+            );
+            send_event(stream_event::Event::ExitCode(i32::from(skipped > 0))).await;
+        });
+
+        let out: OutStream = Box::pin(crate::ssh::receiver_to_stream(evt_rx));
+        Ok(tonic::Response::new(out))
     }
 }
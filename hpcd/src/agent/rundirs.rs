@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Remote run directories of tracked jobs: disk usage and cleanup.
+//!
+//! Several jobs can run in the same directory (resubmitting a local path reuses its last
+//! remote path), so directories are handled as a group of jobs and judged by the most
+//! recent one. A directory is only ever deleted when it lies below the cluster's default
+//! base path and none of its jobs is still running.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::bail;
+use proto::RunDirUsage;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::ssh::{SessionManager, cache_dir_for, sh_escape};
+use crate::state::db::JobRecord;
+use crate::util::remote_path::normalize_path;
+
+/// Directories measured per `du` call.
+const DU_BATCH: usize = 128;
+
+/// A remote directory and the jobs that ran in it, oldest first.
+#[derive(Clone, Debug)]
+pub(crate) struct RunDir {
+    pub remote_path: String,
+    pub jobs: Vec<JobRecord>,
+}
+
+impl RunDir {
+    fn latest(&self) -> &JobRecord {
+        self.jobs
+            .last()
+            .expect("run directories hold at least one job")
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.jobs.iter().any(|job| !job.is_completed)
+    }
+
+    pub(crate) fn is_cleaned(&self) -> bool {
+        self.latest().cleaned_at.is_some()
+    }
+
+    fn state(&self) -> Option<&str> {
+        let latest = self.latest();
+        latest
+            .terminal_state
+            .as_deref()
+            .or(latest.scheduler_state.as_deref())
+    }
+
+    /// Job ids as shown in cleanup output, e.g. "jobs 3, 4".
+    pub(crate) fn describe_jobs(&self) -> String {
+        let ids: Vec<String> = self.jobs.iter().map(|job| job.id.to_string()).collect();
+        let noun = if ids.len() == 1 { "job" } else { "jobs" };
+        format!("{noun} {}", ids.join(", "))
+    }
+
+    pub(crate) fn usage(&self, bytes: Option<u64>) -> RunDirUsage {
+        let latest = self.latest();
+        RunDirUsage {
+            remote_path: self.remote_path.clone(),
+            bytes,
+            job_ids: self.jobs.iter().map(|job| job.id).collect(),
+            state: self.state().map(str::to_string),
+            finished_at: latest.finished_at.clone(),
+            running: self.is_running(),
+        }
+    }
+}
+
+/// Groups jobs by remote path, ordered by path.
+pub(crate) fn group_run_dirs(jobs: Vec<JobRecord>) -> Vec<RunDir> {
+    let mut by_path: BTreeMap<String, Vec<JobRecord>> = BTreeMap::new();
+    for job in jobs {
+        by_path
+            .entry(job.remote_path.clone())
+            .or_default()
+            .push(job);
+    }
+    by_path
+        .into_iter()
+        .map(|(remote_path, mut jobs)| {
+            jobs.sort_by_key(|job| job.id);
+            RunDir { remote_path, jobs }
+        })
+        .collect()
+}
+
+/// Why `remote_path` must not be deleted, if anything. Only directories strictly below
+/// `base_path` qualify, and never the data cache.
+pub(crate) fn removal_refusal(remote_path: &str, base_path: &str) -> Option<String> {
+    let path = normalize_path(remote_path);
+    let base = normalize_path(base_path);
+    if !path.is_absolute() || path == base || !path.starts_with(&base) {
+        return Some(format!("it is not below the default base path {base_path}"));
+    }
+    if path.starts_with(Path::new(&cache_dir_for(base_path))) {
+        return Some("it is part of the data cache".to_string());
+    }
+    None
+}
+
+/// Completed, not yet cleaned directories whose latest job finished at least
+/// `older_than_secs` before `now` and, if given, ended in `state`.
+pub(crate) fn gc_candidates<'a>(
+    dirs: &'a [RunDir],
+    now: OffsetDateTime,
+    older_than_secs: Option<i64>,
+    state: Option<&str>,
+) -> Vec<&'a RunDir> {
+    dirs.iter()
+        .filter(|dir| !dir.is_running() && !dir.is_cleaned())
+        .filter(|dir| {
+            state.is_none_or(|want| dir.state().is_some_and(|s| s.eq_ignore_ascii_case(want)))
+        })
+        .filter(|dir| {
+            let Some(secs) = older_than_secs else {
+                return true;
+            };
+            let latest = dir.latest();
+            let finished = latest.finished_at.as_deref().unwrap_or(&latest.created_at);
+            OffsetDateTime::parse(finished, &Rfc3339)
+                .is_ok_and(|at| now - at >= time::Duration::seconds(secs))
+        })
+        .collect()
+}
+
+/// Prints `<bytes>\t<path>` for every existing directory of `paths`.
+pub(crate) fn build_du_command(paths: &[&str]) -> String {
+    let args: Vec<String> = paths.iter().map(|path| sh_escape(path)).collect();
+    format!("du -s -B1 -- {} 2>/dev/null; true", args.join(" "))
+}
+
+/// Parses [`build_du_command`] output into bytes by path.
+pub(crate) fn parse_du_output(output: &str) -> HashMap<String, u64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (bytes, path) = line.split_once('\t')?;
+            Some((path.to_string(), bytes.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Disk usage of the directories that still exist, by path.
+pub(crate) async fn measure_run_dirs(
+    mgr: &SessionManager,
+    dirs: &[RunDir],
+) -> anyhow::Result<HashMap<String, u64>> {
+    let mut sizes = HashMap::new();
+    let paths: Vec<&str> = dirs.iter().map(|dir| dir.remote_path.as_str()).collect();
+    for chunk in paths.chunks(DU_BATCH) {
+        let (out, _, _) = mgr.exec_capture(&build_du_command(chunk)).await?;
+        sizes.extend(parse_du_output(&String::from_utf8_lossy(&out)));
+    }
+    Ok(sizes)
+}
+
+/// Deletes a run directory on the cluster.
+pub(crate) async fn remove_run_dir(mgr: &SessionManager, remote_path: &str) -> anyhow::Result<()> {
+    let (_, err, code) = mgr
+        .exec_capture(&format!("rm -rf -- {}", sh_escape(remote_path)))
+        .await?;
+    if code != 0 {
+        bail!(
+            "rm exited with code {code}: {}",
+            String::from_utf8_lossy(&err).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A job in `remote_path`, finished on `finished` (a date) unless `state` is `None`.
+    fn job(id: i64, remote_path: &str, state: Option<&str>, finished: &str) -> JobRecord {
+        JobRecord {
+            id,
+            scheduler_id: Some(100 + id),
+            name: "cluster-a".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            finished_at: state.map(|_| format!("{finished}T00:00:00Z")),
+            is_completed: state.is_some(),
+            terminal_state: state.map(str::to_string),
+            scheduler_state: state.map(str::to_string),
+            local_path: "/tmp/local".into(),
+            remote_path: remote_path.into(),
+            stdout_path: format!("{remote_path}/slurm.out"),
+            stderr_path: None,
+            origin: "submitted".into(),
+            cleaned_at: None,
+            job_name: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn run_dirs_are_judged_by_their_latest_job() {
+        let dirs = group_run_dirs(vec![
+            job(3, "/base/a", Some("FAILED"), "2026-03-01"),
+            job(1, "/base/a", Some("COMPLETED"), "2026-01-01"),
+            job(2, "/base/b", Some("COMPLETED"), "2026-01-01"),
+            job(4, "/base/c", None, ""),
+            job(5, "/base/c", Some("COMPLETED"), "2026-01-01"),
+        ]);
+        assert_eq!(dirs.len(), 3);
+        assert_eq!(dirs[0].describe_jobs(), "jobs 1, 3");
+        assert!(dirs[2].is_running());
+
+        let now = OffsetDateTime::parse("2026-03-31T00:00:00Z", &Rfc3339).unwrap();
+        let paths = |found: Vec<&RunDir>| -> Vec<String> {
+            found.iter().map(|dir| dir.remote_path.clone()).collect()
+        };
+        assert_eq!(
+            paths(gc_candidates(&dirs, now, None, None)),
+            ["/base/a", "/base/b"]
+        );
+        assert_eq!(
+            paths(gc_candidates(&dirs, now, Some(45 * 86400), None)),
+            ["/base/b"]
+        );
+        assert_eq!(
+            paths(gc_candidates(&dirs, now, None, Some("completed"))),
+            ["/base/b"]
+        );
+    }
+
+    #[test]
+    fn only_directories_below_the_base_path_can_be_removed() {
+        assert!(removal_refusal("/scratch/u/runs/job-1", "/scratch/u/runs").is_none());
+        assert!(removal_refusal("/scratch/u/runs/", "/scratch/u/runs").is_some());
+        assert!(removal_refusal("/scratch/u/runs/../other", "/scratch/u/runs").is_some());
+        assert!(removal_refusal("/home/u/project", "/scratch/u/runs").is_some());
+        assert!(removal_refusal("/scratch/u/runs/.hpc-cache/blobs", "/scratch/u/runs").is_some());
+        assert!(removal_refusal("relative/dir", "/scratch/u/runs").is_some());
+    }
+
+    #[test]
+    fn du_output_is_parsed_by_path() {
+        assert_eq!(
+            build_du_command(&["/base/a", "/base/my dir"]),
+            "du -s -B1 -- '/base/a' '/base/my dir' 2>/dev/null; true"
+        );
+        let sizes = parse_du_output("4096\t/base/a\n8192\t/base/my dir\nbogus\n");
+        assert_eq!(sizes.get("/base/a"), Some(&4096));
+        assert_eq!(sizes.get("/base/my dir"), Some(&8192));
+        assert_eq!(sizes.len(), 2);
+    }
+}
@@ -78,6 +78,45 @@ pub fn parse_sbatch_log_templates(script: &str) -> SbatchLogTemplates {
     }
 }
 
+/// Account and QoS requested by `#SBATCH` directives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SbatchAccounting {
+    pub account: Option<String>,
+    pub qos: Option<String>,
+}
+
+pub fn parse_sbatch_accounting(script: &str) -> SbatchAccounting {
+    SbatchAccounting {
+        account: last_sbatch_option(script, "-A", "--account"),
+        qos: last_sbatch_option(script, "-q", "--qos"),
+    }
+}
+
+/// Value of the last `#SBATCH` occurrence of an option, in `-x=v`, `--xx=v` or `-x v` form.
+fn last_sbatch_option(script: &str, short: &str, long: &str) -> Option<String> {
+    let mut value = None;
+    for line in script.lines() {
+        let Some(rest) = line.trim().strip_prefix("#SBATCH") else {
+            continue;
+        };
+        let tokens = split_sbatch_args(rest.trim_start());
+        let mut i = 0;
+        while i < tokens.len() {
+            let tok = tokens[i].as_str();
+            if let Some(v) = parse_flag_value(tok, short, long) {
+                value = normalize_value(v);
+            } else if (tok == short || tok == long)
+                && let Some(next) = tokens.get(i + 1)
+            {
+                value = normalize_value(next.clone());
+                i += 1;
+            }
+            i += 1;
+        }
+    }
+    value
+}
+
 pub fn resolve_log_path(
     template: &str,
     remote_root: &str,
@@ -95,6 +134,35 @@ pub fn resolve_log_path(
     }
 }
 
+/// Glob matching every file a log template can expand to: each `%` placeholder, with
+/// optional zero-padding width, becomes `*`, and literal glob characters are escaped.
+pub fn log_template_glob(template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '%' => {
+                if chars.next_if_eq(&'%').is_some() {
+                    out.push('%');
+                    continue;
+                }
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                if chars.next().is_some() {
+                    out.push('*');
+                } else {
+                    out.push('%');
+                }
+            }
+            '*' | '?' | '[' | ']' | '{' | '}' | '\\' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
 fn parse_flag_value(token: &str, short: &str, long: &str) -> Option<String> {
     if let Some(value) = token.strip_prefix(short).and_then(|rest| rest.strip_prefix('=')) {
         return Some(value.to_string());
@@ -207,6 +275,13 @@ fn split_sbatch_args(input: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn log_template_glob_replaces_placeholders() {
+        assert_eq!(log_template_glob("slurm-%j.out"), "slurm-*.out");
+        assert_eq!(log_template_glob("logs/%x_%3a-%A.err"), "logs/*_*-*.err");
+        assert_eq!(log_template_glob("100%%[%u]"), "100%\\[*\\]");
+    }
+
     #[test]
     fn parse_sbatch_log_templates_picks_last_values() {
         let script = r#"
@@ -235,6 +310,25 @@ echo "hi"
         assert_eq!(parsed.job_name.as_deref(), Some("job name"));
     }
 
+    #[test]
+    fn parse_sbatch_accounting_reads_account_and_qos() {
+        let script = r#"#!/bin/bash
+#SBATCH -A first
+#SBATCH --account=proj_a --qos long
+#SBATCH --time=01:00:00
+"#;
+        let parsed = parse_sbatch_accounting(script);
+        assert_eq!(parsed.account.as_deref(), Some("proj_a"));
+        assert_eq!(parsed.qos.as_deref(), Some("long"));
+        assert_eq!(
+            parse_sbatch_accounting("#SBATCH -q=short\n"),
+            SbatchAccounting {
+                account: None,
+                qos: Some("short".into())
+            }
+        );
+    }
+
     #[test]
     fn resolve_log_path_replaces_job_id_and_joins_relative() {
         let path = resolve_log_path("logs/%j.out", "/remote/run", 42, None, None);
@@ -1,21 +1,25 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+use crate::agent::efficiency::fetch_and_store_job_usage;
+use crate::agent::fetch::spawn_pending_fetches;
+use crate::agent::prune::{prune_jobs, retention_filter};
 use crate::agent::sessions::{DefaultSessionFactory, SessionCache, SessionFactory};
-use crate::agent::types::{AgentSvcError, OutStream};
-use crate::agent::error_codes;
+use crate::agent::types::AgentSvcError;
+use crate::config::RetentionPolicy;
 use crate::ssh::SessionManager;
-use crate::ssh::receiver_to_stream;
 use crate::state::db::{HostStore, JobRecord};
-use proto::stream_event;
 use proto::{MfaAnswer, StreamEvent};
 use std::collections::HashMap;
 use std::sync::Arc;
-use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tokio::time::Duration;
 use tonic::Status;
 
+/// How often the retention policy is applied.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 fn parse_squeue_state(output: &str) -> Option<String> {
     output
         .lines()
@@ -61,13 +65,42 @@ impl AgentSvc {
         });
     }
 
+    pub fn spawn_retention(&self, policy: RetentionPolicy) {
+        let svc = self.clone();
+        tokio::spawn(async move {
+            svc.run_retention_loop(policy).await;
+        });
+    }
+
+    async fn run_retention_loop(self, policy: RetentionPolicy) {
+        let mut ticker = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let filter = retention_filter(&policy, OffsetDateTime::now_utc());
+            match prune_jobs(&self.hosts, &filter, policy.archive_path.as_deref(), false).await {
+                Ok(pruned) if !pruned.is_empty() => {
+                    log::info!("retention pruned {} job records", pruned.len());
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("retention failed: {err:#}"),
+            }
+        }
+    }
+
     async fn run_job_check_loop(self, interval: Duration) {
+        // Downloads cut short by a daemon restart start over.
+        if let Err(err) = self.hosts.requeue_running_job_fetch_rules().await {
+            log::warn!("failed to requeue job output fetches: {err}");
+        }
         let mut ticker = tokio::time::interval(interval);
         loop {
             ticker.tick().await;
             if let Err(err) = self.check_running_jobs(interval).await {
                 log::warn!("job check failed: {err}");
             }
+            if let Err(err) = spawn_pending_fetches(&self).await {
+                log::warn!("job output fetch failed: {err}");
+            }
         }
     }
 
@@ -116,6 +149,7 @@ impl AgentSvc {
                 }
             }
 
+            let mut finished_with_accounting: Vec<(i64, i64)> = Vec::new();
             for job in host_jobs {
                 if min_age > time::Duration::ZERO {
                     match OffsetDateTime::parse(&job.created_at, &Rfc3339) {
@@ -125,11 +159,7 @@ impl AgentSvc {
                             }
                         }
                         Err(e) => {
-                            log::debug!(
-                                "failed to parse created_at for job {}: {}",
-                                job.id,
-                                e
-                            );
+                            log::debug!("failed to parse created_at for job {}: {}", job.id, e);
                         }
                     }
                 }
@@ -160,12 +190,11 @@ impl AgentSvc {
                     match crate::agent::slurm::sacct_terminal_state(&output) {
                         Some(v) => {
                             completed_ids.push((job.id, Some(v)));
+                            finished_with_accounting.push((job.id, job_id));
                             continue;
                         }
                         None => {
-                            log::debug!(
-                                "sacct returned no terminal state for {name} job {job_id}"
-                            );
+                            log::debug!("sacct returned no terminal state for {name} job {job_id}");
                         }
                     };
 
@@ -284,6 +313,12 @@ impl AgentSvc {
                     }
                 }
             }
+
+            if let Err(e) =
+                fetch_and_store_job_usage(&sm, &self.hosts, &finished_with_accounting).await
+            {
+                log::warn!("failed to record job usage on {name}: {e}");
+            }
         }
 
         if completed_ids.is_empty() {
@@ -324,44 +359,27 @@ impl AgentSvc {
         self.sessions.get_or_create(name, &host).await
     }
 
-    pub async fn run_command(
-        &self,
-        command: String,
-        name: &str,
-        mfa_rx: tokio::sync::mpsc::Receiver<MfaAnswer>,
-    ) -> Result<tonic::Response<OutStream>, Status> {
-        let (evt_tx, evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(64);
-        let mgr = match self.get_sessionmanager(name).await {
+    /// Session for `name`, connecting non-interactively if needed.
+    ///
+    /// Returns None when the host is unknown or a connection would need user input (e.g. MFA).
+    pub async fn connected_sessionmanager(&self, name: &str) -> Option<Arc<SessionManager>> {
+        let sm = match self.get_sessionmanager(name).await {
             Ok(v) => v,
-            Err(e) => match e {
-                AgentSvcError::UnknownName => {
-                    return Err(Status::invalid_argument(error_codes::NOT_FOUND));
-                }
-                AgentSvcError::NetworkError(e) => {
-                    log::debug!("network error while resolving session manager: {e}");
-                    return Err(Status::internal(error_codes::NETWORK_ERROR));
-                }
-                other_error => {
-                    log::debug!("unexpected error while resolving session manager: {other_error}");
-                    return Err(Status::internal(error_codes::INTERNAL_ERROR));
-                }
-            },
+            Err(e) => {
+                log::debug!("failed to get session for {name}: {e}");
+                return None;
+            }
         };
-        let cmd = command;
-        tokio::spawn(async move {
-            if let Err(err) = mgr.exec(&cmd, evt_tx.clone(), mfa_rx).await {
-                log::debug!("command execution failed: {err}");
-                let _ = evt_tx
-                    .send(Ok(StreamEvent {
-                        event: Some(stream_event::Event::Error(
-                            error_codes::REMOTE_ERROR.to_string(),
-                        )),
-                    }))
-                    .await;
+        if sm.needs_connect().await {
+            let (evt_tx, _evt_rx) = tokio::sync::mpsc::channel::<Result<StreamEvent, Status>>(1);
+            let (mfa_tx, mut mfa_rx) = tokio::sync::mpsc::channel::<MfaAnswer>(1);
+            drop(mfa_tx);
+            if let Err(e) = sm.ensure_connected(&evt_tx, &mut mfa_rx).await {
+                log::debug!("failed to connect to {name} non-interactively: {e}");
+                return None;
             }
-        });
-        let out: OutStream = Box::pin(receiver_to_stream(evt_rx));
-        Ok(tonic::Response::new(out))
+        }
+        Some(sm)
     }
 }
 
@@ -281,6 +281,601 @@ fn is_slurm_state_terminal(state: &str) -> bool {
     )
 }
 
+// SLURM ACCOUNTING USAGE
+
+/// Fields requested from `sacct` for efficiency reporting, in the order `parse_sacct_usage` expects.
+pub const SACCT_USAGE_FIELDS: &str =
+    "JobID,State,Elapsed,TotalCPU,AllocCPUS,NNodes,MaxRSS,ReqMem,Timelimit";
+
+/// Resource usage of a single job or job step as reported by `sacct`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SacctStepUsage {
+    /// The raw `JobID` value, e.g. "123", "123.batch" or "123.0".
+    pub step_id: String,
+    pub state: String,
+    pub elapsed_secs: i64,
+    pub total_cpu_secs: f64,
+    pub alloc_cpus: i64,
+    pub nnodes: i64,
+    pub max_rss_bytes: Option<i64>,
+    /// Requested memory for the whole allocation, with per-CPU/per-node requests expanded.
+    pub req_mem_bytes: Option<i64>,
+    pub timelimit_secs: Option<i64>,
+}
+
+impl SacctStepUsage {
+    /// The numeric scheduler id this line belongs to (strips step and array suffixes).
+    pub fn scheduler_id(&self) -> Option<i64> {
+        self.step_id
+            .split(['.', '_', '+'])
+            .next()
+            .and_then(|id| id.trim().parse::<i64>().ok())
+    }
+}
+
+/// Returns the command that fetches usage for the given scheduler ids.
+pub fn sacct_usage_command(scheduler_ids: &[i64]) -> String {
+    let ids = scheduler_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("sacct -j {ids} -n -P --noconvert -o {SACCT_USAGE_FIELDS}")
+}
+
+/// Parse the output of `sacct -n -P -o SACCT_USAGE_FIELDS`.
+///
+/// Lines that don't have the expected number of fields or an unparsable `Elapsed` are skipped.
+pub fn parse_sacct_usage(output: &str) -> Vec<SacctStepUsage> {
+    let mut steps = Vec::new();
+    for line in output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let cols: Vec<&str> = line.split('|').map(str::trim).collect();
+        if cols.len() < 9 {
+            continue;
+        }
+        let Some(elapsed) = parse_slurm_time_secs(cols[2]) else {
+            continue;
+        };
+        let alloc_cpus = cols[4].parse::<i64>().unwrap_or(0);
+        let nnodes = cols[5].parse::<i64>().unwrap_or(0);
+        steps.push(SacctStepUsage {
+            step_id: cols[0].to_string(),
+            state: normalize_slurm_state(cols[1].split_whitespace().next().unwrap_or_default()),
+            elapsed_secs: elapsed as i64,
+            total_cpu_secs: parse_slurm_time_secs(cols[3]).unwrap_or(0.0),
+            alloc_cpus,
+            nnodes,
+            max_rss_bytes: parse_slurm_memory_bytes(cols[6]),
+            req_mem_bytes: parse_slurm_req_mem_bytes(cols[7], alloc_cpus, nnodes),
+            timelimit_secs: parse_slurm_time_secs(cols[8]).map(|s| s as i64),
+        });
+    }
+    steps
+}
+
+/// Parse SLURM times like "D-HH:MM:SS", "HH:MM:SS", "MM:SS.mmm" into seconds.
+/// Returns None for markers like "UNLIMITED", "Partition_Limit" or empty values.
+fn parse_slurm_time_secs(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if s.is_empty() || !s.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let (days, rest) = match s.split_once('-') {
+        Some((d, rest)) => (d.parse::<u64>().ok()?, rest),
+        None => (0, s),
+    };
+    let parts: Vec<&str> = rest.split(':').collect();
+    let (h, m, sec) = match parts.as_slice() {
+        [h, m, sec] => (h.parse::<u64>().ok()?, m.parse::<u64>().ok()?, *sec),
+        [m, sec] => (0, m.parse::<u64>().ok()?, *sec),
+        [sec] => (0, 0, *sec),
+        _ => return None,
+    };
+    let sec: f64 = sec.parse().ok()?;
+    Some((days * 24 * 3600 + h * 3600 + m * 60) as f64 + sec)
+}
+
+/// Parse SLURM memory values like "1024K", "3.5G" or "512" (bytes) into bytes.
+fn parse_slurm_memory_bytes(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    let (num, multiplier) = match s.chars().last()?.to_ascii_uppercase() {
+        'K' => (&s[..s.len() - 1], 1u64 << 10),
+        'M' => (&s[..s.len() - 1], 1 << 20),
+        'G' => (&s[..s.len() - 1], 1 << 30),
+        'T' => (&s[..s.len() - 1], 1 << 40),
+        'P' => (&s[..s.len() - 1], 1 << 50),
+        _ => (s, 1),
+    };
+    let value: f64 = num.parse().ok()?;
+    Some((value * multiplier as f64) as i64)
+}
+
+/// Parse `ReqMem`, expanding the legacy per-CPU ("c") and per-node ("n") suffixes to a total.
+fn parse_slurm_req_mem_bytes(s: &str, alloc_cpus: i64, nnodes: i64) -> Option<i64> {
+    let s = s.trim();
+    if let Some(per_cpu) = s.strip_suffix('c') {
+        return parse_slurm_memory_bytes(per_cpu).map(|b| b * alloc_cpus.max(1));
+    }
+    if let Some(per_node) = s.strip_suffix('n') {
+        return parse_slurm_memory_bytes(per_node).map(|b| b * nnodes.max(1));
+    }
+    parse_slurm_memory_bytes(s).filter(|b| *b > 0)
+}
+
+// SLURM JOB DETAILS
+
+/// The parts of `scontrol show job -o` needed to track a job submitted outside the daemon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScontrolJob {
+    pub job_id: i64,
+    pub user: Option<String>,
+    pub job_state: Option<String>,
+    pub work_dir: String,
+    pub std_out: Option<String>,
+    pub std_err: Option<String>,
+}
+
+/// Parse the first job of `scontrol show job <id> -o` output.
+///
+/// Returns None when `JobId` or `WorkDir` is missing. `UserId=alice(1000)` is reduced to the
+/// user name, and `StdOut`/`StdErr` are only kept when set.
+pub fn parse_scontrol_job(output: &str) -> Option<ScontrolJob> {
+    let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let fields = scontrol_fields(line);
+    let job_id = fields.get("JobId")?.parse::<i64>().ok()?;
+    let work_dir = fields.get("WorkDir").filter(|v| !v.is_empty())?.to_string();
+    let path = |key: &str| {
+        fields
+            .get(key)
+            .filter(|v| !v.is_empty() && **v != "(null)")
+            .map(|v| v.to_string())
+    };
+    Some(ScontrolJob {
+        job_id,
+        user: fields
+            .get("UserId")
+            .map(|v| v.split('(').next().unwrap_or(v).to_string())
+            .filter(|v| !v.is_empty()),
+        job_state: fields
+            .get("JobState")
+            .map(|v| normalize_slurm_state(v))
+            .filter(|v| !v.is_empty()),
+        work_dir,
+        std_out: path("StdOut"),
+        std_err: path("StdErr"),
+    })
+}
+
+/// Split a `scontrol -o` line into its `Key=value` fields. A value runs up to the next
+/// whitespace followed by `Key=` (keys start with a capital, e.g. `CPUs/Task`), so paths
+/// with spaces are kept whole.
+fn scontrol_fields(line: &str) -> HashMap<&str, &str> {
+    let is_key = |k: &str| {
+        k.starts_with(|c: char| c.is_ascii_uppercase())
+            && k.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '/'))
+    };
+    let mut starts = Vec::new();
+    let mut after_space = true;
+    for (i, c) in line.char_indices() {
+        if after_space && !c.is_whitespace() {
+            let tok = line[i..].split(char::is_whitespace).next().unwrap_or("");
+            if tok.split_once('=').is_some_and(|(k, _)| is_key(k)) {
+                starts.push(i);
+            }
+        }
+        after_space = c.is_whitespace();
+    }
+    let mut fields = HashMap::new();
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(line.len());
+        if let Some((k, v)) = line[start..end].trim_end().split_once('=') {
+            fields.entry(k).or_insert(v);
+        }
+    }
+    fields
+}
+
+/// Parse `squeue -h -o %A` output into scheduler job ids.
+pub fn parse_squeue_job_ids(output: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = output
+        .lines()
+        .filter_map(|line| line.trim().parse::<i64>().ok())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+// SLURM QUEUE
+
+/// `squeue -o` format used by `parse_squeue_queue`. The job name goes last so that a `|` in it
+/// can't shift the other columns.
+pub const SQUEUE_QUEUE_FORMAT: &str = "%i|%A|%u|%P|%T|%r|%Q|%S|%M|%l|%D|%C|%N|%j";
+
+/// One row of the cluster queue as reported by `squeue`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub job_id: String,
+    pub scheduler_id: i64,
+    pub user: String,
+    pub partition: String,
+    pub state: String,
+    pub reason: Option<String>,
+    pub priority: Option<i64>,
+    pub start_time: Option<String>,
+    pub time_used: String,
+    pub time_limit: String,
+    pub nodes: i64,
+    pub cpus: i64,
+    pub nodelist: Option<String>,
+    pub name: String,
+}
+
+/// Build the `squeue` command for a queue listing with optional filters.
+pub fn squeue_queue_command(
+    user: Option<&str>,
+    partition: Option<&str>,
+    state: Option<&str>,
+    start: bool,
+) -> String {
+    let mut command = String::from("squeue -h");
+    if start {
+        command.push_str(" --start");
+    }
+    if let Some(user) = user {
+        command.push_str(&format!(" -u {}", crate::ssh::sh_escape(user)));
+    }
+    if let Some(partition) = partition {
+        command.push_str(&format!(" -p {}", crate::ssh::sh_escape(partition)));
+    }
+    if let Some(state) = state {
+        command.push_str(&format!(" -t {}", crate::ssh::sh_escape(state)));
+    }
+    command.push_str(&format!(
+        " -o {}",
+        crate::ssh::sh_escape(SQUEUE_QUEUE_FORMAT)
+    ));
+    command
+}
+
+/// Parse `squeue -h -o SQUEUE_QUEUE_FORMAT` output. Malformed lines are skipped.
+pub fn parse_squeue_queue(output: &str) -> Vec<QueueEntry> {
+    let optional = |value: &str| {
+        let value = value.trim();
+        if value.is_empty()
+            || value.eq_ignore_ascii_case("n/a")
+            || value.eq_ignore_ascii_case("none")
+            || value == "(null)"
+        {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    };
+    let mut entries = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let cols: Vec<&str> = line.splitn(14, '|').collect();
+        if cols.len() != 14 {
+            continue;
+        }
+        let Ok(scheduler_id) = cols[1].trim().parse::<i64>() else {
+            continue;
+        };
+        entries.push(QueueEntry {
+            job_id: cols[0].trim().to_string(),
+            scheduler_id,
+            user: cols[2].trim().to_string(),
+            partition: cols[3].trim().to_string(),
+            state: normalize_slurm_state(cols[4]),
+            reason: optional(cols[5]),
+            priority: cols[6].trim().parse::<f64>().ok().map(|p| p as i64),
+            start_time: optional(cols[7]),
+            time_used: cols[8].trim().to_string(),
+            time_limit: cols[9].trim().to_string(),
+            nodes: cols[10].trim().parse().unwrap_or(0),
+            cpus: cols[11].trim().parse().unwrap_or(0),
+            nodelist: optional(cols[12]),
+            name: cols[13].trim_end_matches(['\r', '\n']).to_string(),
+        });
+    }
+    entries
+}
+
+// SLURM NODES
+
+pub const SCONTROL_SHOW_NODES_CMD: &str = "scontrol show node -o";
+
+/// GPUs of one type on a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeGpus {
+    /// GPU type from the GRES spec, e.g. `h200`; empty when the GRES is untyped.
+    pub gpu_type: String,
+    pub total: i64,
+    pub alloc: i64,
+}
+
+/// A compute node as parsed from `scontrol show node -o`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+    /// Raw state including flags, e.g. `MIXED+DRAIN` or `IDLE*`.
+    pub state: String,
+    pub partitions: Vec<String>,
+    pub cpus_total: i64,
+    pub cpus_alloc: i64,
+    pub memory_total_mb: i64,
+    pub memory_alloc_mb: i64,
+    pub memory_free_mb: Option<i64>,
+    pub gpus: Vec<NodeGpus>,
+}
+
+impl Node {
+    /// Whether the node can take new work: not down, drained, failed, in maintenance or
+    /// unresponsive (`*` suffix).
+    pub fn is_available(&self) -> bool {
+        let state = self.state.to_ascii_uppercase();
+        if state.contains('*') {
+            return false;
+        }
+        !state.split('+').any(|part| {
+            matches!(
+                part,
+                "DOWN"
+                    | "DRAIN"
+                    | "DRAINED"
+                    | "DRAINING"
+                    | "FAIL"
+                    | "FAILING"
+                    | "MAINT"
+                    | "POWER_DOWN"
+                    | "POWERED_DOWN"
+                    | "RESERVED"
+                    | "UNKNOWN"
+                    | "FUTURE"
+            )
+        })
+    }
+
+    /// Base state without flags, e.g. `MIXED` for `MIXED+DRAIN`.
+    pub fn base_state(&self) -> String {
+        self.state
+            .split('+')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(['*', '~', '#', '!', '%', '$', '@', '^', '-'])
+            .to_ascii_uppercase()
+    }
+}
+
+/// Split a comma separated list while keeping commas inside parentheses, as in
+/// `gpu:h200:2(IDX:0,2)`.
+fn split_top_level_commas(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                out.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(&s[start..]);
+    out.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Parse GPU entries of a node `Gres=` value like `gpu:h200:4(S:0-1),gpu:a100:2,shard:8`.
+fn parse_gres_gpus(gres: &str) -> Vec<(String, i64)> {
+    let mut out: Vec<(String, i64)> = Vec::new();
+    for entry in split_top_level_commas(gres) {
+        let entry = entry.split('(').next().unwrap_or_default();
+        let parts: Vec<&str> = entry.split(':').collect();
+        if parts.first() != Some(&"gpu") {
+            continue;
+        }
+        let (gpu_type, count) = match parts.as_slice() {
+            [_, count] => ("", *count),
+            [_, gpu_type, count, ..] => (*gpu_type, *count),
+            _ => continue,
+        };
+        let Ok(count) = count.parse::<i64>() else {
+            continue;
+        };
+        match out.iter_mut().find(|(t, _)| t == gpu_type) {
+            Some((_, total)) => *total += count,
+            None => out.push((gpu_type.to_string(), count)),
+        }
+    }
+    out
+}
+
+/// Allocated GPUs from an `AllocTRES=` value, per type. The untyped `gres/gpu` total is returned
+/// separately for nodes whose GRES has no type.
+fn parse_alloc_tres_gpus(tres: &str) -> (HashMap<String, i64>, Option<i64>) {
+    let mut typed = HashMap::new();
+    let mut untyped = None;
+    for entry in tres.split(',') {
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        let Ok(value) = value.parse::<i64>() else {
+            continue;
+        };
+        if key == "gres/gpu" {
+            untyped = Some(value);
+        } else if let Some(gpu_type) = key.strip_prefix("gres/gpu:") {
+            typed.insert(gpu_type.to_string(), value);
+        }
+    }
+    (typed, untyped)
+}
+
+/// Parse the output of `scontrol show node -o` into nodes. Lines without `NodeName` are skipped.
+///
+/// Allocated GPUs come from `AllocTRES`; when it only reports an untyped `gres/gpu` count, that
+/// count is attributed to the node's only GPU type.
+pub fn parse_scontrol_nodes(input: &str) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let mut fields = HashMap::<&str, &str>::new();
+        for tok in line.split_whitespace() {
+            if let Some((k, v)) = tok.split_once('=') {
+                fields.entry(k).or_insert(v);
+            }
+        }
+        let Some(name) = fields.get("NodeName").map(|v| v.to_string()) else {
+            continue;
+        };
+        let int = |key: &str| {
+            fields
+                .get(key)
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(0)
+        };
+        let (alloc_typed, alloc_untyped) =
+            parse_alloc_tres_gpus(fields.get("AllocTRES").copied().unwrap_or_default());
+        let gres = parse_gres_gpus(fields.get("Gres").copied().unwrap_or_default());
+        let single_type = gres.len() == 1;
+        let gpus = gres
+            .into_iter()
+            .map(|(gpu_type, total)| {
+                let alloc = alloc_typed
+                    .get(&gpu_type)
+                    .copied()
+                    .or(if single_type { alloc_untyped } else { None })
+                    .unwrap_or(0);
+                NodeGpus {
+                    gpu_type,
+                    total,
+                    alloc,
+                }
+            })
+            .collect();
+        nodes.push(Node {
+            name,
+            state: fields.get("State").copied().unwrap_or_default().to_string(),
+            partitions: fields
+                .get("Partitions")
+                .map(|v| {
+                    v.split(',')
+                        .filter(|p| !p.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cpus_total: int("CPUTot"),
+            cpus_alloc: int("CPUAlloc"),
+            memory_total_mb: int("RealMemory"),
+            memory_alloc_mb: int("AllocMem"),
+            memory_free_mb: fields.get("FreeMem").and_then(|v| v.parse::<i64>().ok()),
+            gpus,
+        });
+    }
+    nodes
+}
+
+// SLURM ACCOUNTS
+
+/// `sacctmgr` association fields parsed by `parse_sacctmgr_assoc`.
+pub const SACCTMGR_ASSOC_FIELDS: &str = "Account,Partition,QOS,DefaultQOS";
+/// `sshare` fields parsed by `parse_sshare`.
+pub const SSHARE_FIELDS: &str = "Account,User,RawShares,NormShares,RawUsage,EffectvUsage,FairShare";
+
+/// A user association as reported by `sacctmgr show assoc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlurmAssociation {
+    pub account: String,
+    pub partition: Option<String>,
+    pub qos: Vec<String>,
+    pub default_qos: Option<String>,
+}
+
+/// Fairshare data of a user in one account as reported by `sshare -U`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshareEntry {
+    pub account: String,
+    pub raw_shares: Option<i64>,
+    pub norm_shares: Option<f64>,
+    pub raw_usage: Option<i64>,
+    pub effective_usage: Option<f64>,
+    pub fairshare: Option<f64>,
+}
+
+pub fn sacctmgr_assoc_command(user: &str) -> String {
+    format!(
+        "sacctmgr -n -P show assoc where user={} format={SACCTMGR_ASSOC_FIELDS}",
+        crate::ssh::sh_escape(user)
+    )
+}
+
+pub fn sshare_command(user: &str) -> String {
+    format!(
+        "sshare -n -P -U -u {} -o {SSHARE_FIELDS}",
+        crate::ssh::sh_escape(user)
+    )
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Parse `sacctmgr -n -P show assoc format=SACCTMGR_ASSOC_FIELDS` output.
+pub fn parse_sacctmgr_assoc(output: &str) -> Vec<SlurmAssociation> {
+    let mut out = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let cols: Vec<&str> = line.split('|').collect();
+        if cols.len() < 4 {
+            continue;
+        }
+        let Some(account) = non_empty(cols[0]) else {
+            continue;
+        };
+        out.push(SlurmAssociation {
+            account,
+            partition: non_empty(cols[1]),
+            qos: cols[2].split(',').filter_map(non_empty).collect(),
+            default_qos: non_empty(cols[3]),
+        });
+    }
+    out
+}
+
+/// Parse `sshare -n -P -U -o SSHARE_FIELDS` output.
+pub fn parse_sshare(output: &str) -> Vec<SshareEntry> {
+    let mut out = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let cols: Vec<&str> = line.split('|').map(str::trim).collect();
+        if cols.len() < 7 {
+            continue;
+        }
+        let Some(account) = non_empty(cols[0]) else {
+            continue;
+        };
+        out.push(SshareEntry {
+            account,
+            raw_shares: cols[2].parse().ok(),
+            norm_shares: cols[3].parse().ok(),
+            raw_usage: cols[4].parse().ok(),
+            effective_usage: cols[5].parse().ok(),
+            fairshare: cols[6].parse().ok(),
+        });
+    }
+    out
+}
+
 // returns a command to be executed on cluster to submit the job
 pub fn path_to_sbatch_command(p: &str, remote_base_path: Option<&str>) -> String {
     let escaped_script = crate::ssh::sh_escape(p);
@@ -470,6 +1065,228 @@ PartitionName=gpu_bynode_q5 AllowGroups=ALL AllowAccounts=ALL AllowQos=ALL Alloc
         assert_eq!(scontrol_job_state(output), None);
     }
 
+    #[test]
+    fn sacct_usage_parses_job_and_steps() {
+        let output = "123|COMPLETED|00:10:00|00:19:59.500|4|1|||1-00:00:00\n\
+123.batch|COMPLETED|00:10:00|00:19:59.500|4|1|2097152K||\n\
+123.extern|COMPLETED|00:10:00|00:00:00.001|4|1|1024K||\n";
+        let steps = parse_sacct_usage(output);
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].scheduler_id(), Some(123));
+        assert_eq!(steps[0].elapsed_secs, 600);
+        assert_eq!(steps[0].total_cpu_secs, 1199.5);
+        assert_eq!(steps[0].alloc_cpus, 4);
+        assert_eq!(steps[0].timelimit_secs, Some(86400));
+        assert_eq!(steps[0].req_mem_bytes, None);
+        assert_eq!(steps[1].scheduler_id(), Some(123));
+        assert_eq!(steps[1].max_rss_bytes, Some(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn sacct_usage_expands_legacy_req_mem() {
+        let output = "7|COMPLETED|01:00:00|02:00:00|4|2||1000Mc|UNLIMITED\n\
+8|COMPLETED|01:00:00|02:00:00|4|2||1000Mn|Partition_Limit\n\
+9|CANCELLED by 42|00:00:10|00:01.000|1|1||16G|01:00\n";
+        let steps = parse_sacct_usage(output);
+        assert_eq!(steps[0].req_mem_bytes, Some(4 * 1000 * 1024 * 1024));
+        assert_eq!(steps[0].timelimit_secs, None);
+        assert_eq!(steps[1].req_mem_bytes, Some(2 * 1000 * 1024 * 1024));
+        assert_eq!(steps[1].timelimit_secs, None);
+        assert_eq!(steps[2].state, "CANCELLED");
+        assert_eq!(steps[2].req_mem_bytes, Some(16 * 1024 * 1024 * 1024));
+        assert_eq!(steps[2].timelimit_secs, Some(60));
+        assert_eq!(steps[2].total_cpu_secs, 1.0);
+    }
+
+    #[test]
+    fn sacct_usage_skips_malformed_lines() {
+        assert!(parse_sacct_usage("garbage\n1|RUNNING|x|y|1|1|||\n").is_empty());
+    }
+
+    #[test]
+    fn sacct_usage_command_joins_ids() {
+        assert_eq!(
+            sacct_usage_command(&[1, 22]),
+            format!("sacct -j 1,22 -n -P --noconvert -o {SACCT_USAGE_FIELDS}")
+        );
+    }
+
+    #[test]
+    fn scontrol_job_parses_paths_and_user() {
+        let output = "JobId=4242 JobName=train UserId=alice(1000) GroupId=alice(1000) JobState=RUNNING Reason=None WorkDir=/home/alice/proj StdErr=/home/alice/proj/err-4242.log StdIn=/dev/null StdOut=/home/alice/proj/slurm-4242.out Power=";
+        let job = parse_scontrol_job(output).unwrap();
+        assert_eq!(job.job_id, 4242);
+        assert_eq!(job.user.as_deref(), Some("alice"));
+        assert_eq!(job.job_state.as_deref(), Some("RUNNING"));
+        assert_eq!(job.work_dir, "/home/alice/proj");
+        assert_eq!(
+            job.std_out.as_deref(),
+            Some("/home/alice/proj/slurm-4242.out")
+        );
+        assert_eq!(
+            job.std_err.as_deref(),
+            Some("/home/alice/proj/err-4242.log")
+        );
+    }
+
+    #[test]
+    fn scontrol_job_requires_id_and_workdir() {
+        assert_eq!(parse_scontrol_job("JobId=1 JobState=PENDING"), None);
+        assert_eq!(parse_scontrol_job("WorkDir=/tmp JobState=PENDING"), None);
+        let job = parse_scontrol_job("JobId=1 WorkDir=/tmp StdOut=(null)").unwrap();
+        assert_eq!(job.std_out, None);
+        assert_eq!(job.std_err, None);
+    }
+
+    #[test]
+    fn scontrol_job_keeps_paths_with_spaces() {
+        let job = parse_scontrol_job(
+            "JobId=7 UserId=bob(1001) JobState=RUNNING WorkDir=/home/bob/my runs/a=1 \
+             StdErr=/home/bob/my runs/err.log StdIn=/dev/null StdOut=/home/bob/my runs/out 7.log",
+        )
+        .unwrap();
+        assert_eq!(job.work_dir, "/home/bob/my runs/a=1");
+        assert_eq!(job.std_err.as_deref(), Some("/home/bob/my runs/err.log"));
+        assert_eq!(job.std_out.as_deref(), Some("/home/bob/my runs/out 7.log"));
+        assert_eq!(job.user.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn squeue_job_ids_are_sorted_and_unique() {
+        assert_eq!(parse_squeue_job_ids("12\n 7\n12\nbogus\n"), vec![7, 12]);
+    }
+
+    #[test]
+    fn squeue_queue_parses_pending_and_running_rows() {
+        let output = "101_3|104|alice|gpu|PENDING|Priority|4242|2026-10-19T08:00:00|0:00|1-00:00:00|2|16||train|v2\n\
+102|102|bob|cpu|RUNNING|None|1000|2026-10-18T07:00:00|1:02:03|4:00:00|1|4|x12|sim\n\
+garbage\n";
+        let rows = parse_squeue_queue(output);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].job_id, "101_3");
+        assert_eq!(rows[0].scheduler_id, 104);
+        assert_eq!(rows[0].state, "PENDING");
+        assert_eq!(rows[0].reason.as_deref(), Some("Priority"));
+        assert_eq!(rows[0].priority, Some(4242));
+        assert_eq!(rows[0].nodelist, None);
+        assert_eq!(rows[0].name, "train|v2");
+        assert_eq!(rows[1].reason, None);
+        assert_eq!(rows[1].nodelist.as_deref(), Some("x12"));
+        assert_eq!(rows[1].cpus, 4);
+    }
+
+    #[test]
+    fn squeue_queue_command_escapes_filters() {
+        let cmd = squeue_queue_command(Some("alice"), Some("gpu,cpu"), Some("PENDING"), true);
+        assert_eq!(
+            cmd,
+            format!(
+                "squeue -h --start -u 'alice' -p 'gpu,cpu' -t 'PENDING' -o '{SQUEUE_QUEUE_FORMAT}'"
+            )
+        );
+        assert_eq!(
+            squeue_queue_command(None, None, None, false),
+            format!("squeue -h -o '{SQUEUE_QUEUE_FORMAT}'")
+        );
+    }
+
+    const NODES_SAMPLE: &str = r#"NodeName=g20 Arch=x86_64 CoresPerSocket=56 CPUAlloc=64 CPUEfctv=112 CPUTot=112 CPULoad=63.02 AvailableFeatures=h200 ActiveFeatures=h200 Gres=gpu:h200:4(S:0-1) NodeAddr=g20 NodeHostName=g20 Version=23.11.5 OS=Linux RealMemory=1031000 AllocMem=512000 FreeMem=480113 Sockets=2 Boards=1 State=MIXED ThreadsPerCore=1 TmpDisk=0 Weight=1 Owner=N/A MCS_label=N/A Partitions=gpu_bygpu_q1,gpu_bygpu_q2 BootTime=2026-09-01T10:00:00 SlurmdStartTime=2026-09-01T10:01:00 CfgTRES=cpu=112,mem=1031000M,billing=112,gres/gpu=4,gres/gpu:h200=4 AllocTRES=cpu=64,mem=500G,gres/gpu=2,gres/gpu:h200=2 CapWatts=n/a CurrentWatts=0 AveWatts=0
+NodeName=g21 CPUAlloc=0 CPUTot=112 Gres=gpu:nvidia_h200_1g.12gb:8(S:0),gpu:h200:2(S:1),shard:16 RealMemory=1031000 AllocMem=0 FreeMem=N/A State=IDLE+DRAIN Partitions=gpu_bygpu_q1 AllocTRES= Reason=maintenance [root@2026-10-01T00:00:00]
+NodeName=c1 CPUAlloc=48 CPUTot=48 Gres=(null) RealMemory=192000 AllocMem=192000 FreeMem=1000 State=ALLOCATED Partitions=cpu AllocTRES=cpu=48,mem=192000M
+NodeName=g1 CPUAlloc=8 CPUTot=32 Gres=gpu:2 RealMemory=256000 AllocMem=64000 State=MIXED* Partitions=gpu_old AllocTRES=cpu=8,mem=64000M,gres/gpu=1
+"#;
+
+    #[test]
+    fn parses_scontrol_nodes() {
+        let nodes = parse_scontrol_nodes(NODES_SAMPLE);
+        assert_eq!(nodes.len(), 4);
+
+        let g20 = &nodes[0];
+        assert_eq!(g20.name, "g20");
+        assert_eq!(g20.state, "MIXED");
+        assert_eq!(g20.partitions, vec!["gpu_bygpu_q1", "gpu_bygpu_q2"]);
+        assert_eq!((g20.cpus_alloc, g20.cpus_total), (64, 112));
+        assert_eq!(
+            (g20.memory_alloc_mb, g20.memory_total_mb),
+            (512000, 1031000)
+        );
+        assert_eq!(g20.memory_free_mb, Some(480113));
+        assert_eq!(
+            g20.gpus,
+            vec![NodeGpus {
+                gpu_type: "h200".into(),
+                total: 4,
+                alloc: 2
+            }]
+        );
+        assert!(g20.is_available());
+    }
+
+    #[test]
+    fn parses_node_gres_variants_and_availability() {
+        let nodes = parse_scontrol_nodes(NODES_SAMPLE);
+
+        let g21 = &nodes[1];
+        assert_eq!(g21.memory_free_mb, None);
+        assert_eq!(g21.gpus.len(), 2);
+        assert_eq!(g21.gpus[0].gpu_type, "nvidia_h200_1g.12gb");
+        assert_eq!((g21.gpus[0].total, g21.gpus[0].alloc), (8, 0));
+        assert_eq!(g21.base_state(), "IDLE");
+        assert!(!g21.is_available());
+
+        assert!(nodes[2].gpus.is_empty());
+        assert!(nodes[2].is_available());
+
+        let g1 = &nodes[3];
+        assert_eq!(g1.gpus[0].gpu_type, "");
+        assert_eq!((g1.gpus[0].total, g1.gpus[0].alloc), (2, 1));
+        assert_eq!(g1.base_state(), "MIXED");
+        assert!(!g1.is_available());
+    }
+
+    #[test]
+    fn gres_split_keeps_parenthesised_commas() {
+        assert_eq!(
+            split_top_level_commas("gpu:a100:2(IDX:0,2),shard:4"),
+            vec!["gpu:a100:2(IDX:0,2)", "shard:4"]
+        );
+        assert_eq!(
+            parse_gres_gpus("gpu:a100:2(IDX:0,2),gpu:a100:1"),
+            vec![("a100".to_string(), 3)]
+        );
+        assert!(parse_gres_gpus("(null)").is_empty());
+    }
+
+    #[test]
+    fn parses_sacctmgr_associations() {
+        let output = "proj_a||normal,long|normal\nproj_b|gpu|gpu_short|\n|x|y|z\n";
+        let assocs = parse_sacctmgr_assoc(output);
+        assert_eq!(assocs.len(), 2);
+        assert_eq!(assocs[0].account, "proj_a");
+        assert_eq!(assocs[0].partition, None);
+        assert_eq!(assocs[0].qos, vec!["normal", "long"]);
+        assert_eq!(assocs[0].default_qos.as_deref(), Some("normal"));
+        assert_eq!(assocs[1].partition.as_deref(), Some("gpu"));
+        assert_eq!(assocs[1].default_qos, None);
+        assert_eq!(
+            sacctmgr_assoc_command("alice"),
+            "sacctmgr -n -P show assoc where user='alice' format=Account,Partition,QOS,DefaultQOS"
+        );
+    }
+
+    #[test]
+    fn parses_sshare_rows() {
+        let output = "proj_a|alice|1|0.250000|123456|0.010000|0.750000\nproj_b|alice|parent|0.5|0|0.000000|\n";
+        let rows = parse_sshare(output);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].account, "proj_a");
+        assert_eq!(rows[0].raw_shares, Some(1));
+        assert_eq!(rows[0].raw_usage, Some(123456));
+        assert_eq!(rows[0].fairshare, Some(0.75));
+        assert_eq!(rows[1].raw_shares, None);
+        assert_eq!(rows[1].fairshare, None);
+    }
+
     #[test]
     fn error_if_partition_name_missing() {
         let bad = "AllowGroups=ALL QoS=N/A";
@@ -1,17 +1,36 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
-use proto::{StreamEvent, SubmitStreamEvent};
+use proto::{
+    CacheGcStreamEvent, ClusterAccountsStreamEvent, ClusterNodesStreamEvent,
+    ClusterQueueStreamEvent, ClusterUsageStreamEvent, ListDirStreamEvent, LsStreamEvent,
+    ReadJobFileStreamEvent, StreamEvent, SubmitStreamEvent,
+};
 use std::pin::Pin;
 use thiserror::Error as ThisError;
 use tokio_stream::Stream;
 use tonic::Status;
 
-
 pub type OutStream =
     Pin<Box<dyn Stream<Item = Result<StreamEvent, Status>> + Send + Sync + 'static>>;
+pub type LsOutStream =
+    Pin<Box<dyn Stream<Item = Result<LsStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ListDirOutStream =
+    Pin<Box<dyn Stream<Item = Result<ListDirStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ReadJobFileOutStream =
+    Pin<Box<dyn Stream<Item = Result<ReadJobFileStreamEvent, Status>> + Send + Sync + 'static>>;
 pub type SubmitOutStream =
     Pin<Box<dyn Stream<Item = Result<SubmitStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ClusterQueueOutStream =
+    Pin<Box<dyn Stream<Item = Result<ClusterQueueStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ClusterNodesOutStream =
+    Pin<Box<dyn Stream<Item = Result<ClusterNodesStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ClusterAccountsOutStream =
+    Pin<Box<dyn Stream<Item = Result<ClusterAccountsStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type ClusterUsageOutStream =
+    Pin<Box<dyn Stream<Item = Result<ClusterUsageStreamEvent, Status>> + Send + Sync + 'static>>;
+pub type CacheGcOutStream =
+    Pin<Box<dyn Stream<Item = Result<CacheGcStreamEvent, Status>> + Send + Sync + 'static>>;
 
 #[derive(Debug, PartialEq, Eq, ThisError)]
 pub enum AgentSvcError {
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! The file manifest of a job and `hpc job verify`.
+//!
+//! Every file a submit uploaded or a retrieve downloaded, and that matched its other copy
+//! by SHA-256, is recorded with that hash. Files a sync skipped because they were already
+//! up to date are not. Verifying a job hashes the local and remote copies again and
+//! reports which side, if any, no longer holds the recorded content.
+
+use crate::ssh::{SessionManager, VerifiedFile, local_file_sha256};
+use crate::state::db::{JobFile, NewJobFile};
+use std::path::Path;
+
+/// Outcome of re-checking one manifest entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FileCheck {
+    Ok,
+    LocalMissing,
+    LocalModified,
+    RemoteMissing,
+    RemoteModified,
+    BothModified,
+}
+
+impl FileCheck {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::LocalMissing => "missing locally",
+            Self::LocalModified => "modified locally",
+            Self::RemoteMissing => "missing on remote",
+            Self::RemoteModified => "modified on remote",
+            Self::BothModified => "modified on both sides",
+        }
+    }
+}
+
+/// Compares the current hashes of both copies with the one recorded at transfer time.
+pub(crate) fn check_file(recorded: &str, local: Option<&str>, remote: Option<&str>) -> FileCheck {
+    let (Some(local), Some(remote)) = (local, remote) else {
+        return if local.is_none() {
+            FileCheck::LocalMissing
+        } else {
+            FileCheck::RemoteMissing
+        };
+    };
+    match (local == recorded, remote == recorded) {
+        (true, true) => FileCheck::Ok,
+        (false, true) => FileCheck::LocalModified,
+        (true, false) => FileCheck::RemoteModified,
+        (false, false) => FileCheck::BothModified,
+    }
+}
+
+/// Manifest entries for the files a transfer wrote and verified; `uploaded` tells a submit
+/// from a retrieve.
+pub(crate) fn manifest_entries(files: &[VerifiedFile], uploaded: bool) -> Vec<NewJobFile> {
+    files
+        .iter()
+        .map(|file| NewJobFile {
+            remote_path: file.remote_path.clone(),
+            local_path: file.local_path.to_string_lossy().into_owned(),
+            size: file.size as i64,
+            sha256: file.sha256.clone(),
+            uploaded,
+        })
+        .collect()
+}
+
+/// Hashes the local and remote copy of every manifest entry and checks them.
+pub(crate) async fn verify_job_files(
+    mgr: &SessionManager,
+    files: &[JobFile],
+) -> anyhow::Result<Vec<FileCheck>> {
+    let remote_paths: Vec<&str> = files.iter().map(|f| f.remote_path.as_str()).collect();
+    let remote = mgr.remote_sha256s(&remote_paths).await?;
+    let mut checks = Vec::with_capacity(files.len());
+    for file in files {
+        let local = local_file_sha256(Path::new(&file.local_path)).await.ok();
+        checks.push(check_file(
+            &file.sha256,
+            local.as_deref(),
+            remote.get(&file.remote_path).map(String::as_str),
+        ));
+    }
+    Ok(checks)
+}
+
+/// One report line per manifest entry.
+pub(crate) fn format_checks(files: &[JobFile], checks: &[FileCheck]) -> String {
+    files
+        .iter()
+        .zip(checks)
+        .map(|(file, check)| format!("{:<22} {}\n", check.label(), file.local_path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileCheck, check_file};
+
+    #[test]
+    fn check_file_reports_which_copy_changed() {
+        assert_eq!(check_file("aa", Some("aa"), Some("aa")), FileCheck::Ok);
+        assert_eq!(check_file("aa", None, Some("aa")), FileCheck::LocalMissing);
+        assert_eq!(check_file("aa", None, None), FileCheck::LocalMissing);
+        assert_eq!(check_file("aa", Some("aa"), None), FileCheck::RemoteMissing);
+        assert_eq!(
+            check_file("aa", Some("bb"), Some("aa")),
+            FileCheck::LocalModified
+        );
+        assert_eq!(
+            check_file("aa", Some("aa"), Some("bb")),
+            FileCheck::RemoteModified
+        );
+        assert_eq!(
+            check_file("aa", Some("bb"), Some("bb")),
+            FileCheck::BothModified
+        );
+    }
+}
@@ -20,6 +20,27 @@ struct FileConfig {
     job_check_interval_secs: Option<u64>,
     port: Option<u16>,
     verbose: Option<bool>,
+    retention: Option<FileRetention>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileRetention {
+    max_age_days: u64,
+    #[serde(default)]
+    states: Vec<String>,
+    archive_path: Option<String>,
+}
+
+/// Job records the daemon prunes from the database on its own, from the `[retention]`
+/// table of the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Finished jobs older than this are pruned.
+    pub max_age_days: u64,
+    /// Only prune jobs that ended in one of these states; empty prunes any finished job.
+    pub states: Vec<String>,
+    /// JSON-lines file pruned records are appended to before they are deleted.
+    pub archive_path: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -29,6 +50,7 @@ pub struct Config {
     pub port: u16,
     pub verbose: bool,
     pub config_path: Option<PathBuf>,
+    pub retention: Option<RetentionPolicy>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -63,6 +85,7 @@ pub struct ConfigReport {
     pub job_check_interval_secs: ConfigValue<u64>,
     pub port: ConfigValue<u16>,
     pub verbose: ConfigValue<bool>,
+    pub retention: Option<RetentionPolicy>,
 }
 
 #[derive(Debug)]
@@ -151,12 +174,22 @@ pub fn load_with_report(
             },
         };
 
+    let config_dir = config_path.as_deref().and_then(|path| path.parent());
+    let retention = file_config.retention.map(|retention| RetentionPolicy {
+        max_age_days: retention.max_age_days,
+        states: retention.states,
+        archive_path: retention
+            .archive_path
+            .map(|raw| resolve_path(&raw, config_dir)),
+    });
+
     let config = Config {
         database_path,
         job_check_interval_secs,
         port,
         verbose,
         config_path: config_path.clone(),
+        retention,
     };
 
     let report = ConfigReport {
@@ -179,6 +212,7 @@ pub fn load_with_report(
             value: config.verbose,
             source: verbose_source,
         },
+        retention: config.retention.clone(),
     };
 
     Ok(LoadResult { config, report })
@@ -288,6 +322,29 @@ mod tests {
         assert_eq!(config.config_path, Some(config_path));
     }
 
+    #[test]
+    fn reads_retention_policy_from_config() {
+        let dir = TempDir::new().unwrap();
+        let config_dir = dir.path().join("config");
+        fs::create_dir_all(&config_dir).unwrap();
+        let config_path = config_dir.join("hpc.toml");
+        fs::write(
+            &config_path,
+            "[retention]\nmax_age_days = 90\nstates = [\"COMPLETED\"]\narchive_path = \"archive/jobs.jsonl\"\n",
+        )
+        .unwrap();
+
+        let config = load(Some(config_path), Overrides::default()).unwrap();
+        assert_eq!(
+            config.retention,
+            Some(RetentionPolicy {
+                max_age_days: 90,
+                states: vec!["COMPLETED".to_string()],
+                archive_path: Some(config_dir.join("archive").join("jobs.jsonl")),
+            })
+        );
+    }
+
     #[test]
     fn cli_overrides_take_precedence_over_file_config() {
         let dir = TempDir::new().unwrap();
@@ -140,6 +140,19 @@ fn log_config_report(report: &config::ConfigReport) {
         report.verbose.value,
         report.verbose.source.as_str()
     );
+    match &report.retention {
+        Some(policy) => log::info!(
+            "config retention: max_age_days={} states={:?} archive_path={}",
+            policy.max_age_days,
+            policy.states,
+            policy
+                .archive_path
+                .as_deref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "-".to_string())
+        ),
+        None => log::info!("config retention: (none)"),
+    }
 }
 
 #[tokio::main]
@@ -173,6 +186,9 @@ async fn main() -> anyhow::Result<()> {
 
     let svc = agent::AgentSvc::new(db);
     svc.spawn_job_checker(Duration::from_secs(config.job_check_interval_secs));
+    if let Some(policy) = config.retention {
+        svc.spawn_retention(policy);
+    }
     log::info!("server listening on {}", server_addr);
     Server::builder()
         .add_service(AgentServer::new(svc))
@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Bulk upload of many small new files as one tar stream.
+//!
+//! Uploading file by file costs a metadata round trip plus an SFTP open/write/close per
+//! file, which dominates for trees of tens of thousands of small files. When a sync plan
+//! has enough small files, the remote tree is listed once and the files missing there
+//! are streamed as a tar archive (optionally zstd-compressed) into `tar -x` over a single
+//! exec channel. Everything else still goes through the per-file path.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::sync::FileStamp;
+use super::sync_plan::{SyncItem, rel_path_to_slash};
+use super::utils::sh_escape;
+
+/// Minimum number of small files for a bulk upload to be worth the extra listing exec.
+pub(crate) const BULK_MIN_FILES: usize = 64;
+/// Files above this size keep using the per-file path (and its delta sync).
+pub(crate) const BULK_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+fn local_size(item: &SyncItem) -> Option<u64> {
+    std::fs::metadata(&item.local_path).ok().map(|m| m.len())
+}
+
+fn is_small(item: &SyncItem) -> bool {
+    local_size(item).is_some_and(|size| size <= BULK_MAX_FILE_SIZE)
+}
+
+/// Whether the plan has enough small files to consider a bulk upload at all.
+pub(crate) fn plan_prefers_bulk(items: &[SyncItem]) -> bool {
+    items.iter().filter(|item| is_small(item)).count() >= BULK_MIN_FILES
+}
+
+/// Splits `items` into small files missing on the remote, to be sent as one archive, and
+/// the rest. `remote` is keyed by slash-separated path relative to the remote root. The
+/// bulk set is empty when it would fall below [`BULK_MIN_FILES`].
+pub(crate) fn split_bulk_items(
+    items: Vec<SyncItem>,
+    remote: &HashMap<String, FileStamp>,
+) -> (Vec<SyncItem>, Vec<SyncItem>) {
+    let (bulk, rest): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| {
+        !remote.contains_key(&rel_path_to_slash(&item.rel_path)) && is_small(item)
+    });
+    if bulk.len() < BULK_MIN_FILES {
+        let mut all = rest;
+        all.extend(bulk);
+        return (Vec::new(), all);
+    }
+    (bulk, rest)
+}
+
+/// Lists regular files under `remote_dir` as `<rel path>\t<size>\t<mtime>` lines.
+pub(crate) fn build_remote_listing_command(remote_dir: &str) -> String {
+    format!(
+        "find {} -type f -printf '%P\\t%s\\t%T@\\n'",
+        sh_escape(remote_dir)
+    )
+}
+
+/// Parses [`build_remote_listing_command`] output; malformed lines are skipped.
+pub(crate) fn parse_remote_listing(output: &str) -> HashMap<String, FileStamp> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut it = line.rsplitn(3, '\t');
+            let mtime = it.next()?;
+            let size = it.next()?.parse::<u64>().ok()?;
+            let path = it.next()?;
+            let mtime = mtime
+                .split('.')
+                .next()
+                .and_then(|secs| secs.parse::<u64>().ok());
+            Some((path.to_string(), FileStamp { size, mtime }))
+        })
+        .collect()
+}
+
+/// Extracts a tar stream read from stdin into `remote_dir`, keeping archived modes and
+/// mtimes, decompressing it first when it was zstd-compressed.
+pub(crate) fn build_untar_command(remote_dir: &str, zstd: bool) -> String {
+    let untar = format!("tar -x -p -f - -C {}", sh_escape(remote_dir));
+    if zstd {
+        format!("zstd -dc | {untar}")
+    } else {
+        untar
+    }
+}
+
+/// Writes `items` as a tar archive into `out`, with paths relative to the sync root and
+/// local modes and mtimes. `on_file` is called with the relative path and size of every
+/// file once it has been written.
+pub(crate) fn write_tar_archive<W: Write>(
+    out: W,
+    items: &[SyncItem],
+    mut on_file: impl FnMut(&str, u64),
+) -> io::Result<W> {
+    let mut builder = tar::Builder::new(out);
+    for item in items {
+        let rel = rel_path_to_slash(&item.rel_path);
+        builder.append_path_with_name(&item.local_path, &rel)?;
+        on_file(&rel, local_size(item).unwrap_or(0));
+    }
+    builder.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BULK_MIN_FILES, build_remote_listing_command, build_untar_command, parse_remote_listing,
+        plan_prefers_bulk, split_bulk_items, write_tar_archive,
+    };
+    use crate::ssh::sync::FileStamp;
+    use crate::ssh::sync_plan::SyncItem;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn items(root: &Path, count: usize) -> Vec<SyncItem> {
+        (0..count)
+            .map(|i| {
+                let rel = PathBuf::from(format!("d/f{i}.txt"));
+                let local = root.join(&rel);
+                fs::create_dir_all(local.parent().unwrap()).unwrap();
+                fs::write(&local, format!("file {i}")).unwrap();
+                SyncItem {
+                    local_path: local,
+                    remote_path: format!("/remote/{}", rel.display()),
+                    rel_path: rel,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bulk_needs_enough_new_small_files() {
+        let tmp = tempdir().unwrap();
+        let items = items(tmp.path(), BULK_MIN_FILES + 1);
+        assert!(plan_prefers_bulk(&items));
+        assert!(!plan_prefers_bulk(&items[..BULK_MIN_FILES - 1]));
+
+        let stamp = FileStamp {
+            size: 1,
+            mtime: Some(1),
+        };
+        let remote: HashMap<String, FileStamp> = [("d/f0.txt".to_string(), stamp)].into();
+        let (bulk, rest) = split_bulk_items(items.clone(), &remote);
+        assert_eq!(bulk.len(), BULK_MIN_FILES);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].rel_path, PathBuf::from("d/f0.txt"));
+
+        let remote: HashMap<String, FileStamp> = [
+            ("d/f0.txt".to_string(), stamp),
+            ("d/f1.txt".to_string(), stamp),
+        ]
+        .into();
+        let (bulk, rest) = split_bulk_items(items, &remote);
+        assert!(bulk.is_empty());
+        assert_eq!(rest.len(), BULK_MIN_FILES + 1);
+    }
+
+    #[test]
+    fn remote_listing_round_trip() {
+        assert_eq!(
+            build_remote_listing_command("/r/it's"),
+            r"find '/r/it'\''s' -type f -printf '%P\t%s\t%T@\n'"
+        );
+        let listing = parse_remote_listing(
+            "a.txt\t12\t1700000000.5000000000\nsub/b c\t0\t1700000001.0\nbad\n",
+        );
+        assert_eq!(listing.len(), 2);
+        assert_eq!(listing["a.txt"].size, 12);
+        assert_eq!(listing["a.txt"].mtime, Some(1_700_000_000));
+        assert_eq!(listing["sub/b c"].size, 0);
+    }
+
+    #[test]
+    fn untar_command_optionally_decompresses() {
+        assert_eq!(build_untar_command("/r", false), "tar -x -p -f - -C '/r'");
+        assert_eq!(
+            build_untar_command("/r", true),
+            "zstd -dc | tar -x -p -f - -C '/r'"
+        );
+    }
+
+    #[test]
+    fn tar_archive_contains_relative_paths() {
+        let tmp = tempdir().unwrap();
+        let items = items(tmp.path(), 3);
+        let mut seen = Vec::new();
+        let data = write_tar_archive(Vec::new(), &items, |rel, size| {
+            seen.push((rel.to_string(), size))
+        })
+        .unwrap();
+        assert_eq!(seen[0], ("d/f0.txt".to_string(), 6));
+
+        let mut archive = tar::Archive::new(&data[..]);
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().display().to_string();
+            let mut body = String::new();
+            entry.read_to_string(&mut body).unwrap();
+            entries.push((path, body));
+        }
+        assert_eq!(
+            entries,
+            vec![
+                ("d/f0.txt".to_string(), "file 0".to_string()),
+                ("d/f1.txt".to_string(), "file 1".to_string()),
+                ("d/f2.txt".to_string(), "file 2".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Per-cluster content-addressed store for large input files.
+//!
+//! Blobs live under `<default base path>/.hpc-cache/blobs/<xx>/<blake3>`. A sync with the
+//! cache enabled hashes the large files the remote does not have yet, hard-links the ones
+//! already stored into the run directory (copying only where links are not possible, such
+//! as across filesystems) and links the rest into the store once they are uploaded, so
+//! identical content takes up space once.
+//!
+//! Blobs and their links are read-only, so a job cannot rewrite a shared input in place,
+//! and a sync gives a linked file its own inode before writing into it. A blob nothing
+//! links to any more only has its own directory entry left, which is what garbage
+//! collection looks for.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+use super::sync::FileStamp;
+use super::sync_plan::{SyncItem, rel_path_to_slash};
+use super::utils::sh_escape;
+
+/// Name of the store directory under a cluster's default base path.
+pub(crate) const CACHE_DIR_NAME: &str = ".hpc-cache";
+/// Smaller files are uploaded as usual; hashing and linking them is not worth it.
+pub(crate) const CACHE_MIN_FILE_SIZE: u64 = 1024 * 1024;
+/// Files placed or stored per exec.
+pub(crate) const CACHE_BATCH: usize = 256;
+
+/// A file of the sync plan and the blob that holds its content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CacheEntry {
+    pub hash: String,
+    pub remote_path: String,
+}
+
+/// The store of a cluster whose runs are placed under `base_path`.
+pub fn cache_dir_for(base_path: &str) -> String {
+    format!("{}/{CACHE_DIR_NAME}", base_path.trim_end_matches('/'))
+}
+
+fn blob_path(cache_dir: &str, hash: &str) -> String {
+    let fanout = hash.get(..2).unwrap_or(hash);
+    format!("{}/blobs/{fanout}/{hash}", cache_dir.trim_end_matches('/'))
+}
+
+fn local_blake3(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(std::fs::File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Cache entries for the large files of `items` the remote does not have yet. `remote` is
+/// keyed by slash-separated path relative to the remote root. Unreadable files are left
+/// out and uploaded as usual.
+pub(crate) async fn cache_candidates(
+    items: &[SyncItem],
+    remote: &HashMap<String, FileStamp>,
+) -> Vec<CacheEntry> {
+    let candidates: Vec<SyncItem> = items
+        .iter()
+        .filter(|item| !remote.contains_key(&rel_path_to_slash(&item.rel_path)))
+        .filter(|item| {
+            std::fs::metadata(&item.local_path).is_ok_and(|meta| meta.len() >= CACHE_MIN_FILE_SIZE)
+        })
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+    log::info!("hashing {} files for the data cache", candidates.len());
+    tokio::task::spawn_blocking(move || {
+        candidates
+            .into_iter()
+            .filter_map(|item| match local_blake3(&item.local_path) {
+                Ok(hash) => Some(CacheEntry {
+                    hash,
+                    remote_path: item.remote_path,
+                }),
+                Err(e) => {
+                    log::debug!("could not hash {}: {e}", item.local_path.display());
+                    None
+                }
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Places every stored blob of `entries` at its remote path, printing the paths placed.
+/// Blobs are hard-linked; where that fails they are copied, and the copy made writable.
+pub(crate) fn build_cache_place_command(cache_dir: &str, entries: &[CacheEntry]) -> String {
+    let mut cmd = String::from(
+        "p() { [ -f \"$1\" ] || return 0; \
+         { ln -f -- \"$1\" \"$2\" 2>/dev/null \
+         || { cp -f --reflink=auto -- \"$1\" \"$2\" && chmod u+w -- \"$2\"; }; } \
+         && printf '%s\\n' \"$2\"; }\n",
+    );
+    for entry in entries {
+        cmd.push_str(&format!(
+            "p {} {}\n",
+            sh_escape(&blob_path(cache_dir, &entry.hash)),
+            sh_escape(&entry.remote_path)
+        ));
+    }
+    cmd.push_str("true");
+    cmd
+}
+
+/// Parses [`build_cache_place_command`] output into the remote paths that were placed.
+pub(crate) fn parse_cache_place_output(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Adds the uploaded files of `entries` to the store. Blobs are linked in (or copied)
+/// under a temporary name, made read-only and renamed, so concurrent syncs never see a
+/// partial blob; blobs that already exist are kept.
+pub(crate) fn build_cache_store_command(cache_dir: &str, entries: &[CacheEntry]) -> String {
+    let mut cmd = String::from(
+        "s() { [ -f \"$1\" ] && return 0; [ -f \"$2\" ] || return 0; \
+         mkdir -p -- \"${1%/*}\" || return 0; t=\"$1.tmp.$$\"; \
+         { { ln -- \"$2\" \"$t\" 2>/dev/null || cp --reflink=auto -- \"$2\" \"$t\"; } \
+         && chmod a-w -- \"$t\" && mv -f -- \"$t\" \"$1\"; } || rm -f -- \"$t\"; }\n",
+    );
+    for entry in entries {
+        cmd.push_str(&format!(
+            "s {} {}\n",
+            sh_escape(&blob_path(cache_dir, &entry.hash)),
+            sh_escape(&entry.remote_path)
+        ));
+    }
+    cmd.push_str("true");
+    cmd
+}
+
+/// Copies `remote_path` onto a fresh inode when it is hard-linked elsewhere, so writing
+/// into it in place cannot change a stored blob.
+pub(crate) fn build_unshare_command(remote_path: &str) -> String {
+    let path = sh_escape(remote_path);
+    format!(
+        "f={path}; if [ \"$(stat -c %h -- \"$f\")\" -gt 1 ]; then \
+         cp -p -- \"$f\" \"$f.hpc-unshare\" && mv -f -- \"$f.hpc-unshare\" \"$f\"; fi"
+    )
+}
+
+/// Lists, and unless `dry_run` deletes, the blobs no run directory links to any more as
+/// one size per line.
+pub(crate) fn build_cache_gc_command(cache_dir: &str, dry_run: bool) -> String {
+    let blobs = format!("{}/blobs", cache_dir.trim_end_matches('/'));
+    let delete = if dry_run { "" } else { " -delete" };
+    format!(
+        "find {} -type f -links 1 ! -name '*.tmp.*' -printf '%s\\n'{delete} 2>/dev/null; true",
+        sh_escape(&blobs)
+    )
+}
+
+/// Parses [`build_cache_gc_command`] output into the number of blobs and their total size.
+pub(crate) fn parse_cache_gc_output(output: &str) -> (u64, u64) {
+    output
+        .lines()
+        .filter_map(|line| line.trim().parse::<u64>().ok())
+        .fold((0, 0), |(count, bytes), size| (count + 1, bytes + size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CACHE_MIN_FILE_SIZE, CacheEntry, blob_path, build_cache_gc_command,
+        build_cache_place_command, build_cache_store_command, build_unshare_command,
+        cache_candidates, cache_dir_for, parse_cache_gc_output, parse_cache_place_output,
+    };
+    use crate::ssh::sync::FileStamp;
+    use crate::ssh::sync_plan::SyncItem;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn blobs_are_fanned_out_by_hash_prefix() {
+        let cache_dir = cache_dir_for("/scratch/alice/runs/");
+        assert_eq!(cache_dir, "/scratch/alice/runs/.hpc-cache");
+        assert_eq!(
+            blob_path(&cache_dir, "abcdef"),
+            "/scratch/alice/runs/.hpc-cache/blobs/ab/abcdef"
+        );
+    }
+
+    #[tokio::test]
+    async fn only_large_new_files_are_cache_candidates() {
+        let tmp = tempdir().unwrap();
+        let item = |name: &str, size: u64| {
+            let local = tmp.path().join(name);
+            fs::write(&local, vec![7u8; size as usize]).unwrap();
+            SyncItem {
+                local_path: local,
+                remote_path: format!("/remote/{name}"),
+                rel_path: PathBuf::from(name),
+            }
+        };
+        let items = vec![
+            item("big.dat", CACHE_MIN_FILE_SIZE),
+            item("small.txt", 10),
+            item("uploaded.dat", CACHE_MIN_FILE_SIZE),
+        ];
+        let remote = HashMap::from([(
+            "uploaded.dat".to_string(),
+            FileStamp {
+                size: CACHE_MIN_FILE_SIZE,
+                mtime: Some(1),
+            },
+        )]);
+
+        let entries = cache_candidates(&items, &remote).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].remote_path, "/remote/big.dat");
+        let expected = blake3::hash(&vec![7u8; CACHE_MIN_FILE_SIZE as usize]);
+        assert_eq!(entries[0].hash, expected.to_hex().to_string());
+    }
+
+    #[test]
+    fn place_and_store_commands_quote_every_path() {
+        let entries = [CacheEntry {
+            hash: "abcd".to_string(),
+            remote_path: "/run/my data.bin".to_string(),
+        }];
+        let place = build_cache_place_command("/base/.hpc-cache", &entries);
+        assert!(place.contains("p '/base/.hpc-cache/blobs/ab/abcd' '/run/my data.bin'\n"));
+        let store = build_cache_store_command("/base/.hpc-cache", &entries);
+        assert!(store.contains("s '/base/.hpc-cache/blobs/ab/abcd' '/run/my data.bin'\n"));
+        assert_eq!(
+            parse_cache_place_output("/run/my data.bin\n\n"),
+            ["/run/my data.bin".to_string()].into()
+        );
+    }
+
+    #[test]
+    fn gc_only_deletes_unlinked_blobs_outside_dry_run() {
+        let gc = build_cache_gc_command("/c", false);
+        assert!(gc.contains("-links 1"));
+        assert!(gc.contains("-printf '%s\\n' -delete"));
+        assert!(!build_cache_gc_command("/c", true).contains("-delete"));
+        assert_eq!(parse_cache_gc_output("10\n20\nbogus\n"), (2, 30));
+        assert_eq!(parse_cache_gc_output(""), (0, 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn blobs_are_linked_into_run_directories_and_collected_once_unlinked() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        use std::process::Command;
+
+        let tmp = tempdir().unwrap();
+        let cache_dir = tmp.path().join(".hpc-cache").display().to_string();
+        let uploaded = tmp.path().join("run-1.dat");
+        let placed = tmp.path().join("run-2.dat");
+        fs::write(&uploaded, b"input data").unwrap();
+        let entry = |path: &std::path::Path| CacheEntry {
+            hash: "abcd".to_string(),
+            remote_path: path.display().to_string(),
+        };
+        let run = |cmd: String| {
+            let out = Command::new("sh").arg("-c").arg(cmd).output().unwrap();
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            assert!(out.status.success(), "{stderr}");
+            String::from_utf8(out.stdout).unwrap()
+        };
+
+        run(build_cache_store_command(&cache_dir, &[entry(&uploaded)]));
+        let blob = PathBuf::from(blob_path(&cache_dir, "abcd"));
+        let blob_meta = fs::metadata(&blob).unwrap();
+        assert_eq!(blob_meta.permissions().mode() & 0o222, 0);
+        assert_eq!(blob_meta.ino(), fs::metadata(&uploaded).unwrap().ino());
+
+        let output = run(build_cache_place_command(&cache_dir, &[entry(&placed)]));
+        assert_eq!(
+            parse_cache_place_output(&output),
+            [placed.display().to_string()].into()
+        );
+        assert_eq!(fs::metadata(&placed).unwrap().ino(), blob_meta.ino());
+        assert_eq!(run(build_cache_gc_command(&cache_dir, false)), "");
+
+        // A sync writing into a linked file unshares it first.
+        run(build_unshare_command(&placed.display().to_string()));
+        fs::set_permissions(&placed, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(&placed, b"changed by a later sync").unwrap();
+        assert_eq!(fs::read(&blob).unwrap(), b"input data");
+
+        fs::remove_file(&uploaded).unwrap();
+        assert_eq!(
+            parse_cache_gc_output(&run(build_cache_gc_command(&cache_dir, true))),
+            (1, 10)
+        );
+        assert!(blob.exists());
+        run(build_cache_gc_command(&cache_dir, false));
+        assert!(!blob.exists());
+    }
+}
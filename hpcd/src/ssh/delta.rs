@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! rsync-style delta encoding against a remote file signature.
+//!
+//! The remote helper reports, per block, a strong SHA-256 and (when it can) an Adler-32
+//! weak checksum. [`compute_delta`] slides a rolling Adler-32 window over the local file
+//! so that blocks are found again after insertions or deletions, and writes a stream of
+//! copy/literal operations that the helper's `patch` mode turns back into the local file.
+//!
+//! Wire format, all integers big-endian:
+//! - `C <u64 first block> <u64 block count>`: copy blocks from the remote basis file
+//! - `D <u64 len> <bytes>`: literal data
+//! - `E <64 hex chars>`: end of stream, SHA-256 of the reconstructed file
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+const MOD_ADLER: u32 = 65521;
+
+/// Literal runs are flushed in chunks of at most this size.
+const LITERAL_CHUNK: usize = 1024 * 1024;
+
+/// Hashes of one block of a remote file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BlockSignature {
+    /// Length of the block; only the last block of a file may be shorter than the block size.
+    pub len: usize,
+    /// Hex SHA-256 of the block.
+    pub strong: String,
+    /// Adler-32 of the block; `None` when the remote could only compute strong hashes.
+    pub weak: Option<u32>,
+}
+
+/// How much of a file a delta carries as literal data versus copies from the basis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DeltaStats {
+    pub literal_bytes: u64,
+    pub matched_bytes: u64,
+}
+
+/// Adler-32 checksum, identical to `zlib.adler32`.
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    RollingChecksum::new(data).digest()
+}
+
+/// Adler-32 over a fixed-size window that can be moved one byte at a time.
+#[derive(Clone, Copy, Debug)]
+struct RollingChecksum {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl RollingChecksum {
+    fn new(window: &[u8]) -> Self {
+        let mut a: u32 = 1;
+        let mut b: u32 = 0;
+        for &byte in window {
+            a = (a + u32::from(byte)) % MOD_ADLER;
+            b = (b + a) % MOD_ADLER;
+        }
+        Self {
+            a,
+            b,
+            len: window.len() as u32,
+        }
+    }
+
+    /// Drops `out` from the front of the window and appends `inp`.
+    fn roll(&mut self, out: u8, inp: u8) {
+        let m = u64::from(MOD_ADLER);
+        let out = u64::from(out);
+        let a = (u64::from(self.a) + m - out + u64::from(inp)) % m;
+        let dropped = (u64::from(self.len) * out + 1) % m;
+        let b = (u64::from(self.b) + m - dropped + a) % m;
+        self.a = a as u32;
+        self.b = b as u32;
+    }
+
+    fn digest(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+/// Writes delta operations, merging adjacent block copies into one run.
+struct DeltaWriter<'w, W: Write> {
+    out: &'w mut W,
+    pending_copy: Option<(u64, u64)>,
+    stats: DeltaStats,
+}
+
+impl<W: Write> DeltaWriter<'_, W> {
+    fn copy(&mut self, index: usize, len: usize) -> io::Result<()> {
+        let index = index as u64;
+        self.stats.matched_bytes += len as u64;
+        match self.pending_copy.as_mut() {
+            Some((start, count)) if *start + *count == index => *count += 1,
+            _ => {
+                self.flush_copy()?;
+                self.pending_copy = Some((index, 1));
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.flush_copy()?;
+        self.stats.literal_bytes += data.len() as u64;
+        self.out.write_all(b"D")?;
+        self.out.write_all(&(data.len() as u64).to_be_bytes())?;
+        self.out.write_all(data)
+    }
+
+    fn flush_copy(&mut self) -> io::Result<()> {
+        if let Some((start, count)) = self.pending_copy.take() {
+            self.out.write_all(b"C")?;
+            self.out.write_all(&start.to_be_bytes())?;
+            self.out.write_all(&count.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, sha256_hex: &str) -> io::Result<DeltaStats> {
+        self.flush_copy()?;
+        self.out.write_all(b"E")?;
+        self.out.write_all(sha256_hex.as_bytes())?;
+        self.out.flush()?;
+        Ok(self.stats)
+    }
+}
+
+fn strong_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Encodes `input` as a delta against a remote file with the given block signatures.
+/// Every block needs a weak checksum; callers fall back to block mode otherwise.
+pub(crate) fn compute_delta<R: Read, W: Write>(
+    mut input: R,
+    blocks: &[BlockSignature],
+    block_size: usize,
+    out: &mut W,
+) -> io::Result<DeltaStats> {
+    if block_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "block size must be positive",
+        ));
+    }
+    // Only full-size blocks can match mid-file; a short trailing block is only tried
+    // against the end of the local file.
+    let mut by_weak: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (idx, block) in blocks.iter().enumerate() {
+        let Some(weak) = block.weak else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "remote signature has no weak checksums",
+            ));
+        };
+        if block.len == block_size {
+            by_weak.entry(weak).or_default().push(idx);
+        }
+    }
+
+    let mut writer = DeltaWriter {
+        out,
+        pending_copy: None,
+        stats: DeltaStats::default(),
+    };
+    let mut file_hash = Sha256::new();
+    let mut buf: Vec<u8> = Vec::with_capacity(block_size * 4);
+    let mut chunk = vec![0u8; block_size.max(64 * 1024)];
+    let mut eof = false;
+    let mut pos = 0;
+    let mut lit_start = 0;
+    let mut rolling: Option<RollingChecksum> = None;
+
+    loop {
+        // Keep one byte beyond the window buffered so the checksum can roll.
+        while !eof && buf.len() - pos <= block_size {
+            if lit_start > 0 {
+                buf.drain(..lit_start);
+                pos -= lit_start;
+                lit_start = 0;
+            }
+            let n = input.read(&mut chunk)?;
+            if n == 0 {
+                eof = true;
+            } else {
+                file_hash.update(&chunk[..n]);
+                buf.extend_from_slice(&chunk[..n]);
+            }
+        }
+        if buf.len() - pos < block_size {
+            break;
+        }
+
+        let window = &buf[pos..pos + block_size];
+        let sum = *rolling.get_or_insert_with(|| RollingChecksum::new(window));
+        let matched = by_weak.get(&sum.digest()).and_then(|candidates| {
+            let strong = strong_hash(window);
+            candidates
+                .iter()
+                .copied()
+                .find(|&idx| blocks[idx].strong == strong)
+        });
+
+        if let Some(idx) = matched {
+            writer.literal(&buf[lit_start..pos])?;
+            writer.copy(idx, block_size)?;
+            pos += block_size;
+            lit_start = pos;
+            rolling = None;
+            continue;
+        }
+
+        if pos + block_size < buf.len() {
+            if let Some(sum) = rolling.as_mut() {
+                sum.roll(buf[pos], buf[pos + block_size]);
+            }
+        } else {
+            rolling = None;
+        }
+        pos += 1;
+        if pos - lit_start >= LITERAL_CHUNK {
+            writer.literal(&buf[lit_start..pos])?;
+            lit_start = pos;
+        }
+    }
+
+    let tail = &buf[pos..];
+    let tail_matches = !tail.is_empty()
+        && blocks.last().is_some_and(|block| {
+            block.len == tail.len()
+                && block.weak == Some(adler32(tail))
+                && block.strong == strong_hash(tail)
+        });
+    if tail_matches {
+        writer.literal(&buf[lit_start..pos])?;
+        writer.copy(blocks.len() - 1, tail.len())?;
+    } else {
+        writer.literal(&buf[lit_start..])?;
+    }
+    writer.finish(&format!("{:x}", file_hash.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockSignature, RollingChecksum, adler32, compute_delta, strong_hash};
+
+    fn signature(data: &[u8], block_size: usize) -> Vec<BlockSignature> {
+        data.chunks(block_size)
+            .map(|block| BlockSignature {
+                len: block.len(),
+                strong: strong_hash(block),
+                weak: Some(adler32(block)),
+            })
+            .collect()
+    }
+
+    /// Reference implementation of the remote helper's `patch` mode.
+    fn apply_delta(basis: &[u8], mut delta: &[u8], block_size: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        let read_u64 = |d: &mut &[u8]| {
+            let (head, rest) = d.split_at(8);
+            *d = rest;
+            u64::from_be_bytes(head.try_into().unwrap())
+        };
+        loop {
+            let (op, rest) = delta.split_first().unwrap();
+            delta = rest;
+            match op {
+                b'C' => {
+                    let start = read_u64(&mut delta) as usize * block_size;
+                    let count = read_u64(&mut delta) as usize * block_size;
+                    let end = (start + count).min(basis.len());
+                    out.extend_from_slice(&basis[start..end]);
+                }
+                b'D' => {
+                    let len = read_u64(&mut delta) as usize;
+                    out.extend_from_slice(&delta[..len]);
+                    delta = &delta[len..];
+                }
+                b'E' => {
+                    assert_eq!(std::str::from_utf8(delta).unwrap(), strong_hash(&out));
+                    return out;
+                }
+                other => panic!("unexpected op {other}"),
+            }
+        }
+    }
+
+    /// Non-repeating pseudo-random bytes, so blocks only match where intended.
+    fn sample(len: usize) -> Vec<u8> {
+        let mut state: u32 = 0x2545_f491;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adler32_matches_known_value() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn rolling_checksum_matches_fresh_checksum() {
+        let data = sample(300);
+        let window = 64;
+        let mut rolling = RollingChecksum::new(&data[..window]);
+        for start in 1..data.len() - window {
+            rolling.roll(data[start - 1], data[start + window - 1]);
+            assert_eq!(rolling.digest(), adler32(&data[start..start + window]));
+        }
+    }
+
+    #[test]
+    fn delta_finds_blocks_shifted_by_an_insertion() {
+        let block_size = 64;
+        let basis = sample(64 * 20 + 17);
+        let mut local = basis.clone();
+        local.insert(5, b'!');
+
+        let mut delta = Vec::new();
+        let stats = compute_delta(
+            &local[..],
+            &signature(&basis, block_size),
+            block_size,
+            &mut delta,
+        )
+        .unwrap();
+
+        assert_eq!(apply_delta(&basis, &delta, block_size), local);
+        assert_eq!(
+            stats.literal_bytes + stats.matched_bytes,
+            local.len() as u64
+        );
+        assert_eq!(stats.literal_bytes, block_size as u64 + 1);
+    }
+
+    #[test]
+    fn delta_of_unrelated_data_is_all_literal() {
+        let block_size = 32;
+        let basis = vec![0u8; 100];
+        let local = sample(150);
+
+        let mut delta = Vec::new();
+        let stats = compute_delta(
+            &local[..],
+            &signature(&basis, block_size),
+            block_size,
+            &mut delta,
+        )
+        .unwrap();
+
+        assert_eq!(apply_delta(&basis, &delta, block_size), local);
+        assert_eq!(stats.matched_bytes, 0);
+        assert_eq!(stats.literal_bytes, 150);
+    }
+
+    #[test]
+    fn delta_requires_weak_checksums() {
+        let blocks = vec![BlockSignature {
+            len: 3,
+            strong: strong_hash(b"abc"),
+            weak: None,
+        }];
+        let mut delta = Vec::new();
+        assert!(compute_delta(&b"abc"[..], &blocks, 3, &mut delta).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Typed listings of remote directory trees, read over SFTP.
+//!
+//! Entries carry their path relative to the listed directory, so a recursive listing can
+//! be rendered flat or as a tree. A name pattern keeps the matching entries plus the
+//! directories leading to them, so filtered trees still render.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use russh_sftp::protocol::{FileAttributes, FileType};
+
+/// Entries a single listing returns at most.
+pub(crate) const MAX_LISTED_ENTRIES: usize = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+impl From<FileType> for EntryKind {
+    fn from(value: FileType) -> Self {
+        match value {
+            FileType::File => Self::File,
+            FileType::Dir => Self::Dir,
+            FileType::Symlink => Self::Symlink,
+            FileType::Other => Self::Other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    /// Path relative to the listed directory, '/'-separated.
+    pub path: String,
+    pub kind: EntryKind,
+    pub size: u64,
+    /// Permission bits, without the file type.
+    pub mode: u32,
+    pub mtime: Option<u32>,
+    pub symlink_target: Option<String>,
+    /// 1 for direct children of the listed directory; 0 when the listed path is not a
+    /// directory and this entry is the path itself.
+    pub depth: u32,
+}
+
+impl DirEntry {
+    pub(crate) fn from_attrs(path: String, depth: u32, attrs: &FileAttributes) -> Self {
+        Self {
+            path,
+            kind: attrs.file_type().into(),
+            size: attrs.size.unwrap_or(0),
+            mode: attrs.permissions.unwrap_or(0) & 0o7777,
+            mtime: attrs.mtime,
+            symlink_target: None,
+            depth,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListDirOptions {
+    /// Levels below the listed directory to descend into; `None` is unlimited.
+    pub max_depth: Option<u32>,
+    /// Glob matched against the entry name, or against its relative path when the
+    /// pattern contains a '/'.
+    pub pattern: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DirListing {
+    /// Entries sorted by path.
+    pub entries: Vec<DirEntry>,
+    /// The walk stopped at [`MAX_LISTED_ENTRIES`].
+    pub truncated: bool,
+}
+
+impl DirListing {
+    /// Sorts by path component, so every directory is directly followed by its subtree.
+    pub(crate) fn sort(&mut self) {
+        self.entries
+            .sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+    }
+
+    /// Number of files, number of directories and bytes of the listed files.
+    pub fn totals(&self) -> (u64, u64, u64) {
+        self.entries
+            .iter()
+            .fold((0, 0, 0), |(files, dirs, bytes), entry| match entry.kind {
+                EntryKind::Dir => (files, dirs + 1, bytes),
+                _ => (files + 1, dirs, bytes + entry.size),
+            })
+    }
+}
+
+/// A compiled [`ListDirOptions::pattern`].
+pub(crate) struct NamePattern {
+    matcher: GlobMatcher,
+    match_path: bool,
+}
+
+impl NamePattern {
+    pub(crate) fn compile(pattern: &str) -> Result<Self> {
+        let pattern = pattern.trim().trim_start_matches('/');
+        if pattern.is_empty() {
+            anyhow::bail!("listing pattern cannot be empty");
+        }
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid listing pattern '{pattern}'"))?
+            .compile_matcher();
+        Ok(Self {
+            matcher,
+            match_path: pattern.contains('/'),
+        })
+    }
+
+    pub(crate) fn matches(&self, entry: &DirEntry) -> bool {
+        if self.match_path {
+            self.matcher.is_match(&entry.path)
+        } else {
+            self.matcher.is_match(entry.name())
+        }
+    }
+}
+
+/// Keeps the entries matching `pattern` and the directories that lead to them.
+pub(crate) fn retain_matches(entries: Vec<DirEntry>, pattern: &NamePattern) -> Vec<DirEntry> {
+    let mut keep: HashSet<String> = HashSet::new();
+    for entry in entries.iter().filter(|entry| pattern.matches(entry)) {
+        let mut path = entry.path.as_str();
+        keep.insert(path.to_string());
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            if !keep.insert(parent.to_string()) {
+                break;
+            }
+            path = parent;
+        }
+    }
+    entries
+        .into_iter()
+        .filter(|entry| keep.contains(&entry.path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, kind: EntryKind, size: u64) -> DirEntry {
+        DirEntry {
+            path: path.to_string(),
+            kind,
+            size,
+            mode: 0o644,
+            mtime: Some(1_767_225_600),
+            symlink_target: None,
+            depth: path.matches('/').count() as u32 + 1,
+        }
+    }
+
+    fn sample() -> Vec<DirEntry> {
+        vec![
+            entry("logs", EntryKind::Dir, 4096),
+            entry("logs/run.log", EntryKind::File, 10),
+            entry("results", EntryKind::Dir, 4096),
+            entry("results/metrics.json", EntryKind::File, 200),
+            entry("results/plots", EntryKind::Dir, 4096),
+            entry("results/plots/loss.json", EntryKind::File, 300),
+            entry("slurm.out", EntryKind::File, 50),
+        ]
+    }
+
+    #[test]
+    fn name_patterns_keep_matches_and_their_parents() {
+        let pattern = NamePattern::compile("*.json").unwrap();
+        let kept = retain_matches(sample(), &pattern);
+        let paths: Vec<&str> = kept.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "results",
+                "results/metrics.json",
+                "results/plots",
+                "results/plots/loss.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn path_patterns_match_relative_paths() {
+        let pattern = NamePattern::compile("results/*.json").unwrap();
+        let kept = retain_matches(sample(), &pattern);
+        let paths: Vec<&str> = kept.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["results", "results/metrics.json"]);
+        assert!(NamePattern::compile(" ").is_err());
+    }
+
+    #[test]
+    fn totals_count_files_dirs_and_file_bytes() {
+        let listing = DirListing {
+            entries: sample(),
+            truncated: false,
+        };
+        assert_eq!(listing.totals(), (4, 3, 560));
+        assert_eq!(listing.entries[5].name(), "loss.json");
+    }
+
+    #[test]
+    fn sort_keeps_subtrees_together() {
+        let mut listing = DirListing {
+            entries: vec![
+                entry("a/b", EntryKind::File, 1),
+                entry("a-b", EntryKind::File, 1),
+                entry("a", EntryKind::Dir, 0),
+            ],
+            truncated: false,
+        };
+        listing.sort();
+        let paths: Vec<&str> = listing.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a", "a/b", "a-b"]);
+    }
+}
@@ -6,3 +6,12 @@ use thiserror::Error as ThisError;
 #[derive(Debug, ThisError)]
 #[error("authentication_failure")]
 pub struct AuthenticationFailure;
+
+/// The server asked for keyboard-interactive answers and nobody was there to give them.
+#[derive(Debug, ThisError)]
+#[error("client disconnected during MFA")]
+pub struct MfaUnanswered;
+
+#[derive(Debug, ThisError)]
+#[error("{0} is not a regular file")]
+pub struct NotAFile(pub String);
@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Reading a slice of a remote file over SFTP: a byte range, optionally cut down to its
+//! first or last lines.
+//!
+//! Only the bytes that end up in the slice are read, so `tail` of a multi-gigabyte log
+//! costs a few chunks from its end.
+
+use anyhow::{Context, Result};
+use russh_sftp::client::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Bytes read from the remote file per request.
+const READ_CHUNK: usize = 64 * 1024;
+/// Bytes at the start of a slice that are checked for binary content.
+const BINARY_SAMPLE: usize = 8 * 1024;
+
+/// Which part of a file to read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileSlice {
+    /// First byte of the range; negative values count back from the end of the file.
+    pub offset: i64,
+    /// Length of the range; `None` reads to the end of the file.
+    pub length: Option<u64>,
+    /// Only the first this many lines of the range.
+    pub head_lines: Option<u32>,
+    /// Only the last this many lines of the range.
+    pub tail_lines: Option<u32>,
+}
+
+impl FileSlice {
+    /// The `[start, end)` byte range of a file of `size` bytes, clamped to the file.
+    pub(crate) fn byte_range(&self, size: u64) -> (u64, u64) {
+        let start = if self.offset < 0 {
+            size.saturating_sub(self.offset.unsigned_abs())
+        } else {
+            self.offset.unsigned_abs().min(size)
+        };
+        let end = match self.length {
+            Some(length) => start.saturating_add(length).min(size),
+            None => size,
+        };
+        (start, end)
+    }
+}
+
+/// Where the bytes of a [`FileSliceReader`] come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSliceInfo {
+    pub path: String,
+    /// Size of the whole file.
+    pub size: u64,
+    /// First byte of the slice.
+    pub start: u64,
+    /// End of the byte range; a head limit may stop reading before it.
+    pub end: u64,
+    /// The start of the slice contains NUL bytes.
+    pub binary: bool,
+}
+
+/// Same heuristic as git and grep: text files do not contain NUL bytes.
+pub(crate) fn looks_binary(sample: &[u8]) -> bool {
+    sample.contains(&0)
+}
+
+/// Counts lines of a forward read and reports where the last wanted line ends.
+#[derive(Debug)]
+pub(crate) struct HeadLimit {
+    remaining: u32,
+}
+
+impl HeadLimit {
+    pub(crate) fn new(lines: u32) -> Self {
+        Self { remaining: lines }
+    }
+
+    /// Length of the prefix of `chunk` to keep when the limit is reached inside it.
+    pub(crate) fn cut(&mut self, chunk: &[u8]) -> Option<usize> {
+        if self.remaining == 0 {
+            return Some(0);
+        }
+        for (index, byte) in chunk.iter().enumerate() {
+            if *byte == b'\n' {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    return Some(index + 1);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Scans a range backwards, chunk by chunk, for the start of its last lines.
+#[derive(Debug)]
+pub(crate) struct TailScan {
+    remaining: u32,
+    /// The last byte of the range has not been seen yet; a final newline there ends
+    /// the last line instead of starting a new one.
+    at_end: bool,
+}
+
+impl TailScan {
+    pub(crate) fn new(lines: u32) -> Self {
+        Self {
+            remaining: lines,
+            at_end: true,
+        }
+    }
+
+    /// Feeds the chunk that precedes everything scanned so far, which starts at byte
+    /// `chunk_start`. Returns where the wanted lines start once that is known. Wants at
+    /// least one line.
+    pub(crate) fn scan_back(&mut self, chunk: &[u8], chunk_start: u64) -> Option<u64> {
+        for (index, byte) in chunk.iter().enumerate().rev() {
+            let at_end = std::mem::replace(&mut self.at_end, false);
+            if *byte != b'\n' || at_end {
+                continue;
+            }
+            self.remaining = self.remaining.saturating_sub(1);
+            if self.remaining == 0 {
+                return Some(chunk_start + index as u64 + 1);
+            }
+        }
+        None
+    }
+}
+
+/// Streams a [`FileSlice`] of an open remote file.
+pub struct FileSliceReader {
+    file: File,
+    info: FileSliceInfo,
+    pos: u64,
+    head: Option<HeadLimit>,
+    done: bool,
+}
+
+impl FileSliceReader {
+    pub(crate) async fn new(
+        mut file: File,
+        path: &str,
+        size: u64,
+        slice: &FileSlice,
+    ) -> Result<Self> {
+        let (mut start, end) = slice.byte_range(size);
+        if let Some(lines) = slice.tail_lines {
+            start = tail_start(&mut file, start, end, lines)
+                .await
+                .with_context(|| format!("reading {path}"))?;
+        }
+        let mut sample = vec![0; BINARY_SAMPLE.min((end - start) as usize)];
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        file.read_exact(&mut sample)
+            .await
+            .with_context(|| format!("reading {path}"))?;
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        Ok(Self {
+            file,
+            info: FileSliceInfo {
+                path: path.to_string(),
+                size,
+                start,
+                end,
+                binary: looks_binary(&sample),
+            },
+            pos: start,
+            head: slice.head_lines.map(HeadLimit::new),
+            done: false,
+        })
+    }
+
+    pub fn info(&self) -> &FileSliceInfo {
+        &self.info
+    }
+
+    /// The next chunk of the slice, or `None` once it has been read completely.
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.done || self.pos >= self.info.end {
+            return Ok(None);
+        }
+        let len = READ_CHUNK.min((self.info.end - self.pos) as usize);
+        let mut chunk = vec![0; len];
+        self.file
+            .read_exact(&mut chunk)
+            .await
+            .with_context(|| format!("reading {}", self.info.path))?;
+        self.pos += len as u64;
+        if let Some(cut) = self.head.as_mut().and_then(|head| head.cut(&chunk)) {
+            chunk.truncate(cut);
+            self.done = true;
+        }
+        Ok(Some(chunk))
+    }
+}
+
+async fn tail_start(file: &mut File, start: u64, end: u64, lines: u32) -> Result<u64> {
+    if lines == 0 {
+        return Ok(end);
+    }
+    let mut scan = TailScan::new(lines);
+    let mut chunk_end = end;
+    while chunk_end > start {
+        let chunk_start = chunk_end.saturating_sub(READ_CHUNK as u64).max(start);
+        let mut chunk = vec![0; (chunk_end - chunk_start) as usize];
+        file.seek(std::io::SeekFrom::Start(chunk_start)).await?;
+        file.read_exact(&mut chunk).await?;
+        if let Some(found) = scan.scan_back(&chunk, chunk_start) {
+            return Ok(found);
+        }
+        chunk_end = chunk_start;
+    }
+    Ok(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_ranges_clamp_to_the_file() {
+        let slice = |offset, length| FileSlice {
+            offset,
+            length,
+            ..Default::default()
+        };
+        assert_eq!(slice(0, None).byte_range(100), (0, 100));
+        assert_eq!(slice(10, Some(20)).byte_range(100), (10, 30));
+        assert_eq!(slice(90, Some(20)).byte_range(100), (90, 100));
+        assert_eq!(slice(200, None).byte_range(100), (100, 100));
+        assert_eq!(slice(-30, None).byte_range(100), (70, 100));
+        assert_eq!(slice(-300, Some(5)).byte_range(100), (0, 5));
+    }
+
+    #[test]
+    fn head_limit_cuts_after_the_last_wanted_line() {
+        let mut head = HeadLimit::new(3);
+        assert_eq!(head.cut(b"one\ntwo"), None);
+        assert_eq!(head.cut(b"\nthree\nfour\n"), Some(7));
+        assert_eq!(HeadLimit::new(0).cut(b"one\n"), Some(0));
+        assert_eq!(HeadLimit::new(5).cut(b"no newline"), None);
+    }
+
+    #[test]
+    fn tail_scan_ignores_the_final_newline() {
+        let text = b"one\ntwo\nthree\n";
+        assert_eq!(TailScan::new(1).scan_back(text, 0), Some(8));
+        assert_eq!(TailScan::new(2).scan_back(text, 0), Some(4));
+        assert_eq!(TailScan::new(3).scan_back(text, 0), None);
+        assert_eq!(TailScan::new(1).scan_back(b"one\ntwo", 0), Some(4));
+    }
+
+    #[test]
+    fn tail_scan_continues_across_chunks() {
+        let mut scan = TailScan::new(2);
+        assert_eq!(scan.scan_back(b"three\n", 8), None);
+        assert_eq!(scan.scan_back(b"two\n", 4), None);
+        assert_eq!(scan.scan_back(b"one\n", 0), Some(4));
+
+        let mut scan = TailScan::new(2);
+        assert_eq!(scan.scan_back(b"\n", 13), None);
+        assert_eq!(scan.scan_back(b"two\nthree", 4), None);
+        assert_eq!(scan.scan_back(b"one\n", 0), Some(4));
+    }
+
+    #[test]
+    fn nul_bytes_mark_binary_content() {
+        assert!(looks_binary(b"\x7fELF\x02\x01\x01\0"));
+        assert!(!looks_binary("plain text, ünïcödé\n".as_bytes()));
+        assert!(!looks_binary(b""));
+    }
+}
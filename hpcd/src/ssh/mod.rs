@@ -1,14 +1,29 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
+mod bulk;
+mod cache;
+mod delta;
+mod dirlist;
 mod error;
+mod fileview;
+mod progress;
+mod retrieve;
 mod session;
 mod sync;
 mod sync_plan;
 mod utils;
 
-pub use error::AuthenticationFailure;
+pub use cache::cache_dir_for;
+pub(crate) use dirlist::NamePattern;
+pub use dirlist::{DirListing, EntryKind, ListDirOptions};
+pub use error::{AuthenticationFailure, MfaUnanswered, NotAFile};
+pub use fileview::FileSlice;
+pub use retrieve::{RetrieveOptions, VerifiedFile};
 pub use session::{SessionManager, SshParams};
-pub use sync::{SyncFilterAction, SyncFilterRule, SyncOptions};
+pub use sync::{
+    SymlinkPolicy, SyncFilterAction, SyncFilterRule, SyncItemStatus, SyncOptions, SyncPreviewItem,
+};
+pub use sync_plan::{SymlinkAction, SymlinkEntry};
 pub use utils::receiver_to_stream;
-pub(crate) use utils::sh_escape;
+pub(crate) use utils::{local_file_sha256, sh_escape};
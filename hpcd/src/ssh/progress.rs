@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Byte-level progress accounting for uploads and downloads.
+//!
+//! Transfers update a shared [`TransferProgress`] from however many parallel file
+//! copies are in flight; [`report_progress`] samples it on a timer and forwards
+//! snapshots to the client stream.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often a snapshot is sent while a transfer is running.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Counters of a running transfer, safe to update from concurrent file copies.
+#[derive(Debug, Default)]
+pub struct TransferProgress {
+    files_total: u64,
+    bytes_total: u64,
+    files_done: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_skipped: AtomicU64,
+    current_file: Mutex<String>,
+}
+
+impl TransferProgress {
+    pub fn new(files_total: u64, bytes_total: u64) -> Self {
+        Self {
+            files_total,
+            bytes_total,
+            ..Default::default()
+        }
+    }
+
+    /// Marks `rel_path` as the file in flight.
+    pub fn start_file(&self, rel_path: &str) {
+        if let Ok(mut current) = self.current_file.lock() {
+            rel_path.clone_into(&mut current);
+        }
+    }
+
+    pub fn finish_file(&self) {
+        self.files_done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records bytes written to the destination.
+    pub fn add_sent(&self, bytes: u64) {
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Records bytes the destination already had and that were not resent.
+    pub fn add_skipped(&self, bytes: u64) {
+        self.bytes_skipped.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Takes back files and bytes counted by a transfer that failed and will be retried.
+    pub fn forget(&self, files: u64, bytes: u64) {
+        self.files_done.fetch_sub(files, Ordering::Relaxed);
+        self.bytes_sent.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> proto::TransferProgress {
+        let files_done = self.files_done.load(Ordering::Relaxed);
+        let current_file = if files_done >= self.files_total {
+            String::new()
+        } else {
+            self.current_file
+                .lock()
+                .map(|current| current.clone())
+                .unwrap_or_default()
+        };
+        proto::TransferProgress {
+            files_total: self.files_total,
+            files_done,
+            bytes_total: self.bytes_total,
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_skipped: self.bytes_skipped.load(Ordering::Relaxed),
+            current_file,
+        }
+    }
+}
+
+/// Drives `fut` to completion while sending changed progress snapshots through `evt_tx`,
+/// wrapped into the stream's event type by `wrap`. Periodic snapshots are dropped rather
+/// than take the last free slot of a lagging stream; the final one is always delivered.
+pub(crate) async fn report_progress<F, E>(
+    progress: &TransferProgress,
+    fut: F,
+    evt_tx: &mpsc::Sender<Result<E, tonic::Status>>,
+    wrap: fn(proto::TransferProgress) -> E,
+) -> F::Output
+where
+    F: Future,
+{
+    tokio::pin!(fut);
+    let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last: Option<proto::TransferProgress> = None;
+    loop {
+        tokio::select! {
+            out = &mut fut => {
+                let _ = evt_tx.send(Ok(wrap(progress.snapshot()))).await;
+                return out;
+            }
+            _ = ticker.tick() => {
+                let snapshot = progress.snapshot();
+                if last.as_ref() != Some(&snapshot) && evt_tx.capacity() > 1 {
+                    let _ = evt_tx.try_send(Ok(wrap(snapshot.clone())));
+                    last = Some(snapshot);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TransferProgress, report_progress};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn snapshot_reports_counters_and_clears_current_file_when_done() {
+        let progress = TransferProgress::new(2, 300);
+        progress.start_file("a.txt");
+        progress.add_sent(100);
+        progress.finish_file();
+        progress.start_file("b.txt");
+        progress.add_skipped(150);
+
+        let snap = progress.snapshot();
+        assert_eq!(snap.files_total, 2);
+        assert_eq!(snap.files_done, 1);
+        assert_eq!(snap.bytes_total, 300);
+        assert_eq!(snap.bytes_sent, 100);
+        assert_eq!(snap.bytes_skipped, 150);
+        assert_eq!(snap.current_file, "b.txt");
+
+        progress.finish_file();
+        assert_eq!(progress.snapshot().current_file, "");
+    }
+
+    #[tokio::test]
+    async fn report_progress_sends_final_snapshot() {
+        let progress = TransferProgress::new(1, 10);
+        let (tx, mut rx) = mpsc::channel(8);
+        let out = report_progress(
+            &progress,
+            async {
+                progress.add_sent(10);
+                progress.finish_file();
+                7
+            },
+            &tx,
+            |p| p,
+        )
+        .await;
+        assert_eq!(out, 7);
+        drop(tx);
+        let mut last = None;
+        while let Some(item) = rx.recv().await {
+            last = Some(item.unwrap());
+        }
+        let last = last.unwrap();
+        assert_eq!(last.files_done, 1);
+        assert_eq!(last.bytes_sent, 10);
+    }
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: AGPL-3.0-only
+// Copyright (C) 2026 Alex Sizykh
+
+//! Incremental, resumable download of remote files and directory trees.
+//!
+//! A retrieve lists the remote tree once, drops paths excluded by the same include/exclude
+//! rules as submit, and skips every file whose local copy has the same size and mtime.
+//! Downloads are written to a `<name>.hpc-part` file next to the destination and renamed
+//! into place, stamped with the remote mtime, once complete; an interrupted retrieve
+//! resumes from whatever the partial file already holds. Every download is checked
+//! against the SHA-256 of the remote copy afterwards and fetched again on a mismatch.
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::sync::{FileStamp, SyncFilterRule, stamps_match};
+
+/// Appended to the local file name while a download is in flight.
+pub(crate) const PARTIAL_SUFFIX: &str = ".hpc-part";
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetrieveOptions<'a> {
+    /// Replace local files that differ from the remote copy instead of failing.
+    pub overwrite: bool,
+    /// Compare SHA-256 checksums of same-sized files whose mtimes differ, and only touch
+    /// up the local mtime when the contents match.
+    pub checksum: bool,
+    /// Include/exclude rules, matched against paths relative to the retrieved directory.
+    pub filters: &'a [SyncFilterRule],
+    pub parallelism: Option<usize>,
+}
+
+/// A remote file selected for download.
+#[derive(Clone, Debug)]
+pub(crate) struct RemoteFile {
+    pub remote_path: String,
+    pub local_path: PathBuf,
+    /// Path relative to the retrieved directory, '/'-separated.
+    pub rel_path: String,
+    pub stamp: FileStamp,
+}
+
+/// A transferred file whose local copy was checked against the remote one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedFile {
+    pub remote_path: String,
+    pub local_path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// What a retrieve does with one remote file, given its local copy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RetrieveAction {
+    /// The local copy has the same size and mtime.
+    Skip,
+    /// A different local copy exists; same-sized ones may still match by checksum.
+    Replace { same_size: bool },
+    /// There is no local copy yet.
+    Download,
+}
+
+fn file_stamp(meta: &fs::Metadata) -> FileStamp {
+    FileStamp {
+        size: meta.len(),
+        mtime: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+    }
+}
+
+/// Where `local_path` is downloaded to before being renamed into place.
+pub(crate) fn partial_path(local_path: &Path) -> PathBuf {
+    let mut name = local_path.file_name().unwrap_or_default().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    local_path.with_file_name(name)
+}
+
+pub(crate) fn retrieve_action(file: &RemoteFile) -> io::Result<RetrieveAction> {
+    match fs::metadata(&file.local_path) {
+        Ok(meta) if meta.is_dir() => Ok(RetrieveAction::Replace { same_size: false }),
+        Ok(meta) => {
+            let local = file_stamp(&meta);
+            if stamps_match(local, file.stamp) {
+                Ok(RetrieveAction::Skip)
+            } else {
+                Ok(RetrieveAction::Replace {
+                    same_size: local.size == file.stamp.size,
+                })
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(RetrieveAction::Download),
+        Err(e) => Err(e),
+    }
+}
+
+/// Bytes of `file` already held by its partial download. A partial file is only resumed
+/// when it is shorter than the remote file and was last written after the remote file was
+/// modified; otherwise the download starts over.
+pub(crate) fn resume_offset(file: &RemoteFile) -> io::Result<u64> {
+    let partial = match fs::metadata(partial_path(&file.local_path)) {
+        Ok(meta) => file_stamp(&meta),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let fresh = partial
+        .mtime
+        .zip(file.stamp.mtime)
+        .is_some_and(|(written, modified)| written >= modified);
+    if fresh && partial.size < file.stamp.size {
+        Ok(partial.size)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Sets the mtime of `path` to `mtime` seconds since the epoch.
+pub(crate) fn set_local_mtime(path: &Path, mtime: u64) -> io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(mtime))
+}
+
+/// Moves the completed partial download of `file` into place with the remote mtime, so
+/// the next retrieve can skip it.
+pub(crate) fn finish_partial(file: &RemoteFile) -> io::Result<()> {
+    let partial = partial_path(&file.local_path);
+    if let Some(mtime) = file.stamp.mtime {
+        set_local_mtime(&partial, mtime)?;
+    }
+    fs::rename(&partial, &file.local_path)
+}
+
+/// The error reported for a local file that differs and may not be overwritten.
+pub(crate) fn local_conflict(path: &Path) -> io::Error {
+    io::Error::new(
+        ErrorKind::AlreadyExists,
+        format!("local path exists: {}", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        RemoteFile, RetrieveAction, finish_partial, partial_path, resume_offset, retrieve_action,
+        set_local_mtime,
+    };
+    use crate::ssh::sync::FileStamp;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    const MTIME: u64 = 1_700_000_000;
+
+    fn remote_file(local: &Path, size: u64) -> RemoteFile {
+        RemoteFile {
+            remote_path: "/r/out.dat".to_string(),
+            local_path: local.to_path_buf(),
+            rel_path: "out.dat".to_string(),
+            stamp: FileStamp {
+                size,
+                mtime: Some(MTIME),
+            },
+        }
+    }
+
+    #[test]
+    fn retrieve_action_compares_size_and_mtime() {
+        let tmp = tempdir().unwrap();
+        let local = tmp.path().join("out.dat");
+        let file = remote_file(&local, 5);
+        assert_eq!(retrieve_action(&file).unwrap(), RetrieveAction::Download);
+
+        fs::write(&local, b"hello").unwrap();
+        assert_eq!(
+            retrieve_action(&file).unwrap(),
+            RetrieveAction::Replace { same_size: true }
+        );
+        set_local_mtime(&local, MTIME).unwrap();
+        assert_eq!(retrieve_action(&file).unwrap(), RetrieveAction::Skip);
+
+        fs::write(&local, b"hello world").unwrap();
+        set_local_mtime(&local, MTIME).unwrap();
+        assert_eq!(
+            retrieve_action(&file).unwrap(),
+            RetrieveAction::Replace { same_size: false }
+        );
+    }
+
+    #[test]
+    fn partial_downloads_resume_only_when_newer_and_shorter() {
+        let tmp = tempdir().unwrap();
+        let local = tmp.path().join("out.dat");
+        let partial = partial_path(&local);
+        assert_eq!(partial, tmp.path().join("out.dat.hpc-part"));
+        let file = remote_file(&local, 10);
+        assert_eq!(resume_offset(&file).unwrap(), 0);
+
+        fs::write(&partial, b"hello").unwrap();
+        assert_eq!(resume_offset(&file).unwrap(), 5);
+
+        // The remote file changed after the partial file was last written.
+        set_local_mtime(&partial, MTIME - 1).unwrap();
+        assert_eq!(resume_offset(&file).unwrap(), 0);
+
+        fs::write(&partial, b"hello world!").unwrap();
+        assert_eq!(resume_offset(&file).unwrap(), 0);
+    }
+
+    #[test]
+    fn finished_partial_is_renamed_with_remote_mtime() {
+        let tmp = tempdir().unwrap();
+        let local = tmp.path().join("out.dat");
+        let file = remote_file(&local, 5);
+        fs::write(partial_path(&local), b"hello").unwrap();
+
+        finish_partial(&file).unwrap();
+        assert!(!partial_path(&local).exists());
+        assert_eq!(fs::read(&local).unwrap(), b"hello");
+        assert_eq!(retrieve_action(&file).unwrap(), RetrieveAction::Skip);
+    }
+}
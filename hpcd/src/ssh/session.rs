@@ -57,8 +57,7 @@ pub(crate) struct SessionManagerTestHooks {
             + Sync,
     >,
     ensure_remote_dir: Arc<dyn Fn(&str) -> BoxFuture<'static, Result<()>> + Send + Sync>,
-    sync_one_file:
-        Arc<dyn Fn(&Path, &str, &str, usize) -> BoxFuture<'static, Result<()>> + Send + Sync>,
+    sync_one_file: Arc<dyn Fn(&Path, &str, usize) -> BoxFuture<'static, Result<()>> + Send + Sync>,
 }
 
 /// Manager that owns a single long-lived SSH connection.
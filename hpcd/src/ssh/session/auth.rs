@@ -1,16 +1,22 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use proto::{
-    MfaAnswer, MfaPrompt, Prompt, StreamEvent, SubmitStreamEvent, stream_event, submit_stream_event,
+    CacheGcStreamEvent, ClusterAccountsStreamEvent, ClusterNodesStreamEvent,
+    ClusterQueueStreamEvent, ClusterUsageStreamEvent, ListDirStreamEvent, LsStreamEvent, MfaAnswer,
+    ReadJobFileStreamEvent, MfaPrompt, Prompt,
+    StreamEvent, SubmitStreamEvent, cache_gc_stream_event, cluster_accounts_stream_event,
+    cluster_nodes_stream_event, cluster_queue_stream_event, cluster_usage_stream_event,
+    list_dir_stream_event, ls_stream_event, read_job_file_stream_event, stream_event,
+    submit_stream_event,
 };
 use russh::client::{AuthResult, KeyboardInteractiveAuthResponse};
 use russh::keys::PrivateKeyWithHashAlg;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
-use crate::ssh::AuthenticationFailure;
+use crate::ssh::{AuthenticationFailure, MfaUnanswered};
 
 use super::{ClientHandler, SessionManager};
 
@@ -20,7 +26,7 @@ enum AuthDecision {
     Failure,
 }
 
-trait MfaEvent {
+pub(crate) trait MfaEvent {
     fn from_prompt(prompt: MfaPrompt) -> Self;
 }
 
@@ -40,6 +46,70 @@ impl MfaEvent for SubmitStreamEvent {
     }
 }
 
+impl MfaEvent for ClusterQueueStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ClusterQueueStreamEvent {
+            event: Some(cluster_queue_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for ClusterNodesStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ClusterNodesStreamEvent {
+            event: Some(cluster_nodes_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for ClusterAccountsStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ClusterAccountsStreamEvent {
+            event: Some(cluster_accounts_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for ClusterUsageStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ClusterUsageStreamEvent {
+            event: Some(cluster_usage_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for LsStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        LsStreamEvent {
+            event: Some(ls_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for ListDirStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ListDirStreamEvent {
+            event: Some(list_dir_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for ReadJobFileStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        ReadJobFileStreamEvent {
+            event: Some(read_job_file_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
+impl MfaEvent for CacheGcStreamEvent {
+    fn from_prompt(prompt: MfaPrompt) -> Self {
+        CacheGcStreamEvent {
+            event: Some(cache_gc_stream_event::Event::Mfa(prompt)),
+        }
+    }
+}
+
 fn auth_decision(result: AuthResult) -> AuthDecision {
     match result {
         AuthResult::Success => AuthDecision::Success,
@@ -75,7 +145,8 @@ impl SessionManager {
         self.ensure_connected_with(evt_tx, mfa_rx).await
     }
 
-    async fn ensure_connected_with<E: MfaEvent>(
+    /// `ensure_connected` for RPCs that stream their own event type.
+    pub(crate) async fn ensure_connected_with<E: MfaEvent>(
         &self,
         evt_tx: &mpsc::Sender<Result<E, tonic::Status>>,
         mfa_rx: &mut mpsc::Receiver<MfaAnswer>,
@@ -235,10 +306,7 @@ impl SessionManager {
                     let _ = evt_tx.send(Ok(E::from_prompt(prompt_msg))).await;
 
                     // Wait for client answers
-                    let answers = mfa_rx
-                        .recv()
-                        .await
-                        .ok_or_else(|| anyhow!("client disconnected during MFA"))?;
+                    let answers = mfa_rx.recv().await.ok_or(MfaUnanswered)?;
 
                     // Respond to the server and continue the KI loop
                     ki = handle
@@ -111,6 +111,39 @@ impl SessionManager {
         Ok((out, err, code))
     }
 
+    /// Execute a command fed with the chunks received on `stdin`, then EOF, and capture
+    /// its output like [`Self::exec_capture`]. Stops feeding if the remote side goes away
+    /// so its stderr and exit code are still collected.
+    pub(crate) async fn exec_with_stdin(
+        &self,
+        cmd: &str,
+        mut stdin: mpsc::Receiver<Vec<u8>>,
+    ) -> Result<(Vec<u8>, Vec<u8>, i32)> {
+        let guard = self.handle.lock().await;
+        let handle = guard.as_ref().ok_or_else(|| anyhow!("SSH handle lost"))?;
+        let mut chan = handle.channel_open_session().await?;
+        log::debug!("executing '{}' with streamed stdin", cmd);
+        chan.exec(true, cmd).await.context("exec request")?;
+        while let Some(chunk) = stdin.recv().await {
+            if let Err(e) = chan.data(&chunk[..]).await {
+                log::debug!("remote stdin closed early: {e}");
+                break;
+            }
+        }
+        drop(stdin);
+        let _ = chan.eof().await;
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let mut code: i32 = 0;
+        while let Some(msg) = chan.wait().await {
+            if handle_capture_message(&msg, &mut out, &mut err, &mut code) {
+                break;
+            }
+        }
+        let _ = chan.close().await;
+        Ok((out, err, code))
+    }
+
     /// Execute a single command over the (shared) SSH connection,
     /// streaming stdout/stderr and exit code to `evt_tx`.
     ///
@@ -2,10 +2,12 @@
 // Copyright (C) 2026 Alex Sizykh
 
 use anyhow::{Context, Result, anyhow};
-use proto::{MfaAnswer, SubmitStreamEvent};
+use futures::StreamExt;
+use proto::{MfaAnswer, StreamEvent, SubmitStreamEvent, stream_event};
 use russh_sftp::client::SftpSession;
 use russh_sftp::protocol::{FileAttributes, OpenFlags, StatusCode};
-use std::io::{self, ErrorKind};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind, Write};
 use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 use tokio::fs as tokiofs;
@@ -13,10 +15,37 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::mpsc;
 
 use super::SessionManager;
-use crate::ssh::sync::{BoxFuture, SyncExecutor, SyncOptions, sync_dir_with_executor};
+use crate::ssh::bulk::{
+    build_remote_listing_command, build_untar_command, parse_remote_listing, write_tar_archive,
+};
+use crate::ssh::cache::{
+    CACHE_BATCH, CacheEntry, build_cache_gc_command, build_cache_place_command,
+    build_cache_store_command, build_unshare_command, parse_cache_gc_output,
+    parse_cache_place_output,
+};
+use crate::ssh::delta::{BlockSignature, DeltaStats, compute_delta};
+use crate::ssh::dirlist::{
+    DirEntry, DirListing, EntryKind, ListDirOptions, MAX_LISTED_ENTRIES, NamePattern,
+    retain_matches,
+};
+use crate::ssh::error::NotAFile;
+use crate::ssh::fileview::{FileSlice, FileSliceReader};
+use crate::ssh::progress::{TransferProgress, report_progress};
+use crate::ssh::retrieve::{
+    RemoteFile, RetrieveAction, RetrieveOptions, VerifiedFile, finish_partial, local_conflict,
+    partial_path, resume_offset, retrieve_action, set_local_mtime,
+};
+use crate::ssh::sync::{
+    BoxFuture, DeltaBasis, FileStamp, RemoteHasher, SyncExecutor, SyncItemStatus, SyncOptions,
+    SyncOutcome, SyncPreview, SyncPreviewItem, TRANSFER_ATTEMPTS, classify_sync_item, stamps_match,
+    sync_dir_with_executor,
+};
+use crate::ssh::sync_plan::{PathFilter, SyncItem, build_sync_plan, plan_remote_deletions};
 use crate::ssh::utils::{
-    build_remote_dir_paths, build_remote_hash_script, local_block_hashes, parse_remote_hash_output,
-    sh_escape,
+    ChannelWriter, REMOTE_HASH_SCRIPT, build_remote_dir_paths, build_remote_hash_command,
+    build_remote_patch_command, build_remote_sha256_command, build_remove_command,
+    build_shell_hash_command, build_symlink_command, local_block_hashes, local_file_sha256,
+    parse_remote_hash_output, parse_sha256sum_output, remote_hash_batches, sh_escape,
 };
 
 impl SessionManager {
@@ -79,11 +108,7 @@ impl SessionManager {
                                     e
                                 );
                             } else {
-                                log::warn!(
-                                    "error when setting metadata for path {}: {}",
-                                    &cur,
-                                    e
-                                );
+                                log::warn!("error when setting metadata for path {}: {}", &cur, e);
                             }
                         }
                     };
@@ -93,11 +118,8 @@ impl SessionManager {
         Ok(())
     }
 
-    /// checks if remote hasher already exists on remote
-    /// if it does - exit and do nothing
-    /// if it doesn't - writes file to remote
-    /// TODO - we don't need to do this within sync_one_file
-    async fn ensure_remote_temp_exe(
+    /// Writes an executable helper to `/tmp/hpcd_<session_id>.<suffix>`, once per sync session.
+    async fn upload_remote_temp_exe(
         &self,
         content: &[u8],
         session_id: &str,
@@ -109,11 +131,9 @@ impl SessionManager {
             session_id,
             suffix.trim_start_matches('.')
         );
-        if sftp.try_exists(&path).await? {
-            return Ok(path);
-        }
-        // Create & write
-        let flags = OpenFlags::WRITE.union(OpenFlags::CREATE);
+        let flags = OpenFlags::WRITE
+            .union(OpenFlags::CREATE)
+            .union(OpenFlags::TRUNCATE);
         let attrs = FileAttributes {
             permissions: Some(0o700),
             ..Default::default()
@@ -122,7 +142,7 @@ impl SessionManager {
             .open_with_flags_and_attributes(&path, flags, attrs)
             .await
             .with_context(|| format!("open remote temp {}", &path))?;
-        f.write(content)
+        f.write_all(content)
             .await
             .with_context(|| format!("write remote temp {}", &path))?;
         f.flush().await?;
@@ -130,50 +150,240 @@ impl SessionManager {
         Ok(path)
     }
 
-    /// computes hashes of blocks on a remote file
-    /// Using a Python helper script.
-    ///
-    async fn remote_block_hashes(
+    /// Picks the remote hashing strategy for a sync session: the Python helper when an
+    /// interpreter is on the PATH, the `dd`/`sha256sum` loop otherwise.
+    async fn prepare_remote_hasher_for_sync(&self, session_id: &str) -> Result<RemoteHasher> {
+        let (out, _, code) = self
+            .exec_capture("command -v python3 || command -v python")
+            .await?;
+        let interpreter = String::from_utf8_lossy(&out)
+            .lines()
+            .next()
+            .map(str::trim)
+            .unwrap_or_default()
+            .to_string();
+        if code != 0 || interpreter.is_empty() {
+            log::info!("python not found on remote, hashing blocks with dd and sha256sum");
+            return Ok(RemoteHasher::Shell);
+        }
+        let script_path = self
+            .upload_remote_temp_exe(REMOTE_HASH_SCRIPT.as_bytes(), session_id, "py")
+            .await?;
+        Ok(RemoteHasher::Python {
+            interpreter,
+            script_path,
+        })
+    }
+
+    /// Computes block hashes of many remote files, one exec per argument batch.
+    async fn remote_block_hashes_for_sync(
         &self,
-        remote_path: &str,
-        session_id: &str,
+        hasher: &RemoteHasher,
+        remote_paths: &[String],
         block_size: usize,
-    ) -> Result<Vec<String>> {
-        let py_script = build_remote_hash_script(remote_path, block_size);
-        // TODO: ensure_remote_path_exe can be run once per transfer, no need to do this for every
-        // file: even if script on remote exists, it will require some network trafic to check if
-        // it exists.
-        let script_path = self
-            .ensure_remote_temp_exe(py_script.as_bytes(), session_id, "py")
+    ) -> Result<Vec<Option<Vec<BlockSignature>>>> {
+        let mut hashes = Vec::with_capacity(remote_paths.len());
+        for batch in remote_hash_batches(remote_paths) {
+            let cmd = match hasher {
+                RemoteHasher::Python {
+                    interpreter,
+                    script_path,
+                } => build_remote_hash_command(interpreter, script_path, batch, block_size),
+                RemoteHasher::Shell => build_shell_hash_command(batch, block_size),
+            };
+            let (out, err, code) = self.exec_capture(&cmd).await?;
+            if code != 0 {
+                log::warn!(
+                    "remote hashing exited with {code}: {}",
+                    String::from_utf8_lossy(&err).trim()
+                );
+            }
+            hashes.extend(parse_remote_hash_output(
+                &String::from_utf8_lossy(&out),
+                batch.len(),
+            ));
+        }
+        Ok(hashes)
+    }
+
+    async fn cleanup_remote_hasher_for_sync(&self, hasher: &RemoteHasher) -> Result<()> {
+        if let RemoteHasher::Python { script_path, .. } = hasher {
+            self.sftp().await?.remove_file(script_path).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_remote_files_for_sync(&self, remote_paths: &[String]) -> Result<()> {
+        for batch in remote_hash_batches(remote_paths) {
+            let cmd = build_remove_command(batch);
+            let (_, err, code) = self.exec_capture(&cmd).await?;
+            if code != 0 {
+                anyhow::bail!(
+                    "deleting remote files exited with {code}: {}",
+                    String::from_utf8_lossy(&err).trim()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn link_cached_files_for_sync(
+        &self,
+        cache_dir: &str,
+        entries: &[CacheEntry],
+    ) -> Result<HashSet<String>> {
+        let mut linked = HashSet::new();
+        for batch in entries.chunks(CACHE_BATCH) {
+            let cmd = build_cache_place_command(cache_dir, batch);
+            let (out, err, code) = self.exec_capture(&cmd).await?;
+            if code != 0 {
+                anyhow::bail!(
+                    "linking from the data cache exited with {code}: {}",
+                    String::from_utf8_lossy(&err).trim()
+                );
+            }
+            linked.extend(parse_cache_place_output(&String::from_utf8_lossy(&out)));
+        }
+        Ok(linked)
+    }
+
+    async fn store_cached_files_for_sync(
+        &self,
+        cache_dir: &str,
+        entries: &[CacheEntry],
+    ) -> Result<()> {
+        for batch in entries.chunks(CACHE_BATCH) {
+            let cmd = build_cache_store_command(cache_dir, batch);
+            let (_, err, code) = self.exec_capture(&cmd).await?;
+            if code != 0 {
+                anyhow::bail!(
+                    "storing in the data cache exited with {code}: {}",
+                    String::from_utf8_lossy(&err).trim()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the blobs of the data cache under `cache_dir` that no run directory links
+    /// to any more, or only counts them with `dry_run`. Returns the number of blobs and
+    /// their size.
+    pub async fn cache_gc(&self, cache_dir: &str, dry_run: bool) -> Result<(u64, u64)> {
+        let cmd = build_cache_gc_command(cache_dir, dry_run);
+        let (out, _, _) = self.exec_capture(&cmd).await?;
+        Ok(parse_cache_gc_output(&String::from_utf8_lossy(&out)))
+    }
+
+    /// Gives `remote_path` its own inode before it is written in place, so files it is
+    /// hard-linked to, such as data cache blobs, are left alone.
+    async fn unshare_remote_file(&self, remote_path: &str) {
+        match self.exec_capture(&build_unshare_command(remote_path)).await {
+            Ok((_, _, 0)) => {}
+            Ok((_, err, code)) => log::debug!(
+                "unsharing {remote_path} exited with {code}: {}",
+                String::from_utf8_lossy(&err).trim()
+            ),
+            Err(e) => log::debug!("could not unshare {remote_path}: {e:#}"),
+        }
+    }
+
+    async fn create_remote_symlink_for_sync(&self, target: &Path, remote_path: &str) -> Result<()> {
+        let cmd = build_symlink_command(&target.to_string_lossy(), remote_path);
+        let (_, err, code) = self.exec_capture(&cmd).await?;
+        if code != 0 {
+            anyhow::bail!(
+                "linking {remote_path} exited with {code}: {}",
+                String::from_utf8_lossy(&err).trim()
+            );
+        }
+        Ok(())
+    }
+
+    async fn remote_file_stamps_for_sync(
+        &self,
+        remote_dir: &str,
+    ) -> Result<HashMap<String, FileStamp>> {
+        let (out, err, code) = self
+            .exec_capture(&build_remote_listing_command(remote_dir))
             .await?;
-        let cmd_py3 = format!("python3 -u {}", &script_path);
-        let (out, err, code) = self.exec_capture(&cmd_py3).await?;
-        let (out, err, code) = if code != 0 {
-            let cmd_py = format!("python -u {}", &script_path);
-            self.exec_capture(&cmd_py).await?
-        } else {
-            (out, err, code)
+        if code != 0 && out.is_empty() {
+            anyhow::bail!(
+                "listing {remote_dir} exited with {code}: {}",
+                String::from_utf8_lossy(&err).trim()
+            );
+        }
+        Ok(parse_remote_listing(&String::from_utf8_lossy(&out)))
+    }
+
+    /// Streams `items` as a tar archive into `tar -x` on the remote, zstd-compressed when
+    /// asked for and available there.
+    async fn bulk_upload_for_sync(
+        &self,
+        remote_dir: &str,
+        items: &[SyncItem],
+        compress: bool,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let zstd = compress && matches!(self.exec_capture("command -v zstd").await, Ok((_, _, 0)));
+        if compress && !zstd {
+            log::info!("zstd not found on remote, sending the tar stream uncompressed");
+        }
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(4);
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel::<(String, u64)>();
+        let owned = items.to_vec();
+        let encoder = tokio::task::spawn_blocking(move || -> Result<()> {
+            let on_file = |rel: &str, size: u64| {
+                let _ = done_tx.send((rel.to_string(), size));
+            };
+            let mut out = if zstd {
+                let encoder = zstd::Encoder::new(ChannelWriter::new(tx), 3)?;
+                write_tar_archive(encoder, &owned, on_file)?.finish()?
+            } else {
+                write_tar_archive(ChannelWriter::new(tx), &owned, on_file)?
+            };
+            out.flush()?;
+            Ok(())
+        });
+        let (mut files, mut bytes) = (0, 0);
+        let track = async {
+            while let Some((rel, size)) = done_rx.recv().await {
+                progress.start_file(&rel);
+                progress.add_sent(size);
+                progress.finish_file();
+                files += 1;
+                bytes += size;
+            }
         };
-        // 3) Cleanup temp script (best-effort)
-        //let _ = self.sftp().await?.remove_file(&script_path).await;
-        if code != 0 {
-            let err_message = String::from_utf8(err)?;
-            return Err(anyhow!(err_message));
+        let cmd = build_untar_command(remote_dir, zstd);
+        let (exec, ()) = tokio::join!(self.exec_with_stdin(&cmd, rx), track);
+        let res = async {
+            let (_, err, code) = exec?;
+            encoder.await.context("tar encoder panicked")??;
+            if code != 0 {
+                anyhow::bail!(
+                    "remote tar exited with {code}: {}",
+                    String::from_utf8_lossy(&err).trim()
+                );
+            }
+            Ok(())
+        }
+        .await;
+        if res.is_err() {
+            progress.forget(files, bytes);
         }
-        let out = String::from_utf8(out)?;
-        parse_remote_hash_output(&out)
+        res
     }
 
-    /// Syncs a single file from source to destination.
-    /// Is kinda stable, but definitely needs more testing.
+    /// Uploads new files in full and skips files whose remote copy has the same size and
+    /// mtime. Other remote copies are left for the delta pass.
     async fn sync_one_file_with_sftp(
         &self,
         sftp: &SftpSession,
         local_path: &Path,
         remote_path: &str,
-        session_id: &str,
         block_size: usize,
-    ) -> Result<()> {
+        progress: &TransferProgress,
+    ) -> Result<SyncOutcome> {
         let lmeta = tokiofs::metadata(local_path).await?;
         let lsize = lmeta.len();
         let lmtime = lmeta
@@ -183,7 +393,7 @@ impl SessionManager {
             .unwrap_or_default()
             .as_secs();
 
-        let mut rmeta = match sftp.metadata(remote_path).await {
+        let rmeta = match sftp.metadata(remote_path).await {
             Ok(v) => v,
             Err(e) => {
                 match e {
@@ -193,13 +403,15 @@ impl SessionManager {
                                 // This is a good case - we just need to transfer file and exit,
                                 // that's it
                                 log::debug!("file {} doesn't exist, uploading", remote_path);
-                                return upload_single_file(
+                                upload_single_file(
                                     sftp,
-                                    &local_path.to_path_buf(),
+                                    local_path,
                                     remote_path,
                                     block_size,
+                                    progress,
                                 )
-                                .await;
+                                .await?;
+                                return Ok(SyncOutcome::Uploaded);
                             }
                             _ => {
                                 log::warn!("encountered Status error with status {:?}", estatus);
@@ -214,119 +426,195 @@ impl SessionManager {
         if rmeta.is_dir() {
             anyhow::bail!("{} is a directory", remote_path);
         }
-        if let Some(rmtime) = rmeta.mtime {
-            if rmtime as u64 >= lmtime {
-                log::debug!(
-                    "remote file {remote_path} is at least as new as local file {local_path:?}, skipping transfer",
-                );
-
-                return Ok(());
-            }
-        } else {
-            log::debug!("could not get mtime for remote path {remote_path}")
+        let local = FileStamp {
+            size: lsize,
+            mtime: Some(lmtime),
+        };
+        let remote = FileStamp {
+            size: rmeta.len(),
+            mtime: rmeta.mtime.map(u64::from),
+        };
+        if stamps_match(local, remote) {
+            log::debug!(
+                "remote file {remote_path} has the size and mtime of local file {local_path:?}, skipping transfer",
+            );
+            progress.add_skipped(lsize);
+            return Ok(SyncOutcome::Done);
         }
 
         if rmeta.is_empty() {
             // Remote file is empty - weird but OK, we'll just upload our local file.
-            return upload_single_file(sftp, &local_path.to_path_buf(), remote_path, block_size)
-                .await;
+            upload_single_file(sftp, local_path, remote_path, block_size, progress).await?;
+            return Ok(SyncOutcome::Uploaded);
         }
+        Ok(SyncOutcome::NeedsDelta {
+            same_size: remote.size == local.size,
+        })
+    }
+
+    /// Sends an rsync-style delta of `local_path` through the helper's `patch` mode, which
+    /// rebuilds the remote file next to the old copy and swaps it in.
+    async fn rolling_delta_sync(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        interpreter: &str,
+        script_path: &str,
+        blocks: &[BlockSignature],
+        block_size: usize,
+    ) -> Result<DeltaStats> {
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(4);
+        let local = local_path.to_path_buf();
+        let blocks = blocks.to_vec();
+        let encoder = tokio::task::spawn_blocking(move || -> Result<DeltaStats> {
+            let file = std::fs::File::open(&local)
+                .with_context(|| format!("opening local file {}", local.display()))?;
+            let mut out = ChannelWriter::new(tx);
+            Ok(compute_delta(
+                std::io::BufReader::new(file),
+                &blocks,
+                block_size,
+                &mut out,
+            )?)
+        });
+        let cmd = build_remote_patch_command(interpreter, script_path, remote_path, block_size);
+        let (_, err, code) = self.exec_with_stdin(&cmd, rx).await?;
+        let stats = encoder.await.context("delta encoder panicked")??;
+        if code != 0 {
+            anyhow::bail!(
+                "remote patch exited with {code}: {}",
+                String::from_utf8_lossy(&err).trim()
+            );
+        }
+        Ok(stats)
+    }
+
+    /// Brings a stale remote file up to date. With the Python helper, an rsync-style
+    /// rolling delta is applied remotely so shifted content is reused; otherwise, or if
+    /// that fails, only the aligned blocks whose hashes differ are rewritten.
+    /// Falls back to a full upload when the remote could not be hashed.
+    /// Is kinda stable, but definitely needs more testing.
+    async fn delta_sync_one_file_with_sftp(
+        &self,
+        sftp: &SftpSession,
+        local_path: &Path,
+        remote_path: &str,
+        basis: Option<DeltaBasis<'_>>,
+        block_size: usize,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let Some(basis) = basis else {
+            log::warn!(
+                "remote hash plan unavailable for {remote_path}; falling back to full upload"
+            );
+            self.unshare_remote_file(remote_path).await;
+            upload_single_file(sftp, local_path, remote_path, block_size, progress).await?;
+            return Ok(());
+        };
+        let rblocks = basis.blocks;
+        if let RemoteHasher::Python {
+            interpreter,
+            script_path,
+        } = basis.hasher
+            && rblocks.iter().all(|block| block.weak.is_some())
+        {
+            match self
+                .rolling_delta_sync(
+                    local_path,
+                    remote_path,
+                    interpreter,
+                    script_path,
+                    rblocks,
+                    block_size,
+                )
+                .await
+            {
+                Ok(stats) => {
+                    log::debug!(
+                        "rolling delta for {remote_path}: {} literal, {} matched bytes",
+                        stats.literal_bytes,
+                        stats.matched_bytes
+                    );
+                    progress.add_sent(stats.literal_bytes);
+                    progress.add_skipped(stats.matched_bytes);
+                    preserve_local_metadata(sftp, local_path, remote_path).await;
+                    return Ok(());
+                }
+                Err(e) => log::warn!(
+                    "rolling delta failed for {remote_path}: {e:#}; falling back to block mode"
+                ),
+            }
+        }
+        let lsize = tokiofs::metadata(local_path).await?.len();
+        self.unshare_remote_file(remote_path).await;
         log::debug!("Opening remote file for random-access writes");
-        // Try to open remote file for random-access writes (create if absent)
         let flags = OpenFlags::WRITE.union(OpenFlags::READ);
-        /*
-            .union(OpenFlags::APPEND)
-            .union(OpenFlags::TRUNCATE);
-        */
-        let mut rfile = sftp
-            .open_with_flags(remote_path, flags)
+        let mut rfile = open_for_writing(sftp, remote_path, flags)
             .await
             .context("opening remote file for random-access writes")?;
-        // Plan: compute remote block hashes (without downloading file data) by running
-        // a tiny Python hashing script *on the remote* over an exec channel.
-        // If that fails (python missing, permission issues, etc), fall back to full upload.
-        let remote_hashes = match self
-            .remote_block_hashes(remote_path, session_id, block_size)
-            .await
-        {
-            Ok(h) => Some(h),
-            Err(e) => {
-                log::warn!(
-                    "remote hash plan unavailable for {}: {} ; falling back to full upload",
-                    remote_path,
-                    e
-                );
-                None
-            }
-        };
-        if let Some(rblocks) = remote_hashes {
-            // Compute local block hashes
-            let lblocks = local_block_hashes(local_path, block_size).await?;
-
-            // Compare and send only differing blocks
-            for (i, lbh) in lblocks.iter().enumerate() {
-                let offset = (i as u64) * (block_size as u64);
-                let remaining = lsize.saturating_sub(offset);
-                let this_block = remaining.min(block_size as u64) as usize;
-
-                let differing = match rblocks.get(i) {
-                    Some(rh) => rh != lbh,
-                    None => true, // remote shorter; need to append
-                };
+        let mut rmeta = rfile.metadata().await?;
 
-                if differing {
-                    log::debug!("found differing block at offset {offset}, index {i}");
-                    // Read local block and write to remote at offset
-                    let mut buf = vec![0u8; this_block];
-                    let mut f = tokiofs::File::open(local_path).await.context(format!(
-                        "opening local file {}",
-                        local_path.to_string_lossy()
-                    ))?;
-                    f.seek(std::io::SeekFrom::Start(offset))
-                        .await
-                        .context(format!("seeking in remote file at offset {offset}"))?;
-                    let n = f.read(&mut buf[..]).await.context(format!(
-                        "reading from {:?} at offset {}",
-                        local_path, offset
-                    ))?;
-                    // TODO: in case of errors, fallback to doing full transfer
-                    rfile
-                        .seek(std::io::SeekFrom::Start(offset))
-                        .await
-                        .context(format!("seeking in remote file at offset {offset}"))?;
-                    rfile
-                        .write(&buf[..n])
-                        .await
-                        .with_context(|| format!("writing block @{} to {}", offset, remote_path))?;
-                    rfile.flush().await?;
-                }
+        // Compute local block hashes
+        let lblocks = local_block_hashes(local_path, block_size).await?;
+
+        // Compare and send only differing blocks
+        for (i, lbh) in lblocks.iter().enumerate() {
+            let offset = (i as u64) * (block_size as u64);
+            let remaining = lsize.saturating_sub(offset);
+            let this_block = remaining.min(block_size as u64) as usize;
+
+            let differing = match rblocks.get(i) {
+                Some(rh) => rh.strong != *lbh,
+                None => true, // remote shorter; need to append
+            };
+
+            if differing {
+                log::debug!("found differing block at offset {offset}, index {i}");
+                // Read local block and write to remote at offset
+                let mut buf = vec![0u8; this_block];
+                let mut f = tokiofs::File::open(local_path).await.context(format!(
+                    "opening local file {}",
+                    local_path.to_string_lossy()
+                ))?;
+                f.seek(std::io::SeekFrom::Start(offset))
+                    .await
+                    .context(format!("seeking in remote file at offset {offset}"))?;
+                let n = f.read(&mut buf[..]).await.context(format!(
+                    "reading from {:?} at offset {}",
+                    local_path, offset
+                ))?;
+                // TODO: in case of errors, fallback to doing full transfer
+                rfile
+                    .seek(std::io::SeekFrom::Start(offset))
+                    .await
+                    .context(format!("seeking in remote file at offset {offset}"))?;
+                rfile
+                    .write(&buf[..n])
+                    .await
+                    .with_context(|| format!("writing block @{} to {}", offset, remote_path))?;
+                rfile.flush().await?;
+                progress.add_sent(n as u64);
+            } else {
+                progress.add_skipped(this_block as u64);
             }
+        }
 
-            // Truncate/extend remote file to match local size if needed
-            // this is done in case the local file was truncated
-            if rblocks.len() as u64 * block_size as u64 != lsize {
-                log::info!("setting length medatata on remote file");
-                // Use SFTP fsetstat(size) when supported; otherwise remote 'truncate'
-                rmeta.size = Some(lsize);
-                if let Err(_e) = rfile.set_metadata(rmeta).await {
-                    log::warn!("setting metadata on remote failed - falling back to truncate");
-                    // fallback via small exec
-                    let cmd = format!("truncate -s {} {}", lsize, sh_escape(remote_path));
-                    let _ = self.exec_simple(&cmd).await;
-                }
+        // Truncate/extend remote file to match local size if needed
+        // this is done in case the local file was truncated
+        if rblocks.len() as u64 * block_size as u64 != lsize {
+            log::info!("setting length medatata on remote file");
+            // Use SFTP fsetstat(size) when supported; otherwise remote 'truncate'
+            rmeta.size = Some(lsize);
+            if let Err(_e) = rfile.set_metadata(rmeta).await {
+                log::warn!("setting metadata on remote failed - falling back to truncate");
+                // fallback via small exec
+                let cmd = format!("truncate -s {} {}", lsize, sh_escape(remote_path));
+                let _ = self.exec_simple(&cmd).await;
             }
-        } else {
-            return upload_single_file(sftp, &local_path.to_path_buf(), remote_path, block_size)
-                .await;
         }
 
-        /*
-        // Try to set remote mtime to local mtime so next run can skip
-        log::debug!("setting remote mtime equal to local mtime");
-        let mut attrs = FileAttributes::default();
-        attrs.mtime = Some(lmtime as u32);
-        let _ = sftp.set_metadata(remote_path, attrs).await;
-        */
+        drop(rfile);
+        preserve_local_metadata(sftp, local_path, remote_path).await;
         Ok(())
     }
 
@@ -334,22 +622,46 @@ impl SessionManager {
         &self,
         local_path: &Path,
         remote_path: &str,
-        session_id: &str,
         block_size: usize,
-    ) -> Result<()> {
+        progress: &TransferProgress,
+    ) -> Result<SyncOutcome> {
         #[cfg(test)]
         if let Some(hooks) = &self.test_hooks {
-            return (hooks.sync_one_file)(local_path, remote_path, session_id, block_size).await;
+            (hooks.sync_one_file)(local_path, remote_path, block_size).await?;
+            return Ok(SyncOutcome::Done);
         }
         let sftp = self.sftp().await?;
-        self.sync_one_file_with_sftp(&sftp, local_path, remote_path, session_id, block_size)
+        self.sync_one_file_with_sftp(&sftp, local_path, remote_path, block_size, progress)
             .await
     }
 
+    async fn delta_sync_one_file_for_sync(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        basis: Option<DeltaBasis<'_>>,
+        block_size: usize,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let sftp = self.sftp().await?;
+        self.delta_sync_one_file_with_sftp(
+            &sftp,
+            local_path,
+            remote_path,
+            basis,
+            block_size,
+            progress,
+        )
+        .await
+    }
+
     /// Sync an entire local directory tree into a remote directory.
     /// - Creates remote directories as needed
     /// - Skips files where remote mtime >= local mtime
     /// - Uses block-delta writes for changed files
+    /// - Checks every file it wrote against the local SHA-256, in one batch
+    ///
+    /// Returns the written files that matched their local copy.
     pub async fn sync_dir<P: AsRef<Path>>(
         &self,
         local_dir: P,
@@ -357,30 +669,416 @@ impl SessionManager {
         options: SyncOptions<'_>,
         evt_tx: &mpsc::Sender<Result<SubmitStreamEvent, tonic::Status>>,
         mfa_rx: mpsc::Receiver<MfaAnswer>,
-    ) -> Result<()> {
+    ) -> Result<Vec<VerifiedFile>> {
         sync_dir_with_executor(self, local_dir, remote_dir, options, evt_tx, mfa_rx).await
     }
 
+    /// Classify every file of the sync plan against the remote without writing anything.
+    /// With `options.checksum`, same-sized files the mtime rule would upload are compared
+    /// by SHA-256 and reported unchanged when their contents already match. Symbolic links
+    /// are reported with what the sync would do with them, and with `options.delete` the
+    /// remote files a mirror sync would remove are listed as [`SyncItemStatus::Delete`].
+    pub async fn preview_sync<P: AsRef<Path>>(
+        &self,
+        local_dir: P,
+        remote_dir: &str,
+        options: SyncOptions<'_>,
+    ) -> Result<SyncPreview> {
+        let checksum = options.checksum;
+        let parallelism = options.parallelism.unwrap_or(8);
+        let plan = build_sync_plan(
+            local_dir,
+            remote_dir,
+            options.filters,
+            options.ignore_files,
+            options.symlinks,
+        )?;
+        let sftp = self.sftp().await?;
+        let remote_root_exists = sftp.try_exists(remote_dir).await?;
+
+        let sftp = &sftp;
+        let paths: Vec<(PathBuf, String)> = plan
+            .items
+            .iter()
+            .map(|item| (item.local_path.clone(), item.remote_path.clone()))
+            .collect();
+        let classified = futures::stream::iter(paths)
+            .map(|(local_path, remote_path)| async move {
+                let local = local_stamp(&local_path).await?;
+                let remote = if remote_root_exists {
+                    remote_stamp(sftp, &remote_path).await?
+                } else {
+                    None
+                };
+                Ok::<_, anyhow::Error>((local, remote))
+            })
+            .buffered(parallelism)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut items = Vec::with_capacity(plan.items.len());
+        let mut hash_candidates = Vec::new();
+        for (idx, (item, res)) in plan.items.iter().zip(classified).enumerate() {
+            let (local, remote) = res?;
+            let status = classify_sync_item(local, remote);
+            if checksum
+                && status == SyncItemStatus::Changed
+                && remote.is_some_and(|r| r.size == local.size)
+            {
+                hash_candidates.push(idx);
+            }
+            items.push(SyncPreviewItem {
+                rel_path: item.rel_path.clone(),
+                size: local.size,
+                status,
+            });
+        }
+
+        if !hash_candidates.is_empty() {
+            let remote_paths: Vec<&str> = hash_candidates
+                .iter()
+                .map(|&idx| plan.items[idx].remote_path.as_str())
+                .collect();
+            let remote_hashes = self.remote_sha256s(&remote_paths).await?;
+            for idx in hash_candidates {
+                let item = &plan.items[idx];
+                let Some(remote_hash) = remote_hashes.get(&item.remote_path) else {
+                    log::debug!("no remote hash for {}", item.remote_path);
+                    continue;
+                };
+                if local_file_sha256(&item.local_path).await? == *remote_hash {
+                    items[idx].status = SyncItemStatus::Unchanged;
+                }
+            }
+        }
+        if options.delete && remote_root_exists {
+            let remote = self.remote_file_stamps_for_sync(remote_dir).await?;
+            let deletions = plan_remote_deletions(
+                &plan,
+                remote.keys().map(String::as_str),
+                options.filters,
+                options.ignore_files,
+                options.protect,
+                options.uploaded,
+            )?;
+            items.extend(deletions.into_iter().map(|rel| SyncPreviewItem {
+                size: remote[&rel].size,
+                rel_path: PathBuf::from(rel),
+                status: SyncItemStatus::Delete,
+            }));
+        }
+        Ok(SyncPreview {
+            items,
+            symlinks: plan.symlinks,
+        })
+    }
+
+    /// Lists `remote_path` down to `options.max_depth`, or the path itself when it is not
+    /// a directory. Symlinks are reported, not followed.
+    pub async fn list_dir(
+        &self,
+        remote_path: &str,
+        options: &ListDirOptions,
+    ) -> Result<DirListing> {
+        let pattern = options
+            .pattern
+            .as_deref()
+            .map(NamePattern::compile)
+            .transpose()?;
+        let sftp = self.sftp().await?;
+        let root = match remote_path.trim_end_matches('/') {
+            "" => "/",
+            path => path,
+        };
+        let meta = sftp
+            .metadata(root)
+            .await
+            .with_context(|| format!("reading metadata of {root}"))?;
+        let mut listing = DirListing::default();
+        if !meta.is_dir() {
+            let attrs = sftp
+                .symlink_metadata(root)
+                .await
+                .with_context(|| format!("reading metadata of {root}"))?;
+            let name = root.rsplit('/').next().unwrap_or(root).to_string();
+            let mut entry = DirEntry::from_attrs(name, 0, &attrs);
+            if entry.kind == EntryKind::Symlink {
+                entry.symlink_target = sftp.read_link(root).await.ok();
+            }
+            listing.entries.push(entry);
+        }
+
+        let mut stack: Vec<(String, String, u32)> = Vec::new();
+        if meta.is_dir() {
+            stack.push((root.to_string(), String::new(), 1));
+        }
+        'walk: while let Some((dir, rel_dir, depth)) = stack.pop() {
+            let items = sftp
+                .read_dir(dir.as_str())
+                .await
+                .with_context(|| format!("listing {dir}"))?;
+            for item in items {
+                let name = item.file_name();
+                let remote_child = if dir == "/" {
+                    format!("/{name}")
+                } else {
+                    format!("{dir}/{name}")
+                };
+                let rel_child = if rel_dir.is_empty() {
+                    name
+                } else {
+                    format!("{rel_dir}/{name}")
+                };
+                let mut entry = DirEntry::from_attrs(rel_child.clone(), depth, &item.metadata());
+                if entry.kind == EntryKind::Symlink {
+                    entry.symlink_target = sftp.read_link(remote_child.as_str()).await.ok();
+                }
+                if entry.kind == EntryKind::Dir && options.max_depth.is_none_or(|max| depth < max) {
+                    stack.push((remote_child, rel_child, depth + 1));
+                }
+                listing.entries.push(entry);
+                if listing.entries.len() >= MAX_LISTED_ENTRIES {
+                    listing.truncated = true;
+                    break 'walk;
+                }
+            }
+        }
+        listing.sort();
+        if let Some(pattern) = pattern {
+            listing.entries = retain_matches(listing.entries, &pattern);
+        }
+        Ok(listing)
+    }
+
+    /// Opens a regular file (following symlinks) for reading `slice` of it.
+    pub async fn open_file_slice(
+        &self,
+        remote_path: &str,
+        slice: &FileSlice,
+    ) -> Result<FileSliceReader> {
+        let sftp = self.sftp().await?;
+        let meta = sftp
+            .metadata(remote_path)
+            .await
+            .with_context(|| format!("reading metadata of {remote_path}"))?;
+        if !meta.file_type().is_file() {
+            return Err(NotAFile(remote_path.to_string()).into());
+        }
+        let file = sftp
+            .open(remote_path)
+            .await
+            .with_context(|| format!("opening {remote_path}"))?;
+        FileSliceReader::new(file, remote_path, meta.size.unwrap_or(0), slice).await
+    }
+
+    /// SHA-256 of remote files keyed by path, `SHA256_BATCH` files per exec.
+    pub(crate) async fn remote_sha256s(
+        &self,
+        remote_paths: &[&str],
+    ) -> Result<HashMap<String, String>> {
+        let mut hashes = HashMap::with_capacity(remote_paths.len());
+        for chunk in remote_paths.chunks(SHA256_BATCH) {
+            let cmd = build_remote_sha256_command(chunk);
+            // sha256sum exits non-zero if any file is unreadable, but still hashes the rest
+            let (out, _, _) = self.exec_capture(&cmd).await?;
+            hashes.extend(parse_sha256sum_output(&String::from_utf8_lossy(&out)));
+        }
+        Ok(hashes)
+    }
+
+    /// Download a remote file or directory tree, streaming transfer progress to `evt_tx`.
+    /// Files whose local copy already matches are skipped and partial downloads are
+    /// resumed; see [`crate::ssh::retrieve`]. Returns the files that were downloaded and
+    /// verified against the SHA-256 of their remote copy.
     pub async fn retrieve_path(
         &self,
         remote_path: &str,
         local_path: &Path,
-        overwrite: bool,
-    ) -> Result<()> {
+        options: &RetrieveOptions<'_>,
+        evt_tx: &mpsc::Sender<Result<StreamEvent, tonic::Status>>,
+    ) -> Result<Vec<VerifiedFile>> {
         let sftp = self.sftp().await?;
         let meta = sftp.metadata(remote_path).await?;
-        if !overwrite {
-            if meta.is_dir() {
-                ensure_no_dir_overwrite(&sftp, remote_path, local_path).await?;
-            } else {
-                ensure_no_file_overwrite(local_path).await?;
+        let (dirs, files) = if meta.is_dir() {
+            let filter = PathFilter::new(options.filters)?;
+            list_remote_files(&sftp, remote_path, local_path, &filter).await?
+        } else {
+            let name = Path::new(remote_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let file = RemoteFile {
+                remote_path: remote_path.to_string(),
+                local_path: local_path.to_path_buf(),
+                rel_path: name,
+                stamp: FileStamp {
+                    size: meta.size.unwrap_or(0),
+                    mtime: meta.mtime.map(u64::from),
+                },
+            };
+            (Vec::new(), vec![file])
+        };
+
+        let progress = TransferProgress::new(
+            files.len() as u64,
+            files.iter().map(|file| file.stamp.size).sum(),
+        );
+        let mut downloads = Vec::with_capacity(files.len());
+        let mut changed = Vec::new();
+        for file in files {
+            match retrieve_action(&file)? {
+                RetrieveAction::Skip => {
+                    progress.add_skipped(file.stamp.size);
+                    progress.finish_file();
+                }
+                RetrieveAction::Replace { same_size } => changed.push((file, same_size)),
+                RetrieveAction::Download => downloads.push(file),
             }
         }
-        if meta.is_dir() {
-            download_dir(&sftp, remote_path, local_path).await
+        let changed = if options.checksum {
+            self.skip_identical_downloads(changed, &progress).await
         } else {
-            download_file(&sftp, remote_path, local_path).await
+            changed.into_iter().map(|(file, _)| file).collect()
+        };
+        if !options.overwrite
+            && let Some(file) = changed.first()
+        {
+            return Err(local_conflict(&file.local_path).into());
         }
+        downloads.extend(changed);
+        for dir in &dirs {
+            match tokiofs::metadata(dir).await {
+                Ok(meta) if !meta.is_dir() => return Err(local_conflict(dir).into()),
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => tokiofs::create_dir_all(dir).await?,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let parallelism = options.parallelism.unwrap_or(8);
+        let (sftp, progress) = (&sftp, &progress);
+        let download = async {
+            let mut verified = Vec::with_capacity(downloads.len());
+            let mut pending = downloads;
+            for attempt in 1..=TRANSFER_ATTEMPTS {
+                let results = futures::stream::iter(pending.into_iter().map(|file| async move {
+                    progress.start_file(&file.rel_path);
+                    let res = download_file(sftp, &file, progress).await;
+                    if res.is_ok() {
+                        progress.finish_file();
+                    }
+                    (file, res)
+                }))
+                .buffer_unordered(parallelism)
+                .collect::<Vec<_>>()
+                .await;
+                let mut downloaded = Vec::with_capacity(results.len());
+                let mut sent = HashMap::with_capacity(results.len());
+                for (file, res) in results {
+                    sent.insert(file.remote_path.clone(), res?);
+                    downloaded.push(file);
+                }
+                let mismatched = self.verify_downloads(downloaded, &mut verified).await?;
+                if mismatched.is_empty() {
+                    return Ok(verified);
+                }
+                log::warn!(
+                    "{} downloaded files do not match their remote copy (attempt {attempt})",
+                    mismatched.len()
+                );
+                if attempt < TRANSFER_ATTEMPTS {
+                    let bytes = mismatched.iter().map(|f| sent[&f.remote_path]).sum();
+                    progress.forget(mismatched.len() as u64, bytes);
+                }
+                pending = mismatched;
+            }
+            anyhow::bail!(
+                "{} does not match {} after {TRANSFER_ATTEMPTS} downloads",
+                pending[0].local_path.display(),
+                pending[0].remote_path
+            )
+        };
+        report_progress(progress, download, evt_tx, |p| StreamEvent {
+            event: Some(stream_event::Event::TransferProgress(p)),
+        })
+        .await
+    }
+
+    /// Compares freshly downloaded `files` with the SHA-256 of their remote copies, moving
+    /// the matching ones to `verified` and returning the rest. Files the remote cannot
+    /// hash are left unverified.
+    async fn verify_downloads(
+        &self,
+        files: Vec<RemoteFile>,
+        verified: &mut Vec<VerifiedFile>,
+    ) -> Result<Vec<RemoteFile>> {
+        let remote_paths: Vec<&str> = files.iter().map(|f| f.remote_path.as_str()).collect();
+        let remote = self
+            .remote_sha256s(&remote_paths)
+            .await
+            .context("verifying downloads")?;
+        let mut mismatched = Vec::new();
+        for file in files {
+            let Some(remote_hash) = remote.get(&file.remote_path) else {
+                log::debug!("could not hash {}; download not verified", file.remote_path);
+                continue;
+            };
+            let local_hash = local_file_sha256(&file.local_path).await?;
+            if local_hash == *remote_hash {
+                verified.push(VerifiedFile {
+                    remote_path: file.remote_path,
+                    local_path: file.local_path,
+                    size: file.stamp.size,
+                    sha256: local_hash,
+                });
+            } else {
+                mismatched.push(file);
+            }
+        }
+        Ok(mismatched)
+    }
+
+    /// Drops same-sized files whose local copy has the remote SHA-256 from `candidates`,
+    /// stamping them with the remote mtime so the next retrieve skips them by stamp.
+    async fn skip_identical_downloads(
+        &self,
+        candidates: Vec<(RemoteFile, bool)>,
+        progress: &TransferProgress,
+    ) -> Vec<RemoteFile> {
+        let same_size: Vec<&str> = candidates
+            .iter()
+            .filter(|(_, same_size)| *same_size)
+            .map(|(file, _)| file.remote_path.as_str())
+            .collect();
+        if same_size.is_empty() {
+            return candidates.into_iter().map(|(file, _)| file).collect();
+        }
+        let remote = self.remote_sha256s(&same_size).await.unwrap_or_else(|e| {
+            log::warn!("remote checksums failed: {e:#}; retrieving by size and mtime only");
+            HashMap::new()
+        });
+        let mut rest = Vec::with_capacity(candidates.len());
+        for (file, same_size) in candidates {
+            if same_size
+                && let Some(remote_hash) = remote.get(&file.remote_path)
+                && local_file_sha256(&file.local_path).await.ok().as_ref() == Some(remote_hash)
+            {
+                log::debug!("{} is identical by checksum", file.local_path.display());
+                if let Some(mtime) = file.stamp.mtime
+                    && let Err(e) = set_local_mtime(&file.local_path, mtime)
+                {
+                    log::debug!(
+                        "could not update mtime of {}: {e}",
+                        file.local_path.display()
+                    );
+                }
+                progress.add_skipped(file.stamp.size);
+                progress.finish_file();
+                continue;
+            }
+            rest.push(file);
+        }
+        rest
     }
 }
 
@@ -397,26 +1095,178 @@ impl SyncExecutor for SessionManager {
         Box::pin(async move { self.ensure_remote_dir_for_sync(remote_dir).await })
     }
 
+    fn remote_file_stamps<'a>(
+        &'a self,
+        remote_dir: &'a str,
+    ) -> BoxFuture<'a, Result<HashMap<String, FileStamp>>> {
+        Box::pin(async move { self.remote_file_stamps_for_sync(remote_dir).await })
+    }
+
+    fn bulk_upload<'a>(
+        &'a self,
+        remote_dir: &'a str,
+        items: &'a [SyncItem],
+        compress: bool,
+        progress: &'a TransferProgress,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.bulk_upload_for_sync(remote_dir, items, compress, progress)
+                .await
+        })
+    }
+
+    fn remote_checksums<'a>(
+        &'a self,
+        remote_paths: &'a [String],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>>> {
+        Box::pin(async move {
+            let paths: Vec<&str> = remote_paths.iter().map(String::as_str).collect();
+            self.remote_sha256s(&paths).await
+        })
+    }
+
+    fn copy_file_metadata<'a>(
+        &'a self,
+        local_path: &'a Path,
+        remote_path: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut attrs = local_metadata_attrs(&tokiofs::metadata(local_path).await?);
+            let sftp = self.sftp().await?;
+            // A read-only remote file may be a data cache blob linked into the run
+            // directory; giving it write bits back would let jobs modify the cache.
+            let remote_mode = sftp.metadata(remote_path).await?.permissions;
+            if let (Some(mode), Some(remote_mode)) = (attrs.permissions.as_mut(), remote_mode) {
+                *mode &= remote_mode | !0o222;
+            }
+            sftp.set_metadata(remote_path, attrs).await?;
+            Ok(())
+        })
+    }
+
+    fn remove_remote_files<'a>(&'a self, remote_paths: &'a [String]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.remove_remote_files_for_sync(remote_paths).await })
+    }
+
+    fn create_remote_symlink<'a>(
+        &'a self,
+        target: &'a Path,
+        remote_path: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.create_remote_symlink_for_sync(target, remote_path)
+                .await
+        })
+    }
+
+    fn link_cached_files<'a>(
+        &'a self,
+        cache_dir: &'a str,
+        entries: &'a [CacheEntry],
+    ) -> BoxFuture<'a, Result<HashSet<String>>> {
+        Box::pin(async move { self.link_cached_files_for_sync(cache_dir, entries).await })
+    }
+
+    fn store_cached_files<'a>(
+        &'a self,
+        cache_dir: &'a str,
+        entries: &'a [CacheEntry],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.store_cached_files_for_sync(cache_dir, entries).await })
+    }
+
     fn sync_one_file<'a>(
         &'a self,
         local_path: &'a Path,
         remote_path: &'a str,
+        block_size: usize,
+        progress: &'a TransferProgress,
+    ) -> BoxFuture<'a, Result<SyncOutcome>> {
+        Box::pin(async move {
+            self.sync_one_file_for_sync(local_path, remote_path, block_size, progress)
+                .await
+        })
+    }
+
+    fn prepare_remote_hasher<'a>(
+        &'a self,
         session_id: &'a str,
+    ) -> BoxFuture<'a, Result<RemoteHasher>> {
+        Box::pin(async move { self.prepare_remote_hasher_for_sync(session_id).await })
+    }
+
+    fn remote_block_hashes<'a>(
+        &'a self,
+        hasher: &'a RemoteHasher,
+        remote_paths: &'a [String],
         block_size: usize,
+    ) -> BoxFuture<'a, Result<Vec<Option<Vec<BlockSignature>>>>> {
+        Box::pin(async move {
+            self.remote_block_hashes_for_sync(hasher, remote_paths, block_size)
+                .await
+        })
+    }
+
+    fn delta_sync_one_file<'a>(
+        &'a self,
+        local_path: &'a Path,
+        remote_path: &'a str,
+        basis: Option<DeltaBasis<'a>>,
+        block_size: usize,
+        progress: &'a TransferProgress,
     ) -> BoxFuture<'a, Result<()>> {
         Box::pin(async move {
-            self.sync_one_file_for_sync(local_path, remote_path, session_id, block_size)
+            self.delta_sync_one_file_for_sync(local_path, remote_path, basis, block_size, progress)
                 .await
         })
     }
+
+    fn cleanup_remote_hasher<'a>(&'a self, hasher: &'a RemoteHasher) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.cleanup_remote_hasher_for_sync(hasher).await })
+    }
+}
+
+/// Remote files hashed per `sha256sum` invocation during a dry-run.
+const SHA256_BATCH: usize = 256;
+
+async fn local_stamp(local_path: &Path) -> Result<FileStamp> {
+    let meta = tokiofs::metadata(local_path)
+        .await
+        .with_context(|| format!("reading metadata of {}", local_path.display()))?;
+    Ok(FileStamp {
+        size: meta.len(),
+        mtime: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+    })
+}
+
+async fn remote_stamp(sftp: &SftpSession, remote_path: &str) -> Result<Option<FileStamp>> {
+    match sftp.metadata(remote_path).await {
+        Ok(meta) if meta.is_dir() => anyhow::bail!("{} is a directory", remote_path),
+        Ok(meta) => Ok(Some(FileStamp {
+            size: meta.size.unwrap_or(0),
+            mtime: meta.mtime.map(u64::from),
+        })),
+        Err(russh_sftp::client::error::Error::Status(status))
+            if status.status_code == StatusCode::NoSuchFile =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e).with_context(|| format!("reading metadata of {remote_path}")),
+    }
 }
 
+/// Copies `local_path` over `remote_path`, returning the number of bytes sent.
 async fn upload_single_file(
     sftp: &SftpSession,
-    local_path: &PathBuf,
+    local_path: &Path,
     remote_path: &str,
     block_size: usize,
-) -> anyhow::Result<()> {
+    progress: &TransferProgress,
+) -> anyhow::Result<u64> {
     // File just doesn't exist yet we simply copy it over SFTP
     log::debug!(
         "Uploading file over sftp: {} -> {}",
@@ -427,8 +1277,9 @@ async fn upload_single_file(
     // Removed flag APPEND because it's not needed
     let flags = OpenFlags::READ
         .union(OpenFlags::WRITE)
-        .union(OpenFlags::CREATE);
-    let mut rfile = sftp.open_with_flags(remote_path, flags).await?;
+        .union(OpenFlags::CREATE)
+        .union(OpenFlags::TRUNCATE);
+    let mut rfile = open_for_writing(sftp, remote_path, flags).await?;
     let mut offset = 0u64;
     let mut buf = vec![0u8; block_size];
     loop {
@@ -439,97 +1290,171 @@ async fn upload_single_file(
         rfile.seek(std::io::SeekFrom::Start(offset)).await?;
         rfile.write_all(&buf[..n]).await?;
         offset += n as u64;
+        progress.add_sent(n as u64);
     }
     rfile.flush().await?;
-    Ok(())
+    drop(rfile);
+    preserve_local_metadata(sftp, local_path, remote_path).await;
+    Ok(offset)
 }
 
-async fn download_file(sftp: &SftpSession, remote_path: &str, local_path: &Path) -> Result<()> {
-    if let Some(parent) = local_path.parent() {
-        tokiofs::create_dir_all(parent).await?;
-    }
-    let mut rfile = sftp.open(remote_path).await?;
-    let mut lfile = tokiofs::File::create(local_path).await?;
-    tokio::io::copy(&mut rfile, &mut lfile).await?;
-    lfile.flush().await?;
-    Ok(())
+/// Opens `remote_path` with write access. A remote copy of a read-only local file has no
+/// owner write bit, so on a permission error the bit is added and the open retried;
+/// [`preserve_local_metadata`] restores the local mode once the file is written.
+async fn open_for_writing(
+    sftp: &SftpSession,
+    remote_path: &str,
+    flags: OpenFlags,
+) -> Result<russh_sftp::client::fs::File> {
+    let err = match sftp.open_with_flags(remote_path, flags).await {
+        Ok(file) => return Ok(file),
+        Err(e) if is_permission_denied(&e) => e,
+        Err(e) => return Err(e.into()),
+    };
+    let Some(mode) = sftp
+        .metadata(remote_path)
+        .await
+        .ok()
+        .and_then(|meta| meta.permissions)
+        .filter(|mode| mode & 0o200 == 0)
+    else {
+        return Err(err.into());
+    };
+    let attrs = FileAttributes {
+        permissions: Some((mode & 0o7777) | 0o200),
+        ..FileAttributes::empty()
+    };
+    sftp.set_metadata(remote_path, attrs)
+        .await
+        .with_context(|| format!("making {remote_path} writable"))?;
+    Ok(sftp.open_with_flags(remote_path, flags).await?)
 }
 
-async fn download_dir(sftp: &SftpSession, remote_dir: &str, local_dir: &Path) -> Result<()> {
-    let mut stack: Vec<(String, PathBuf)> = vec![(
-        remote_dir.trim_end_matches('/').to_string(),
-        local_dir.to_path_buf(),
-    )];
+/// SFTP attributes carrying the mtime, atime and permission bits of a local file. Built on
+/// [`FileAttributes::empty`]: the `Default` attributes carry a zero size and owner, which
+/// a setstat would apply.
+fn local_metadata_attrs(meta: &std::fs::Metadata) -> FileAttributes {
+    use std::os::unix::fs::PermissionsExt;
+    let secs = |time: io::Result<std::time::SystemTime>| {
+        time.ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as u32)
+    };
+    let mtime = secs(meta.modified());
+    FileAttributes {
+        permissions: Some(meta.permissions().mode() & 0o7777),
+        atime: secs(meta.accessed()).or(mtime),
+        mtime,
+        ..FileAttributes::empty()
+    }
+}
 
-    while let Some((remote_base, local_base)) = stack.pop() {
-        tokiofs::create_dir_all(&local_base).await?;
-        let entries = sftp.read_dir(&remote_base).await?;
-        for entry in entries {
-            let name = entry.file_name();
-            let remote_child = format!("{}/{}", remote_base, name);
-            let local_child = local_base.join(&name);
-            let meta = entry.metadata();
-            if meta.is_dir() {
-                stack.push((remote_child, local_child));
-            } else {
-                download_file(sftp, &remote_child, &local_child).await?;
-            }
+/// Copies mtime, atime and permissions of `local_path` onto `remote_path`, so the next
+/// sync can skip the file by size and mtime. Failures are logged, not fatal: the file
+/// itself was transferred.
+async fn preserve_local_metadata(sftp: &SftpSession, local_path: &Path, remote_path: &str) {
+    let attrs = match tokiofs::metadata(local_path).await {
+        Ok(meta) => local_metadata_attrs(&meta),
+        Err(e) => {
+            log::warn!("could not stat {local_path:?} to copy its metadata: {e}");
+            return;
         }
+    };
+    if let Err(e) = sftp.set_metadata(remote_path, attrs).await {
+        log::warn!("could not set metadata on {remote_path}: {e}");
     }
-    Ok(())
 }
 
-async fn ensure_no_file_overwrite(path: &Path) -> Result<()> {
-    match tokiofs::metadata(path).await {
-        Ok(_) => Err(io::Error::new(
-            ErrorKind::AlreadyExists,
-            format!("local path exists: {}", path.display()),
-        )
-        .into()),
-        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
-        Err(e) => Err(e.into()),
+/// Downloads `file` into its partial file, appending to what an earlier, interrupted
+/// download left there, and moves it into place once complete. Returns the bytes sent.
+async fn download_file(
+    sftp: &SftpSession,
+    file: &RemoteFile,
+    progress: &TransferProgress,
+) -> Result<u64> {
+    if let Some(parent) = file.local_path.parent() {
+        tokiofs::create_dir_all(parent).await?;
     }
+    let partial = partial_path(&file.local_path);
+    let offset = resume_offset(file)?;
+    let mut rfile = sftp.open(&file.remote_path).await?;
+    let mut lfile = if offset > 0 {
+        log::debug!("resuming {} at byte {offset}", file.remote_path);
+        rfile.seek(std::io::SeekFrom::Start(offset)).await?;
+        progress.add_skipped(offset);
+        tokiofs::OpenOptions::new()
+            .append(true)
+            .open(&partial)
+            .await?
+    } else {
+        tokiofs::File::create(&partial).await?
+    };
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut sent = 0u64;
+    loop {
+        let n = rfile.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        lfile.write_all(&buf[..n]).await?;
+        sent += n as u64;
+        progress.add_sent(n as u64);
+    }
+    lfile.flush().await?;
+    drop(lfile);
+    finish_partial(file)?;
+    Ok(sent)
 }
 
-async fn ensure_no_dir_overwrite(
+/// Walk a remote directory tree, collecting the local directories to create and the
+/// files to download. Paths excluded by `filter` are left out; excluded directories
+/// are not descended into.
+async fn list_remote_files(
     sftp: &SftpSession,
     remote_dir: &str,
     local_dir: &Path,
-) -> Result<()> {
-    let mut stack: Vec<(String, PathBuf)> = vec![(
+    filter: &PathFilter,
+) -> Result<(Vec<PathBuf>, Vec<RemoteFile>)> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    let mut stack: Vec<(String, PathBuf, String)> = vec![(
         remote_dir.trim_end_matches('/').to_string(),
         local_dir.to_path_buf(),
+        String::new(),
     )];
 
-    while let Some((remote_base, local_base)) = stack.pop() {
-        match tokiofs::metadata(&local_base).await {
-            Ok(meta) => {
-                if !meta.is_dir() {
-                    return Err(io::Error::new(
-                        ErrorKind::AlreadyExists,
-                        format!("local path exists: {}", local_base.display()),
-                    )
-                    .into());
-                }
-            }
-            Err(e) if e.kind() == ErrorKind::NotFound => {}
-            Err(e) => return Err(e.into()),
-        }
-
+    while let Some((remote_base, local_base, rel_base)) = stack.pop() {
+        dirs.push(local_base.clone());
         let entries = sftp.read_dir(&remote_base).await?;
         for entry in entries {
             let name = entry.file_name();
             let remote_child = format!("{}/{}", remote_base, name);
             let local_child = local_base.join(&name);
+            let rel_child = if rel_base.is_empty() {
+                name
+            } else {
+                format!("{rel_base}/{name}")
+            };
             let meta = entry.metadata();
+            if !filter.should_include(Path::new(&rel_child), meta.is_dir()) {
+                continue;
+            }
             if meta.is_dir() {
-                stack.push((remote_child, local_child));
+                stack.push((remote_child, local_child, rel_child));
             } else {
-                ensure_no_file_overwrite(&local_child).await?;
+                files.push(RemoteFile {
+                    remote_path: remote_child,
+                    local_path: local_child,
+                    rel_path: rel_child,
+                    stamp: FileStamp {
+                        size: meta.size.unwrap_or(0),
+                        mtime: meta.mtime.map(u64::from),
+                    },
+                });
             }
         }
     }
-    Ok(())
+    Ok((dirs, files))
 }
 
 fn is_permission_denied(err: &russh_sftp::client::error::Error) -> bool {
@@ -543,3 +1468,272 @@ fn is_permission_denied(err: &russh_sftp::client::error::Error) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionManager, SftpSession};
+    use crate::ssh::SshParams;
+    use crate::ssh::delta::BlockSignature;
+    use crate::ssh::progress::TransferProgress;
+    use crate::ssh::sync::{DeltaBasis, RemoteHasher, SyncOutcome};
+    use russh_sftp::protocol::{
+        Attrs, Data, FileAttributes, Handle, OpenFlags, Status, StatusCode, Version,
+    };
+    use std::collections::HashMap;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    #[derive(Clone, Debug, Default)]
+    struct MemFile {
+        data: Vec<u8>,
+        mode: u32,
+        mtime: Option<u32>,
+    }
+
+    /// SFTP server over a map of files that, like a server not running as root, refuses
+    /// to open files for writing without the owner write bit.
+    #[derive(Default)]
+    struct MemSftp {
+        files: Arc<Mutex<HashMap<String, MemFile>>>,
+        handles: HashMap<String, String>,
+    }
+
+    impl MemSftp {
+        fn ok(id: u32) -> Status {
+            Status {
+                id,
+                status_code: StatusCode::Ok,
+                error_message: "Ok".to_string(),
+                language_tag: "en-US".to_string(),
+            }
+        }
+
+        fn attrs(id: u32, file: &MemFile) -> Attrs {
+            let mut attrs = FileAttributes::empty();
+            attrs.size = Some(file.data.len() as u64);
+            attrs.permissions = Some(0o100000 | file.mode);
+            attrs.mtime = file.mtime;
+            attrs.atime = file.mtime;
+            Attrs { id, attrs }
+        }
+
+        fn set_attrs(file: &mut MemFile, attrs: &FileAttributes) {
+            if let Some(size) = attrs.size {
+                file.data.resize(size as usize, 0);
+            }
+            if let Some(mode) = attrs.permissions {
+                file.mode = mode & 0o7777;
+            }
+            if attrs.mtime.is_some() {
+                file.mtime = attrs.mtime;
+            }
+        }
+
+        fn path(&self, handle: &str) -> Result<String, StatusCode> {
+            self.handles.get(handle).cloned().ok_or(StatusCode::Failure)
+        }
+    }
+
+    impl russh_sftp::server::Handler for MemSftp {
+        type Error = StatusCode;
+
+        fn unimplemented(&self) -> Self::Error {
+            StatusCode::OpUnsupported
+        }
+
+        async fn init(
+            &mut self,
+            _version: u32,
+            _extensions: HashMap<String, String>,
+        ) -> Result<Version, Self::Error> {
+            Ok(Version::new())
+        }
+
+        async fn open(
+            &mut self,
+            id: u32,
+            filename: String,
+            pflags: OpenFlags,
+            attrs: FileAttributes,
+        ) -> Result<Handle, Self::Error> {
+            let mut files = self.files.lock().unwrap();
+            match files.get_mut(&filename) {
+                Some(file) if pflags.contains(OpenFlags::WRITE) && file.mode & 0o200 == 0 => {
+                    return Err(StatusCode::PermissionDenied);
+                }
+                Some(file) if pflags.contains(OpenFlags::TRUNCATE) => file.data.clear(),
+                Some(_) => {}
+                None if pflags.contains(OpenFlags::CREATE) => {
+                    let mode = attrs.permissions.unwrap_or(0o644) & 0o7777;
+                    files.insert(
+                        filename.clone(),
+                        MemFile {
+                            mode,
+                            ..MemFile::default()
+                        },
+                    );
+                }
+                None => return Err(StatusCode::NoSuchFile),
+            }
+            let handle = format!("h{}", self.handles.len());
+            self.handles.insert(handle.clone(), filename);
+            Ok(Handle { id, handle })
+        }
+
+        async fn close(&mut self, id: u32, _handle: String) -> Result<Status, Self::Error> {
+            Ok(Self::ok(id))
+        }
+
+        async fn read(
+            &mut self,
+            id: u32,
+            handle: String,
+            offset: u64,
+            len: u32,
+        ) -> Result<Data, Self::Error> {
+            let path = self.path(&handle)?;
+            let files = self.files.lock().unwrap();
+            let data = &files[&path].data;
+            let start = offset as usize;
+            if start >= data.len() {
+                return Err(StatusCode::Eof);
+            }
+            let end = (start + len as usize).min(data.len());
+            Ok(Data {
+                id,
+                data: data[start..end].to_vec(),
+            })
+        }
+
+        async fn write(
+            &mut self,
+            id: u32,
+            handle: String,
+            offset: u64,
+            data: Vec<u8>,
+        ) -> Result<Status, Self::Error> {
+            let path = self.path(&handle)?;
+            let mut files = self.files.lock().unwrap();
+            let file = &mut files.get_mut(&path).unwrap().data;
+            let end = offset as usize + data.len();
+            if file.len() < end {
+                file.resize(end, 0);
+            }
+            file[offset as usize..end].copy_from_slice(&data);
+            Ok(Self::ok(id))
+        }
+
+        async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+            let files = self.files.lock().unwrap();
+            let file = files.get(&path).ok_or(StatusCode::NoSuchFile)?;
+            Ok(Self::attrs(id, file))
+        }
+
+        async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+            let path = self.path(&handle)?;
+            self.stat(id, path).await
+        }
+
+        async fn setstat(
+            &mut self,
+            id: u32,
+            path: String,
+            attrs: FileAttributes,
+        ) -> Result<Status, Self::Error> {
+            let mut files = self.files.lock().unwrap();
+            let file = files.get_mut(&path).ok_or(StatusCode::NoSuchFile)?;
+            Self::set_attrs(file, &attrs);
+            Ok(Self::ok(id))
+        }
+
+        async fn fsetstat(
+            &mut self,
+            id: u32,
+            handle: String,
+            attrs: FileAttributes,
+        ) -> Result<Status, Self::Error> {
+            let path = self.path(&handle)?;
+            self.setstat(id, path, attrs).await
+        }
+    }
+
+    async fn mem_sftp() -> (SftpSession, Arc<Mutex<HashMap<String, MemFile>>>) {
+        let (client, server) = tokio::io::duplex(256 * 1024);
+        let handler = MemSftp::default();
+        let files = Arc::clone(&handler.files);
+        russh_sftp::server::run(server, handler).await;
+        (SftpSession::new(client).await.unwrap(), files)
+    }
+
+    fn manager() -> SessionManager {
+        SessionManager::new(SshParams {
+            addr: "127.0.0.1:22".parse().unwrap(),
+            username: "test".to_string(),
+            identity_path: None,
+            ki_submethods: None,
+            keepalive_secs: 1,
+        })
+    }
+
+    fn write_read_only(path: &Path, content: &str) {
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        fs::write(path, content).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o444)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_only_files_can_be_synced_again() {
+        let tmp = tempdir().unwrap();
+        let local = tmp.path().join("input.dat");
+        let remote = "/run/input.dat";
+        let (sftp, files) = mem_sftp().await;
+        let mgr = manager();
+        let progress = TransferProgress::new(0, 0);
+        let remote_file = || files.lock().unwrap()[remote].clone();
+
+        write_read_only(&local, "first");
+        let outcome = mgr
+            .sync_one_file_with_sftp(&sftp, &local, remote, 4, &progress)
+            .await
+            .unwrap();
+        assert_eq!(outcome, SyncOutcome::Uploaded);
+        assert_eq!(remote_file().mode, 0o444);
+
+        // A full upload truncates the read-only remote copy.
+        write_read_only(&local, "second version");
+        let outcome = mgr
+            .sync_one_file_with_sftp(&sftp, &local, remote, 4, &progress)
+            .await
+            .unwrap();
+        assert_eq!(outcome, SyncOutcome::NeedsDelta { same_size: false });
+        mgr.delta_sync_one_file_with_sftp(&sftp, &local, remote, None, 4, &progress)
+            .await
+            .unwrap();
+        assert_eq!(remote_file().data, b"second version");
+        assert_eq!(remote_file().mode, 0o444);
+
+        // Block-wise patching opens it for random-access writes.
+        write_read_only(&local, "third");
+        let blocks: Vec<BlockSignature> = (0..4)
+            .map(|i| BlockSignature {
+                len: 4,
+                strong: format!("stale-{i}"),
+                weak: None,
+            })
+            .collect();
+        let basis = DeltaBasis {
+            hasher: &RemoteHasher::Shell,
+            blocks: &blocks,
+        };
+        mgr.delta_sync_one_file_with_sftp(&sftp, &local, remote, Some(basis), 4, &progress)
+            .await
+            .unwrap();
+        assert_eq!(remote_file().data, b"third");
+        assert_eq!(remote_file().mode, 0o444);
+    }
+}
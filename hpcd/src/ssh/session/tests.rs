@@ -2,8 +2,9 @@
 // Copyright (C) 2026 Alex Sizykh
 
 use super::{SessionManager, SessionManagerTestHooks};
-use crate::ssh::sync::{SyncFilterRule, SyncOptions, sync_dir_with_executor};
+use crate::ssh::sync::{SymlinkPolicy, SyncFilterRule, SyncOptions, sync_dir_with_executor};
 use anyhow::Result;
+use std::collections::HashSet;
 use std::fs;
 use std::net::SocketAddr;
 use std::path::Path;
@@ -49,17 +50,15 @@ async fn session_manager_executor_uses_test_hooks() {
     });
 
     let sync_calls = Arc::clone(&calls);
-    let sync_one_file = Arc::new(
-        move |_local: &Path, remote: &str, _session_id: &str, _block_size: usize| {
-            let sync_calls = Arc::clone(&sync_calls);
-            let remote = remote.to_string();
-            let fut: crate::ssh::sync::BoxFuture<'static, Result<()>> = Box::pin(async move {
-                sync_calls.lock().unwrap().push(format!("sync:{remote}"));
-                Ok(())
-            });
-            fut
-        },
-    );
+    let sync_one_file = Arc::new(move |_local: &Path, remote: &str, _block_size: usize| {
+        let sync_calls = Arc::clone(&sync_calls);
+        let remote = remote.to_string();
+        let fut: crate::ssh::sync::BoxFuture<'static, Result<()>> = Box::pin(async move {
+            sync_calls.lock().unwrap().push(format!("sync:{remote}"));
+            Ok(())
+        });
+        fut
+    });
 
     let hooks = SessionManagerTestHooks {
         ensure_connected,
@@ -83,6 +82,14 @@ async fn session_manager_executor_uses_test_hooks() {
         block_size: None,
         parallelism: Some(1),
         filters: &[] as &[SyncFilterRule],
+        ignore_files: false,
+        compress: false,
+        checksum: false,
+        symlinks: SymlinkPolicy::Skip,
+        delete: false,
+        protect: &[],
+        uploaded: &HashSet::new(),
+        cache_dir: None,
     };
 
     sync_dir_with_executor(&manager, root, "/remote", options, &evt_tx, mfa_rx)
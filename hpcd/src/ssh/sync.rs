@@ -1,16 +1,26 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 // Copyright (C) 2026 Alex Sizykh
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use futures_util::StreamExt;
-use proto::{MfaAnswer, SubmitStreamEvent};
+use proto::{MfaAnswer, SubmitStreamEvent, submit_stream_event};
 use rand::{Rng, distr::Alphanumeric};
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use tokio::sync::mpsc;
 
-use super::sync_plan::build_sync_plan;
+use super::bulk::{plan_prefers_bulk, split_bulk_items};
+use super::cache::{CacheEntry, cache_candidates};
+use super::delta::BlockSignature;
+use super::progress::{TransferProgress, report_progress};
+use super::retrieve::VerifiedFile;
+use super::sync_plan::{
+    SymlinkAction, SymlinkEntry, SyncItem, build_sync_plan, plan_remote_deletions,
+    rel_path_to_slash,
+};
+use super::utils::local_file_sha256;
 
 #[derive(Clone, Copy, Debug)]
 pub enum SyncFilterAction {
@@ -24,11 +34,97 @@ pub struct SyncFilterRule {
     pub pattern: String,
 }
 
+/// How a sync treats symbolic links under the local root.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Leave links out of the upload, with a warning for each.
+    #[default]
+    Skip,
+    /// Create the same links on the remote, targets unchanged.
+    Preserve,
+    /// Upload whatever the links point to as regular files and directories.
+    Follow,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct SyncOptions<'a> {
     pub block_size: Option<usize>,
     pub parallelism: Option<usize>,
     pub filters: &'a [SyncFilterRule],
+    /// Honour `.gitignore` files and `.hpcignore` for paths not decided by `filters`.
+    pub ignore_files: bool,
+    /// zstd-compress bulk tar uploads when the remote has `zstd`.
+    pub compress: bool,
+    /// Compare SHA-256 checksums of same-sized files whose mtimes differ, and only touch
+    /// up the remote metadata when the contents match.
+    pub checksum: bool,
+    pub symlinks: SymlinkPolicy,
+    /// Delete remote files that are not part of the plan and that `uploaded` lists, except
+    /// excluded and protected ones.
+    pub delete: bool,
+    /// Extra protected patterns, relative to the remote root, for `delete`.
+    pub protect: &'a [String],
+    /// Remote paths earlier syncs uploaded, the only files `delete` may remove.
+    pub uploaded: &'a HashSet<String>,
+    /// Content-addressed store to take large new files from and add uploaded ones to.
+    pub cache_dir: Option<&'a str>,
+}
+
+/// What a sync would do with one planned file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncItemStatus {
+    /// The file does not exist on the remote yet.
+    New,
+    /// The remote copy differs in size or mtime and would be (delta-)uploaded.
+    Changed,
+    /// The remote copy would be left alone.
+    Unchanged,
+    /// The file only exists on the remote and would be deleted by a mirror sync.
+    Delete,
+}
+
+/// One planned file as classified by a dry-run against the remote.
+#[derive(Clone, Debug)]
+pub struct SyncPreviewItem {
+    /// Path relative to the local root.
+    pub rel_path: PathBuf,
+    /// Local size in bytes, or the remote size for [`SyncItemStatus::Delete`].
+    pub size: u64,
+    pub status: SyncItemStatus,
+}
+
+/// Dry-run result: every planned file, and every symbolic link with what the sync would
+/// do with it.
+#[derive(Clone, Debug)]
+pub struct SyncPreview {
+    pub items: Vec<SyncPreviewItem>,
+    pub symlinks: Vec<SymlinkEntry>,
+}
+
+/// Size and modification time (seconds since the epoch) of one side of a planned file.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FileStamp {
+    pub size: u64,
+    pub mtime: Option<u64>,
+}
+
+/// Classifies a planned file the way `sync_one_file` treats it: a remote copy with the
+/// same size and mtime is skipped, any other existing copy is uploaded.
+pub(crate) fn classify_sync_item(local: FileStamp, remote: Option<FileStamp>) -> SyncItemStatus {
+    let Some(remote) = remote else {
+        return SyncItemStatus::New;
+    };
+    if stamps_match(local, remote) {
+        SyncItemStatus::Unchanged
+    } else {
+        SyncItemStatus::Changed
+    }
+}
+
+/// Whether a remote copy counts as up to date: same size and same mtime. Uploads copy the
+/// local mtime to the remote, so anything else means one side changed since.
+pub(crate) fn stamps_match(local: FileStamp, remote: FileStamp) -> bool {
+    local.size == remote.size && local.mtime.is_some() && local.mtime == remote.mtime
 }
 
 pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
@@ -45,14 +141,179 @@ pub(crate) trait SyncExecutor: Sync {
     /// Ensure the given remote directory exists.
     fn ensure_remote_dir<'a>(&'a self, remote_dir: &'a str) -> BoxFuture<'a, Result<()>>;
 
-    /// Sync one local file to its remote destination.
+    /// Size and mtime of every regular file under `remote_dir`, keyed by slash-separated
+    /// path relative to it.
+    fn remote_file_stamps<'a>(
+        &'a self,
+        remote_dir: &'a str,
+    ) -> BoxFuture<'a, Result<HashMap<String, FileStamp>>>;
+
+    /// Upload `items` as one tar stream extracted into `remote_dir`, counting each file in
+    /// `progress`. On error nothing is counted and the items can be retried one by one.
+    fn bulk_upload<'a>(
+        &'a self,
+        remote_dir: &'a str,
+        items: &'a [SyncItem],
+        compress: bool,
+        progress: &'a TransferProgress,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// SHA-256 of each of `remote_paths`, keyed by path; unreadable files are left out.
+    fn remote_checksums<'a>(
+        &'a self,
+        remote_paths: &'a [String],
+    ) -> BoxFuture<'a, Result<HashMap<String, String>>>;
+
+    /// Copy mtime, atime and permissions of a local file onto its remote copy, leaving
+    /// write bits off where the remote copy has none.
+    fn copy_file_metadata<'a>(
+        &'a self,
+        local_path: &'a Path,
+        remote_path: &'a str,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Delete the given remote files; missing ones are not an error.
+    fn remove_remote_files<'a>(&'a self, remote_paths: &'a [String]) -> BoxFuture<'a, Result<()>>;
+
+    /// Make `remote_path` a symbolic link to `target`, replacing a file or link there.
+    fn create_remote_symlink<'a>(
+        &'a self,
+        target: &'a Path,
+        remote_path: &'a str,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Place the stored blobs of `entries` at their remote paths, returning the paths placed.
+    fn link_cached_files<'a>(
+        &'a self,
+        cache_dir: &'a str,
+        entries: &'a [CacheEntry],
+    ) -> BoxFuture<'a, Result<HashSet<String>>>;
+
+    /// Add the uploaded files of `entries` to the store, keeping blobs that already exist.
+    fn store_cached_files<'a>(
+        &'a self,
+        cache_dir: &'a str,
+        entries: &'a [CacheEntry],
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Sync one local file to its remote destination, counting sent and skipped bytes.
+    /// Files whose remote copy is stale are not touched; they are reported as
+    /// [`SyncOutcome::NeedsDelta`] so their remote block hashes can be fetched in bulk.
     fn sync_one_file<'a>(
         &'a self,
         local_path: &'a Path,
         remote_path: &'a str,
+        block_size: usize,
+        progress: &'a TransferProgress,
+    ) -> BoxFuture<'a, Result<SyncOutcome>>;
+
+    /// Make the block hashing helper available on the remote for this sync session.
+    fn prepare_remote_hasher<'a>(
+        &'a self,
         session_id: &'a str,
+    ) -> BoxFuture<'a, Result<RemoteHasher>>;
+
+    /// Block signatures of every file in `remote_paths`, in order; `None` where hashing failed.
+    fn remote_block_hashes<'a>(
+        &'a self,
+        hasher: &'a RemoteHasher,
+        remote_paths: &'a [String],
+        block_size: usize,
+    ) -> BoxFuture<'a, Result<Vec<Option<Vec<BlockSignature>>>>>;
+
+    /// Bring a stale remote file up to date against `basis`, or upload it whole when no
+    /// signature is available. Also re-sends files that failed verification.
+    fn delta_sync_one_file<'a>(
+        &'a self,
+        local_path: &'a Path,
+        remote_path: &'a str,
+        basis: Option<DeltaBasis<'a>>,
         block_size: usize,
+        progress: &'a TransferProgress,
     ) -> BoxFuture<'a, Result<()>>;
+
+    /// Remove whatever `prepare_remote_hasher` left on the remote.
+    fn cleanup_remote_hasher<'a>(&'a self, hasher: &'a RemoteHasher) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Result of the first pass over a planned file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SyncOutcome {
+    /// The remote copy already matched; nothing left to do.
+    Done,
+    /// Uploaded in full; checked against the local SHA-256 once all transfers are done.
+    Uploaded,
+    /// The remote copy is stale and should be patched block by block. `same_size` marks
+    /// copies that only differ by mtime and may turn out identical by checksum.
+    NeedsDelta { same_size: bool },
+}
+
+/// Remote signature a stale file is patched against.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DeltaBasis<'a> {
+    pub hasher: &'a RemoteHasher,
+    pub blocks: &'a [BlockSignature],
+}
+
+/// How block hashes are computed on the remote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RemoteHasher {
+    /// Helper script uploaded to `script_path`, run with `interpreter`. Also applies
+    /// rolling-checksum deltas.
+    Python {
+        interpreter: String,
+        script_path: String,
+    },
+    /// `dd` + `sha256sum` loop for clusters without Python.
+    Shell,
+}
+
+/// How often a file is transferred before a checksum mismatch is reported as an error.
+pub(crate) const TRANSFER_ATTEMPTS: u32 = 3;
+
+/// Drops delta candidates whose content matches the remote copy by SHA-256, bringing the
+/// remote metadata in line so the next sync skips them by size and mtime.
+async fn skip_identical_by_checksum<E: SyncExecutor + ?Sized>(
+    executor: &E,
+    candidates: Vec<(SyncItem, bool)>,
+    progress: &TransferProgress,
+) -> Vec<SyncItem> {
+    let same_size: Vec<String> = candidates
+        .iter()
+        .filter(|(_, same_size)| *same_size)
+        .map(|(item, _)| item.remote_path.clone())
+        .collect();
+    if same_size.is_empty() {
+        return candidates.into_iter().map(|(item, _)| item).collect();
+    }
+    let remote = executor
+        .remote_checksums(&same_size)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("remote checksums failed: {e:#}; syncing by size and mtime only");
+            HashMap::new()
+        });
+    let mut rest = Vec::with_capacity(candidates.len());
+    for (item, same_size) in candidates {
+        if same_size
+            && let Some(remote_hash) = remote.get(&item.remote_path)
+            && local_file_sha256(&item.local_path).await.ok().as_ref() == Some(remote_hash)
+        {
+            log::debug!("{} is identical by checksum", item.remote_path);
+            if let Err(e) = executor
+                .copy_file_metadata(&item.local_path, &item.remote_path)
+                .await
+            {
+                log::debug!("could not update metadata of {}: {e:#}", item.remote_path);
+            }
+            let size = std::fs::metadata(&item.local_path).map_or(0, |m| m.len());
+            progress.add_skipped(size);
+            progress.finish_file();
+            continue;
+        }
+        rest.push(item);
+    }
+    rest
 }
 
 pub(crate) async fn sync_dir_with_executor<E, P>(
@@ -62,7 +323,7 @@ pub(crate) async fn sync_dir_with_executor<E, P>(
     options: SyncOptions<'_>,
     evt_tx: &mpsc::Sender<Result<SubmitStreamEvent, tonic::Status>>,
     mut mfa_rx: mpsc::Receiver<MfaAnswer>,
-) -> Result<()>
+) -> Result<Vec<VerifiedFile>>
 where
     E: SyncExecutor + ?Sized,
     P: AsRef<Path>,
@@ -71,7 +332,13 @@ where
     let parallelism = options.parallelism.unwrap_or(8);
 
     executor.ensure_connected(evt_tx, &mut mfa_rx).await?;
-    let plan = build_sync_plan(local_dir, remote_dir, options.filters)?;
+    let plan = build_sync_plan(
+        local_dir,
+        remote_dir,
+        options.filters,
+        options.ignore_files,
+        options.symlinks,
+    )?;
 
     log::info!("making sure the remote directory exists");
     executor.ensure_remote_dir(remote_dir).await?;
@@ -85,27 +352,183 @@ where
         .map(char::from)
         .collect();
 
-    let results = futures::stream::iter(plan.items.into_iter().map(|item| {
-        let session_id_clone = session_id.clone();
-        async move {
-            executor
-                .sync_one_file(
-                    &item.local_path,
-                    &item.remote_path,
-                    &session_id_clone,
-                    block_size,
-                )
+    let bytes_total = plan
+        .items
+        .iter()
+        .filter_map(|item| std::fs::metadata(&item.local_path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    let progress = TransferProgress::new(plan.items.len() as u64, bytes_total);
+    let progress = &progress;
+
+    // Listed before anything is uploaded, so that the listing only holds files that were
+    // already there.
+    let mut remote_listing = None;
+    if options.delete || options.cache_dir.is_some() || plan_prefers_bulk(&plan.items) {
+        remote_listing = Some(executor.remote_file_stamps(remote_dir).await);
+    }
+    let cache_entries = match (options.cache_dir, &remote_listing) {
+        (Some(_), Some(Ok(remote))) => cache_candidates(&plan.items, remote).await,
+        (Some(_), Some(Err(e))) => {
+            log::warn!("could not list {remote_dir} for the data cache: {e:#}");
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    let cache_entries = &cache_entries;
+    let deletions = match (&remote_listing, options.delete) {
+        (Some(Ok(remote)), true) => Some(plan_remote_deletions(
+            &plan,
+            remote.keys().map(String::as_str),
+            options.filters,
+            options.ignore_files,
+            options.protect,
+            options.uploaded,
+        )),
+        (Some(Err(e)), true) => Some(Err(anyhow::anyhow!(
+            "could not list {remote_dir} for deletion: {e:#}"
+        ))),
+        _ => None,
+    };
+
+    let transfers = async {
+        let mut errs = Vec::new();
+        // Everything written to the remote, checked against the local files at the end.
+        let mut written = Vec::new();
+        for link in &plan.symlinks {
+            if link.action != SymlinkAction::Recreate {
+                continue;
+            }
+            if let Err(e) = executor
+                .create_remote_symlink(&link.target, &link.remote_path)
                 .await
+            {
+                errs.push(e.context(format!("symlink {}", link.remote_path)));
+            }
         }
-    }))
-    .buffer_unordered(parallelism)
-    .collect::<Vec<_>>()
-    .await;
-    let mut errs = Vec::new();
-    for res in results {
-        if let Err(e) = res {
-            errs.push(e);
+        let mut items = plan.items;
+        if let Some(cache_dir) = options.cache_dir
+            && !cache_entries.is_empty()
+        {
+            match executor.link_cached_files(cache_dir, cache_entries).await {
+                Ok(linked) if !linked.is_empty() => {
+                    log::info!("placed {} files from the data cache", linked.len());
+                    let (cached, rest): (Vec<_>, Vec<_>) = items
+                        .into_iter()
+                        .partition(|item| linked.contains(&item.remote_path));
+                    items = rest;
+                    for item in cached {
+                        if let Err(e) = executor
+                            .copy_file_metadata(&item.local_path, &item.remote_path)
+                            .await
+                        {
+                            log::debug!("could not update metadata of {}: {e:#}", item.remote_path);
+                        }
+                        let size = std::fs::metadata(&item.local_path).map_or(0, |m| m.len());
+                        progress.add_skipped(size);
+                        progress.finish_file();
+                        written.push(item);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("data cache unavailable: {e:#}; uploading all files"),
+            }
+        }
+        if plan_prefers_bulk(&items)
+            && let Some(listing) = remote_listing
+        {
+            match listing {
+                Ok(remote) => {
+                    let (bulk, rest) = split_bulk_items(items, &remote);
+                    items = rest;
+                    if !bulk.is_empty() {
+                        log::info!("uploading {} new files as a tar stream", bulk.len());
+                        match executor
+                            .bulk_upload(remote_dir, &bulk, options.compress, progress)
+                            .await
+                        {
+                            Ok(()) => written.extend(bulk),
+                            Err(e) => {
+                                log::warn!("bulk upload failed: {e:#}; uploading files one by one");
+                                items.extend(bulk);
+                            }
+                        }
+                    }
+                }
+                Err(e) => log::debug!("could not list {remote_dir} for bulk upload: {e:#}"),
+            }
+        }
+        let first_pass = futures::stream::iter(items.into_iter().map(|item| async move {
+            progress.start_file(&rel_path_to_slash(&item.rel_path));
+            let res = executor
+                .sync_one_file(&item.local_path, &item.remote_path, block_size, progress)
+                .await;
+            (item, res)
+        }))
+        .buffer_unordered(parallelism)
+        .collect::<Vec<_>>()
+        .await;
+        let mut candidates = Vec::new();
+        for (item, res) in first_pass {
+            match res {
+                Ok(SyncOutcome::Done) => progress.finish_file(),
+                Ok(SyncOutcome::Uploaded) => {
+                    progress.finish_file();
+                    written.push(item);
+                }
+                Ok(SyncOutcome::NeedsDelta { same_size }) => candidates.push((item, same_size)),
+                Err(e) => errs.push(e),
+            }
+        }
+        let candidates = if options.checksum {
+            skip_identical_by_checksum(executor, candidates, progress).await
+        } else {
+            candidates.into_iter().map(|(item, _)| item).collect()
+        };
+        let (patched, patch_errs) = patch_stale_files(
+            executor,
+            candidates,
+            &session_id,
+            block_size,
+            parallelism,
+            progress,
+        )
+        .await;
+        written.extend(patched);
+        errs.extend(patch_errs);
+
+        let (verified, verify_errs) = verify_uploads(executor, written, block_size).await;
+        errs.extend(verify_errs);
+        (errs, verified)
+    };
+    let (mut errs, verified) =
+        report_progress(progress, transfers, evt_tx, |p| SubmitStreamEvent {
+            event: Some(submit_stream_event::Event::TransferProgress(p)),
+        })
+        .await;
+    if let Some(cache_dir) = options.cache_dir
+        && errs.is_empty()
+        && !cache_entries.is_empty()
+        && let Err(e) = executor.store_cached_files(cache_dir, cache_entries).await
+    {
+        log::warn!("could not add uploaded files to the data cache: {e:#}");
+    }
+    match deletions {
+        // Never leave the remote with fewer files than before when the upload failed.
+        Some(_) if !errs.is_empty() => log::warn!("upload failed; not deleting remote files"),
+        Some(Ok(deletions)) if !deletions.is_empty() => {
+            log::info!("deleting {} remote files missing locally", deletions.len());
+            let remote_root = remote_dir.trim_end_matches('/');
+            let remote_paths: Vec<String> = deletions
+                .iter()
+                .map(|rel| format!("{remote_root}/{rel}"))
+                .collect();
+            if let Err(e) = executor.remove_remote_files(&remote_paths).await {
+                errs.push(e);
+            }
         }
+        Some(Err(e)) => errs.push(e),
+        Some(Ok(_)) | None => {}
     }
     if !errs.is_empty() {
         // Build a helpful combined error message.
@@ -116,27 +539,245 @@ where
         }
         anyhow::bail!("sync_dir encountered {} error(s):\n{}", errs.len(), msg);
     }
-    Ok(())
+    Ok(verified)
+}
+
+/// Brings stale remote files up to date with one helper and as few hashing execs as
+/// possible. Returns the files that were patched and the errors of the others.
+async fn patch_stale_files<E: SyncExecutor + ?Sized>(
+    executor: &E,
+    mut candidates: Vec<SyncItem>,
+    session_id: &str,
+    block_size: usize,
+    parallelism: usize,
+    progress: &TransferProgress,
+) -> (Vec<SyncItem>, Vec<anyhow::Error>) {
+    if candidates.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    candidates.sort_by(|a, b| a.remote_path.cmp(&b.remote_path));
+
+    let remote_paths: Vec<String> = candidates
+        .iter()
+        .map(|item| item.remote_path.clone())
+        .collect();
+    let hasher = match executor.prepare_remote_hasher(session_id).await {
+        Ok(hasher) => Some(hasher),
+        Err(e) => {
+            log::warn!("remote hashing unavailable: {e:#}; falling back to full uploads");
+            None
+        }
+    };
+    let hashes = match &hasher {
+        Some(hasher) => executor
+            .remote_block_hashes(hasher, &remote_paths, block_size)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("remote hashing failed: {e:#}; falling back to full uploads");
+                vec![None; remote_paths.len()]
+            }),
+        None => vec![None; remote_paths.len()],
+    };
+
+    let hasher_ref = hasher.as_ref();
+    let results = futures::stream::iter(candidates.into_iter().zip(hashes).map(
+        |(item, remote_hashes)| async move {
+            progress.start_file(&rel_path_to_slash(&item.rel_path));
+            let res = executor
+                .delta_sync_one_file(
+                    &item.local_path,
+                    &item.remote_path,
+                    hasher_ref
+                        .zip(remote_hashes.as_deref())
+                        .map(|(hasher, blocks)| DeltaBasis { hasher, blocks }),
+                    block_size,
+                    progress,
+                )
+                .await;
+            if res.is_ok() {
+                progress.finish_file();
+            }
+            res.map(|()| item)
+        },
+    ))
+    .buffer_unordered(parallelism)
+    .collect::<Vec<_>>()
+    .await;
+
+    if let Some(hasher) = &hasher
+        && let Err(e) = executor.cleanup_remote_hasher(hasher).await
+    {
+        log::debug!("could not clean up remote hasher: {e:#}");
+    }
+    let mut patched = Vec::with_capacity(results.len());
+    let mut errs = Vec::new();
+    for res in results {
+        match res {
+            Ok(item) => patched.push(item),
+            Err(e) => errs.push(e),
+        }
+    }
+    (patched, errs)
+}
+
+/// Compares every file the sync wrote with the SHA-256 of its local copy, hashing the
+/// remote copies in one batch per attempt, and uploads mismatching files again in full.
+/// Files the remote cannot hash are left unverified and out of the result.
+async fn verify_uploads<E: SyncExecutor + ?Sized>(
+    executor: &E,
+    written: Vec<SyncItem>,
+    block_size: usize,
+) -> (Vec<VerifiedFile>, Vec<anyhow::Error>) {
+    let mut verified = Vec::with_capacity(written.len());
+    let mut errs = Vec::new();
+    let mut pending = Vec::with_capacity(written.len());
+    for item in written {
+        match local_file_sha256(&item.local_path).await {
+            Ok(hash) => pending.push((item, hash)),
+            Err(e) => errs.push(e.context(format!("hashing {}", item.local_path.display()))),
+        }
+    }
+    // Re-sent bytes are not counted; the progress already reports these files as done.
+    let resend_progress = TransferProgress::new(0, 0);
+    for attempt in 1..=TRANSFER_ATTEMPTS {
+        if pending.is_empty() {
+            break;
+        }
+        let remote_paths: Vec<String> = pending
+            .iter()
+            .map(|(item, _)| item.remote_path.clone())
+            .collect();
+        let remote = match executor.remote_checksums(&remote_paths).await {
+            Ok(remote) => remote,
+            Err(e) => {
+                errs.push(e.context("verifying uploads"));
+                return (verified, errs);
+            }
+        };
+        let mut mismatched = Vec::new();
+        for (item, hash) in pending {
+            match remote.get(&item.remote_path) {
+                None => log::debug!("could not hash {}; upload not verified", item.remote_path),
+                Some(remote_hash) if *remote_hash == hash => {
+                    let size = std::fs::metadata(&item.local_path).map_or(0, |m| m.len());
+                    verified.push(VerifiedFile {
+                        remote_path: item.remote_path,
+                        local_path: item.local_path,
+                        size,
+                        sha256: hash,
+                    });
+                }
+                Some(_) => mismatched.push((item, hash)),
+            }
+        }
+        if mismatched.is_empty() || attempt == TRANSFER_ATTEMPTS {
+            pending = mismatched;
+            break;
+        }
+        log::warn!(
+            "{} uploaded files do not match their local copy (attempt {attempt})",
+            mismatched.len()
+        );
+        pending = Vec::with_capacity(mismatched.len());
+        for (item, hash) in mismatched {
+            match executor
+                .delta_sync_one_file(
+                    &item.local_path,
+                    &item.remote_path,
+                    None,
+                    block_size,
+                    &resend_progress,
+                )
+                .await
+            {
+                Ok(()) => pending.push((item, hash)),
+                Err(e) => errs.push(e),
+            }
+        }
+    }
+    for (item, _) in pending {
+        errs.push(anyhow!(
+            "{} does not match {} after {TRANSFER_ATTEMPTS} uploads",
+            item.remote_path,
+            item.local_path.display()
+        ));
+    }
+    (verified, errs)
+}
+
+#[cfg(test)]
+mod classify_tests {
+    use super::{FileStamp, SyncItemStatus, classify_sync_item};
+
+    fn stamp(size: u64, mtime: Option<u64>) -> FileStamp {
+        FileStamp { size, mtime }
+    }
+
+    #[test]
+    fn classify_sync_item_requires_equal_size_and_mtime() {
+        let local = stamp(10, Some(100));
+        assert_eq!(classify_sync_item(local, None), SyncItemStatus::New);
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(10, Some(100)))),
+            SyncItemStatus::Unchanged
+        );
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(3, Some(200)))),
+            SyncItemStatus::Changed
+        );
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(10, Some(200)))),
+            SyncItemStatus::Changed
+        );
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(11, Some(100)))),
+            SyncItemStatus::Changed
+        );
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(10, Some(99)))),
+            SyncItemStatus::Changed
+        );
+        assert_eq!(
+            classify_sync_item(local, Some(stamp(10, None))),
+            SyncItemStatus::Changed
+        );
+    }
 }
 
 #[cfg(test)]
 mod executor_tests {
     use super::{
-        MfaAnswer, SubmitStreamEvent, SyncExecutor, SyncFilterAction, SyncFilterRule, SyncOptions,
-        sync_dir_with_executor,
+        BlockSignature, DeltaBasis, FileStamp, MfaAnswer, RemoteHasher, SubmitStreamEvent,
+        SymlinkPolicy, SyncExecutor, SyncFilterAction, SyncFilterRule, SyncItem, SyncOptions,
+        SyncOutcome, TransferProgress, sync_dir_with_executor,
     };
+    use crate::ssh::bulk::BULK_MIN_FILES;
+    use crate::ssh::cache::{CACHE_MIN_FILE_SIZE, CacheEntry};
     use anyhow::{Result, anyhow};
-    use std::collections::HashSet;
+    use sha2::{Digest, Sha256};
+    use std::collections::{HashMap, HashSet};
     use std::fs;
     use std::path::Path;
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
     use tokio::sync::mpsc;
 
+    /// What every remote file hashes to in [`FakeExecutor::remote_checksums`].
+    const REMOTE_CONTENT: &[u8] = b"same";
+
     #[derive(Clone, Default)]
     struct FakeExecutor {
         calls: Arc<Mutex<Vec<String>>>,
         fail_on: HashSet<String>,
+        stale: HashSet<String>,
+        /// Blobs in the data cache, by hash.
+        cached: HashMap<String, Vec<u8>>,
+        /// What the files written so far hash to on the remote.
+        written: Arc<Mutex<HashMap<String, String>>>,
+        /// Remote paths whose first write comes out garbled.
+        corrupt: HashSet<String>,
+        /// Writes leave the remote files as they were.
+        lose_writes: bool,
     }
 
     impl FakeExecutor {
@@ -144,12 +785,33 @@ mod executor_tests {
             Self {
                 calls: Arc::new(Mutex::new(Vec::new())),
                 fail_on: fail_on.iter().map(|s| s.to_string()).collect(),
+                ..Self::default()
+            }
+        }
+
+        fn with_stale(stale: &[&str]) -> Self {
+            Self {
+                stale: stale.iter().map(|s| s.to_string()).collect(),
+                ..Self::default()
             }
         }
 
         fn calls(&self) -> Vec<String> {
             self.calls.lock().unwrap().clone()
         }
+
+        fn record_write(&self, local_path: &Path, remote_path: &str) {
+            if self.lose_writes {
+                return;
+            }
+            let mut written = self.written.lock().unwrap();
+            let hash = if self.corrupt.contains(remote_path) && !written.contains_key(remote_path) {
+                "garbled".to_string()
+            } else {
+                format!("{:x}", Sha256::digest(fs::read(local_path).unwrap()))
+            };
+            written.insert(remote_path.to_string(), hash);
+        }
     }
 
     impl SyncExecutor for FakeExecutor {
@@ -177,40 +839,270 @@ mod executor_tests {
             })
         }
 
-        fn sync_one_file<'a>(
+        fn remote_file_stamps<'a>(
+            &'a self,
+            remote_dir: &'a str,
+        ) -> super::BoxFuture<'a, Result<HashMap<String, FileStamp>>> {
+            let calls = Arc::clone(&self.calls);
+            let prefix = format!("{remote_dir}/");
+            let existing = self
+                .stale
+                .iter()
+                .filter_map(|p| p.strip_prefix(&prefix))
+                .map(|rel| {
+                    let stamp = FileStamp {
+                        size: 1,
+                        mtime: Some(0),
+                    };
+                    (rel.to_string(), stamp)
+                })
+                .collect();
+            Box::pin(async move {
+                calls.lock().unwrap().push(format!("list:{remote_dir}"));
+                Ok(existing)
+            })
+        }
+
+        fn bulk_upload<'a>(
+            &'a self,
+            _remote_dir: &'a str,
+            items: &'a [SyncItem],
+            _compress: bool,
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            let fail = self.fail_on.contains("bulk");
+            Box::pin(async move {
+                calls.lock().unwrap().push(format!("bulk:{}", items.len()));
+                if fail {
+                    return Err(anyhow!("forced bulk error"));
+                }
+                for item in items {
+                    self.record_write(&item.local_path, &item.remote_path);
+                }
+                Ok(())
+            })
+        }
+
+        fn remote_checksums<'a>(
+            &'a self,
+            remote_paths: &'a [String],
+        ) -> super::BoxFuture<'a, Result<HashMap<String, String>>> {
+            let calls = Arc::clone(&self.calls);
+            let written = self.written.lock().unwrap().clone();
+            Box::pin(async move {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("checksums:{}", remote_paths.join(",")));
+                let hash = format!("{:x}", Sha256::digest(REMOTE_CONTENT));
+                Ok(remote_paths
+                    .iter()
+                    .map(|p| (p.clone(), written.get(p).unwrap_or(&hash).clone()))
+                    .collect())
+            })
+        }
+
+        fn copy_file_metadata<'a>(
             &'a self,
             _local_path: &'a Path,
             remote_path: &'a str,
-            _session_id: &'a str,
-            _block_size: usize,
         ) -> super::BoxFuture<'a, Result<()>> {
             let calls = Arc::clone(&self.calls);
-            let remote_path = remote_path.to_string();
+            Box::pin(async move {
+                calls.lock().unwrap().push(format!("meta:{remote_path}"));
+                Ok(())
+            })
+        }
+
+        fn remove_remote_files<'a>(
+            &'a self,
+            remote_paths: &'a [String],
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            Box::pin(async move {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("rm:{}", remote_paths.join(",")));
+                Ok(())
+            })
+        }
+
+        fn create_remote_symlink<'a>(
+            &'a self,
+            target: &'a Path,
+            remote_path: &'a str,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            Box::pin(async move {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("symlink:{remote_path}->{}", target.display()));
+                Ok(())
+            })
+        }
+
+        fn link_cached_files<'a>(
+            &'a self,
+            _cache_dir: &'a str,
+            entries: &'a [CacheEntry],
+        ) -> super::BoxFuture<'a, Result<HashSet<String>>> {
+            let calls = Arc::clone(&self.calls);
+            let linked: HashSet<String> = entries
+                .iter()
+                .filter(|entry| self.cached.contains_key(&entry.hash))
+                .map(|entry| entry.remote_path.clone())
+                .collect();
+            for entry in entries.iter().filter(|e| linked.contains(&e.remote_path)) {
+                let hash = format!("{:x}", Sha256::digest(&self.cached[&entry.hash]));
+                self.written
+                    .lock()
+                    .unwrap()
+                    .insert(entry.remote_path.clone(), hash);
+            }
+            Box::pin(async move {
+                let paths: Vec<&str> = entries.iter().map(|e| e.remote_path.as_str()).collect();
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("link:{}", paths.join(",")));
+                Ok(linked)
+            })
+        }
+
+        fn store_cached_files<'a>(
+            &'a self,
+            _cache_dir: &'a str,
+            entries: &'a [CacheEntry],
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            Box::pin(async move {
+                let paths: Vec<&str> = entries.iter().map(|e| e.remote_path.as_str()).collect();
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("store:{}", paths.join(",")));
+                Ok(())
+            })
+        }
+
+        fn sync_one_file<'a>(
+            &'a self,
+            local_path: &'a Path,
+            remote_path: &'a str,
+            _block_size: usize,
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<SyncOutcome>> {
+            let calls = Arc::clone(&self.calls);
             let fail_on = self.fail_on.clone();
+            let stale = self.stale.clone();
             Box::pin(async move {
                 calls.lock().unwrap().push(format!("sync:{remote_path}"));
-                if fail_on.contains(&remote_path) {
+                if fail_on.contains(remote_path) {
                     return Err(anyhow!("forced error for {remote_path}"));
                 }
-                Ok(())
+                if stale.contains(remote_path) {
+                    return Ok(SyncOutcome::NeedsDelta { same_size: true });
+                }
+                self.record_write(local_path, remote_path);
+                Ok(SyncOutcome::Uploaded)
             })
         }
-    }
 
-    fn rule(action: SyncFilterAction, pattern: &str) -> SyncFilterRule {
-        SyncFilterRule {
-            action,
-            pattern: pattern.to_string(),
+        fn prepare_remote_hasher<'a>(
+            &'a self,
+            session_id: &'a str,
+        ) -> super::BoxFuture<'a, Result<RemoteHasher>> {
+            let calls = Arc::clone(&self.calls);
+            let script_path = format!("/tmp/hpcd_{session_id}.py");
+            Box::pin(async move {
+                calls.lock().unwrap().push("prepare_hasher".to_string());
+                Ok(RemoteHasher::Python {
+                    interpreter: "python3".to_string(),
+                    script_path,
+                })
+            })
         }
-    }
 
-    #[tokio::test]
-    async fn sync_dir_calls_remote_dirs_before_sync() {
-        let tmp = tempdir().unwrap();
-        let root = tmp.path();
+        fn remote_block_hashes<'a>(
+            &'a self,
+            _hasher: &'a RemoteHasher,
+            remote_paths: &'a [String],
+            _block_size: usize,
+        ) -> super::BoxFuture<'a, Result<Vec<Option<Vec<BlockSignature>>>>> {
+            let calls = Arc::clone(&self.calls);
+            Box::pin(async move {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("hash:{}", remote_paths.join(",")));
+                Ok(remote_paths
+                    .iter()
+                    .map(|p| {
+                        Some(vec![BlockSignature {
+                            len: 1,
+                            strong: format!("hash-of-{p}"),
+                            weak: None,
+                        }])
+                    })
+                    .collect())
+            })
+        }
 
-        fs::create_dir_all(root.join("src/bin")).unwrap();
-        fs::write(root.join("README.md"), "readme").unwrap();
+        fn delta_sync_one_file<'a>(
+            &'a self,
+            local_path: &'a Path,
+            remote_path: &'a str,
+            basis: Option<DeltaBasis<'a>>,
+            _block_size: usize,
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            self.record_write(local_path, remote_path);
+            let hashes = basis
+                .map(|basis| {
+                    let strong: Vec<&str> =
+                        basis.blocks.iter().map(|b| b.strong.as_str()).collect();
+                    strong.join(",")
+                })
+                .unwrap_or_default();
+            Box::pin(async move {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("delta:{remote_path}:{hashes}"));
+                Ok(())
+            })
+        }
+
+        fn cleanup_remote_hasher<'a>(
+            &'a self,
+            _hasher: &'a RemoteHasher,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            let calls = Arc::clone(&self.calls);
+            Box::pin(async move {
+                calls.lock().unwrap().push("cleanup_hasher".to_string());
+                Ok(())
+            })
+        }
+    }
+
+    fn rule(action: SyncFilterAction, pattern: &str) -> SyncFilterRule {
+        SyncFilterRule {
+            action,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_dir_calls_remote_dirs_before_sync() {
+        let tmp = tempdir().unwrap();
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::write(root.join("README.md"), "readme").unwrap();
         fs::write(root.join("src/lib.rs"), "lib").unwrap();
         fs::write(root.join("src/bin/main.rs"), "bin").unwrap();
 
@@ -222,6 +1114,14 @@ mod executor_tests {
             block_size: None,
             parallelism: Some(1),
             filters: &filters,
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
         };
 
         sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
@@ -255,6 +1155,14 @@ mod executor_tests {
             block_size: None,
             parallelism: Some(1),
             filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
         };
 
         let err = sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
@@ -265,15 +1173,448 @@ mod executor_tests {
         assert!(msg.contains("sync_dir encountered 1 error(s):"));
         assert!(msg.contains("forced error for /remote/src/lib.rs"));
     }
+
+    #[tokio::test]
+    async fn sync_dir_hashes_stale_files_in_one_batch_and_cleans_up() {
+        let tmp = tempdir().unwrap();
+        let root = tmp.path();
+
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::write(root.join("c.txt"), "c").unwrap();
+
+        let executor = FakeExecutor::with_stale(&["/remote/a.txt", "/remote/c.txt"]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        let tail: Vec<&str> = calls
+            .iter()
+            .skip_while(|c| {
+                c.starts_with("connect") || c.starts_with("mkdir") || c.starts_with("sync")
+            })
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            tail,
+            vec![
+                "prepare_hasher",
+                "hash:/remote/a.txt,/remote/c.txt",
+                "delta:/remote/a.txt:hash-of-/remote/a.txt",
+                "delta:/remote/c.txt:hash-of-/remote/c.txt",
+                "cleanup_hasher",
+                "checksums:/remote/b.txt,/remote/a.txt,/remote/c.txt",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_dir_checksum_skips_identical_files_and_copies_metadata() {
+        let tmp = tempdir().unwrap();
+        fs::write(tmp.path().join("a.txt"), REMOTE_CONTENT).unwrap();
+        fs::write(tmp.path().join("b.txt"), "edit").unwrap();
+
+        let executor = FakeExecutor::with_stale(&["/remote/a.txt", "/remote/b.txt"]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: true,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        assert!(calls.contains(&"meta:/remote/a.txt".to_string()));
+        assert!(calls.contains(&"hash:/remote/b.txt".to_string()));
+        assert!(!calls.iter().any(|c| c.starts_with("delta:/remote/a.txt")));
+        let checksums: Vec<&String> = calls
+            .iter()
+            .filter(|c| c.starts_with("checksums:"))
+            .collect();
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums[1], "checksums:/remote/b.txt");
+    }
+
+    #[tokio::test]
+    async fn sync_dir_verifies_every_written_file_in_one_batch_and_resends_mismatches() {
+        let tmp = tempdir().unwrap();
+        fs::write(tmp.path().join("new.txt"), "new").unwrap();
+        fs::write(tmp.path().join("patched.txt"), "patched").unwrap();
+        fs::write(tmp.path().join("same.txt"), REMOTE_CONTENT).unwrap();
+
+        let mut executor = FakeExecutor::with_stale(&["/remote/patched.txt", "/remote/same.txt"]);
+        executor.corrupt = ["/remote/patched.txt".to_string()].into();
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: true,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        let verified =
+            sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+                .await
+                .unwrap();
+
+        let calls = executor.calls();
+        let tail: Vec<&str> = calls
+            .iter()
+            .skip_while(|c| *c != "cleanup_hasher")
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            tail,
+            vec![
+                "cleanup_hasher",
+                "checksums:/remote/new.txt,/remote/patched.txt",
+                "delta:/remote/patched.txt:",
+                "checksums:/remote/patched.txt",
+            ]
+        );
+        let mut paths: Vec<&str> = verified.iter().map(|f| f.remote_path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/remote/new.txt", "/remote/patched.txt"]);
+        let patched = verified
+            .iter()
+            .find(|f| f.remote_path == "/remote/patched.txt")
+            .unwrap();
+        assert_eq!(patched.size, 7);
+        assert_eq!(patched.sha256, format!("{:x}", Sha256::digest(b"patched")));
+    }
+
+    #[tokio::test]
+    async fn sync_dir_fails_when_a_file_keeps_mismatching() {
+        let tmp = tempdir().unwrap();
+        fs::write(tmp.path().join("a.txt"), "a").unwrap();
+
+        let executor = FakeExecutor {
+            lose_writes: true,
+            ..FakeExecutor::default()
+        };
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+        let err =
+            sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+                .await
+                .unwrap_err();
+
+        let msg = format!("{err:#}");
+        assert!(msg.contains("/remote/a.txt does not match"), "{msg}");
+        let checksums = executor
+            .calls()
+            .iter()
+            .filter(|c| c.starts_with("checksums:"))
+            .count();
+        assert_eq!(checksums, super::TRANSFER_ATTEMPTS as usize);
+    }
+
+    fn write_small_files(root: &Path, count: usize) {
+        for i in 0..count {
+            fs::write(root.join(format!("f{i}.txt")), "x").unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_dir_bulk_uploads_many_new_small_files() {
+        let tmp = tempdir().unwrap();
+        write_small_files(tmp.path(), BULK_MIN_FILES + 1);
+
+        let executor = FakeExecutor::with_stale(&["/remote/f0.txt"]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        assert!(calls.contains(&"list:/remote".to_string()));
+        assert!(calls.contains(&format!("bulk:{BULK_MIN_FILES}")));
+        let synced: Vec<&String> = calls.iter().filter(|c| c.starts_with("sync:")).collect();
+        assert_eq!(synced, vec!["sync:/remote/f0.txt"]);
+    }
+
+    #[tokio::test]
+    async fn sync_dir_falls_back_to_per_file_uploads_when_bulk_fails() {
+        let tmp = tempdir().unwrap();
+        write_small_files(tmp.path(), BULK_MIN_FILES);
+
+        let executor = FakeExecutor::new(&["bulk"]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(4),
+            filters: &[],
+            ignore_files: false,
+            compress: true,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        assert!(calls.contains(&format!("bulk:{BULK_MIN_FILES}")));
+        let synced = calls.iter().filter(|c| c.starts_with("sync:")).count();
+        assert_eq!(synced, BULK_MIN_FILES);
+    }
+
+    #[tokio::test]
+    async fn sync_dir_skips_hasher_when_nothing_is_stale() {
+        let tmp = tempdir().unwrap();
+        fs::write(tmp.path().join("a.txt"), "a").unwrap();
+
+        let executor = FakeExecutor::default();
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, tmp.path(), "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        assert!(!executor.calls().iter().any(|c| c.contains("hasher")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sync_dir_recreates_preserved_symlinks() {
+        let tmp = tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("conf")).unwrap();
+        fs::write(root.join("conf/base.yaml"), "base").unwrap();
+        std::os::unix::fs::symlink("base.yaml", root.join("conf/config.yaml")).unwrap();
+
+        let executor = FakeExecutor::default();
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Preserve,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
+        };
+
+        sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        assert!(calls.contains(&"mkdir:/remote/conf".to_string()));
+        assert!(calls.contains(&"symlink:/remote/conf/config.yaml->base.yaml".to_string()));
+        assert!(
+            !calls
+                .iter()
+                .any(|c| c.contains("config.yaml") && !c.starts_with("symlink"))
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_dir_deletes_unplanned_remote_files_after_successful_upload() {
+        let tmp = tempdir().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let run = |fail_on: &[&str]| {
+            let mut executor = FakeExecutor::new(fail_on);
+            executor.stale = [
+                "/remote/a.txt",
+                "/remote/gone.txt",
+                "/remote/slurm-1.out",
+                "/remote/results/model.pt",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+            executor
+        };
+        // Only files an earlier sync uploaded may go; the job wrote results/model.pt.
+        let uploaded = ["/remote/a.txt", "/remote/gone.txt", "/remote/slurm-1.out"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: true,
+            protect: &[],
+            uploaded: &uploaded,
+            cache_dir: None,
+        };
+
+        let executor = run(&[]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+        let calls = executor.calls();
+        assert!(calls.contains(&"list:/remote".to_string()));
+        assert_eq!(calls.last().unwrap(), "rm:/remote/gone.txt");
+        assert!(!calls.iter().any(|c| c.contains("model.pt")));
+
+        let executor = run(&["/remote/b.txt"]);
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap_err();
+        assert!(!executor.calls().iter().any(|c| c.starts_with("rm:")));
+    }
+
+    #[tokio::test]
+    async fn sync_dir_places_cached_files_and_stores_uploaded_ones() {
+        let tmp = tempdir().unwrap();
+        let root = tmp.path();
+        let size = CACHE_MIN_FILE_SIZE as usize;
+        fs::write(root.join("cached.dat"), vec![1u8; size]).unwrap();
+        fs::write(root.join("fresh.dat"), vec![2u8; size]).unwrap();
+        fs::write(root.join("small.txt"), "small").unwrap();
+
+        let executor = FakeExecutor {
+            cached: [(
+                blake3::hash(&vec![1u8; size]).to_hex().to_string(),
+                vec![1u8; size],
+            )]
+            .into(),
+            ..FakeExecutor::default()
+        };
+        let (evt_tx, _evt_rx) = mpsc::channel::<Result<SubmitStreamEvent, tonic::Status>>(1);
+        let (_mfa_tx, mfa_rx) = mpsc::channel::<MfaAnswer>(1);
+        let options = SyncOptions {
+            block_size: None,
+            parallelism: Some(1),
+            filters: &[],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: Some("/base/.hpc-cache"),
+        };
+
+        sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
+            .await
+            .unwrap();
+
+        let calls = executor.calls();
+        let link = calls.iter().find(|c| c.starts_with("link:")).unwrap();
+        assert!(link.contains("/remote/cached.dat") && link.contains("/remote/fresh.dat"));
+        assert!(!link.contains("small.txt"));
+        assert!(calls.contains(&"meta:/remote/cached.dat".to_string()));
+        assert!(!calls.contains(&"sync:/remote/cached.dat".to_string()));
+        assert!(calls.contains(&"sync:/remote/fresh.dat".to_string()));
+        assert!(calls.last().unwrap().starts_with("store:"));
+    }
 }
 
 #[cfg(test)]
 mod sync_option_tests {
     use super::{
-        MfaAnswer, SubmitStreamEvent, SyncExecutor, SyncFilterRule, SyncOptions,
-        sync_dir_with_executor,
+        BlockSignature, CacheEntry, DeltaBasis, FileStamp, MfaAnswer, RemoteHasher,
+        SubmitStreamEvent, SymlinkPolicy, SyncExecutor, SyncFilterRule, SyncItem, SyncOptions,
+        SyncOutcome, TransferProgress, sync_dir_with_executor,
     };
     use anyhow::Result;
+    use std::collections::{HashMap, HashSet};
     use std::fs;
     use std::path::Path;
     use std::sync::{Arc, Mutex};
@@ -307,19 +1648,116 @@ mod sync_option_tests {
             Box::pin(async move { Ok(()) })
         }
 
+        fn remote_file_stamps<'a>(
+            &'a self,
+            _remote_dir: &'a str,
+        ) -> super::BoxFuture<'a, Result<HashMap<String, FileStamp>>> {
+            Box::pin(async move { Ok(HashMap::new()) })
+        }
+
+        fn bulk_upload<'a>(
+            &'a self,
+            _remote_dir: &'a str,
+            _items: &'a [SyncItem],
+            _compress: bool,
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn remote_checksums<'a>(
+            &'a self,
+            _remote_paths: &'a [String],
+        ) -> super::BoxFuture<'a, Result<HashMap<String, String>>> {
+            Box::pin(async move { Ok(HashMap::new()) })
+        }
+
+        fn copy_file_metadata<'a>(
+            &'a self,
+            _local_path: &'a Path,
+            _remote_path: &'a str,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn remove_remote_files<'a>(
+            &'a self,
+            _remote_paths: &'a [String],
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn create_remote_symlink<'a>(
+            &'a self,
+            _target: &'a Path,
+            _remote_path: &'a str,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn link_cached_files<'a>(
+            &'a self,
+            _cache_dir: &'a str,
+            _entries: &'a [CacheEntry],
+        ) -> super::BoxFuture<'a, Result<HashSet<String>>> {
+            Box::pin(async move { Ok(HashSet::new()) })
+        }
+
+        fn store_cached_files<'a>(
+            &'a self,
+            _cache_dir: &'a str,
+            _entries: &'a [CacheEntry],
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
         fn sync_one_file<'a>(
             &'a self,
             _local_path: &'a Path,
             _remote_path: &'a str,
-            _session_id: &'a str,
             block_size: usize,
-        ) -> super::BoxFuture<'a, Result<()>> {
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<SyncOutcome>> {
             let sizes = Arc::clone(&self.sizes);
             Box::pin(async move {
                 sizes.lock().unwrap().push(block_size);
-                Ok(())
+                Ok(SyncOutcome::Done)
             })
         }
+
+        fn prepare_remote_hasher<'a>(
+            &'a self,
+            _session_id: &'a str,
+        ) -> super::BoxFuture<'a, Result<RemoteHasher>> {
+            Box::pin(async move { Ok(RemoteHasher::Shell) })
+        }
+
+        fn remote_block_hashes<'a>(
+            &'a self,
+            _hasher: &'a RemoteHasher,
+            remote_paths: &'a [String],
+            _block_size: usize,
+        ) -> super::BoxFuture<'a, Result<Vec<Option<Vec<BlockSignature>>>>> {
+            Box::pin(async move { Ok(vec![None; remote_paths.len()]) })
+        }
+
+        fn delta_sync_one_file<'a>(
+            &'a self,
+            _local_path: &'a Path,
+            _remote_path: &'a str,
+            _basis: Option<DeltaBasis<'a>>,
+            _block_size: usize,
+            _progress: &'a TransferProgress,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn cleanup_remote_hasher<'a>(
+            &'a self,
+            _hasher: &'a RemoteHasher,
+        ) -> super::BoxFuture<'a, Result<()>> {
+            Box::pin(async move { Ok(()) })
+        }
     }
 
     #[tokio::test]
@@ -337,6 +1775,14 @@ mod sync_option_tests {
             block_size: None,
             parallelism: None,
             filters: &[] as &[SyncFilterRule],
+            ignore_files: false,
+            compress: false,
+            checksum: false,
+            symlinks: SymlinkPolicy::Skip,
+            delete: false,
+            protect: &[],
+            uploaded: &HashSet::new(),
+            cache_dir: None,
         };
 
         sync_dir_with_executor(&executor, root, "/remote", options, &evt_tx, mfa_rx)
@@ -4,16 +4,17 @@
 //! Sync planning utilities for SSH file synchronization.
 //!
 //! This module builds a deterministic, testable plan that enumerates local files,
-//! applies include/exclude rules, and maps each file to a remote path. It does
-//! not perform any network or SFTP operations.
+//! applies include/exclude rules and ignore files, and maps each file to a remote
+//! path. Symbolic links are handled according to a [`SymlinkPolicy`] and reported
+//! individually. It does not perform any network or SFTP operations.
 
 use anyhow::{Context, Result};
 use globset::{GlobBuilder, GlobMatcher};
-use std::collections::BTreeSet;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use walkdir::WalkDir;
 
-use super::{SyncFilterAction, SyncFilterRule};
+use super::{SymlinkPolicy, SyncFilterAction, SyncFilterRule};
 
 /// A single file chosen for synchronization from local to remote.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -37,8 +38,47 @@ pub struct SyncPlan {
     pub items: Vec<SyncItem>,
     /// Unique remote directories that must exist before uploads.
     pub remote_dirs: Vec<String>,
+    /// Every symbolic link found in the tree and what the sync does with it.
+    pub symlinks: Vec<SymlinkEntry>,
 }
 
+/// What a sync does with one symbolic link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkAction {
+    /// Created on the remote as a link with the same target.
+    Recreate,
+    /// Replaced by the file or directory tree it points to.
+    Follow,
+    /// Left out of the upload.
+    Skip,
+    /// Left out because it is dangling or part of a loop and could not be followed.
+    Broken,
+}
+
+/// A symbolic link found under the local root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymlinkEntry {
+    /// Path of the link relative to the local sync root.
+    pub rel_path: PathBuf,
+    /// Remote path the link maps to.
+    pub remote_path: String,
+    /// Link target as stored in the link.
+    pub target: PathBuf,
+    /// Whether the target resolves to a path outside the local root.
+    pub outside_root: bool,
+    pub action: SymlinkAction,
+}
+
+/// Project-level ignore file read from the sync root, with `PathFilter` rule syntax.
+pub const HPCIGNORE_FILE: &str = ".hpcignore";
+/// Git ignore files, honoured in every directory of the tree.
+const GITIGNORE_FILE: &str = ".gitignore";
+/// Project-level list of remote paths a mirror sync must never delete, one pattern per
+/// line with `PathFilter` syntax.
+pub const HPCPROTECT_FILE: &str = ".hpcprotect";
+/// Remote paths a mirror sync keeps even without a `.hpcprotect` file.
+const DEFAULT_PROTECTED: &[&str] = &["slurm-*.out"];
+
 /// Build a sync plan by scanning `local_dir` and applying `filters`.
 ///
 /// This function uses the local directory as provided, compiles glob-style
@@ -46,41 +86,118 @@ pub struct SyncPlan {
 /// directories, collects file entries, maps each file to a remote path, and
 /// records the required remote parent directories. `remote_dirs` is returned
 /// sorted.
+///
+/// With `ignore_files`, paths not decided by `filters` are checked against
+/// `.hpcignore` and then the `.gitignore` files of the tree; `.git` is never
+/// uploaded in that mode.
+///
+/// Symbolic links that pass the filters are recorded in `symlinks`. Only with
+/// [`SymlinkPolicy::Follow`] do their targets end up in `items`; followed
+/// directory links are walked like regular directories.
 pub fn build_sync_plan<P: AsRef<Path>>(
     local_dir: P,
     remote_dir: &str,
     filters: &[SyncFilterRule],
+    ignore_files: bool,
+    symlink_policy: SymlinkPolicy,
 ) -> Result<SyncPlan> {
     let local_root = local_dir.as_ref().to_path_buf();
+    let canonical_root = std::fs::canonicalize(&local_root).unwrap_or_else(|_| local_root.clone());
     let path_filter = PathFilter::new(filters)?;
-    let use_filter = !path_filter.is_empty();
+    let mut ignores = if ignore_files {
+        Some(IgnoreFiles::load(&local_root)?)
+    } else {
+        None
+    };
     let mut items = Vec::new();
     let mut remote_dirs = BTreeSet::new();
-    let follow_links = false;
+    let mut symlinks = Vec::new();
+    let follow_links = symlink_policy == SymlinkPolicy::Follow;
 
     for entry in WalkDir::new(&local_root)
         .follow_links(follow_links)
         .into_iter()
         .filter_entry(|entry| {
-            if !use_filter || entry.depth() == 0 {
+            if entry.depth() == 0 {
                 return true;
             }
-            if !entry.file_type().is_dir() {
+            let Ok(rel) = entry.path().strip_prefix(&local_root) else {
                 return true;
+            };
+            // Links to directories match directory-only patterns too.
+            let is_dir =
+                entry.file_type().is_dir() || (entry.path_is_symlink() && entry.path().is_dir());
+            if let Some(include) = path_filter.decide(rel, is_dir) {
+                return include;
             }
-            match entry.path().strip_prefix(&local_root) {
-                Ok(rel) => path_filter.should_include(rel, true),
-                Err(_) => true,
+            match ignores.as_mut() {
+                Some(ignores) => !ignores.is_ignored(rel, is_dir),
+                None => true,
             }
         })
     {
         let direntry = match entry {
             Ok(v) => v,
             Err(e) => {
+                // Dangling links and link loops can only fail when links are followed.
+                if let Some(path) = e.path()
+                    && path.is_symlink()
+                    && let Ok(rel_path) = path.strip_prefix(&local_root)
+                {
+                    log::warn!("skipping symlink {}: {e}", path.display());
+                    symlinks.push(symlink_entry(
+                        path,
+                        rel_path,
+                        remote_dir,
+                        &canonical_root,
+                        SymlinkAction::Broken,
+                    ));
+                    continue;
+                }
                 log::warn!("encountered error when enumerating local files: {:?}", e);
                 continue;
             }
         };
+        if direntry.depth() > 0 && direntry.path_is_symlink() {
+            let Ok(rel_path) = direntry.path().strip_prefix(&local_root) else {
+                continue;
+            };
+            let action = match symlink_policy {
+                SymlinkPolicy::Follow => SymlinkAction::Follow,
+                SymlinkPolicy::Preserve => SymlinkAction::Recreate,
+                SymlinkPolicy::Skip => SymlinkAction::Skip,
+            };
+            let entry = symlink_entry(
+                direntry.path(),
+                rel_path,
+                remote_dir,
+                &canonical_root,
+                action,
+            );
+            if entry.outside_root {
+                log::warn!(
+                    "symlink {} points outside {}: {}",
+                    rel_path.display(),
+                    local_root.display(),
+                    entry.target.display()
+                );
+            }
+            match action {
+                SymlinkAction::Skip => log::warn!("skipping symlink {}", rel_path.display()),
+                SymlinkAction::Recreate => {
+                    if let Some(parent_rel) = rel_path.parent()
+                        && !parent_rel.as_os_str().is_empty()
+                    {
+                        remote_dirs.insert(join_remote(remote_dir, parent_rel));
+                    }
+                }
+                SymlinkAction::Follow | SymlinkAction::Broken => {}
+            }
+            symlinks.push(entry);
+            if action != SymlinkAction::Follow {
+                continue;
+            }
+        }
         if !direntry.file_type().is_file() {
             // Directory creation is handled by file parents.
             continue;
@@ -97,9 +214,6 @@ pub fn build_sync_plan<P: AsRef<Path>>(
                 continue;
             }
         };
-        if use_filter && !path_filter.should_include(&rel_path, false) {
-            continue;
-        }
         let remote_path = join_remote(remote_dir, &rel_path);
         if let Some(parent_rel) = rel_path.parent()
             && !parent_rel.as_os_str().is_empty()
@@ -118,9 +232,149 @@ pub fn build_sync_plan<P: AsRef<Path>>(
         remote_root: remote_dir.trim_end_matches('/').to_string(),
         items,
         remote_dirs: remote_dirs.into_iter().collect(),
+        symlinks,
     })
 }
 
+/// Remote files a mirror sync deletes: those under the remote root that `plan` does not
+/// cover and that an earlier sync uploaded. `remote_files` are slash-separated paths
+/// relative to the remote root; `uploaded` holds absolute remote paths. Files created on
+/// the remote, such as job outputs, are never deleted.
+///
+/// Of the uploaded files, those that the plan leaves out on purpose are kept, as rsync does without
+/// `--delete-excluded`: anything excluded by `filters` or ignore files, anything at or
+/// under a local symlink, and anything matching a protected pattern. Protected patterns
+/// are [`DEFAULT_PROTECTED`], the lines of [`HPCPROTECT_FILE`] in the local root and
+/// `protect`; a pattern matching a directory protects everything under it. The result
+/// is sorted.
+pub(crate) fn plan_remote_deletions<'a>(
+    plan: &SyncPlan,
+    remote_files: impl IntoIterator<Item = &'a str>,
+    filters: &[SyncFilterRule],
+    ignore_files: bool,
+    protect: &[String],
+    uploaded: &HashSet<String>,
+) -> Result<Vec<String>> {
+    let path_filter = PathFilter::new(filters)?;
+    let mut ignores = if ignore_files {
+        Some(IgnoreFiles::load(&plan.local_root)?)
+    } else {
+        None
+    };
+    let protected = protected_paths(&plan.local_root, protect)?;
+    let planned: BTreeSet<String> = plan
+        .items
+        .iter()
+        .map(|item| rel_path_to_slash(&item.rel_path))
+        .collect();
+    let links: Vec<&Path> = plan
+        .symlinks
+        .iter()
+        .map(|link| link.rel_path.as_path())
+        .collect();
+
+    let mut deletions = Vec::new();
+    for remote in remote_files {
+        if planned.contains(remote) || !uploaded.contains(&format!("{}/{remote}", plan.remote_root))
+        {
+            continue;
+        }
+        let rel_path = Path::new(remote);
+        if links.iter().any(|link| rel_path.starts_with(link)) {
+            continue;
+        }
+        // Walk the same path the local scan would, stopping at the first pruned level.
+        let mut excluded = false;
+        let mut is_protected = false;
+        let mut prefix = PathBuf::new();
+        let components: Vec<_> = rel_path.components().collect();
+        for (idx, component) in components.iter().enumerate() {
+            prefix.push(component);
+            let is_dir = idx + 1 < components.len();
+            is_protected |= protected.decide(&prefix, is_dir).is_some();
+            let include = match path_filter.decide(&prefix, is_dir) {
+                Some(include) => include,
+                None => !ignores
+                    .as_mut()
+                    .is_some_and(|ignores| ignores.is_ignored(&prefix, is_dir)),
+            };
+            if !include {
+                excluded = true;
+                break;
+            }
+        }
+        if !excluded && !is_protected {
+            deletions.push(remote.to_string());
+        }
+    }
+    deletions.sort();
+    Ok(deletions)
+}
+
+/// Compiles the protected patterns into a filter in which any match means "keep".
+fn protected_paths(local_root: &Path, protect: &[String]) -> Result<PathFilter> {
+    let from_file = match std::fs::read_to_string(local_root.join(HPCPROTECT_FILE)) {
+        Ok(contents) => contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e).with_context(|| format!("couldn't read {HPCPROTECT_FILE}")),
+    };
+    let rules: Vec<SyncFilterRule> = DEFAULT_PROTECTED
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .chain(from_file)
+        .chain(protect.iter().cloned())
+        .map(|pattern| SyncFilterRule {
+            action: SyncFilterAction::Include,
+            pattern,
+        })
+        .collect();
+    PathFilter::new(&rules).context("invalid protected path pattern")
+}
+
+/// Describes the link at `path`, checking where its target resolves relative to
+/// `canonical_root`.
+fn symlink_entry(
+    path: &Path,
+    rel_path: &Path,
+    remote_dir: &str,
+    canonical_root: &Path,
+    action: SymlinkAction,
+) -> SymlinkEntry {
+    let target = std::fs::read_link(path).unwrap_or_default();
+    let resolved = std::fs::canonicalize(path).ok().or_else(|| {
+        // Dangling: resolve the target lexically from the link's directory.
+        let parent = std::fs::canonicalize(path.parent()?).ok()?;
+        Some(normalize_lexically(&parent.join(&target)))
+    });
+    SymlinkEntry {
+        rel_path: rel_path.to_path_buf(),
+        remote_path: join_remote(remote_dir, rel_path),
+        outside_root: resolved.is_none_or(|resolved| !resolved.starts_with(canonical_root)),
+        target,
+        action,
+    }
+}
+
+/// Removes `.` and `..` components without touching the filesystem.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
 /// One compiled filter rule, including pattern metadata needed for matching.
 #[derive(Debug)]
 struct CompiledFilterRule {
@@ -132,13 +386,13 @@ struct CompiledFilterRule {
 
 /// An ordered set of compiled include/exclude rules.
 #[derive(Debug)]
-struct PathFilter {
+pub(crate) struct PathFilter {
     rules: Vec<CompiledFilterRule>,
 }
 
 impl PathFilter {
     /// Compile all rules into matchers while preserving order.
-    fn new(rules: &[SyncFilterRule]) -> Result<Self> {
+    pub(crate) fn new(rules: &[SyncFilterRule]) -> Result<Self> {
         let mut compiled = Vec::with_capacity(rules.len());
         for rule in rules {
             compiled.push(CompiledFilterRule::compile(rule)?);
@@ -146,27 +400,199 @@ impl PathFilter {
         Ok(Self { rules: compiled })
     }
 
-    /// Return true when there are no rules and everything should be included.
-    fn is_empty(&self) -> bool {
-        self.rules.is_empty()
+    /// Decide whether a path should be included, honoring first-match wins.
+    pub(crate) fn should_include(&self, rel_path: &Path, is_dir: bool) -> bool {
+        self.decide(rel_path, is_dir).unwrap_or(true)
     }
 
-    /// Decide whether a path should be included, honoring first-match wins.
-    fn should_include(&self, rel_path: &Path, is_dir: bool) -> bool {
+    /// Return the action of the first matching rule, or None when no rule matches.
+    fn decide(&self, rel_path: &Path, is_dir: bool) -> Option<bool> {
         if self.rules.is_empty() {
-            return true;
+            return None;
         }
         let rel_str = rel_path_to_slash(rel_path);
         let basename = rel_path
             .file_name()
             .map(|s| s.to_string_lossy())
             .unwrap_or_default();
-        for rule in &self.rules {
-            if rule.matches(&rel_str, &basename, is_dir) {
-                return matches!(rule.action, SyncFilterAction::Include);
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(&rel_str, &basename, is_dir))
+            .map(|rule| matches!(rule.action, SyncFilterAction::Include))
+    }
+}
+
+/// Parse `.hpcignore` contents into filter rules.
+///
+/// Each non-empty, non-comment line is a pattern. `+ PATTERN` and `!PATTERN`
+/// include, `- PATTERN` and a bare pattern exclude.
+fn parse_hpcignore(contents: &str) -> Vec<SyncFilterRule> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (action, pattern) = if let Some(rest) = line.strip_prefix("+ ") {
+                (SyncFilterAction::Include, rest)
+            } else if let Some(rest) = line.strip_prefix("- ") {
+                (SyncFilterAction::Exclude, rest)
+            } else if let Some(rest) = line.strip_prefix('!') {
+                (SyncFilterAction::Include, rest)
+            } else {
+                (SyncFilterAction::Exclude, line)
+            };
+            SyncFilterRule {
+                action,
+                pattern: pattern.trim().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// One `.gitignore` pattern, relative to the directory holding the file.
+#[derive(Debug)]
+struct GitignoreRule {
+    matcher: GlobMatcher,
+    negate: bool,
+    only_dir: bool,
+    match_basename: bool,
+}
+
+impl GitignoreRule {
+    /// Compile a `.gitignore` line; blank lines and comments yield None.
+    fn compile(line: &str) -> Option<Result<Self>> {
+        let mut pattern = line.trim_end();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+        let negate = pattern.starts_with('!');
+        if negate {
+            pattern = &pattern[1..];
+        }
+        if pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
+        }
+        let only_dir = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        // A slash anywhere but the end anchors the pattern to the .gitignore directory.
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid .gitignore pattern '{line}'"))
+            .map(|glob| glob.compile_matcher());
+        Some(matcher.map(|matcher| Self {
+            matcher,
+            negate,
+            only_dir,
+            match_basename: !anchored,
+        }))
+    }
+
+    fn matches(&self, rel_path: &str, basename: &str, is_dir: bool) -> bool {
+        if self.only_dir && !is_dir {
+            return false;
+        }
+        if self.match_basename {
+            self.matcher.is_match(basename)
+        } else {
+            self.matcher.is_match(rel_path)
+        }
+    }
+}
+
+/// `.hpcignore` rules plus `.gitignore` files loaded lazily per directory.
+#[derive(Debug)]
+struct IgnoreFiles {
+    root: PathBuf,
+    hpcignore: PathFilter,
+    gitignores: HashMap<PathBuf, Vec<GitignoreRule>>,
+}
+
+impl IgnoreFiles {
+    fn load(root: &Path) -> Result<Self> {
+        let hpcignore = match std::fs::read_to_string(root.join(HPCIGNORE_FILE)) {
+            Ok(contents) => PathFilter::new(&parse_hpcignore(&contents))
+                .with_context(|| format!("invalid {HPCIGNORE_FILE}"))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PathFilter::new(&[])?,
+            Err(e) => {
+                return Err(e).with_context(|| format!("couldn't read {HPCIGNORE_FILE}"));
+            }
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            hpcignore,
+            gitignores: HashMap::new(),
+        })
+    }
+
+    /// Rules of the `.gitignore` in `dir` (relative to the root); unreadable
+    /// files and invalid lines are skipped with a warning.
+    fn gitignore_rules(&mut self, dir: &Path) -> &[GitignoreRule] {
+        let root = &self.root;
+        self.gitignores.entry(dir.to_path_buf()).or_insert_with(|| {
+            let path = root.join(dir).join(GITIGNORE_FILE);
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(v) => v,
+                Err(e) => {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("couldn't read {}: {e}", path.display());
+                    }
+                    return Vec::new();
+                }
+            };
+            contents
+                .lines()
+                .filter_map(GitignoreRule::compile)
+                .filter_map(|rule| match rule {
+                    Ok(rule) => Some(rule),
+                    Err(e) => {
+                        log::warn!("skipping line in {}: {e:#}", path.display());
+                        None
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Whether a path is excluded by `.hpcignore` or, failing a match there,
+    /// by the `.gitignore` files of its ancestors (deeper files and later lines win).
+    fn is_ignored(&mut self, rel_path: &Path, is_dir: bool) -> bool {
+        if rel_path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        if let Some(include) = self.hpcignore.decide(rel_path, is_dir) {
+            return !include;
+        }
+        let basename = rel_path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut ignored = false;
+        let mut dir = PathBuf::new();
+        let mut ancestors = vec![dir.clone()];
+        if let Some(parent) = rel_path.parent() {
+            for comp in parent.components() {
+                dir.push(comp);
+                ancestors.push(dir.clone());
+            }
+        }
+        for dir in ancestors {
+            let Ok(rel_to_dir) = rel_path.strip_prefix(&dir) else {
+                continue;
+            };
+            let rel_str = rel_path_to_slash(rel_to_dir);
+            for rule in self.gitignore_rules(&dir) {
+                if rule.matches(&rel_str, &basename, is_dir) {
+                    ignored = !rule.negate;
+                }
             }
         }
-        true
+        ignored
     }
 }
 
@@ -228,7 +654,7 @@ impl CompiledFilterRule {
 }
 
 /// Convert a relative path to a forward-slash string for glob matching.
-fn rel_path_to_slash(path: &Path) -> String {
+pub(crate) fn rel_path_to_slash(path: &Path) -> String {
     let mut out = String::new();
     for comp in path.components() {
         if let std::path::Component::Normal(os) = comp {
@@ -255,8 +681,12 @@ fn join_remote(base: &str, rel: &Path) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::build_sync_plan;
-    use super::{PathFilter, SyncFilterAction, SyncFilterRule};
+    use super::{
+        HPCPROTECT_FILE, SymlinkAction, SymlinkEntry, SymlinkPolicy, build_sync_plan,
+        plan_remote_deletions,
+    };
+    use super::{PathFilter, SyncFilterAction, SyncFilterRule, parse_hpcignore};
+    use std::collections::HashSet;
     use std::fs;
     use std::path::Path;
 
@@ -333,7 +763,7 @@ mod tests {
 
         let filters = [rule(SyncFilterAction::Exclude, "target/")];
 
-        let plan = build_sync_plan(root, "/remote", &filters).unwrap();
+        let plan = build_sync_plan(root, "/remote", &filters, false, SymlinkPolicy::Skip).unwrap();
 
         let mut remote_paths: Vec<String> = plan
             .items
@@ -356,4 +786,276 @@ mod tests {
             vec!["/remote/src".to_string(), "/remote/src/bin".to_string()]
         );
     }
+
+    fn plan_paths(root: &Path, filters: &[SyncFilterRule], ignore_files: bool) -> Vec<String> {
+        let plan =
+            build_sync_plan(root, "/remote", filters, ignore_files, SymlinkPolicy::Skip).unwrap();
+        let mut paths: Vec<String> = plan
+            .items
+            .iter()
+            .map(|item| item.rel_path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn ignore_tree() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in [".git", "build", "sub/data", "sub/other/data", "__pycache__"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            ".git/HEAD",
+            "build/out.o",
+            "run.sh",
+            "debug.log",
+            "keep.log",
+            "sub/data/a.bin",
+            "sub/other/data/b.bin",
+            "sub/notes.txt",
+            "__pycache__/m.pyc",
+        ] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+        fs::write(
+            root.join(".gitignore"),
+            "*.log\n!keep.log\nbuild/\n# comment\n",
+        )
+        .unwrap();
+        fs::write(root.join("sub/.gitignore"), "data/*.bin\n").unwrap();
+        tmp
+    }
+
+    #[test]
+    fn build_sync_plan_honors_gitignore_files_hierarchically() {
+        let tmp = ignore_tree();
+        assert_eq!(
+            plan_paths(tmp.path(), &[], true),
+            vec![
+                ".gitignore",
+                "__pycache__/m.pyc",
+                "keep.log",
+                "run.sh",
+                "sub/.gitignore",
+                "sub/notes.txt",
+                "sub/other/data/b.bin",
+            ]
+        );
+        assert_eq!(plan_paths(tmp.path(), &[], false).len(), 11);
+    }
+
+    #[test]
+    fn hpcignore_overrides_gitignore_and_explicit_rules_override_both() {
+        let tmp = ignore_tree();
+        fs::write(
+            tmp.path().join(".hpcignore"),
+            "# project rules\n+ debug.log\n- __pycache__/\nsub/notes.txt\n",
+        )
+        .unwrap();
+        let paths = plan_paths(tmp.path(), &[], true);
+        assert!(paths.contains(&"debug.log".to_string()));
+        assert!(!paths.contains(&"__pycache__/m.pyc".to_string()));
+        assert!(!paths.contains(&"sub/notes.txt".to_string()));
+
+        let filters = [
+            rule(SyncFilterAction::Include, "notes.txt"),
+            rule(SyncFilterAction::Exclude, "debug.log"),
+            rule(SyncFilterAction::Include, "build/"),
+        ];
+        let paths = plan_paths(tmp.path(), &filters, true);
+        assert!(paths.contains(&"sub/notes.txt".to_string()));
+        assert!(!paths.contains(&"debug.log".to_string()));
+        assert!(paths.contains(&"build/out.o".to_string()));
+    }
+
+    #[test]
+    fn hpcignore_lines_map_to_rsync_rules() {
+        let rules = parse_hpcignore("# c\n\n+ a\n- b\n!c\nd/\n");
+        let got: Vec<(bool, &str)> = rules
+            .iter()
+            .map(|r| {
+                (
+                    matches!(r.action, SyncFilterAction::Include),
+                    r.pattern.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            vec![(true, "a"), (false, "b"), (true, "c"), (false, "d/")]
+        );
+    }
+
+    /// A tree with a file link and a directory link inside the root, a link to a
+    /// directory outside it, and a dangling link. The returned guard owns both dirs.
+    #[cfg(unix)]
+    fn symlink_tree() -> (tempfile::TempDir, tempfile::TempDir) {
+        use std::os::unix::fs::symlink;
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("conf")).unwrap();
+        fs::write(root.join("conf/base.yaml"), "base").unwrap();
+        fs::write(shared.path().join("data.csv"), "1,2").unwrap();
+        symlink("conf/base.yaml", root.join("config.yaml")).unwrap();
+        symlink("conf", root.join("conf-link")).unwrap();
+        symlink(shared.path(), root.join("datasets")).unwrap();
+        symlink("missing.txt", root.join("dangling")).unwrap();
+        (tmp, shared)
+    }
+
+    #[cfg(unix)]
+    fn symlink_summary(symlinks: &[SymlinkEntry]) -> Vec<(String, SymlinkAction, bool)> {
+        let mut out: Vec<_> = symlinks
+            .iter()
+            .map(|link| {
+                (
+                    link.rel_path.to_string_lossy().into_owned(),
+                    link.action,
+                    link.outside_root,
+                )
+            })
+            .collect();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_skipped_and_reported_by_default() {
+        let (tmp, _shared) = symlink_tree();
+        let plan = build_sync_plan(tmp.path(), "/remote", &[], false, SymlinkPolicy::Skip).unwrap();
+        let paths: Vec<_> = plan.items.iter().map(|i| i.rel_path.clone()).collect();
+        assert_eq!(paths, vec![Path::new("conf/base.yaml").to_path_buf()]);
+        assert_eq!(
+            symlink_summary(&plan.symlinks),
+            vec![
+                ("conf-link".to_string(), SymlinkAction::Skip, false),
+                ("config.yaml".to_string(), SymlinkAction::Skip, false),
+                ("dangling".to_string(), SymlinkAction::Skip, false),
+                ("datasets".to_string(), SymlinkAction::Skip, true),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preserved_symlinks_keep_their_targets() {
+        let (tmp, _shared) = symlink_tree();
+        let filters = [rule(SyncFilterAction::Exclude, "datasets")];
+        let plan = build_sync_plan(
+            tmp.path(),
+            "/remote",
+            &filters,
+            false,
+            SymlinkPolicy::Preserve,
+        )
+        .unwrap();
+        assert_eq!(plan.items.len(), 1);
+        let config = plan
+            .symlinks
+            .iter()
+            .find(|link| link.rel_path == Path::new("config.yaml"))
+            .unwrap();
+        assert_eq!(config.target, Path::new("conf/base.yaml"));
+        assert_eq!(config.remote_path, "/remote/config.yaml");
+        assert_eq!(
+            symlink_summary(&plan.symlinks),
+            vec![
+                ("conf-link".to_string(), SymlinkAction::Recreate, false),
+                ("config.yaml".to_string(), SymlinkAction::Recreate, false),
+                ("dangling".to_string(), SymlinkAction::Recreate, false),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlinks_upload_their_targets() {
+        let (tmp, _shared) = symlink_tree();
+        let plan =
+            build_sync_plan(tmp.path(), "/remote", &[], false, SymlinkPolicy::Follow).unwrap();
+        let mut paths: Vec<_> = plan
+            .items
+            .iter()
+            .map(|i| i.rel_path.to_string_lossy().into_owned())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "conf-link/base.yaml",
+                "conf/base.yaml",
+                "config.yaml",
+                "datasets/data.csv"
+            ]
+        );
+        assert_eq!(
+            symlink_summary(&plan.symlinks),
+            vec![
+                ("conf-link".to_string(), SymlinkAction::Follow, false),
+                ("config.yaml".to_string(), SymlinkAction::Follow, false),
+                ("dangling".to_string(), SymlinkAction::Broken, false),
+                ("datasets".to_string(), SymlinkAction::Follow, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_deletions_keep_planned_excluded_and_protected_paths() {
+        let tmp = ignore_tree();
+        let root = tmp.path();
+        fs::write(root.join(HPCPROTECT_FILE), "# outputs\nresults/\n").unwrap();
+        let plan = build_sync_plan(root, "/remote", &[], true, SymlinkPolicy::Skip).unwrap();
+        let remote = [
+            "run.sh",
+            "old_input.csv",
+            "sub/renamed.txt",
+            "build/out.o",
+            "stale.log",
+            "slurm-123.out",
+            "results/epoch1/model.pt",
+            "custom.log.keep",
+            "notes/results",
+        ];
+        let uploaded = remote.iter().map(|rel| format!("/remote/{rel}")).collect();
+        let deletions = plan_remote_deletions(
+            &plan,
+            remote,
+            &[],
+            true,
+            &["/custom.log.keep".to_string()],
+            &uploaded,
+        )
+        .unwrap();
+        assert_eq!(
+            deletions,
+            vec!["notes/results", "old_input.csv", "sub/renamed.txt"]
+        );
+
+        // Without ignore files, remote files matching .gitignore are no longer excluded.
+        let plan = build_sync_plan(root, "/remote", &[], false, SymlinkPolicy::Skip).unwrap();
+        let deletions = plan_remote_deletions(&plan, remote, &[], false, &[], &uploaded).unwrap();
+        assert_eq!(
+            deletions,
+            vec![
+                "custom.log.keep",
+                "notes/results",
+                "old_input.csv",
+                "stale.log",
+                "sub/renamed.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_deletions_keep_files_no_sync_uploaded() {
+        let tmp = ignore_tree();
+        let plan = build_sync_plan(tmp.path(), "/remote/", &[], true, SymlinkPolicy::Skip).unwrap();
+        let remote = ["old_input.csv", "results/model.pt", "checkpoint.bin"];
+        let uploaded = HashSet::from(["/remote/old_input.csv".to_string()]);
+        let deletions = plan_remote_deletions(&plan, remote, &[], true, &[], &uploaded).unwrap();
+        assert_eq!(deletions, vec!["old_input.csv"]);
+    }
 }
@@ -3,12 +3,15 @@
 
 use anyhow::Result;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
 use tokio::fs as tokiofs;
 use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
+use super::delta::BlockSignature;
+
 /// Helper to wrap an mpsc receiver as a tonic stream.
 pub fn receiver_to_stream<T>(
     rx: mpsc::Receiver<Result<T, tonic::Status>>,
@@ -16,6 +19,44 @@ pub fn receiver_to_stream<T>(
     ReceiverStream::new(rx)
 }
 
+/// Blocking [`std::io::Write`] adapter that forwards buffered chunks over an mpsc channel,
+/// for encoders running on `spawn_blocking` that feed a remote command's stdin.
+pub(crate) struct ChannelWriter {
+    tx: mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    const CHUNK: usize = 256 * 1024;
+
+    pub(crate) fn new(tx: mpsc::Sender<Vec<u8>>) -> Self {
+        Self {
+            tx,
+            buf: Vec::with_capacity(Self::CHUNK),
+        }
+    }
+}
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= Self::CHUNK {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(Self::CHUNK));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "receiver closed"))
+    }
+}
+
 /// Very small, safe-ish shell escaper for paths.
 pub(crate) fn sh_escape(p: &str) -> String {
     let mut out = String::from("'");
@@ -43,48 +84,265 @@ pub(crate) async fn local_block_hashes(path: &Path, block_size: usize) -> Result
     Ok(out)
 }
 
-pub(crate) fn build_remote_hash_script(remote_path: &str, block_size: usize) -> String {
-    let escaped_path = remote_path.replace("\\", "\\\\").replace("'", "\\'");
-    let quoted_path = format!("'{escaped_path}'");
+/// Compute the SHA-256 of a whole local file.
+pub(crate) async fn local_file_sha256(path: &Path) -> Result<String> {
+    let mut f = tokiofs::File::open(path).await?;
+    let mut h = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = f.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        h.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", h.finalize()))
+}
+
+/// Build a `sha256sum` invocation over the given remote files.
+pub(crate) fn build_remote_sha256_command(remote_paths: &[&str]) -> String {
+    let mut cmd = String::from("sha256sum --");
+    for path in remote_paths {
+        cmd.push(' ');
+        cmd.push_str(&sh_escape(path));
+    }
+    cmd
+}
+
+/// Build an `rm -f` invocation over the given remote files.
+pub(crate) fn build_remove_command(remote_paths: &[String]) -> String {
+    let mut cmd = String::from("rm -f --");
+    for path in remote_paths {
+        cmd.push(' ');
+        cmd.push_str(&sh_escape(path));
+    }
+    cmd
+}
+
+/// Point `remote_path` at `target`, replacing a file or link there but never a directory.
+pub(crate) fn build_symlink_command(target: &str, remote_path: &str) -> String {
     format!(
-        r#"#!/usr/bin/env python3
-import sys, os, hashlib
-path = r{rp}
-bs = {bs}
-try:
-    st = os.stat(path)
-except Exception as e:
-    print("ERR", e, file=sys.stderr)
+        "ln -sfnT -- {} {}",
+        sh_escape(target),
+        sh_escape(remote_path)
+    )
+}
+
+/// Parse `sha256sum` output into path -> hash.
+/// Lines for names that sha256sum had to escape (leading `\`) are skipped.
+pub(crate) fn parse_sha256sum_output(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('\\'))
+        .filter_map(|line| {
+            let (hash, path) = line.split_once("  ").or_else(|| line.split_once(" *"))?;
+            Some((path.to_string(), hash.to_string()))
+        })
+        .collect()
+}
+
+/// Python helper used during sync.
+///
+/// `hash <block_size> <path>...` prints `<idx> <offset> <len> <sha256> <adler32>` per
+/// block and `ERR <idx> <reason>` for files it cannot read.
+///
+/// `patch <block_size> <path>` rebuilds `path` from an rsync-style delta read from stdin
+/// (see `ssh::delta`) into a temporary file next to it, checks the SHA-256 trailer and
+/// renames it into place, leaving the original untouched on any error.
+pub(crate) const REMOTE_HASH_SCRIPT: &str = r#"#!/usr/bin/env python3
+import sys, os, hashlib, struct, tempfile, zlib
+
+def hash_files(bs, paths):
+    out = sys.stdout
+    for idx, path in enumerate(paths):
+        try:
+            with open(path, 'rb') as f:
+                off = 0
+                while True:
+                    b = f.read(bs)
+                    if not b: break
+                    out.write("%d %d %d %s %08x\n" % (idx, off, len(b), hashlib.sha256(b).hexdigest(), zlib.adler32(b) & 0xffffffff))
+                    off += len(b)
+        except Exception as e:
+            out.write("ERR %d %s\n" % (idx, e))
+    out.flush()
+
+def read_exact(inp, n):
+    b = inp.read(n)
+    if len(b) != n: raise Exception('truncated delta')
+    return b
+
+def patch(bs, dst):
+    inp = sys.stdin.buffer
+    fd, tmp = tempfile.mkstemp(dir=os.path.dirname(dst) or '.', prefix='.hpcd-')
+    try:
+        h = hashlib.sha256()
+        with open(dst, 'rb') as basis, os.fdopen(fd, 'wb') as out:
+            while True:
+                op = read_exact(inp, 1)
+                if op == b'C':
+                    start, count = struct.unpack('>QQ', read_exact(inp, 16))
+                    basis.seek(start * bs)
+                    left = count * bs
+                    while left > 0:
+                        b = basis.read(min(left, 1 << 20))
+                        if not b: break
+                        h.update(b); out.write(b); left -= len(b)
+                elif op == b'D':
+                    (n,) = struct.unpack('>Q', read_exact(inp, 8))
+                    while n > 0:
+                        b = read_exact(inp, min(n, 1 << 20))
+                        h.update(b); out.write(b); n -= len(b)
+                elif op == b'E':
+                    if read_exact(inp, 64).decode() != h.hexdigest():
+                        raise Exception('checksum mismatch')
+                    break
+                else:
+                    raise Exception('bad delta op %r' % op)
+        os.chmod(tmp, os.stat(dst).st_mode & 0o7777)
+        os.rename(tmp, dst)
+    except Exception as e:
+        try: os.unlink(tmp)
+        except OSError: pass
+        sys.stderr.write("%s\n" % e)
+        sys.exit(1)
+
+if sys.argv[1] == 'hash':
+    hash_files(int(sys.argv[2]), sys.argv[3:])
+elif sys.argv[1] == 'patch':
+    patch(int(sys.argv[2]), sys.argv[3])
+else:
     sys.exit(2)
-h = hashlib.sha256
-off = 0
-with open(path, 'rb') as f:
-    while True:
-        b = f.read(bs)
-        if not b: break
-        print(off, len(b), h(b).hexdigest(), file=sys.stdout)
-        off += len(b)
-sys.stdout.flush()
-"#,
-        rp = quoted_path,
-        bs = block_size
+"#;
+
+/// Upper bound for the escaped paths passed to one hashing exec, well below common ARG_MAX.
+pub(crate) const REMOTE_HASH_ARGS_BUDGET: usize = 64 * 1024;
+
+/// Command running the uploaded Python helper over `remote_paths`.
+pub(crate) fn build_remote_hash_command(
+    interpreter: &str,
+    script_path: &str,
+    remote_paths: &[String],
+    block_size: usize,
+) -> String {
+    let mut cmd = format!(
+        "{} -u {} hash {}",
+        interpreter,
+        sh_escape(script_path),
+        block_size
+    );
+    for path in remote_paths {
+        cmd.push(' ');
+        cmd.push_str(&sh_escape(path));
+    }
+    cmd
+}
+
+/// Command running the uploaded Python helper's `patch` mode on `remote_path`.
+pub(crate) fn build_remote_patch_command(
+    interpreter: &str,
+    script_path: &str,
+    remote_path: &str,
+    block_size: usize,
+) -> String {
+    format!(
+        "{} -u {} patch {} {}",
+        interpreter,
+        sh_escape(script_path),
+        block_size,
+        sh_escape(remote_path)
     )
 }
 
-pub(crate) fn parse_remote_hash_output(output: &str) -> Result<Vec<String>> {
-    let mut hashes = Vec::new();
-    for line in output.lines() {
-        if line.starts_with("ERR ") {
-            anyhow::bail!("remote: {}", line);
+/// Pure-shell equivalent of the `hash` mode of [`REMOTE_HASH_SCRIPT`] built on `dd` and
+/// `sha256sum`, for clusters without Python. Same output format minus the Adler-32
+/// column, so only block mode is possible.
+pub(crate) fn build_shell_hash_command(remote_paths: &[String], block_size: usize) -> String {
+    let mut files = String::new();
+    for path in remote_paths {
+        files.push(' ');
+        files.push_str(&sh_escape(path));
+    }
+    format!(
+        "bs={block_size}; i=0; for f in{files}; do \
+if [ -f \"$f\" ] && [ -r \"$f\" ]; then \
+size=$(wc -c < \"$f\"); off=0; b=0; \
+while [ \"$off\" -lt \"$size\" ]; do \
+len=$((size - off)); if [ \"$len\" -gt \"$bs\" ]; then len=$bs; fi; \
+h=$(dd if=\"$f\" bs=\"$bs\" skip=\"$b\" count=1 2>/dev/null | sha256sum | cut -d' ' -f1); \
+echo \"$i $off $len $h\"; off=$((off + len)); b=$((b + 1)); \
+done; \
+else echo \"ERR $i unreadable\"; fi; \
+i=$((i + 1)); done"
+    )
+}
+
+/// Split `remote_paths` into batches whose escaped length fits [`REMOTE_HASH_ARGS_BUDGET`].
+pub(crate) fn remote_hash_batches(remote_paths: &[String]) -> Vec<&[String]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (idx, path) in remote_paths.iter().enumerate() {
+        let len = sh_escape(path).len() + 1;
+        if idx > start && used + len > REMOTE_HASH_ARGS_BUDGET {
+            batches.push(&remote_paths[start..idx]);
+            start = idx;
+            used = 0;
         }
+        used += len;
+    }
+    if start < remote_paths.len() {
+        batches.push(&remote_paths[start..]);
+    }
+    batches
+}
+
+/// Parse helper output for a batch of `count` files into per-file block signatures.
+/// Files reported with `ERR`, or with malformed lines, map to `None`.
+pub(crate) fn parse_remote_hash_output(
+    output: &str,
+    count: usize,
+) -> Vec<Option<Vec<BlockSignature>>> {
+    let mut hashes: Vec<Option<Vec<BlockSignature>>> = vec![Some(Vec::new()); count];
+    let mut failed = vec![false; count];
+    for line in output.lines() {
         let mut it = line.split_whitespace();
+        let first = it.next();
+        if first == Some("ERR") {
+            if let Some(idx) = it.next().and_then(|v| v.parse::<usize>().ok())
+                && idx < count
+            {
+                failed[idx] = true;
+            }
+            continue;
+        }
+        let Some(idx) = first.and_then(|v| v.parse::<usize>().ok()) else {
+            continue;
+        };
+        if idx >= count {
+            continue;
+        }
         let _off = it.next();
-        let _len = it.next();
-        if let Some(hex) = it.next() {
-            hashes.push(hex.to_string());
+        let len = it.next().and_then(|v| v.parse::<usize>().ok());
+        let strong = it.next();
+        let weak = it.next().map(|v| u32::from_str_radix(v, 16).ok());
+        match (len, strong, weak, hashes[idx].as_mut()) {
+            (Some(len), Some(strong), None | Some(Some(_)), Some(list)) => {
+                list.push(BlockSignature {
+                    len,
+                    strong: strong.to_string(),
+                    weak: weak.flatten(),
+                })
+            }
+            _ => failed[idx] = true,
         }
     }
-    Ok(hashes)
+    for (slot, failed) in hashes.iter_mut().zip(failed) {
+        if failed {
+            *slot = None;
+        }
+    }
+    hashes
 }
 
 pub(crate) fn build_remote_dir_paths(remote_dir: &str) -> Vec<String> {
@@ -114,9 +372,13 @@ pub(crate) fn build_remote_dir_paths(remote_dir: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::{
-        build_remote_dir_paths, build_remote_hash_script, local_block_hashes,
-        parse_remote_hash_output, receiver_to_stream, sh_escape,
+        ChannelWriter, REMOTE_HASH_ARGS_BUDGET, build_remote_dir_paths, build_remote_hash_command,
+        build_remote_patch_command, build_remote_sha256_command, build_remove_command,
+        build_shell_hash_command, build_symlink_command, local_block_hashes, local_file_sha256,
+        parse_remote_hash_output, parse_sha256sum_output, receiver_to_stream, remote_hash_batches,
+        sh_escape,
     };
+    use crate::ssh::delta::BlockSignature;
     use proto::{StreamEvent, stream_event};
     use sha2::{Digest, Sha256};
     use std::fs;
@@ -142,6 +404,46 @@ mod tests {
         assert_eq!(hashes, expected);
     }
 
+    #[tokio::test]
+    async fn local_file_sha256_hashes_whole_file() {
+        let tmp = tempdir().unwrap();
+        let path = tmp.path().join("data.bin");
+        fs::write(&path, b"hello world").unwrap();
+        assert_eq!(
+            local_file_sha256(&path).await.unwrap(),
+            hash_hex(b"hello world")
+        );
+    }
+
+    #[test]
+    fn sha256sum_command_and_output_round_trip() {
+        let cmd = build_remote_sha256_command(&["/r/a.txt", "/r/it's.txt"]);
+        assert_eq!(cmd, r"sha256sum -- '/r/a.txt' '/r/it'\''s.txt'");
+
+        let out = "abc  /r/a.txt\n\\def  /r/new\\nline\nfff */r/bin.dat\n";
+        let parsed = parse_sha256sum_output(out);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.get("/r/a.txt").map(String::as_str), Some("abc"));
+        assert_eq!(parsed.get("/r/bin.dat").map(String::as_str), Some("fff"));
+    }
+
+    #[test]
+    fn remove_command_escapes_every_path() {
+        let paths = ["/r/a.txt".to_string(), "/r/it's".to_string()];
+        assert_eq!(
+            build_remove_command(&paths),
+            r"rm -f -- '/r/a.txt' '/r/it'\''s'"
+        );
+    }
+
+    #[test]
+    fn symlink_command_replaces_links_but_not_directories() {
+        assert_eq!(
+            build_symlink_command("../data/it's", "/r/data"),
+            r"ln -sfnT -- '../data/it'\''s' '/r/data'"
+        );
+    }
+
     #[test]
     fn sh_escape_wraps_and_escapes_quotes() {
         assert_eq!(sh_escape("plain"), "'plain'");
@@ -176,24 +478,81 @@ mod tests {
     }
 
     #[test]
-    fn build_remote_hash_script_includes_path_and_block_size() {
-        let script = build_remote_hash_script("/tmp/hi", 123);
-        assert!(script.contains("path = r'/tmp/hi'"));
-        assert!(script.contains("bs = 123"));
+    fn channel_writer_forwards_chunks_on_flush() {
+        use std::io::Write;
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut writer = ChannelWriter::new(tx);
+        writer.write_all(b"abc").unwrap();
+        writer.write_all(b"def").unwrap();
+        assert!(rx.try_recv().is_err());
+        writer.flush().unwrap();
+        assert_eq!(rx.try_recv().unwrap(), b"abcdef".to_vec());
+        drop(rx);
+        writer.write_all(b"x").unwrap();
+        assert!(writer.flush().is_err());
+    }
+
+    #[test]
+    fn build_remote_hash_command_passes_block_size_and_paths() {
+        let cmd = build_remote_hash_command(
+            "python3",
+            "/tmp/hpcd_x.py",
+            &["/r/a b".to_string(), "/r/c".to_string()],
+            123,
+        );
+        assert_eq!(cmd, "python3 -u '/tmp/hpcd_x.py' hash 123 '/r/a b' '/r/c'");
     }
 
     #[test]
-    fn parse_remote_hash_output_collects_hashes() {
-        let output = "0 4 abcd\n4 4 efgh\n";
-        let hashes = parse_remote_hash_output(output).unwrap();
-        assert_eq!(hashes, vec!["abcd".to_string(), "efgh".to_string()]);
+    fn build_remote_patch_command_targets_one_file() {
+        let cmd = build_remote_patch_command("python", "/tmp/hpcd_x.py", "/r/a b", 64);
+        assert_eq!(cmd, "python -u '/tmp/hpcd_x.py' patch 64 '/r/a b'");
     }
 
     #[test]
-    fn parse_remote_hash_output_errors_on_err_line() {
-        let err = parse_remote_hash_output("ERR nope").unwrap_err();
-        let msg = format!("{err:#}");
-        assert!(msg.contains("remote: ERR nope"));
+    fn build_shell_hash_command_loops_over_paths() {
+        let cmd = build_shell_hash_command(&["/r/it's".to_string()], 4096);
+        assert!(cmd.starts_with(r"bs=4096; i=0; for f in '/r/it'\''s'; do"));
+        assert!(cmd.contains("dd if=\"$f\" bs=\"$bs\" skip=\"$b\" count=1"));
+        assert!(cmd.contains("sha256sum"));
+        assert!(cmd.contains("echo \"ERR $i unreadable\""));
+    }
+
+    #[test]
+    fn remote_hash_batches_respects_budget() {
+        let long = "x".repeat(REMOTE_HASH_ARGS_BUDGET / 2);
+        let paths = vec![long.clone(), long.clone(), "short".to_string()];
+        let batches = remote_hash_batches(&paths);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), 1);
+        assert_eq!(batches[1].len(), 2);
+        assert!(remote_hash_batches(&[]).is_empty());
+    }
+
+    #[test]
+    fn parse_remote_hash_output_groups_blocks_per_file() {
+        let output = "0 0 4 abcd 0000001f\n0 4 2 efgh 00ff0001\nERR 1 [Errno 2] No such file\n\
+3 0 4\n4 0 4 ijkl\n5 0 4 mnop zz\n";
+        let hashes = parse_remote_hash_output(output, 6);
+        let block = |len, strong: &str, weak| BlockSignature {
+            len,
+            strong: strong.to_string(),
+            weak,
+        };
+        assert_eq!(
+            hashes,
+            vec![
+                Some(vec![
+                    block(4, "abcd", Some(0x1f)),
+                    block(2, "efgh", Some(0x00ff_0001))
+                ]),
+                None,
+                Some(Vec::new()),
+                None,
+                Some(vec![block(4, "ijkl", None)]),
+                None,
+            ]
+        );
     }
 
     #[test]
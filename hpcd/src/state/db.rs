@@ -5,9 +5,11 @@ use sqlx::{
     Row, SqlitePool,
     sqlite::{SqliteConnectOptions, SqlitePoolOptions},
 };
-use std::{net::IpAddr, path::Path, str::FromStr, time::Duration};
+use std::{collections::HashSet, net::IpAddr, path::Path, str::FromStr, time::Duration};
 use thiserror::Error;
-use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use time::{OffsetDateTime, UtcOffset, macros::format_description};
+
+use crate::util::remote_path::normalize_path;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -188,6 +190,217 @@ pub struct JobRecord {
     pub remote_path: String,
     pub stdout_path: String,
     pub stderr_path: Option<String>,
+    /// How the job came to be tracked: `JOB_ORIGIN_SUBMITTED` or `JOB_ORIGIN_ADOPTED`.
+    pub origin: String,
+    /// When the remote run directory was deleted (RFC 3339).
+    pub cleaned_at: Option<String>,
+    /// Slurm job name, from `#SBATCH --job-name` or the script's file name.
+    pub job_name: Option<String>,
+    /// Free-form note attached at submit or with `hpc job label`.
+    pub note: Option<String>,
+}
+
+/// Job submitted through the daemon.
+pub const JOB_ORIGIN_SUBMITTED: &str = "submitted";
+/// Job submitted outside the daemon (e.g. `sbatch` on a login node) and adopted afterwards.
+pub const JOB_ORIGIN_ADOPTED: &str = "adopted";
+
+/// Status of a job as `hpc job list` shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Not finished and waiting in the scheduler queue.
+    Pending,
+    /// Not finished and not known to be queued.
+    Running,
+    /// Finished with `COMPLETED`, or without a known terminal state.
+    Completed,
+    /// Finished in any other terminal state.
+    Failed,
+}
+
+impl JobStatus {
+    fn sql_condition(self) -> &'static str {
+        match self {
+            Self::Pending => "(j.is_completed = 0 and upper(j.scheduler_state) = 'PENDING')",
+            Self::Running => {
+                "(j.is_completed = 0 and (j.scheduler_state is null or upper(j.scheduler_state) <> 'PENDING'))"
+            }
+            Self::Completed => {
+                "(j.is_completed = 1 and (j.terminal_state is null or j.terminal_state = 'COMPLETED'))"
+            }
+            Self::Failed => "(j.is_completed = 1 and j.terminal_state <> 'COMPLETED')",
+        }
+    }
+}
+
+/// Order of [`HostStore::query_jobs`] results; ties are broken by job id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JobSort {
+    #[default]
+    Created,
+    /// Unfinished jobs sort before finished ones.
+    Finished,
+}
+
+impl JobSort {
+    fn sql_key(self) -> &'static str {
+        match self {
+            Self::Created => "j.created_at",
+            Self::Finished => "coalesce(j.completed_at, '')",
+        }
+    }
+}
+
+/// Position after the last job of a page: its sort key and id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobCursor {
+    pub key: String,
+    pub id: i64,
+}
+
+impl std::fmt::Display for JobCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.id, self.key)
+    }
+}
+impl FromStr for JobCursor {
+    type Err = std::num::ParseIntError;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (id, key) = s.split_once(':').unwrap_or((s, ""));
+        Ok(JobCursor {
+            key: key.to_string(),
+            id: id.parse()?,
+        })
+    }
+}
+
+/// Filters, order and page of a job listing. Time bounds are in the stored format (see
+/// [`db_time`]); lower bounds are inclusive and upper bounds exclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobQuery {
+    pub host_id: Option<i64>,
+    /// Jobs in any of these states; empty means all.
+    pub statuses: Vec<JobStatus>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub finished_after: Option<String>,
+    pub finished_before: Option<String>,
+    /// The local path itself or anything below it.
+    pub local_path_prefix: Option<String>,
+    pub scheduler_id: Option<i64>,
+    /// Jobs carrying all of these labels.
+    pub labels: Vec<(String, String)>,
+    pub sort: JobSort,
+    pub descending: bool,
+    pub limit: Option<u32>,
+    pub after: Option<JobCursor>,
+}
+
+/// One page of [`HostStore::query_jobs`]; `next` is set when more jobs follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobPage {
+    pub jobs: Vec<JobRecord>,
+    pub next: Option<JobCursor>,
+}
+
+/// A `key=value` label attached to a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JobLabel {
+    pub job_id: i64,
+    pub key: String,
+    pub value: String,
+}
+
+/// Resource usage of one job step, as captured from `sacct` once the job has finished.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JobStepUsage {
+    /// Raw sacct `JobID`, e.g. "123" for the allocation or "123.batch" for a step.
+    pub step_id: String,
+    pub state: String,
+    pub elapsed_secs: i64,
+    pub total_cpu_secs: f64,
+    pub alloc_cpus: i64,
+    pub nnodes: i64,
+    pub max_rss_bytes: Option<i64>,
+    pub req_mem_bytes: Option<i64>,
+    pub timelimit_secs: Option<i64>,
+}
+/// Output to download once a job has finished, as declared with `--fetch-on-complete`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewJobFetchRule {
+    /// Remote file or directory, absolute or relative to the job's remote path.
+    pub remote_path: String,
+    /// Local directory the output is placed in.
+    pub local_dir: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JobFetchRule {
+    pub id: i64,
+    pub job_id: i64,
+    pub remote_path: String,
+    pub local_dir: String,
+    /// One of the `FETCH_*` states.
+    pub state: String,
+    /// Why the last attempt failed or was postponed.
+    pub error: Option<String>,
+    /// When the output was downloaded (RFC 3339).
+    pub fetched_at: Option<String>,
+}
+
+/// Waiting for the job to finish, or for the cluster to become reachable.
+pub const FETCH_PENDING: &str = "pending";
+/// Claimed by a background download.
+pub const FETCH_RUNNING: &str = "running";
+pub const FETCH_DONE: &str = "done";
+pub const FETCH_FAILED: &str = "failed";
+
+/// A transferred file whose local copy matched the remote one by SHA-256.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewJobFile {
+    pub remote_path: String,
+    pub local_path: String,
+    pub size: i64,
+    pub sha256: String,
+    /// Whether a submit uploaded the file, rather than a retrieve downloading it.
+    pub uploaded: bool,
+}
+
+/// Entry of a job's file manifest, one per local copy of a transferred file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JobFile {
+    pub id: i64,
+    pub job_id: i64,
+    pub remote_path: String,
+    pub local_path: String,
+    pub size: i64,
+    pub sha256: String,
+    /// When the hash was last checked against the remote copy (RFC 3339).
+    pub verified_at: String,
+    /// Whether a submit uploaded the file, rather than a retrieve downloading it.
+    pub uploaded: bool,
+}
+
+/// A Slurm association of the cluster user with its fairshare data, cached from `sacctmgr` and
+/// `sshare`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClusterAccount {
+    pub account: String,
+    pub partition: Option<String>,
+    pub qos: Vec<String>,
+    pub default_qos: Option<String>,
+    pub raw_shares: Option<i64>,
+    pub norm_shares: Option<f64>,
+    pub raw_usage: Option<i64>,
+    pub effective_usage: Option<f64>,
+    pub fairshare: Option<f64>,
 }
 /// Async store
 /// TODO: since it stores not only hosts but also partitions, jobs etc., this needs to be renamed.
@@ -281,6 +494,11 @@ impl HostStore {
         .execute(&self.pool)
         .await?;
         self.ensure_jobs_table().await?;
+        self.ensure_job_usage_table().await?;
+        self.ensure_job_fetch_rules_table().await?;
+        self.ensure_job_files_table().await?;
+        self.ensure_job_labels_table().await?;
+        self.ensure_cluster_accounts_table().await?;
         Ok(())
     }
     async fn ensure_partitions_table(&self) -> Result<()> {
@@ -396,7 +614,11 @@ impl HostStore {
             created_at text not null default (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
             completed_at text,
             terminal_state text,
-            scheduler_state text);
+            scheduler_state text,
+            origin text not null default 'submitted',
+            cleaned_at text,
+            job_name text,
+            note text);
     "#,
         )
         .execute(&self.pool)
@@ -409,15 +631,21 @@ impl HostStore {
         let mut has_stdout_path = false;
         let mut has_stderr_path = false;
         let mut stderr_notnull = false;
+        let mut has_origin = false;
+        let mut has_cleaned_at = false;
+        let mut has_job_name = false;
+        let mut has_note = false;
 
         for row in &columns {
-            let name = row
-                .try_get::<String, _>("name")
-                .unwrap_or_default();
+            let name = row.try_get::<String, _>("name").unwrap_or_default();
             match name.as_str() {
                 "terminal_state" => has_terminal_state = true,
                 "scheduler_state" => has_scheduler_state = true,
                 "stdout_path" => has_stdout_path = true,
+                "origin" => has_origin = true,
+                "cleaned_at" => has_cleaned_at = true,
+                "job_name" => has_job_name = true,
+                "note" => has_note = true,
                 "stderr_path" => {
                     has_stderr_path = true;
                     let notnull = row.try_get::<i64, _>("notnull").unwrap_or(0);
@@ -495,6 +723,31 @@ impl HostStore {
                 .execute(&mut *tx)
                 .await?;
             tx.commit().await?;
+            // The rebuilt table predates the columns below; re-add them.
+            has_origin = false;
+            has_cleaned_at = false;
+            has_job_name = false;
+            has_note = false;
+        }
+        if !has_origin {
+            sqlx::query("ALTER TABLE jobs ADD COLUMN origin TEXT NOT NULL DEFAULT 'submitted'")
+                .execute(&self.pool)
+                .await?;
+        }
+        if !has_cleaned_at {
+            sqlx::query("ALTER TABLE jobs ADD COLUMN cleaned_at TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        if !has_job_name {
+            sqlx::query("ALTER TABLE jobs ADD COLUMN job_name TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        if !has_note {
+            sqlx::query("ALTER TABLE jobs ADD COLUMN note TEXT")
+                .execute(&self.pool)
+                .await?;
         }
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_host_id ON jobs(host_id)")
             .execute(&self.pool)
@@ -504,6 +757,131 @@ impl HostStore {
         )
         .execute(&self.pool)
         .await?;
+        // Sort keys and prefix ranges of `query_jobs`.
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_jobs_created_at_id ON jobs(created_at, id);
+            CREATE INDEX IF NOT EXISTS idx_jobs_completed_at_id ON jobs(coalesce(completed_at, ''), id);
+            CREATE INDEX IF NOT EXISTS idx_jobs_local_path ON jobs(local_path);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    async fn ensure_job_usage_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            create table if not exists job_usage (
+              id integer primary key autoincrement,
+              job_id integer not null references jobs(id) on delete cascade,
+              step_id text not null,
+              state text not null,
+              elapsed_secs integer not null,
+              total_cpu_secs real not null,
+              alloc_cpus integer not null,
+              nnodes integer not null,
+              max_rss_bytes integer,
+              req_mem_bytes integer,
+              timelimit_secs integer,
+              created_at text not null default (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+              unique(job_id, step_id)
+            );
+            create index if not exists idx_job_usage_job_id on job_usage(job_id);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    async fn ensure_job_fetch_rules_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            create table if not exists job_fetch_rules (
+              id integer primary key autoincrement,
+              job_id integer not null references jobs(id) on delete cascade,
+              remote_path text not null,
+              local_dir text not null,
+              state text not null default 'pending',
+              error text,
+              fetched_at text
+            );
+            create index if not exists idx_job_fetch_rules_job_id on job_fetch_rules(job_id);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    async fn ensure_job_files_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            create table if not exists job_files (
+              id integer primary key autoincrement,
+              job_id integer not null references jobs(id) on delete cascade,
+              remote_path text not null,
+              local_path text not null,
+              size integer not null,
+              sha256 text not null,
+              verified_at text not null,
+              uploaded integer not null default 0,
+              unique(job_id, local_path)
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        let columns = sqlx::query("PRAGMA table_info('job_files');")
+            .fetch_all(&self.pool)
+            .await?;
+        let has_uploaded = columns
+            .iter()
+            .any(|row| row.try_get::<String, _>("name").unwrap_or_default() == "uploaded");
+        if !has_uploaded {
+            sqlx::query("ALTER TABLE job_files ADD COLUMN uploaded INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+    async fn ensure_job_labels_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            create table if not exists job_labels (
+              job_id integer not null references jobs(id) on delete cascade,
+              key text not null,
+              value text not null,
+              primary key(job_id, key)
+            );
+            create index if not exists idx_job_labels_key_value on job_labels(key, value);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    async fn ensure_cluster_accounts_table(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            create table if not exists cluster_accounts (
+              id integer primary key autoincrement,
+              host_id integer not null references hosts(id) on delete cascade,
+              account text not null,
+              partition text,
+              qos text not null default '',
+              default_qos text,
+              raw_shares integer,
+              norm_shares real,
+              raw_usage integer,
+              effective_usage real,
+              fairshare real,
+              fetched_at text not null
+            );
+            create index if not exists idx_cluster_accounts_host_id on cluster_accounts(host_id);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
         Ok(())
     }
     /// Insert a new host. Returns the new row id.
@@ -857,11 +1235,13 @@ impl HostStore {
         Ok(Some((host, parts)))
     }
 
-    pub async fn insert_job(&self, job: &NewJob) -> Result<i64> {
+    /// Insert a job with `origin` (`JOB_ORIGIN_SUBMITTED` or `JOB_ORIGIN_ADOPTED`).
+    /// Returns the new row id.
+    pub async fn insert_job(&self, job: &NewJob, origin: &str) -> Result<i64> {
         let rec = sqlx::query(
             r#"
-        insert into jobs(scheduler_id, host_id, local_path, remote_path, stdout_path, stderr_path)
-        values (?1, ?2, ?3, ?4, ?5, ?6)
+        insert into jobs(scheduler_id, host_id, local_path, remote_path, stdout_path, stderr_path, origin)
+        values (?1, ?2, ?3, ?4, ?5, ?6, ?7)
         returning id;
     "#,
         )
@@ -871,11 +1251,34 @@ impl HostStore {
         .bind(job.remote_path.clone())
         .bind(job.stdout_path.clone())
         .bind(job.stderr_path.clone())
+        .bind(origin)
         .fetch_one(&self.pool)
         .await?;
         Ok(rec.try_get::<i64, _>("id")?)
     }
 
+    /// Internal id of the job tracking `scheduler_id` on a host, if any.
+    pub async fn job_id_for_scheduler_id(
+        &self,
+        host_id: i64,
+        scheduler_id: i64,
+    ) -> Result<Option<i64>> {
+        let row = sqlx::query(
+            r#"
+            select id
+            from jobs
+            where host_id = ?1 and scheduler_id = ?2
+            order by id desc
+            limit 1
+            "#,
+        )
+        .bind(host_id)
+        .bind(scheduler_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| r.try_get::<i64, _>("id").unwrap()))
+    }
+
     pub async fn latest_remote_path_for_local_path(
         &self,
         host_name: &str,
@@ -920,13 +1323,113 @@ impl HostStore {
         Ok(row.map(|r| r.try_get::<i64, _>("id").unwrap()))
     }
 
+    /// The most recent running job whose run directory is `remote_path`, lies inside it or
+    /// contains it. Paths are compared component-wise after normalization, so trailing
+    /// slashes, `//` and `.` segments do not hide an overlap.
+    pub async fn running_job_id_overlapping_remote_path(
+        &self,
+        host_name: &str,
+        remote_path: &str,
+    ) -> Result<Option<i64>> {
+        let rows = sqlx::query(
+            r#"
+            select j.id as id, j.remote_path as remote_path
+            from jobs j
+            join hosts h on j.host_id = h.id
+            where h.name = ?1 and j.is_completed = 0
+            order by j.id desc
+            "#,
+        )
+        .bind(host_name)
+        .fetch_all(&self.pool)
+        .await?;
+        let target = normalize_path(remote_path);
+        Ok(rows.iter().find_map(|row| {
+            let running = normalize_path(row.try_get::<String, _>("remote_path").unwrap());
+            (running.starts_with(&target) || target.starts_with(&running))
+                .then(|| row.try_get::<i64, _>("id").unwrap())
+        }))
+    }
+
+    /// Record that the run directory `remote_path` on a host was deleted, for every job that
+    /// ran in it. Returns the number of jobs updated.
+    pub async fn mark_remote_path_cleaned(&self, host_id: i64, remote_path: &str) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            update jobs
+            set cleaned_at = ?1
+            where host_id = ?2 and remote_path = ?3 and cleaned_at is null
+            "#,
+        )
+        .bind(now_rfc3339())
+        .bind(host_id)
+        .bind(remote_path)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Stdout and stderr paths of every job recorded in `remote_path` on a host.
+    pub async fn log_paths_for_remote_path(
+        &self,
+        host_name: &str,
+        remote_path: &str,
+    ) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            select j.stdout_path as stdout_path, j.stderr_path as stderr_path
+            from jobs j
+            join hosts h on j.host_id = h.id
+            where h.name = ?1 and j.remote_path = ?2
+            order by j.id
+            "#,
+        )
+        .bind(host_name)
+        .bind(remote_path)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut paths = Vec::with_capacity(rows.len());
+        for row in rows {
+            paths.push(row.try_get::<String, _>("stdout_path")?);
+            if let Some(stderr) = row.try_get::<Option<String>, _>("stderr_path")? {
+                paths.push(stderr);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Remote paths that submits to `remote_path` on a host uploaded, as recorded in the
+    /// file manifests of its jobs.
+    pub async fn uploaded_paths_for_remote_path(
+        &self,
+        host_name: &str,
+        remote_path: &str,
+    ) -> Result<HashSet<String>> {
+        let rows = sqlx::query(
+            r#"
+            select distinct f.remote_path as remote_path
+            from job_files f
+            join jobs j on f.job_id = j.id
+            join hosts h on j.host_id = h.id
+            where h.name = ?1 and j.remote_path = ?2 and f.uploaded = 1
+            "#,
+        )
+        .bind(host_name)
+        .bind(remote_path)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| Ok(row.try_get::<String, _>("remote_path")?))
+            .collect()
+    }
+
     pub async fn list_jobs_for_host(&self, host_id: i64) -> Result<Vec<JobRecord>> {
         let rows = sqlx::query(
             r#"
             with all_jobs as (
                 select * from jobs where host_id = ?1
             )
-            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,h.name as name
+            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,aj.origin as origin,aj.cleaned_at as cleaned_at,aj.job_name as job_name,aj.note as note,h.name as name
             from all_jobs aj
             join hosts h
               on aj.host_id = h.id;
@@ -951,6 +1454,10 @@ impl HostStore {
                    aj.remote_path as remote_path,
                    aj.stdout_path as stdout_path,
                    aj.stderr_path as stderr_path,
+                   aj.origin as origin,
+                   aj.cleaned_at as cleaned_at,
+                   aj.job_name as job_name,
+                   aj.note as note,
                    h.name as name
             from jobs aj
             join hosts h on aj.host_id = h.id
@@ -969,7 +1476,7 @@ impl HostStore {
             with all_jobs as (
                 select * from jobs
             )
-            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,h.name as name
+            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,aj.origin as origin,aj.cleaned_at as cleaned_at,aj.job_name as job_name,aj.note as note,h.name as name
             from all_jobs aj
             join hosts h
               on aj.host_id = h.id;
@@ -980,10 +1487,109 @@ impl HostStore {
         Ok(rows.into_iter().map(row_to_job).collect())
     }
 
+    /// Jobs matching `query`, one page at a time.
+    pub async fn query_jobs(&self, query: &JobQuery) -> Result<JobPage> {
+        let key = query.sort.sql_key();
+        let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            r#"
+            select j.id as id, j.scheduler_id as scheduler_id, j.is_completed as is_completed,
+                   j.created_at as created_at, j.completed_at as completed_at,
+                   j.terminal_state as terminal_state, j.scheduler_state as scheduler_state,
+                   j.local_path as local_path, j.remote_path as remote_path,
+                   j.stdout_path as stdout_path, j.stderr_path as stderr_path,
+                   j.origin as origin, j.cleaned_at as cleaned_at, j.job_name as job_name,
+                   j.note as note, h.name as name
+            from jobs j
+            join hosts h on j.host_id = h.id
+            where 1 = 1"#,
+        );
+        if let Some(host_id) = query.host_id {
+            qb.push(" and j.host_id = ").push_bind(host_id);
+        }
+        if !query.statuses.is_empty() {
+            let conditions: Vec<&str> = query.statuses.iter().map(|s| s.sql_condition()).collect();
+            qb.push(format!(" and ({})", conditions.join(" or ")));
+        }
+        // Bounds are in the stored format (see `db_time`), and the finish bounds use the
+        // expression of `idx_jobs_completed_at_id`, so both are index range scans.
+        if let Some(after) = &query.created_after {
+            qb.push(" and j.created_at >= ").push_bind(after.clone());
+        }
+        if let Some(before) = &query.created_before {
+            qb.push(" and j.created_at < ").push_bind(before.clone());
+        }
+        if let Some(after) = &query.finished_after {
+            qb.push(" and coalesce(j.completed_at, '') >= ")
+                .push_bind(after.clone());
+        }
+        if let Some(before) = &query.finished_before {
+            qb.push(" and coalesce(j.completed_at, '') > '' and coalesce(j.completed_at, '') < ")
+                .push_bind(before.clone());
+        }
+        if let Some(prefix) = &query.local_path_prefix {
+            // '0' follows '/', so the range holds exactly the paths below `prefix`.
+            let prefix = prefix.trim_end_matches('/');
+            qb.push(" and (j.local_path = ")
+                .push_bind(prefix.to_string())
+                .push(" or (j.local_path >= ")
+                .push_bind(format!("{prefix}/"))
+                .push(" and j.local_path < ")
+                .push_bind(format!("{prefix}0"))
+                .push("))");
+        }
+        if let Some(scheduler_id) = query.scheduler_id {
+            qb.push(" and j.scheduler_id = ").push_bind(scheduler_id);
+        }
+        for (label_key, value) in &query.labels {
+            qb.push(" and exists (select 1 from job_labels l where l.job_id = j.id and l.key = ")
+                .push_bind(label_key.clone())
+                .push(" and l.value = ")
+                .push_bind(value.clone())
+                .push(")");
+        }
+        let (cmp, order) = if query.descending {
+            ("<", "desc")
+        } else {
+            (">", "asc")
+        };
+        if let Some(cursor) = &query.after {
+            qb.push(format!(" and ({key}, j.id) {cmp} ("))
+                .push_bind(cursor.key.clone())
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        qb.push(format!(" order by {key} {order}, j.id {order}"));
+        if let Some(limit) = query.limit {
+            qb.push(" limit ").push_bind(i64::from(limit) + 1);
+        }
+
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        let mut next = None;
+        let mut jobs = Vec::with_capacity(rows.len());
+        for row in rows {
+            if query
+                .limit
+                .is_some_and(|limit| jobs.len() >= limit as usize)
+            {
+                next = jobs.last().map(|last: &JobRecord| JobCursor {
+                    key: match query.sort {
+                        JobSort::Created => last.created_at.clone(),
+                        JobSort::Finished => last.finished_at.clone().unwrap_or_default(),
+                    },
+                    id: last.id,
+                });
+                break;
+            }
+            jobs.push(row_to_job(row));
+        }
+        Ok(JobPage { jobs, next })
+    }
+
     pub async fn list_running_jobs(&self) -> Result<Vec<JobRecord>> {
         let rows = sqlx::query(
             r#"
-            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,h.name as name
+            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,aj.origin as origin,aj.cleaned_at as cleaned_at,aj.job_name as job_name,aj.note as note,h.name as name
             from jobs aj
             join hosts h
               on aj.host_id = h.id
@@ -995,30 +1601,127 @@ impl HostStore {
         Ok(rows.into_iter().map(row_to_job).collect())
     }
 
-    pub async fn mark_job_completed(&self, id: i64, terminal_state: Option<&str>) -> Result<()> {
-        let now = now_rfc3339();
+    /// Delete job records together with their usage, fetch rules, file manifest and labels.
+    /// Returns the number of jobs deleted.
+    pub async fn delete_jobs(&self, ids: &[i64]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+        for id in ids {
+            deleted += sqlx::query("delete from jobs where id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Set the Slurm job name and note of a job; `None` leaves a field as it is.
+    pub async fn set_job_details(
+        &self,
+        id: i64,
+        job_name: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<()> {
         sqlx::query(
             r#"
             update jobs
-            set is_completed = 1,
-                completed_at = ?1,
-                terminal_state = ?2,
-                scheduler_state = ?3
-            where id = ?4
+            set job_name = coalesce(?1, job_name), note = coalesce(?2, note)
+            where id = ?3
             "#,
         )
-        .bind(now)
-        .bind(terminal_state)
-        .bind(terminal_state)
+        .bind(job_name)
+        .bind(note)
         .bind(id)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
-    pub async fn update_job_scheduler_state(
+    /// Remove the note of a job.
+    pub async fn clear_job_note(&self, id: i64) -> Result<()> {
+        sqlx::query("update jobs set note = null where id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Add or overwrite the labels in `set` and drop the keys in `remove`.
+    pub async fn update_job_labels(
         &self,
-        id: i64,
+        job_id: i64,
+        set: &[(String, String)],
+        remove: &[String],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for key in remove {
+            sqlx::query("delete from job_labels where job_id = ?1 and key = ?2")
+                .bind(job_id)
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for (key, value) in set {
+            sqlx::query(
+                r#"
+                insert into job_labels (job_id, key, value) values (?1, ?2, ?3)
+                on conflict(job_id, key) do update set value = excluded.value
+                "#,
+            )
+            .bind(job_id)
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Labels of all jobs, grouped by job and ordered by key.
+    pub async fn list_job_labels(&self) -> Result<Vec<JobLabel>> {
+        let rows = sqlx::query("select job_id, key, value from job_labels order by job_id, key")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(row_to_job_label).collect())
+    }
+
+    /// Labels of one job, ordered by key.
+    pub async fn list_labels_for_job(&self, job_id: i64) -> Result<Vec<JobLabel>> {
+        let rows =
+            sqlx::query("select job_id, key, value from job_labels where job_id = ?1 order by key")
+                .bind(job_id)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().map(row_to_job_label).collect())
+    }
+
+    pub async fn mark_job_completed(&self, id: i64, terminal_state: Option<&str>) -> Result<()> {
+        let now = now_rfc3339();
+        sqlx::query(
+            r#"
+            update jobs
+            set is_completed = 1,
+                completed_at = ?1,
+                terminal_state = ?2,
+                scheduler_state = ?3
+            where id = ?4
+            "#,
+        )
+        .bind(now)
+        .bind(terminal_state)
+        .bind(terminal_state)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_job_scheduler_state(
+        &self,
+        id: i64,
         scheduler_state: Option<&str>,
     ) -> Result<()> {
         sqlx::query(
@@ -1034,14 +1737,301 @@ impl HostStore {
         .await?;
         Ok(())
     }
+
+    /// Completed jobs on a host, newest first; with `since` (see [`db_time`]) only those
+    /// created at or after it.
+    pub async fn list_completed_jobs_for_host_since(
+        &self,
+        host_id: i64,
+        since: Option<&str>,
+    ) -> Result<Vec<JobRecord>> {
+        let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            r#"
+            select aj.id as id, aj.scheduler_id as scheduler_id,aj.is_completed as is_completed,aj.created_at as created_at,aj.completed_at as completed_at,aj.terminal_state as terminal_state,aj.scheduler_state as scheduler_state,aj.local_path as local_path,aj.remote_path as remote_path,aj.stdout_path as stdout_path,aj.stderr_path as stderr_path,aj.origin as origin,aj.cleaned_at as cleaned_at,aj.job_name as job_name,aj.note as note,h.name as name
+            from jobs aj
+            join hosts h
+              on aj.host_id = h.id
+            where aj.is_completed = 1 and aj.host_id = "#,
+        );
+        qb.push_bind(host_id);
+        if let Some(since) = since {
+            qb.push(" and aj.created_at >= ")
+                .push_bind(since.to_string());
+        }
+        qb.push(" order by aj.id desc");
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(row_to_job).collect())
+    }
+
+    /// Replace the stored usage of a job with `steps`.
+    pub async fn replace_job_usage(&self, job_id: i64, steps: &[JobStepUsage]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("delete from job_usage where job_id = ?1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        for step in steps {
+            sqlx::query(
+                r#"
+                insert into job_usage (
+                  job_id, step_id, state, elapsed_secs, total_cpu_secs, alloc_cpus,
+                  nnodes, max_rss_bytes, req_mem_bytes, timelimit_secs
+                ) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                "#,
+            )
+            .bind(job_id)
+            .bind(&step.step_id)
+            .bind(&step.state)
+            .bind(step.elapsed_secs)
+            .bind(step.total_cpu_secs)
+            .bind(step.alloc_cpus)
+            .bind(step.nnodes)
+            .bind(step.max_rss_bytes)
+            .bind(step.req_mem_bytes)
+            .bind(step.timelimit_secs)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Stored usage of a job, allocation line first.
+    pub async fn list_job_usage(&self, job_id: i64) -> Result<Vec<JobStepUsage>> {
+        let rows = sqlx::query(
+            r#"
+            select step_id, state, elapsed_secs, total_cpu_secs, alloc_cpus, nnodes,
+                   max_rss_bytes, req_mem_bytes, timelimit_secs
+            from job_usage
+            where job_id = ?1
+            order by id
+            "#,
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(row_to_job_usage).collect())
+    }
+
+    /// Store the outputs to download once a job has finished.
+    pub async fn insert_job_fetch_rules(
+        &self,
+        job_id: i64,
+        rules: &[NewJobFetchRule],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for rule in rules {
+            sqlx::query(
+                "insert into job_fetch_rules (job_id, remote_path, local_dir) values (?1, ?2, ?3)",
+            )
+            .bind(job_id)
+            .bind(&rule.remote_path)
+            .bind(&rule.local_dir)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Fetch rules of all jobs, grouped by job in declaration order.
+    pub async fn list_job_fetch_rules(&self) -> Result<Vec<JobFetchRule>> {
+        let rows = sqlx::query(
+            r#"
+            select id, job_id, remote_path, local_dir, state, error, fetched_at
+            from job_fetch_rules
+            order by job_id, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(row_to_job_fetch_rule).collect())
+    }
+
+    /// Mark the pending fetch rules of completed jobs as running and return them, so each
+    /// rule is picked up by one background download only.
+    pub async fn claim_pending_job_fetch_rules(&self) -> Result<Vec<JobFetchRule>> {
+        let rows = sqlx::query(
+            r#"
+            update job_fetch_rules
+            set state = ?1
+            where state = ?2
+              and job_id in (select id from jobs where is_completed = 1)
+            returning id, job_id, remote_path, local_dir, state, error, fetched_at
+            "#,
+        )
+        .bind(FETCH_RUNNING)
+        .bind(FETCH_PENDING)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut rules: Vec<JobFetchRule> = rows.into_iter().map(row_to_job_fetch_rule).collect();
+        rules.sort_by_key(|rule| (rule.job_id, rule.id));
+        Ok(rules)
+    }
+
+    /// Put rules left running by a previous daemon process back in the queue.
+    pub async fn requeue_running_job_fetch_rules(&self) -> Result<u64> {
+        let res = sqlx::query("update job_fetch_rules set state = ?1 where state = ?2")
+            .bind(FETCH_PENDING)
+            .bind(FETCH_RUNNING)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Record the outcome of a fetch attempt; `fetched_at` is set when it is `FETCH_DONE`.
+    pub async fn update_job_fetch_rule(
+        &self,
+        id: i64,
+        state: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let fetched_at = (state == FETCH_DONE).then(now_rfc3339);
+        sqlx::query(
+            r#"
+            update job_fetch_rules
+            set state = ?1, error = ?2, fetched_at = coalesce(?3, fetched_at)
+            where id = ?4
+            "#,
+        )
+        .bind(state)
+        .bind(error)
+        .bind(fetched_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Add verified uploads or downloads to the file manifest of a job, replacing earlier
+    /// entries for the same local paths.
+    pub async fn record_job_files(&self, job_id: i64, files: &[NewJobFile]) -> Result<()> {
+        let verified_at = now_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        for file in files {
+            sqlx::query(
+                r#"
+                insert into job_files
+                  (job_id, remote_path, local_path, size, sha256, verified_at, uploaded)
+                values (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                on conflict(job_id, local_path) do update set
+                  remote_path = excluded.remote_path,
+                  size = excluded.size,
+                  sha256 = excluded.sha256,
+                  verified_at = excluded.verified_at,
+                  uploaded = excluded.uploaded
+                "#,
+            )
+            .bind(job_id)
+            .bind(&file.remote_path)
+            .bind(&file.local_path)
+            .bind(file.size)
+            .bind(&file.sha256)
+            .bind(&verified_at)
+            .bind(file.uploaded)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// File manifest of a job, ordered by local path.
+    pub async fn list_job_files(&self, job_id: i64) -> Result<Vec<JobFile>> {
+        let rows = sqlx::query(
+            r#"
+            select id, job_id, remote_path, local_path, size, sha256, verified_at, uploaded
+            from job_files
+            where job_id = ?1
+            order by local_path
+            "#,
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(row_to_job_file).collect())
+    }
+
+    /// Replace the cached accounts of a host with `accounts`.
+    pub async fn replace_cluster_accounts(
+        &self,
+        host_id: i64,
+        accounts: &[ClusterAccount],
+    ) -> Result<()> {
+        let fetched_at = now_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("delete from cluster_accounts where host_id = ?1")
+            .bind(host_id)
+            .execute(&mut *tx)
+            .await?;
+        for account in accounts {
+            sqlx::query(
+                r#"
+                insert into cluster_accounts (
+                  host_id, account, partition, qos, default_qos, raw_shares, norm_shares,
+                  raw_usage, effective_usage, fairshare, fetched_at
+                ) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                "#,
+            )
+            .bind(host_id)
+            .bind(&account.account)
+            .bind(&account.partition)
+            .bind(account.qos.join(","))
+            .bind(&account.default_qos)
+            .bind(account.raw_shares)
+            .bind(account.norm_shares)
+            .bind(account.raw_usage)
+            .bind(account.effective_usage)
+            .bind(account.fairshare)
+            .bind(&fetched_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Cached accounts of a host in fetch order, with the time they were fetched.
+    pub async fn list_cluster_accounts(
+        &self,
+        host_id: i64,
+    ) -> Result<(Vec<ClusterAccount>, Option<String>)> {
+        let rows = sqlx::query(
+            r#"
+            select account, partition, qos, default_qos, raw_shares, norm_shares, raw_usage,
+                   effective_usage, fairshare, fetched_at
+            from cluster_accounts
+            where host_id = ?1
+            order by id
+            "#,
+        )
+        .bind(host_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let fetched_at = rows
+            .first()
+            .and_then(|row| row.try_get::<String, _>("fetched_at").ok());
+        Ok((
+            rows.into_iter().map(row_to_cluster_account).collect(),
+            fetched_at,
+        ))
+    }
 }
 
 // -- helpers
 
 fn now_rfc3339() -> String {
-    OffsetDateTime::now_utc()
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".into())
+    db_time(OffsetDateTime::now_utc())
+}
+
+/// `at` in UTC, formatted like the column defaults (`strftime('%Y-%m-%dT%H:%M:%fZ')`) so
+/// that stored times and bounds compare correctly as text.
+pub fn db_time(at: OffsetDateTime) -> String {
+    at.to_offset(UtcOffset::UTC)
+        .format(format_description!(
+            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
+        ))
+        .unwrap_or_else(|_| "1970-01-01T00:00:00.000Z".into())
 }
 
 fn row_to_host(row: sqlx::sqlite::SqliteRow) -> HostRecord {
@@ -1112,6 +2102,78 @@ fn row_to_job(row: sqlx::sqlite::SqliteRow) -> JobRecord {
         remote_path: row.try_get("remote_path").unwrap(),
         stdout_path: row.try_get("stdout_path").unwrap(),
         stderr_path: row.try_get("stderr_path").ok().flatten(),
+        origin: row
+            .try_get("origin")
+            .unwrap_or_else(|_| JOB_ORIGIN_SUBMITTED.to_string()),
+        cleaned_at: row.try_get("cleaned_at").ok().flatten(),
+        job_name: row.try_get("job_name").ok().flatten(),
+        note: row.try_get("note").ok().flatten(),
+    }
+}
+
+fn row_to_job_label(row: sqlx::sqlite::SqliteRow) -> JobLabel {
+    JobLabel {
+        job_id: row.try_get("job_id").unwrap(),
+        key: row.try_get("key").unwrap(),
+        value: row.try_get("value").unwrap(),
+    }
+}
+
+fn row_to_job_usage(row: sqlx::sqlite::SqliteRow) -> JobStepUsage {
+    JobStepUsage {
+        step_id: row.try_get("step_id").unwrap(),
+        state: row.try_get("state").unwrap(),
+        elapsed_secs: row.try_get("elapsed_secs").unwrap(),
+        total_cpu_secs: row.try_get("total_cpu_secs").unwrap(),
+        alloc_cpus: row.try_get("alloc_cpus").unwrap(),
+        nnodes: row.try_get("nnodes").unwrap(),
+        max_rss_bytes: row.try_get("max_rss_bytes").ok().flatten(),
+        req_mem_bytes: row.try_get("req_mem_bytes").ok().flatten(),
+        timelimit_secs: row.try_get("timelimit_secs").ok().flatten(),
+    }
+}
+
+fn row_to_job_fetch_rule(row: sqlx::sqlite::SqliteRow) -> JobFetchRule {
+    JobFetchRule {
+        id: row.try_get("id").unwrap(),
+        job_id: row.try_get("job_id").unwrap(),
+        remote_path: row.try_get("remote_path").unwrap(),
+        local_dir: row.try_get("local_dir").unwrap(),
+        state: row.try_get("state").unwrap(),
+        error: row.try_get("error").ok().flatten(),
+        fetched_at: row.try_get("fetched_at").ok().flatten(),
+    }
+}
+
+fn row_to_job_file(row: sqlx::sqlite::SqliteRow) -> JobFile {
+    JobFile {
+        id: row.try_get("id").unwrap(),
+        job_id: row.try_get("job_id").unwrap(),
+        remote_path: row.try_get("remote_path").unwrap(),
+        local_path: row.try_get("local_path").unwrap(),
+        size: row.try_get("size").unwrap(),
+        sha256: row.try_get("sha256").unwrap(),
+        verified_at: row.try_get("verified_at").unwrap(),
+        uploaded: row.try_get("uploaded").unwrap(),
+    }
+}
+
+fn row_to_cluster_account(row: sqlx::sqlite::SqliteRow) -> ClusterAccount {
+    let qos: String = row.try_get("qos").unwrap_or_default();
+    ClusterAccount {
+        account: row.try_get("account").unwrap(),
+        partition: row.try_get("partition").ok().flatten(),
+        qos: qos
+            .split(',')
+            .filter(|q| !q.is_empty())
+            .map(str::to_string)
+            .collect(),
+        default_qos: row.try_get("default_qos").ok().flatten(),
+        raw_shares: row.try_get("raw_shares").ok().flatten(),
+        norm_shares: row.try_get("norm_shares").ok().flatten(),
+        raw_usage: row.try_get("raw_usage").ok().flatten(),
+        effective_usage: row.try_get("effective_usage").ok().flatten(),
+        fairshare: row.try_get("fairshare").ok().flatten(),
     }
 }
 
@@ -1371,7 +2433,7 @@ mod tests {
             stdout_path: "/remote/run/slurm-42.out".into(),
             stderr_path: Some("/remote/run/slurm-42.out".into()),
         };
-        db.insert_job(&job).await.unwrap();
+        db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         let jobs = db.list_jobs_for_host(host_row.id).await.unwrap();
         assert_eq!(jobs.len(), 1);
@@ -1387,6 +2449,143 @@ mod tests {
         assert!(!got.created_at.is_empty());
     }
 
+    #[tokio::test]
+    async fn query_jobs_filters_sorts_and_pages() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for (scheduler_id, local_path) in [
+            (1, "/work/exp"),
+            (2, "/work/exp/sub"),
+            (3, "/work/experiment"),
+            (4, "/work/exp"),
+        ] {
+            let job = NewJob {
+                scheduler_id: Some(scheduler_id),
+                host_id: host_row.id,
+                local_path: local_path.into(),
+                remote_path: "/remote/run".into(),
+                stdout_path: format!("/remote/run/slurm-{scheduler_id}.out"),
+                stderr_path: None,
+            };
+            let id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+            sqlx::query("update jobs set created_at = ?1 where id = ?2")
+                .bind(format!("2026-01-0{scheduler_id}T00:00:00.000Z"))
+                .bind(id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        db.mark_job_completed(ids[0], Some("COMPLETED"))
+            .await
+            .unwrap();
+        db.mark_job_completed(ids[1], Some("FAILED")).await.unwrap();
+        db.update_job_scheduler_state(ids[2], Some("PENDING"))
+            .await
+            .unwrap();
+        db.update_job_labels(ids[3], &[("exp".into(), "ablation".into())], &[])
+            .await
+            .unwrap();
+
+        let found = |page: JobPage| -> Vec<i64> { page.jobs.iter().map(|j| j.id).collect() };
+        let query = |q: JobQuery| {
+            let db = db.clone();
+            async move { found(db.query_jobs(&q).await.unwrap()) }
+        };
+        assert_eq!(
+            query(JobQuery {
+                statuses: vec![JobStatus::Failed, JobStatus::Pending],
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[1], ids[2]]
+        );
+        assert_eq!(
+            query(JobQuery {
+                statuses: vec![JobStatus::Running],
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[3]]
+        );
+        assert_eq!(
+            query(JobQuery {
+                local_path_prefix: Some("/work/exp/".into()),
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[0], ids[1], ids[3]]
+        );
+        assert_eq!(
+            query(JobQuery {
+                created_after: Some("2026-01-02T00:00:00.000Z".into()),
+                created_before: Some("2026-01-04T00:00:00.000Z".into()),
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[1], ids[2]]
+        );
+        let now = OffsetDateTime::now_utc();
+        assert_eq!(
+            query(JobQuery {
+                finished_after: Some(db_time(now - time::Duration::hours(1))),
+                finished_before: Some(db_time(now + time::Duration::hours(1))),
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[0], ids[1]]
+        );
+        assert_eq!(
+            query(JobQuery {
+                finished_before: Some(db_time(now + time::Duration::hours(1))),
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[0], ids[1]]
+        );
+        let since = db
+            .list_completed_jobs_for_host_since(host_row.id, Some("2026-01-01T00:00:00.000Z"))
+            .await
+            .unwrap();
+        assert_eq!(
+            since.iter().map(|j| j.id).collect::<Vec<_>>(),
+            [ids[1], ids[0]]
+        );
+        assert_eq!(
+            query(JobQuery {
+                labels: vec![("exp".into(), "ablation".into())],
+                scheduler_id: Some(4),
+                ..JobQuery::default()
+            })
+            .await,
+            [ids[3]]
+        );
+
+        let mut page_query = JobQuery {
+            descending: true,
+            limit: Some(3),
+            ..JobQuery::default()
+        };
+        let first = db.query_jobs(&page_query).await.unwrap();
+        let cursor = first.next.clone().expect("a second page");
+        assert_eq!(found(first), [ids[3], ids[2], ids[1]]);
+        page_query.after = Some(cursor.to_string().parse().unwrap());
+        let second = db.query_jobs(&page_query).await.unwrap();
+        assert!(second.next.is_none());
+        assert_eq!(found(second), [ids[0]]);
+
+        let by_finish = query(JobQuery {
+            sort: JobSort::Finished,
+            ..JobQuery::default()
+        })
+        .await;
+        assert_eq!(&by_finish[..2], [ids[2], ids[3]]);
+    }
+
     #[tokio::test]
     async fn latest_remote_path_for_local_path_returns_latest() {
         let db = HostStore::open_memory().await.unwrap();
@@ -1410,8 +2609,8 @@ mod tests {
             stdout_path: "/remote/run2/slurm-41.out".into(),
             stderr_path: None,
         };
-        db.insert_job(&job1).await.unwrap();
-        db.insert_job(&job2).await.unwrap();
+        db.insert_job(&job1, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        db.insert_job(&job2, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         let latest = db
             .latest_remote_path_for_local_path("host-a", "/tmp/project")
@@ -1441,7 +2640,7 @@ mod tests {
             stdout_path: "/remote/run/slurm-200.out".into(),
             stderr_path: None,
         };
-        let job_id = db.insert_job(&job).await.unwrap();
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         let running = db
             .running_job_id_for_remote_path("host-a", "/remote/run")
@@ -1460,6 +2659,218 @@ mod tests {
         assert!(running.is_none());
     }
 
+    #[tokio::test]
+    async fn running_job_overlap_covers_nested_and_unnormalized_paths() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let job = NewJob {
+            scheduler_id: Some(201),
+            host_id: host_row.id,
+            local_path: "/tmp/project".into(),
+            remote_path: "/remote/runs/a/sub".into(),
+            stdout_path: "/remote/runs/a/sub/slurm-201.out".into(),
+            stderr_path: None,
+        };
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+
+        for path in [
+            "/remote/runs/a",
+            "/remote/runs/a/",
+            "/remote//runs/a",
+            "/remote/runs/./a/sub/",
+            "/remote/runs/a/sub/deeper",
+        ] {
+            let running = db
+                .running_job_id_overlapping_remote_path("host-a", path)
+                .await
+                .unwrap();
+            assert_eq!(running, Some(job_id), "{path}");
+        }
+        for path in ["/remote/runs/ab", "/remote/runs/b"] {
+            let running = db
+                .running_job_id_overlapping_remote_path("host-a", path)
+                .await
+                .unwrap();
+            assert!(running.is_none(), "{path}");
+        }
+
+        db.mark_job_completed(job_id, Some("COMPLETED"))
+            .await
+            .unwrap();
+        let running = db
+            .running_job_id_overlapping_remote_path("host-a", "/remote/runs/a")
+            .await
+            .unwrap();
+        assert!(running.is_none());
+    }
+
+    #[tokio::test]
+    async fn mark_remote_path_cleaned_updates_every_job_in_the_directory() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for (scheduler_id, remote_path) in
+            [(1, "/remote/run"), (2, "/remote/run"), (3, "/remote/other")]
+        {
+            let job = NewJob {
+                scheduler_id: Some(scheduler_id),
+                host_id: host_row.id,
+                local_path: "/tmp/project".into(),
+                remote_path: remote_path.into(),
+                stdout_path: format!("{remote_path}/slurm-{scheduler_id}.out"),
+                stderr_path: None,
+            };
+            ids.push(db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap());
+        }
+
+        let updated = db
+            .mark_remote_path_cleaned(host_row.id, "/remote/run")
+            .await
+            .unwrap();
+        assert_eq!(updated, 2);
+        let first = db.get_job_by_job_id(ids[0]).await.unwrap().unwrap();
+        let cleaned_at = first.cleaned_at.clone().expect("cleaned_at set");
+        let other = db.get_job_by_job_id(ids[2]).await.unwrap().unwrap();
+        assert!(other.cleaned_at.is_none());
+
+        // Cleaning again keeps the first timestamp.
+        let updated = db
+            .mark_remote_path_cleaned(host_row.id, "/remote/run")
+            .await
+            .unwrap();
+        assert_eq!(updated, 0);
+        let first = db.get_job_by_job_id(ids[0]).await.unwrap().unwrap();
+        assert_eq!(first.cleaned_at, Some(cleaned_at));
+    }
+
+    #[tokio::test]
+    async fn delete_jobs_removes_dependent_rows() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for scheduler_id in [1, 2] {
+            let job = NewJob {
+                scheduler_id: Some(scheduler_id),
+                host_id: host_row.id,
+                local_path: "/tmp/project".into(),
+                remote_path: "/remote/run".into(),
+                stdout_path: format!("/remote/run/slurm-{scheduler_id}.out"),
+                stderr_path: None,
+            };
+            ids.push(db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap());
+        }
+        db.record_job_files(
+            ids[0],
+            &[NewJobFile {
+                remote_path: "/remote/run/out.txt".into(),
+                local_path: "/tmp/project/out.txt".into(),
+                size: 3,
+                sha256: "ab".into(),
+                uploaded: false,
+            }],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(db.delete_jobs(&[ids[0], 999]).await.unwrap(), 1);
+        assert!(db.get_job_by_job_id(ids[0]).await.unwrap().is_none());
+        assert!(db.list_job_files(ids[0]).await.unwrap().is_empty());
+        assert!(db.get_job_by_job_id(ids[1]).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn job_labels_and_notes_can_be_edited() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let job = NewJob {
+            scheduler_id: Some(1),
+            host_id: host_row.id,
+            local_path: "/tmp/project".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-1.out".into(),
+            stderr_path: None,
+        };
+        let id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        db.set_job_details(id, Some("train"), Some("baseline run"))
+            .await
+            .unwrap();
+        let label = |k: &str, v: &str| (k.to_string(), v.to_string());
+        db.update_job_labels(id, &[label("exp", "ablation"), label("lr", "0.1")], &[])
+            .await
+            .unwrap();
+        db.update_job_labels(id, &[label("lr", "0.01")], &["exp".to_string()])
+            .await
+            .unwrap();
+
+        let record = db.get_job_by_job_id(id).await.unwrap().unwrap();
+        assert_eq!(record.job_name.as_deref(), Some("train"));
+        assert_eq!(record.note.as_deref(), Some("baseline run"));
+        let labels = db.list_labels_for_job(id).await.unwrap();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].key, "lr");
+        assert_eq!(labels[0].value, "0.01");
+
+        db.set_job_details(id, None, Some("rerun")).await.unwrap();
+        let record = db.get_job_by_job_id(id).await.unwrap().unwrap();
+        assert_eq!(record.job_name.as_deref(), Some("train"));
+        assert_eq!(record.note.as_deref(), Some("rerun"));
+        db.clear_job_note(id).await.unwrap();
+        let record = db.get_job_by_job_id(id).await.unwrap().unwrap();
+        assert!(record.note.is_none());
+
+        db.delete_jobs(&[id]).await.unwrap();
+        assert!(db.list_job_labels().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn log_paths_for_remote_path_lists_stdout_and_stderr() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        for (scheduler_id, remote_path, stderr_path) in [
+            (1, "/remote/run", None),
+            (2, "/remote/run", Some("/remote/run/logs/2.err")),
+            (3, "/remote/other", None),
+        ] {
+            let job = NewJob {
+                scheduler_id: Some(scheduler_id),
+                host_id: host_row.id,
+                local_path: "/tmp/project".into(),
+                remote_path: remote_path.into(),
+                stdout_path: format!("{remote_path}/slurm-{scheduler_id}.out"),
+                stderr_path: stderr_path.map(str::to_string),
+            };
+            db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        }
+
+        let paths = db
+            .log_paths_for_remote_path("host-a", "/remote/run")
+            .await
+            .unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                "/remote/run/slurm-1.out",
+                "/remote/run/slurm-2.out",
+                "/remote/run/logs/2.err"
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn get_job_by_job_id_returns_row() {
         let db = HostStore::open_memory().await.unwrap();
@@ -1475,7 +2886,7 @@ mod tests {
             stdout_path: "/remote/run-a/slurm-42.out".into(),
             stderr_path: Some("/remote/run-a/slurm-42.out".into()),
         };
-        let job_id = db.insert_job(&job).await.unwrap();
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         let got = db.get_job_by_job_id(job_id).await.unwrap().unwrap();
         assert_eq!(got.id, job_id);
@@ -1498,7 +2909,7 @@ mod tests {
             stdout_path: "/remote/run-a/slurm-42.out".into(),
             stderr_path: Some("/remote/run-a/slurm-42.out".into()),
         };
-        let job_id = db.insert_job(&job).await.unwrap();
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         db.update_job_scheduler_state(job_id, Some("PENDING"))
             .await
@@ -1531,8 +2942,8 @@ mod tests {
             stdout_path: "/remote/run2/slurm-102.out".into(),
             stderr_path: Some("/remote/run2/slurm-102.out".into()),
         };
-        let job1_id = db.insert_job(&job1).await.unwrap();
-        let job2_id = db.insert_job(&job2).await.unwrap();
+        let job1_id = db.insert_job(&job1, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        let job2_id = db.insert_job(&job2, JOB_ORIGIN_SUBMITTED).await.unwrap();
 
         db.mark_job_completed(job1_id, Some("FAILED"))
             .await
@@ -1549,4 +2960,283 @@ mod tests {
         assert_eq!(completed.terminal_state.as_deref(), Some("FAILED"));
         assert_eq!(completed.scheduler_state.as_deref(), Some("FAILED"));
     }
+
+    #[tokio::test]
+    async fn job_fetch_rules_are_claimed_once_the_job_completes() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let job = NewJob {
+            scheduler_id: Some(7),
+            host_id: host_row.id,
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-7.out".into(),
+            stderr_path: None,
+        };
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        let rule = |path: &str| NewJobFetchRule {
+            remote_path: path.into(),
+            local_dir: "/tmp/local".into(),
+        };
+        db.insert_job_fetch_rules(job_id, &[rule("results"), rule("logs/run.log")])
+            .await
+            .unwrap();
+
+        assert!(db.claim_pending_job_fetch_rules().await.unwrap().is_empty());
+        db.mark_job_completed(job_id, Some("COMPLETED"))
+            .await
+            .unwrap();
+        let claimed = db.claim_pending_job_fetch_rules().await.unwrap();
+        assert_eq!(claimed.len(), 2);
+        assert_eq!(claimed[0].remote_path, "results");
+        assert!(claimed.iter().all(|rule| rule.state == FETCH_RUNNING));
+        assert!(db.claim_pending_job_fetch_rules().await.unwrap().is_empty());
+
+        db.update_job_fetch_rule(claimed[0].id, FETCH_DONE, None)
+            .await
+            .unwrap();
+        assert_eq!(db.requeue_running_job_fetch_rules().await.unwrap(), 1);
+        let rules = db.list_job_fetch_rules().await.unwrap();
+        assert_eq!(rules[0].state, FETCH_DONE);
+        assert!(rules[0].fetched_at.is_some());
+        assert_eq!(rules[1].state, FETCH_PENDING);
+        assert_eq!(rules[1].fetched_at, None);
+    }
+
+    #[tokio::test]
+    async fn job_files_are_upserted_by_local_path() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let job = NewJob {
+            scheduler_id: Some(7),
+            host_id: host_row.id,
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-7.out".into(),
+            stderr_path: None,
+        };
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        let file = |local: &str, sha256: &str| NewJobFile {
+            remote_path: format!("/remote/run/{local}"),
+            local_path: format!("/tmp/out/{local}"),
+            size: 5,
+            sha256: sha256.into(),
+            uploaded: false,
+        };
+        db.record_job_files(job_id, &[file("b.dat", "bb"), file("a.dat", "aa")])
+            .await
+            .unwrap();
+        db.record_job_files(job_id, &[file("b.dat", "b2")])
+            .await
+            .unwrap();
+
+        let files = db.list_job_files(job_id).await.unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].local_path, "/tmp/out/a.dat");
+        assert_eq!(files[1].sha256, "b2");
+        assert_eq!(files[1].remote_path, "/remote/run/b.dat");
+        assert!(db.list_job_files(job_id + 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn uploaded_paths_leave_out_downloads_and_other_run_dirs() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let mut ids = Vec::new();
+        for remote_path in ["/remote/run", "/remote/other"] {
+            let job = NewJob {
+                scheduler_id: Some(7),
+                host_id: host_row.id,
+                local_path: "/tmp/local".into(),
+                remote_path: remote_path.into(),
+                stdout_path: format!("{remote_path}/slurm-7.out"),
+                stderr_path: None,
+            };
+            ids.push(db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap());
+        }
+        let file = |remote_root: &str, name: &str, uploaded: bool| NewJobFile {
+            remote_path: format!("{remote_root}/{name}"),
+            local_path: format!("/tmp/local/{name}"),
+            size: 5,
+            sha256: "aa".into(),
+            uploaded,
+        };
+        db.record_job_files(
+            ids[0],
+            &[
+                file("/remote/run", "input.dat", true),
+                file("/remote/run", "results/model.pt", false),
+            ],
+        )
+        .await
+        .unwrap();
+        db.record_job_files(ids[1], &[file("/remote/other", "other.dat", true)])
+            .await
+            .unwrap();
+
+        let uploaded = db
+            .uploaded_paths_for_remote_path("host-a", "/remote/run")
+            .await
+            .unwrap();
+        assert_eq!(
+            uploaded,
+            HashSet::from(["/remote/run/input.dat".to_string()])
+        );
+        assert!(db.list_job_files(ids[0]).await.unwrap()[0].uploaded);
+    }
+
+    #[tokio::test]
+    async fn job_usage_replace_and_list_round_trip() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+        let job = NewJob {
+            scheduler_id: Some(7),
+            host_id: host_row.id,
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-7.out".into(),
+            stderr_path: None,
+        };
+        let job_id = db.insert_job(&job, JOB_ORIGIN_SUBMITTED).await.unwrap();
+        let step = |id: &str, rss: Option<i64>| JobStepUsage {
+            step_id: id.into(),
+            state: "COMPLETED".into(),
+            elapsed_secs: 60,
+            total_cpu_secs: 30.5,
+            alloc_cpus: 2,
+            nnodes: 1,
+            max_rss_bytes: rss,
+            req_mem_bytes: Some(1 << 30),
+            timelimit_secs: Some(3600),
+        };
+
+        db.replace_job_usage(job_id, &[step("7", None), step("7.batch", Some(1024))])
+            .await
+            .unwrap();
+        db.replace_job_usage(job_id, &[step("7", None), step("7.batch", Some(2048))])
+            .await
+            .unwrap();
+
+        let usage = db.list_job_usage(job_id).await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].step_id, "7");
+        assert_eq!(usage[1].max_rss_bytes, Some(2048));
+
+        assert!(
+            db.list_completed_jobs_for_host_since(host_row.id, Some("1970-01-01T00:00:00.000Z"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        db.mark_job_completed(job_id, Some("COMPLETED"))
+            .await
+            .unwrap();
+        let done = db
+            .list_completed_jobs_for_host_since(host_row.id, Some("1970-01-01T00:00:00.000Z"))
+            .await
+            .unwrap();
+        assert_eq!(done.len(), 1);
+        assert!(
+            db.list_completed_jobs_for_host_since(host_row.id, Some("9999-01-01T00:00:00.000Z"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let all = db
+            .list_completed_jobs_for_host_since(host_row.id, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn adopted_jobs_record_origin_and_are_found_by_scheduler_id() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+
+        let submitted = NewJob {
+            scheduler_id: Some(10),
+            host_id: host_row.id,
+            local_path: "/tmp/local".into(),
+            remote_path: "/remote/run".into(),
+            stdout_path: "/remote/run/slurm-10.out".into(),
+            stderr_path: None,
+        };
+        let adopted = NewJob {
+            scheduler_id: Some(11),
+            host_id: host_row.id,
+            local_path: String::new(),
+            remote_path: "/home/alice/other".into(),
+            stdout_path: "/home/alice/other/slurm-11.out".into(),
+            stderr_path: Some("/home/alice/other/slurm-11.err".into()),
+        };
+        let submitted_id = db
+            .insert_job(&submitted, JOB_ORIGIN_SUBMITTED)
+            .await
+            .unwrap();
+        let adopted_id = db.insert_job(&adopted, JOB_ORIGIN_ADOPTED).await.unwrap();
+
+        let got = db.get_job_by_job_id(submitted_id).await.unwrap().unwrap();
+        assert_eq!(got.origin, JOB_ORIGIN_SUBMITTED);
+        let got = db.get_job_by_job_id(adopted_id).await.unwrap().unwrap();
+        assert_eq!(got.origin, JOB_ORIGIN_ADOPTED);
+        assert_eq!(got.remote_path, "/home/alice/other");
+
+        assert_eq!(
+            db.job_id_for_scheduler_id(host_row.id, 11).await.unwrap(),
+            Some(adopted_id)
+        );
+        assert_eq!(
+            db.job_id_for_scheduler_id(host_row.id, 12).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn cluster_accounts_replace_and_list_round_trip() {
+        let db = HostStore::open_memory().await.unwrap();
+        let host = make_host("host-a", "alice", Address::Hostname("node-a".into()));
+        db.insert_host(&host).await.unwrap();
+        let host_row = db.get_by_name("host-a").await.unwrap().unwrap();
+
+        let (accounts, fetched_at) = db.list_cluster_accounts(host_row.id).await.unwrap();
+        assert!(accounts.is_empty());
+        assert_eq!(fetched_at, None);
+
+        let account = |name: &str, qos: &[&str]| ClusterAccount {
+            account: name.into(),
+            partition: None,
+            qos: qos.iter().map(|q| q.to_string()).collect(),
+            default_qos: qos.first().map(|q| q.to_string()),
+            raw_shares: Some(1),
+            norm_shares: Some(0.5),
+            raw_usage: Some(100),
+            effective_usage: Some(0.1),
+            fairshare: Some(0.9),
+        };
+        db.replace_cluster_accounts(host_row.id, &[account("old", &["normal"])])
+            .await
+            .unwrap();
+        let stored = vec![
+            account("proj_a", &["normal", "long"]),
+            account("proj_b", &[]),
+        ];
+        db.replace_cluster_accounts(host_row.id, &stored)
+            .await
+            .unwrap();
+
+        let (accounts, fetched_at) = db.list_cluster_accounts(host_row.id).await.unwrap();
+        assert_eq!(accounts, stored);
+        assert!(fetched_at.is_some());
+    }
 }
@@ -29,6 +29,25 @@ pub fn resolve_relative(remote_base: impl AsRef<Path>, relative_path: impl AsRef
     normalize_path(base.join(rel))
 }
 
+/// Resolves `path` the way a shell in `remote_base` would: absolute paths are kept as
+/// they are, relative ones are joined to the base. The result is normalized.
+pub fn resolve_remote(remote_base: impl AsRef<Path>, path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    if path.has_root() {
+        normalize_path(path)
+    } else {
+        resolve_relative(remote_base, path)
+    }
+}
+
+/// Like [`resolve_remote`], but `None` when the result lies outside `remote_base`.
+/// The check is syntactic, so a symlink inside the base can still lead out of it.
+pub fn resolve_within(remote_base: impl AsRef<Path>, path: impl AsRef<Path>) -> Option<PathBuf> {
+    let base = normalize_path(remote_base.as_ref());
+    let resolved = resolve_remote(&base, path);
+    resolved.starts_with(&base).then_some(resolved)
+}
+
 /// Normalize a path syntactically
 /// - remove `.`
 /// - resolve .. where possible
@@ -140,4 +159,24 @@ mod tests {
         // We can’t pop past root, so .. remains
         assert_eq!(got, Path::new("/.."));
     }
+
+    #[test]
+    fn unix_resolve_within_rejects_paths_leaving_the_base() {
+        let base = Path::new("/scratch/runs/7/");
+        assert_eq!(
+            resolve_within(base, "results/../metrics.json"),
+            Some(pb("/scratch/runs/7/metrics.json"))
+        );
+        assert_eq!(
+            resolve_within(base, "/scratch/runs/7/slurm.out"),
+            Some(pb("/scratch/runs/7/slurm.out"))
+        );
+        assert_eq!(resolve_within(base, "../8/slurm.out"), None);
+        assert_eq!(resolve_within(base, "/etc/passwd"), None);
+        assert_eq!(resolve_within(base, "/scratch/runs/70/x"), None);
+        assert_eq!(
+            resolve_remote(base, "../8/slurm.out"),
+            pb("/scratch/runs/8/slurm.out")
+        );
+    }
 }